@@ -0,0 +1,62 @@
+// 本机地址变化检测：换楼、DHCP 续租后地址变化时，门户会话仍绑定在旧地址上，需要用新地址重新认证
+use std::fmt;
+use std::net::Ipv4Addr;
+
+/// 一次地址变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressChange {
+    pub old: Ipv4Addr,
+    pub new: Ipv4Addr,
+}
+
+impl AddressChange {
+    /// 是否换到了另一个 /24 网段（通常是换了楼或 VLAN）
+    pub fn subnet_changed(&self) -> bool {
+        self.old.octets()[..3] != self.new.octets()[..3]
+    }
+}
+
+impl fmt::Display for AddressChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.subnet_changed() {
+            write!(f, "Moved to another subnet: {} -> {}", self.old, self.new)
+        } else {
+            write!(f, "Local address changed: {} -> {}", self.old, self.new)
+        }
+    }
+}
+
+/// 记录最近一次看到的本机地址
+#[derive(Debug, Default)]
+pub struct AddressWatcher {
+    last: Option<Ipv4Addr>,
+}
+
+impl AddressWatcher {
+    /// 记录当前地址，与上次不同时返回变化；暂时没有地址（如断开网线）时保留上次的地址
+    pub fn observe(&mut self, current: Option<Ipv4Addr>) -> Option<AddressChange> {
+        let current = current?;
+        let previous = self.last.replace(current)?;
+        (previous != current).then_some(AddressChange { old: previous, new: current })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe() {
+        let mut watcher = AddressWatcher::default();
+        assert_eq!(watcher.observe(Some(Ipv4Addr::new(10, 96, 12, 34))), None);
+        assert_eq!(watcher.observe(None), None);
+        assert_eq!(watcher.observe(Some(Ipv4Addr::new(10, 96, 12, 34))), None);
+
+        let change = watcher.observe(Some(Ipv4Addr::new(10, 96, 12, 80))).unwrap();
+        assert!(!change.subnet_changed());
+
+        let change = watcher.observe(Some(Ipv4Addr::new(10, 97, 3, 5))).unwrap();
+        assert!(change.subnet_changed());
+        assert_eq!(change.to_string(), "Moved to another subnet: 10.96.12.80 -> 10.97.3.5");
+    }
+}
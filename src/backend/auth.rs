@@ -1,57 +1,340 @@
+use crate::backend::cassette::{Interaction, Recorder};
+use crate::backend::config::Config;
+use crate::backend::ip_stack::{IpStack, StackPreference};
+use crate::backend::maintenance::{self, PortalState};
+use crate::backend::portal::{PortalProvider, PortalType};
+use crate::backend::portal_session::PortalSession;
+use crate::backend::rate_limit::{self, RateLimiter};
+use crate::backend::secret::SecretString;
+use crate::backend::triage::{self, Failure};
+use futures_util::future::LocalBoxFuture;
+use parking_lot::Mutex;
+use reqwest::header::{COOKIE, SET_COOKIE};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::error::Error;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// 认证响应的JSON结构
 #[derive(Debug, Deserialize)]
 pub struct AuthResponse {
     pub result: i32,
     pub msg: String,
+    #[serde(default)]
     pub ret_code: i32,
 }
 
-/// 运营商类型
-#[derive(Debug, Clone)]
-pub enum ISP {
-    Unicom,    // 联通 @unicomn
-    Mobile,    // 移动 @cmccn
-    Telecom,   // 电信 @telecomn
-    Campus,    // 校园网 ""
+impl AuthResponse {
+    /// 门户是否接受了本次请求
+    pub fn is_success(&self) -> bool {
+        self.result == 1
+    }
+
+    /// 转为门户插件的结果：成功时为门户的提示信息，拒绝时错误信息为门户的提示并附带能识别的故障
+    pub fn into_result(self) -> anyhow::Result<String> {
+        if self.is_success() {
+            return Ok(self.msg);
+        }
+        Err(match self.failure() {
+            Some(failure) => anyhow::Error::new(failure).context(self.msg),
+            None => anyhow::anyhow!(self.msg),
+        })
+    }
+
+    /// 失败原因中能识别的故障；ret_code 为 1 表示账号或密码错误（msg 为 base64 编码的 "ldap auth error"）
+    pub fn failure(&self) -> Option<Failure> {
+        (!self.is_success() && self.ret_code == 1).then_some(Failure::WrongPassword)
+    }
+}
+
+/// 门户记录的在线状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnlineStatus {
+    // 门户是否认为本机已认证
+    pub online: bool,
+    pub ip: Option<String>,
+    pub account: Option<String>,
+    // 本次登录已用流量
+    pub used_bytes: Option<u64>,
+    // 本次登录在线时长
+    pub duration: Option<Duration>,
+    // 本机 HTTP 登录时使用的协议栈，不是通过本机 HTTP 登录时为 None
+    pub stack: Option<IpStack>,
+}
+
+impl OnlineStatus {
+    /// 解析门户首页（Dr.com）中的状态变量：uid 为账号，flow 为已用流量（KB），time 为在线时长（分钟）
+    pub fn parse(text: &str) -> Self {
+        let number = |name: &str| js_var(text, name).and_then(|value| value.parse::<u64>().ok());
+        let account = js_var(text, "uid").filter(|account| !account.is_empty());
+        Self {
+            online: account.is_some(),
+            ip: AuthClient::extract_ip(text),
+            account,
+            used_bytes: number("flow").map(|kb| kb * 1024),
+            duration: number("time").map(|minutes| Duration::from_secs(minutes * 60)),
+            stack: None,
+        }
+    }
+
+    /// 状态栏中显示的单行说明
+    pub fn summary(&self) -> String {
+        if !self.online {
+            return "Not signed in".to_string();
+        }
+        let mut parts = vec![format!("Signed in as {}", self.account.as_deref().unwrap_or("unknown"))];
+        if let Some(stack) = self.stack {
+            parts.push(format!("via {}", stack.label()));
+        }
+        if let Some(bytes) = self.used_bytes {
+            parts.push(format!("{:.1} MB used", bytes as f64 / 1024.0 / 1024.0));
+        }
+        if let Some(duration) = self.duration {
+            let minutes = duration.as_secs() / 60;
+            parts.push(format!("online {}h {:02}m", minutes / 60, minutes % 60));
+        }
+        parts.join(", ")
+    }
+}
+
+// 用量接口中各数值可能的字段名，不同学校的 eportal 不完全相同；流量单位为 MB，余额单位为元。
+// 不使用 flow：门户首页的 flow 变量单位为 KB（见 OnlineStatus::parse），同名字段无法确定单位
+const USED_FIELDS: [&str; 2] = ["used_flow", "use_flow"];
+const REMAINING_FIELDS: [&str; 3] = ["remain_flow", "left_flow", "surplus_flow"];
+const BALANCE_FIELDS: [&str; 3] = ["balance", "fee", "money"];
+
+/// 流量的显示文字，1 GB 以上用 GB
+pub fn format_size(bytes: u64) -> String {
+    let mb = bytes as f64 / 1024.0 / 1024.0;
+    if mb >= 1024.0 { format!("{:.2} GB", mb / 1024.0) } else { format!("{:.1} MB", mb) }
+}
+
+/// 账号的流量和余额
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Usage {
+    // 本月已用流量
+    pub used_bytes: Option<u64>,
+    // 套餐剩余流量
+    pub remaining_bytes: Option<u64>,
+    // 账户余额（元）
+    pub balance: Option<f64>,
 }
 
-impl ISP {
-    fn as_str(&self) -> &'static str {
-        match self {
-            ISP::Unicom => "unicomn",
-            ISP::Mobile => "cmccn",
-            ISP::Telecom => "telecomn",
-            ISP::Campus => "",
+impl Usage {
+    /// 解析用量接口返回的 JSON：数据可能在 data 对象、data 数组的第一项或顶层，数值可能是数字或字符串
+    pub fn parse(value: &serde_json::Value) -> Self {
+        let data = match value.get("data") {
+            Some(serde_json::Value::Array(items)) => items.first().unwrap_or(value),
+            Some(data @ serde_json::Value::Object(_)) => data,
+            _ => value,
+        };
+        let number = |fields: &[&str]| fields.iter().find_map(|field| match data.get(field)? {
+            serde_json::Value::Number(number) => number.as_f64(),
+            serde_json::Value::String(text) => text.trim().parse::<f64>().ok(),
+            _ => None,
+        });
+        let bytes = |mb: f64| (mb.max(0.0) * 1024.0 * 1024.0) as u64;
+        Self {
+            used_bytes: number(&USED_FIELDS).map(bytes),
+            remaining_bytes: number(&REMAINING_FIELDS).map(bytes),
+            balance: number(&BALANCE_FIELDS),
         }
     }
+
+    /// 门户没有返回任何用量数据
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// 用量卡片中每行的名称和数值
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        [
+            ("Used this month", self.used_bytes.map(format_size)),
+            ("Remaining", self.remaining_bytes.map(format_size)),
+            ("Balance", self.balance.map(|yuan| format!("¥{:.2}", yuan))),
+        ]
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?)))
+            .collect()
+    }
+}
+
+// 读取页面脚本中形如 name='value' 的变量
+pub fn js_var(text: &str, name: &str) -> Option<String> {
+    let pattern = format!("{}='", name);
+    text.match_indices(&pattern)
+        .find(|(index, _)| {
+            // 排除 xuid= 之类名字只是后缀相同的变量
+            !text[..*index].chars().next_back().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+        .and_then(|(index, _)| text[index + pattern.len()..].split('\'').next())
+        .map(|value| value.trim().to_string())
+}
+
+pub use crate::backend::isp::ISP;
+
+// Dr.COM 门户首页的特征：Dr.COM 字样、eportal 接口路径，以及记录本机地址的 v46ip、v4serip 变量
+const DRCOM_MARKERS: [&str; 4] = ["dr.com", "/eportal/", "v46ip=", "v4serip="];
+
+// 请求失败的错误转为 anyhow，保留能识别的故障
+fn into_anyhow(error: Box<dyn Error>) -> anyhow::Error {
+    match triage::diagnose(error.as_ref()) {
+        Some(failure) => anyhow::Error::new(failure),
+        None => anyhow::anyhow!(error.to_string()),
+    }
 }
 
 /// 认证客户端结构
 pub struct AuthClient {
     client: Client,
     base_url: String,
+    ip_url: String,
     username: String,
-    password: String,
+    password: SecretString,
     isp: ISP,
+    bypass_proxy: bool,
+    // 双栈门户上 HTTP 登录依次尝试的协议栈
+    stack_preference: StackPreference,
+    recorder: Option<Mutex<Recorder>>,
+    // 请求使用的并发和频率限制，默认为所有门户请求共用的限制器
+    limiter: &'static RateLimiter,
+    // 上次 HTTP 登录的会话，以及保存会话的文件（回放测试中为 None）
+    session: Mutex<PortalSession>,
+    session_file: Option<PathBuf>,
 }
 
 impl AuthClient {
     /// 创建新的认证客户端实例
-    pub fn new(username: String, password: String, isp: ISP) -> Self {
+    pub fn new(username: String, password: SecretString, isp: ISP) -> Self {
+        // 设置了录制环境变量时记录所有交互，账号密码脱敏后写入文件
+        let recorder = Recorder::from_env().map(|mut recorder| {
+            recorder.redact(&username, "test_user");
+            recorder.redact(password.expose(), "test_pass");
+            Mutex::new(recorder)
+        });
+
+        let session_file = PortalSession::default_path();
         Self {
-            client: Client::builder()
-                .danger_accept_invalid_certs(true)  // 接受无效证书
-                .build()
-                .unwrap_or_else(|_| Client::new()),
+            client: Self::build_client(false, None),
             base_url: "https://portal.csu.edu.cn:802/eportal/portal".to_string(),
+            ip_url: "http://10.1.1.1".to_string(),
             username,
             password,
             isp,
+            bypass_proxy: false,
+            stack_preference: StackPreference::default(),
+            recorder,
+            limiter: rate_limit::portal_limiter(),
+            session: Mutex::new(PortalSession::load(&session_file).unwrap_or_default()),
+            session_file: Some(session_file),
+        }
+    }
+
+    /// 按配置中的账号、运营商、代理和协议栈设置创建
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.username.clone(), config.password.clone(), config.isp)
+            .with_proxy_bypass(config.bypass_proxy_for_portal)
+            .with_stack_preference(config.stack_preference)
+    }
+
+    /// 指定门户地址和获取IP的地址（用于回放测试），不读写会话文件
+    #[cfg(test)]
+    pub fn with_endpoints(mut self, base_url: &str, ip_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self.ip_url = ip_url.to_string();
+        self.session = Mutex::new(PortalSession::default());
+        self.session_file = None;
+        self
+    }
+
+    /// 使用单独的限制器（用于测试），不受其他测试修改的共用限制器影响
+    #[cfg(test)]
+    pub fn with_limiter(mut self, limiter: &'static RateLimiter) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// 设置访问门户时是否绕过系统代理
+    pub fn with_proxy_bypass(mut self, bypass: bool) -> Self {
+        self.client = Self::build_client(bypass, None);
+        self.bypass_proxy = bypass;
+        self
+    }
+
+    /// 设置双栈门户上登录使用的协议栈顺序
+    pub fn with_stack_preference(mut self, preference: StackPreference) -> Self {
+        self.stack_preference = preference;
+        self
+    }
+
+    // 创建 HTTP 客户端，指定本地地址时只通过对应的协议栈连接
+    fn build_client(bypass_proxy: bool, local_address: Option<IpAddr>) -> Client {
+        let mut builder = Client::builder()
+            .danger_accept_invalid_certs(true)  // 接受无效证书
+            .local_address(local_address);
+        if bypass_proxy {
+            builder = builder.no_proxy();
+        }
+        builder.build().unwrap_or_else(|_| Client::new())
+    }
+
+    // 发送 GET 请求并返回响应文本，录制模式下同时记录本次交互；请求带上会话 Cookie，并记录门户下发的 Cookie
+    async fn fetch(&self, request: reqwest::RequestBuilder) -> Result<String, Box<dyn Error>> {
+        // 使用创建请求的客户端发送，登录时各协议栈的客户端不同
+        let (client, request) = request.build_split();
+        let mut request = request?;
+        let host = request.url().host_str().unwrap_or_default().to_string();
+        if let Some(cookie) = self.session.lock().cookie_header(&host) {
+            request.headers_mut().insert(COOKIE, cookie.parse()?);
+        }
+
+        // 所有门户请求共用并发和频率限制
+        let _permit = self.limiter.acquire().await;
+        let response = client.execute(request).await.map_err(|e| -> Box<dyn Error> {
+            // 连接失败或超时时多半是网线未接或没有连上校园网
+            if e.is_connect() || e.is_timeout() {
+                Box::new(Failure::PortalUnreachable(e.to_string()))
+            } else {
+                Box::new(e)
+            }
+        })?;
+        {
+            let mut session = self.session.lock();
+            for set_cookie in response.headers().get_all(SET_COOKIE) {
+                if let Ok(set_cookie) = set_cookie.to_str() {
+                    session.store_cookie(&host, set_cookie);
+                }
+            }
+        }
+        let url = response.url().clone();
+        let status = response.status().as_u16();
+        let text = response.text().await?;
+
+        if let Some(recorder) = &self.recorder {
+            if let Err(e) = recorder.lock().record(Interaction::new("GET", &url, status, &text)) {
+                log::warn!("Failed to record cassette: {}", e);
+            }
+        }
+
+        // 门户维护或服务器错误时返回 PortalState 错误，便于调用方识别
+        match maintenance::classify(status, &text) {
+            PortalState::Healthy => Ok(text),
+            state => Err(Box::new(state)),
+        }
+    }
+
+    /// 轻量检查门户状态，网络不可达时返回错误
+    pub async fn check_portal(&self) -> Result<PortalState, Box<dyn Error>> {
+        let request = self.client
+            .get(&self.ip_url)
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0");
+
+        match self.fetch(request).await {
+            Ok(_) => Ok(PortalState::Healthy),
+            Err(e) => e.downcast::<PortalState>().map(|state| *state),
         }
     }
 
@@ -79,78 +362,437 @@ impl AuthClient {
         None
     }
 
+    // 门户页面中指定协议栈的本机地址：双栈时 v4ip、v6ip 分别记录两个地址，否则只有门户看到的 v46ip
+    fn extract_ip_for(text: &str, stack: IpStack) -> Option<String> {
+        let name = match stack {
+            IpStack::V4 => "v4ip",
+            IpStack::V6 => "v6ip",
+        };
+        js_var(text, name)
+            .into_iter()
+            .chain(Self::extract_ip(text))
+            .find(|ip| IpStack::of(ip) == Some(stack))
+    }
+
+    /// 获取门户首页，用于识别门户类型
+    pub async fn portal_page(&self) -> Result<String, Box<dyn Error>> {
+        let request = self.client
+            .get(&self.ip_url)
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0");
+        self.fetch(request).await
+    }
+
     /// 获取IP地址
     pub async fn get_ip(&self) -> Result<String, Box<dyn Error>> {
-        let response = self.client
-            .get("http://10.1.1.1")
-            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0")
-            .send()
-            .await?;
+        let request = self.client
+            .get(&self.ip_url)
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0");
             
-        let text = response.text().await?;
+        let text = self.fetch(request).await?;
         
         if let Some(ip) = Self::extract_ip(&text) {
+            if let Some(recorder) = &self.recorder {
+                recorder.lock().redact(&ip, "10.0.0.1");
+            }
             Ok(ip)
         } else {
             Err("无法获取IP地址".into())
         }
     }
 
-    /// 执行登录请求
+    /// 查询门户记录的在线状态，区分"已认证"和"只是能 ping 通"
+    pub async fn query_status(&self) -> Result<OnlineStatus, Box<dyn Error>> {
+        let request = self.client
+            .get(&self.ip_url)
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0");
+
+        let text = self.fetch(request).await?;
+        let mut status = OnlineStatus::parse(&text);
+        // 门户已不认为本机在线，保存的会话失效；仍在线时按登录时的地址报告协议栈
+        if status.online {
+            status.stack = IpStack::of(&self.session.lock().ip);
+        } else {
+            self.clear_session();
+        }
+        Ok(status)
+    }
+
+    /// 查询账号本月已用流量、剩余流量和余额（eportal 的 loadUserInfo 接口），优先使用登录时保存的会话地址
+    pub async fn query_usage(&self) -> Result<Usage, Box<dyn Error>> {
+        let saved_ip = Some(self.session.lock().ip.clone()).filter(|ip| !ip.is_empty());
+        let ip = match saved_ip {
+            Some(ip) => ip,
+            None => self.get_ip().await?,
+        };
+        let callback = "dr1006";
+        let request = self.client
+            .get(format!("{}/custom/loadUserInfo", self.base_url))
+            .query(&[("callback", callback), ("wlan_user_ip", ip.as_str()), ("user_account", self.username.as_str())])
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0")
+            .header("Referer", "https://portal.csu.edu.cn/")
+            .header("Origin", "https://portal.csu.edu.cn");
+
+        let text = self.fetch(request).await?;
+        let value: serde_json::Value = serde_json::from_str(Self::strip_jsonp(&text, callback))?;
+        if value.get("result").and_then(serde_json::Value::as_i64).is_some_and(|result| result != 1) {
+            let msg = value.get("msg").and_then(serde_json::Value::as_str).unwrap_or("门户拒绝了用量查询");
+            return Err(msg.into());
+        }
+        let usage = Usage::parse(&value);
+        if usage.is_empty() {
+            return Err("门户没有返回流量和余额信息".into());
+        }
+        Ok(usage)
+    }
+
+    /// 执行登录请求，按设置的顺序依次尝试各协议栈；整个过程记录为 login span，结束时日志中输出耗时
+    #[tracing::instrument(name = "login", skip_all, fields(backend = "http"))]
     pub async fn login(&self) -> Result<AuthResponse, Box<dyn Error>> {
-        // 获取IP地址
-        let ip = self.get_ip().await?;
-        
+        let mut last_error = None;
+        for &stack in self.stack_preference.order() {
+            match self.login_on(stack).await {
+                Ok(response) => return Ok(response),
+                // 门户已经答复（包括拒绝登录）时不再换协议栈，只有连不上或没有该协议栈的地址时才换
+                Err(e) => {
+                    log::info!("{} login unavailable: {}", stack.label(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| "没有可用的协议栈".into()))
+    }
+
+    // 通过指定协议栈获取本机地址并登录
+    async fn login_on(&self, stack: IpStack) -> Result<AuthResponse, Box<dyn Error>> {
+        let client = Self::build_client(self.bypass_proxy, Some(stack.bind_address()));
+        let request = client
+            .get(&self.ip_url)
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0");
+        let page = self.fetch(request).await?;
+        let ip = Self::extract_ip_for(&page, stack)
+            .ok_or_else(|| format!("门户没有返回{}地址", stack.label()))?;
+        if let Some(recorder) = &self.recorder {
+            recorder.lock().redact(&ip, "10.0.0.1");
+        }
+        // 以 IPv6 登录时 wlan_user_ip 填写同时分配的 IPv4 地址（可能没有）
+        let (ipv4, ipv6) = match stack {
+            IpStack::V4 => (ip.clone(), None),
+            IpStack::V6 => (Self::extract_ip_for(&page, IpStack::V4).unwrap_or_default(), Some(ip.clone())),
+        };
+
         // 构造用户账号
-        let user_account = format!(",1,{}@{}", self.username, self.isp.as_str());
+        let user_account = format!(",1,{}@{}", self.username, self.isp.suffix());
         
         // 构造请求参数
-        let mut params = HashMap::new();
+        let mut params: HashMap<&str, &str> = HashMap::new();
         let callback = "dr1004".to_string();
         let login_method = "1".to_string();
         
         params.insert("callback", &callback);
         params.insert("login_method", &login_method);
         params.insert("user_account", &user_account);
-        params.insert("user_password", &self.password);
-        params.insert("wlan_user_ip", &ip);
+        params.insert("user_password", self.password.expose());
+        params.insert("wlan_user_ip", &ipv4);
+        if let Some(ipv6) = &ipv6 {
+            params.insert("wlan_user_ipv6", ipv6);
+        }
 
         // 发送请求
-        let response = self
-            .client
+        let request = client
             .get(&format!("{}/login", self.base_url))
             .query(&params)
             .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0")
             .header("Referer", "https://portal.csu.edu.cn/")
-            .header("Origin", "https://portal.csu.edu.cn")
-            .send()
-            .await?;
+            .header("Origin", "https://portal.csu.edu.cn");
 
         // 获取响应文本
-        let text = response.text().await?;
-        
-        // 解析JSONP响应
-        let json_str = text
-            .trim_start_matches("dr1004(")
-            .trim_end_matches(");");
-            
-        // 解析JSON
-        let auth_response: AuthResponse = serde_json::from_str(json_str)?;
+        let text = self.fetch(request).await?;
         
+        let response = Self::parse_jsonp(&text, &callback)?;
+        if response.is_success() {
+            self.save_session(&ip);
+        }
+        Ok(response)
+    }
+
+    /// 执行登出请求，优先复用登录时保存的会话地址；会话已被门户作废时重新获取地址再试一次
+    pub async fn logout(&self) -> Result<AuthResponse, Box<dyn Error>> {
+        let saved_ip = Some(self.session.lock().ip.clone()).filter(|ip| !ip.is_empty());
+        if let Some(ip) = saved_ip {
+            let response = self.logout_from(&ip).await?;
+            self.clear_session();
+            if response.is_success() {
+                return Ok(response);
+            }
+            log::info!("Saved portal session was rejected, logging out with the current address");
+        }
+
+        let ip = self.get_ip().await?;
+        let response = self.logout_from(&ip).await?;
+        if response.is_success() {
+            self.clear_session();
+        }
+        Ok(response)
+    }
+
+    // 保存登录成功的会话
+    fn save_session(&self, ip: &str) {
+        let mut session = self.session.lock();
+        session.mark_login(ip);
+        if let Some(path) = &self.session_file {
+            if let Err(e) = session.save(path) {
+                log::warn!("Failed to save portal session: {}", e);
+            }
+        }
+    }
+
+    // 清除会话，之后的请求重新获取地址
+    fn clear_session(&self) {
+        *self.session.lock() = PortalSession::default();
+        if let Some(path) = &self.session_file {
+            PortalSession::remove(path);
+        }
+    }
+
+    // 以指定地址发送登出请求
+    async fn logout_from(&self, ip: &str) -> Result<AuthResponse, Box<dyn Error>> {
+        let ip = ip.to_string();
+
+        // 构造请求参数
+        let mut params = HashMap::new();
+        let callback = "dr1005".to_string();
+        let login_method = "1".to_string();
+        let user_account = "drcom".to_string();
+        let user_password = "123".to_string();
+        let ac_logout = "1".to_string();
+
+        params.insert("callback", &callback);
+        params.insert("login_method", &login_method);
+        params.insert("user_account", &user_account);
+        params.insert("user_password", &user_password);
+        params.insert("ac_logout", &ac_logout);
+        params.insert("wlan_user_ip", &ip);
+
+        // 发送请求
+        let request = self
+            .client
+            .get(format!("{}/logout", self.base_url))
+            .query(&params)
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0")
+            .header("Referer", "https://portal.csu.edu.cn/")
+            .header("Origin", "https://portal.csu.edu.cn");
+
+        let text = self.fetch(request).await?;
+
+        Self::parse_jsonp(&text, &callback)
+    }
+
+    // 去掉 JSONP 的回调函数名和括号
+    fn strip_jsonp<'a>(text: &'a str, callback: &str) -> &'a str {
+        text
+            .trim()
+            .trim_start_matches(callback)
+            .trim_start_matches('(')
+            .trim_end_matches(';')
+            .trim_end_matches(')')
+    }
+
+    /// 解析 JSONP 响应
+    fn parse_jsonp(text: &str, callback: &str) -> Result<AuthResponse, Box<dyn Error>> {
+        let auth_response: AuthResponse = serde_json::from_str(Self::strip_jsonp(text, callback))?;
+
         Ok(auth_response)
     }
 
 }
 
+/// Dr.COM eportal 的 HTTP 实现
+impl PortalProvider for AuthClient {
+    fn portal_type(&self) -> PortalType {
+        PortalType::Drcom
+    }
+
+    fn login(&mut self) -> LocalBoxFuture<'_, anyhow::Result<String>> {
+        Box::pin(async move { AuthClient::login(self).await.map_err(into_anyhow)?.into_result() })
+    }
+
+    fn logout(&mut self) -> LocalBoxFuture<'_, anyhow::Result<String>> {
+        Box::pin(async move { AuthClient::logout(self).await.map_err(into_anyhow)?.into_result() })
+    }
+
+    fn status(&self) -> LocalBoxFuture<'_, anyhow::Result<OnlineStatus>> {
+        Box::pin(async move { self.query_status().await.map_err(into_anyhow) })
+    }
+
+    fn portal_state(&self) -> LocalBoxFuture<'_, anyhow::Result<PortalState>> {
+        Box::pin(async move { self.check_portal().await.map_err(into_anyhow) })
+    }
+
+    fn usage(&self) -> LocalBoxFuture<'_, anyhow::Result<Usage>> {
+        Box::pin(async move { self.query_usage().await.map_err(into_anyhow) })
+    }
+
+    fn detect(page: &str) -> bool {
+        let page = page.to_lowercase();
+        DRCOM_MARKERS.iter().any(|marker| page.contains(marker))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::cassette::{replay, Cassette, Interaction};
+    use std::sync::OnceLock;
     use tokio;
+
+    #[test]
+    fn test_parse_jsonp() {
+        let response = AuthClient::parse_jsonp("dr1004({\"result\":1,\"msg\":\"Portal协议认证成功！\"});", "dr1004").unwrap();
+        assert!(response.is_success());
+        assert_eq!(response.ret_code, 0);
+
+        let response = AuthClient::parse_jsonp("dr1005({\"result\":0,\"msg\":\"AC999\",\"ret_code\":2})", "dr1005").unwrap();
+        assert!(!response.is_success());
+        assert_eq!(response.ret_code, 2);
+    }
+
+    #[test]
+    fn test_parse_online_status() {
+        let page = "<script>time='200       ';flow='1048576   ';fsele=1;fee='0 ';xip=000.000.000.000;uid='8208190101';v46ip='10.96.12.34';</script>";
+        let status = OnlineStatus::parse(page);
+        assert!(status.online);
+        assert_eq!(status.account.as_deref(), Some("8208190101"));
+        assert_eq!(status.ip.as_deref(), Some("10.96.12.34"));
+        assert_eq!(status.used_bytes, Some(1024 * 1024 * 1024));
+        assert_eq!(status.duration, Some(Duration::from_secs(200 * 60)));
+        assert_eq!(status.summary(), "Signed in as 8208190101, 1024.0 MB used, online 3h 20m");
+
+        let status = OnlineStatus::parse("<script>xuid='1';uid='';v46ip='10.0.0.1';</script>");
+        assert!(!status.online);
+        assert_eq!(status.summary(), "Not signed in");
+    }
+
+    #[test]
+    fn test_parse_usage() {
+        let text = r#"dr1006({"result":1,"data":[{"used_flow":"2560.5","remain_flow":512,"balance":"12.3"}]})"#;
+        let value: serde_json::Value = serde_json::from_str(AuthClient::strip_jsonp(text, "dr1006")).unwrap();
+        let usage = Usage::parse(&value);
+        assert_eq!(usage.used_bytes, Some((2560.5 * 1024.0 * 1024.0) as u64));
+        assert_eq!(usage.remaining_bytes, Some(512 * 1024 * 1024));
+        assert_eq!(usage.rows(), vec![
+            ("Used this month", "2.50 GB".to_string()),
+            ("Remaining", "512.0 MB".to_string()),
+            ("Balance", "¥12.30".to_string()),
+        ]);
+
+        // flow 的单位不确定（首页中为 KB），不当作 MB 的已用流量
+        let usage = Usage::parse(&serde_json::json!({"result": 1, "data": {"flow": "2621952", "fee": 5}}));
+        assert_eq!(usage.used_bytes, None);
+        assert_eq!(usage.rows(), vec![("Balance", "¥5.00".to_string())]);
+        assert!(Usage::parse(&serde_json::json!({"result": 0, "msg": "error"})).is_empty());
+    }
+
+    // 用录制的门户响应离线回放
+    async fn replay_client(fixture: &str) -> AuthClient {
+        // 回放时不需要请求间隔
+        static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+        let limiter = LIMITER.get_or_init(|| RateLimiter::new(rate_limit::DEFAULT_MAX_CONCURRENT, std::time::Duration::ZERO));
+        let cassette: Cassette = serde_json::from_str(fixture).unwrap();
+        let base_url = replay::serve(cassette).await;
+        AuthClient::new("test_user".to_string(), "test_pass".into(), ISP::Mobile)
+            .with_proxy_bypass(true)
+            .with_endpoints(&format!("{}/eportal/portal", base_url), &base_url)
+            .with_limiter(limiter)
+    }
+
+    #[tokio::test]
+    async fn test_replay_login_success() {
+        let client = replay_client(include_str!("../../fixtures/cassettes/csu_login_success.json")).await;
+        assert_eq!(client.get_ip().await.unwrap(), "10.0.0.1");
+
+        let client = replay_client(include_str!("../../fixtures/cassettes/csu_login_success.json")).await;
+        let response = client.login().await.unwrap();
+        assert!(response.is_success());
+        assert_eq!(client.session.lock().ip, "10.0.0.1");
+
+        // 登录前的门户首页没有账号信息
+        let client = replay_client(include_str!("../../fixtures/cassettes/csu_login_success.json")).await;
+        let status = client.query_status().await.unwrap();
+        assert!(!status.online);
+        assert_eq!(status.ip.as_deref(), Some("10.0.0.1"));
+    }
+
+    #[tokio::test]
+    async fn test_login_stack_fallback() {
+        // 回放服务器只监听 IPv4，优先 IPv6 时先失败再改用 IPv4
+        let client = replay_client(include_str!("../../fixtures/cassettes/csu_login_success.json")).await
+            .with_stack_preference(StackPreference::V6First);
+        assert!(client.login().await.unwrap().is_success());
+        assert_eq!(IpStack::of(&client.session.lock().ip), Some(IpStack::V4));
+
+        let client = replay_client(include_str!("../../fixtures/cassettes/csu_login_success.json")).await
+            .with_stack_preference(StackPreference::V6Only);
+        assert!(client.login().await.is_err());
+
+        let page = "<script>v46ip='10.96.12.34';v4ip='10.96.12.34';v6ip='2001:250:4400::1234';</script>";
+        assert_eq!(AuthClient::extract_ip_for(page, IpStack::V6).as_deref(), Some("2001:250:4400::1234"));
+        assert_eq!(AuthClient::extract_ip_for("<script>v46ip='10.0.0.1';</script>", IpStack::V6), None);
+    }
+
+    #[tokio::test]
+    async fn test_replay_login_wrong_password() {
+        let client = replay_client(include_str!("../../fixtures/cassettes/csu_login_wrong_password.json")).await;
+        let response = client.login().await.unwrap();
+        assert!(!response.is_success());
+        assert_eq!(response.ret_code, 1);
+        assert_eq!(response.failure(), Some(Failure::WrongPassword));
+    }
+
+    #[tokio::test]
+    async fn test_replay_logout() {
+        let client = replay_client(include_str!("../../fixtures/cassettes/csu_logout.json")).await;
+        let response = client.logout().await.unwrap();
+        assert!(response.is_success());
+    }
+
+    #[tokio::test]
+    async fn test_logout_with_stale_session() {
+        // 保存的会话已被门户作废：以旧地址登出被拒绝后，重新获取地址再登出
+        let mut cassette: Cassette = serde_json::from_str(include_str!("../../fixtures/cassettes/csu_logout.json")).unwrap();
+        let mut rejected = cassette.interactions[1].clone();
+        rejected.body = "dr1005({\"result\":0,\"msg\":\"\"});".to_string();
+        cassette.interactions.insert(0, rejected);
+
+        let client = replay_client(&serde_json::to_string(&cassette).unwrap()).await;
+        client.session.lock().mark_login("10.0.0.99");
+        let response = client.logout().await.unwrap();
+        assert!(response.is_success());
+        assert_eq!(*client.session.lock(), PortalSession::default());
+    }
+
+    #[tokio::test]
+    async fn test_check_portal_maintenance() {
+        let cassette = Cassette {
+            interactions: vec![Interaction {
+                method: "GET".to_string(),
+                path: "/".to_string(),
+                query: Vec::new(),
+                status: 503,
+                body: "<h1>系统维护中</h1>".to_string(),
+            }],
+        };
+        let client = replay_client(&serde_json::to_string(&cassette).unwrap()).await;
+        assert_eq!(client.check_portal().await.unwrap(), PortalState::MaintenancePage);
+
+        // 回放记录已用完，返回 404 视为门户正常响应
+        assert_eq!(client.check_portal().await.unwrap(), PortalState::Healthy);
+    }
+
     #[tokio::test]
     async fn test_auth_flow() {
         let client = AuthClient::new(
             "1234567890".to_string(),
-            "1234567890".to_string(),
+            "1234567890".into(),
             ISP::Unicom,
         );
         match client.login().await {
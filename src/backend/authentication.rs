@@ -4,23 +4,254 @@ use tokio::runtime::Runtime;
 use std::process::Command;
 use std::process::Stdio;
 use thirtyfour::prelude::*;
+use thirtyfour::extensions::cdp::ChromeDevTools;
 use anyhow::{Result, anyhow};
+use futures_util::future::LocalBoxFuture;
 use log::info;
-use crate::backend::config::{Config, ISP};
-use crate::backend::network_monitor::NetworkMonitor;
+use crate::backend::auth::{AuthClient, OnlineStatus};
+use crate::backend::browser;
+use crate::backend::config::{BrowserKind, Config};
+use crate::backend::maintenance::PortalState;
+use crate::backend::portal::{BrowserPortal, PortalProvider, PortalType};
+use crate::backend::triage::Failure;
+use crate::backend::system_info;
+
+// 等待 ChromeDriver 或 msedgedriver 就绪的最长时间和查询间隔
+const DRIVER_READY_TIMEOUT: Duration = Duration::from_secs(10);
+const DRIVER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// 预热会话点击登录后等待跳转的时间，页面已在浏览器中加载过，比完整登录短
+const WARM_LOGIN_SETTLE: Duration = Duration::from_secs(1);
+
+// 登录页关键元素的选择器，{form} 为登录表单所在标签页的序号（教师、学生、访客等，从 1 开始）
+const USERNAME_SELECTOR: &str = "#login-box > div > div.mt_body > div:nth-child({form}) > div > form > input:nth-child(2)";
+const PASSWORD_SELECTOR: &str = "#login-box > div > div.mt_body > div:nth-child({form}) > div > form > input:nth-child(3)";
+const ISP_SELECT_XPATH: &str = "//*[@id='login-box']/div/div[3]/div[{form}]/div/select";
+const LOGIN_BUTTON_SELECTOR: &str = "#login-box > div > div.mt_body > div:nth-child({form}) > div > form > input.edit_lobo_cell.sms_login";
+
+// 登录页结构指纹中需要检查的元素（名称，选择器）
+const EXPECTED_ELEMENTS: [(&str, &str); 4] = [
+    ("username", USERNAME_SELECTOR),
+    ("password", PASSWORD_SELECTOR),
+    ("isp", ISP_SELECT_XPATH),
+    ("login_button", LOGIN_BUTTON_SELECTOR),
+];
+
+// 切换到第 arguments[0] 个登录表单（从 0 开始）并返回表单总数：点击与表单一一对应的标签，
+// 找不到标签栏时直接显示目标表单、隐藏其他表单
+const ACTIVATE_FORM_SCRIPT: &str = r#"
+    var index = arguments[0];
+    var forms = document.querySelectorAll('#login-box > div > div.mt_body > div');
+    if (forms.length < 2 || index >= forms.length) { return forms.length; }
+    var target = forms[index];
+    if (target.offsetParent === null) {
+        var candidates = document.querySelectorAll('#login-box *');
+        for (var i = 0; i < candidates.length; i++) {
+            var bar = candidates[i];
+            if (bar.children.length === forms.length && !bar.querySelector('form, input')) {
+                bar.children[index].click();
+                break;
+            }
+        }
+    }
+    if (target.offsetParent === null) {
+        for (var j = 0; j < forms.length; j++) {
+            forms[j].style.display = j === index ? 'block' : 'none';
+        }
+    }
+    return forms.length;
+"#;
+
+// 收集登录页结构信息的脚本，以 '/' 开头的选择器按 XPath 处理
+const FINGERPRINT_SCRIPT: &str = r#"
+    var selectors = arguments[0];
+    var present = selectors.map(function (s) {
+        if (s.charAt(0) === '/') {
+            return document.evaluate(s, document, null, XPathResult.FIRST_ORDERED_NODE_TYPE, null).singleNodeValue !== null;
+        }
+        return document.querySelector(s) !== null;
+    });
+    var fields = Array.prototype.map.call(
+        document.querySelectorAll('#login-box input, #login-box select'),
+        function (e) { return e.name || e.id || e.type; }
+    );
+    return { present: present, fields: fields };
+"#;
+
+// 在每个页面加载前注入，记录控制台错误、脚本异常和失败的网络请求
+const DIAGNOSTICS_HOOK_SCRIPT: &str = r#"
+    (function () {
+        var log = window.__csuDiagnostics = [];
+        function push(kind, text) {
+            if (log.length < 50) { log.push({ kind: kind, text: String(text) }); }
+        }
+        var consoleError = console.error;
+        console.error = function () {
+            push('console', Array.prototype.join.call(arguments, ' '));
+            return consoleError.apply(console, arguments);
+        };
+        window.addEventListener('error', function (e) {
+            var target = e.target;
+            if (target && target !== window && (target.src || target.href)) {
+                push('request', 'Failed to load ' + (target.src || target.href));
+            } else {
+                push('console', e.message + ' (' + e.filename + ':' + e.lineno + ')');
+            }
+        }, true);
+        window.addEventListener('unhandledrejection', function (e) {
+            push('console', 'Unhandled rejection: ' + e.reason);
+        });
+        var open = XMLHttpRequest.prototype.open;
+        XMLHttpRequest.prototype.open = function (method, url) {
+            this.addEventListener('loadend', function () {
+                if (this.status === 0 || this.status >= 400) {
+                    push('request', method + ' ' + url + ' -> ' + (this.status || 'network error'));
+                }
+            });
+            return open.apply(this, arguments);
+        };
+        if (window.fetch) {
+            var fetch = window.fetch;
+            window.fetch = function (input) {
+                var url = typeof input === 'string' ? input : input.url;
+                return fetch.apply(this, arguments).then(function (r) {
+                    if (!r.ok) { push('request', 'fetch ' + url + ' -> ' + r.status); }
+                    return r;
+                }, function (err) {
+                    push('request', 'fetch ' + url + ' -> ' + err);
+                    throw err;
+                });
+            };
+        }
+    })();
+"#;
+
+// 读取注入脚本记录的诊断信息
+const DIAGNOSTICS_COLLECT_SCRIPT: &str = "return window.__csuDiagnostics || [];";
+
+/// 登录页的控制台错误和失败的网络请求
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PageDiagnostics {
+    pub console_errors: Vec<String>,
+    pub failed_requests: Vec<String>,
+}
+
+impl PageDiagnostics {
+    /// 从采集脚本的返回值解析
+    fn from_json(value: &serde_json::Value) -> Self {
+        let mut diagnostics = Self::default();
+        for entry in value.as_array().into_iter().flatten() {
+            let text = entry["text"].as_str().unwrap_or_default().to_string();
+            match entry["kind"].as_str() {
+                Some("request") => diagnostics.failed_requests.push(text),
+                _ => diagnostics.console_errors.push(text),
+            }
+        }
+        diagnostics
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.console_errors.is_empty() && self.failed_requests.is_empty()
+    }
+
+    /// 附加在错误信息后的简短说明
+    pub fn summary(&self) -> String {
+        format!(
+            "portal reported {} console error(s), {} failed request(s)",
+            self.console_errors.len(),
+            self.failed_requests.len()
+        )
+    }
+
+    /// 把简短说明附加到错误上，显示的信息为“原错误 (说明)”；作为上下文附加，错误链中的故障类型和
+    /// PortalLayoutChanged 仍能被识别
+    pub fn attach(&self, error: anyhow::Error) -> anyhow::Error {
+        let message = format!("{} ({})", error, self.summary());
+        error.context(message)
+    }
+
+    /// 完整的诊断报告
+    pub fn report(&self) -> String {
+        self.console_errors.iter()
+            .map(|e| format!("[console] {}", e))
+            .chain(self.failed_requests.iter().map(|r| format!("[request] {}", r)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// 登录页结构指纹：缺失的关键元素和表单字段名
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PortalFingerprint {
+    pub missing: Vec<String>,
+    pub fields: Vec<String>,
+}
+
+impl PortalFingerprint {
+    /// 从指纹脚本的返回值解析
+    fn from_json(value: &serde_json::Value) -> Self {
+        let present = value["present"].as_array().cloned().unwrap_or_default();
+        let missing = EXPECTED_ELEMENTS.iter()
+            .enumerate()
+            .filter(|(i, _)| !present.get(*i).and_then(|v| v.as_bool()).unwrap_or(false))
+            .map(|(_, (name, _))| name.to_string())
+            .collect();
+        let fields = value["fields"].as_array()
+            .map(|fields| fields.iter().filter_map(|f| f.as_str().map(String::from)).collect())
+            .unwrap_or_default();
+        Self { missing, fields }
+    }
+
+    /// 用于与上次记录比较的签名字符串
+    pub fn signature(&self) -> String {
+        format!("missing={};fields={}", self.missing.join(","), self.fields.join(","))
+    }
+}
+
+/// 登录页结构与预期不符，选择器需要更新
+#[derive(Debug)]
+pub struct PortalLayoutChanged {
+    pub missing: Vec<String>,
+}
+
+impl std::fmt::Display for PortalLayoutChanged {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Portal layout changed — selectors may need updating (missing: {})", self.missing.join(", "))
+    }
+}
+
+impl std::error::Error for PortalLayoutChanged {}
+
+// 由系统分配一个空闲的本地端口；释放后交给 ChromeDriver 监听，其间被占用的情况由启动失败时的检查报告
+fn free_port() -> std::io::Result<u16> {
+    Ok(std::net::TcpListener::bind(("127.0.0.1", 0))?.local_addr()?.port())
+}
+
+// ChromeDriver /status 的回复中 value.ready 为 true 表示可以创建会话
+fn driver_ready(status: &serde_json::Value) -> bool {
+    status["value"]["ready"].as_bool().unwrap_or(false)
+}
+
+// 填入登录表单序号（从 0 开始）后的选择器
+fn form_selector(template: &str, form_index: usize) -> String {
+    template.replace("{form}", &(form_index + 1).to_string())
+}
 
 /// 认证器状态结构体
 #[derive(Default)]
 struct DriverState {
     driver: Option<WebDriver>,
+    // ChromeDriver 或 msedgedriver 进程
     chromedriver_process: Option<std::process::Child>,
+    // 进程监听的端口，每次启动时随机选择空闲端口，避免与其他 Selenium 工具的 9515 冲突
+    port: u16,
 }
 
 /// 认证器结构体
 pub struct Authenticator {
     config: Arc<Config>,
     driver_state: DriverState,
-    network_monitor: NetworkMonitor,
+    last_fingerprint: Option<PortalFingerprint>,
+    last_diagnostics: Option<PageDiagnostics>,
 }
 
 impl Authenticator {
@@ -29,23 +260,75 @@ impl Authenticator {
         Self {
             config,
             driver_state: DriverState::default(),
-            network_monitor: NetworkMonitor::new(),
+            last_fingerprint: None,
+            last_diagnostics: None,
         }
     }
 
+    /// 最近一次登录时采集到的登录页指纹
+    pub fn last_fingerprint(&self) -> Option<&PortalFingerprint> {
+        self.last_fingerprint.as_ref()
+    }
+
+    /// 最近一次登录失败时采集到的页面诊断信息
+    pub fn last_diagnostics(&self) -> Option<&PageDiagnostics> {
+        self.last_diagnostics.as_ref()
+    }
+
+    /// 通过 CDP 注入诊断脚本，之后打开的每个页面都会记录错误
+    async fn install_diagnostics(driver: &WebDriver) -> Result<()> {
+        let dev_tools = ChromeDevTools::new(driver.handle.clone());
+        dev_tools.execute_cdp_with_params(
+            "Page.addScriptToEvaluateOnNewDocument",
+            serde_json::json!({ "source": DIAGNOSTICS_HOOK_SCRIPT }),
+        ).await?;
+        Ok(())
+    }
+
+    /// 采集当前页面记录的诊断信息
+    async fn collect_diagnostics(driver: &WebDriver) -> Result<PageDiagnostics> {
+        let ret = driver.execute(DIAGNOSTICS_COLLECT_SCRIPT, Vec::new()).await?;
+        Ok(PageDiagnostics::from_json(ret.json()))
+    }
+
+    /// 切换到配置的登录表单标签页，页面上没有这么多表单时报错
+    async fn activate_form(driver: &WebDriver, form_index: usize) -> Result<()> {
+        let ret = driver.execute(ACTIVATE_FORM_SCRIPT, vec![serde_json::json!(form_index)]).await?;
+        let count = ret.json().as_u64().unwrap_or(0) as usize;
+        // 没有找到任何表单时交给结构指纹报告
+        if count > 0 && form_index >= count {
+            return Err(anyhow!("Login form {} not found, the portal shows {} form(s)", form_index + 1, count));
+        }
+        if count > 1 {
+            info!("Using login form {} of {}", form_index + 1, count);
+        }
+        Ok(())
+    }
+
+    /// 采集当前页面的结构指纹
+    async fn fingerprint_page(driver: &WebDriver, form_index: usize) -> Result<PortalFingerprint> {
+        let selectors: Vec<String> = EXPECTED_ELEMENTS.iter().map(|(_, selector)| form_selector(selector, form_index)).collect();
+        let ret = driver.execute(FINGERPRINT_SCRIPT, vec![serde_json::json!(selectors)]).await?;
+        Ok(PortalFingerprint::from_json(ret.json()))
+    }
+
     /// 初始化认证器
     pub async fn init(&mut self) -> Result<()> {
-        // 检查 ChromeDriver 是否存在
+        // 检查 ChromeDriver 或 msedgedriver 是否存在
         let current_dir = std::env::current_dir()?;
-        let chromedriver_path = current_dir.join("chromedriver.exe");
+        let chromedriver_path = current_dir.join(self.config.browser.driver_file());
 
         if !chromedriver_path.exists() {
-            return Err(anyhow!("ChromeDriver not found at: {}", chromedriver_path.display()));
+            let path = chromedriver_path.display().to_string();
+            return Err(match self.config.browser {
+                BrowserKind::Chrome => Failure::ChromeDriverMissing(path),
+                BrowserKind::Edge => Failure::EdgeDriverMissing(path),
+            }.into());
         }
 
         // 尝试启动 ChromeDriver
-        if let Err(e) = self.start_chromedriver() {
-            return Err(anyhow!("Failed to start ChromeDriver: {}", e));
+        if let Err(e) = self.start_chromedriver().await {
+            return Err(anyhow!("Failed to start {}: {}", self.config.browser.driver_file(), e));
         }
 
         // 尝试创建 WebDriver
@@ -56,16 +339,35 @@ impl Authenticator {
             }
             Err(e) => {
                 // 如果创建 WebDriver 失败，确保关闭 ChromeDriver
+                let mut driver_exited = false;
                 if let Some(mut process) = self.driver_state.chromedriver_process.take() {
+                    driver_exited = matches!(process.try_wait(), Ok(Some(_)));
                     let _ = process.kill();
                 }
+                // ChromeDriver 启动后立即退出且端口仍被占用，说明端口被其他程序占用
+                let port = self.driver_state.port;
+                if driver_exited && std::net::TcpListener::bind(("127.0.0.1", port)).is_err() {
+                    let owner = system_info::port_owner(port).unwrap_or_else(|| "another program".to_string());
+                    return Err(Failure::DriverPortBusy { port, owner }.into());
+                }
+                if e.downcast_ref::<Failure>().is_some() {
+                    return Err(e);
+                }
                 Err(anyhow!("Failed to create WebDriver: {}", e))
             }
         }
     }
 
-    /// 启动 ChromeDriver
-    fn start_chromedriver(&mut self) -> Result<()> {
+    // 尚未启动浏览器时初始化，已经调用过 init() 时不再启动第二个 ChromeDriver
+    async fn ensure_initialized(&mut self) -> Result<()> {
+        if self.driver_state.driver.is_some() {
+            return Ok(());
+        }
+        self.init().await
+    }
+
+    /// 在空闲端口上启动 ChromeDriver，等待 /status 报告就绪
+    async fn start_chromedriver(&mut self) -> Result<()> {
         // 先检查 ChromeDriver 是否已在运行
         if let Some(p) = &mut self.driver_state.chromedriver_process {
             match p.try_wait() {
@@ -82,71 +384,109 @@ impl Authenticator {
         }
 
         let current_dir = std::env::current_dir()?;
-        let chromedriver_path = current_dir.join("chromedriver.exe");
+        let chromedriver_path = current_dir.join(self.config.browser.driver_file());
 
-        info!("Starting ChromeDriver...");
+        let port = free_port()?;
+        info!("Starting {} on port {}...", self.config.browser.driver_file(), port);
         let child = Command::new(chromedriver_path)
-            .arg("--port=9515")
+            .arg(format!("--port={}", port))
             .spawn()?;
 
         self.driver_state.chromedriver_process = Some(child);
-        
-        // 等待 ChromeDriver 启动
-        std::thread::sleep(Duration::from_secs(2));
-        
-        Ok(())
+        self.driver_state.port = port;
+        self.wait_for_driver().await
+    }
+
+    // 轮询 ChromeDriver 的 /status，就绪后立即返回，不再固定等待 2 秒；进程提前退出时交给 init 判断原因
+    async fn wait_for_driver(&mut self) -> Result<()> {
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .timeout(Duration::from_secs(1))
+            .build()?;
+        let url = format!("http://127.0.0.1:{}/status", self.driver_state.port);
+        let deadline = tokio::time::Instant::now() + DRIVER_READY_TIMEOUT;
+        loop {
+            if let Some(process) = &mut self.driver_state.chromedriver_process {
+                if let Ok(Some(status)) = process.try_wait() {
+                    return Err(anyhow!("{} exited with {}", self.config.browser.driver_file(), status));
+                }
+            }
+            if let Ok(response) = client.get(&url).send().await {
+                let status = response.text().await.ok().and_then(|body| serde_json::from_str(&body).ok());
+                if status.is_some_and(|status| driver_ready(&status)) {
+                    return Ok(());
+                }
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow!("{} did not become ready within {}s", self.config.browser.driver_file(), DRIVER_READY_TIMEOUT.as_secs()));
+            }
+            tokio::time::sleep(DRIVER_POLL_INTERVAL).await;
+        }
     }
 
     /// 创建 WebDriver
     async fn create_webdriver(&mut self) -> Result<WebDriver> {
-        let mut caps = DesiredCapabilities::chrome();
-        
-        // 配置 Chrome 选项
-        let chrome_args = vec![
+        // 配置浏览器选项
+        let mut args = vec![
             "--no-sandbox",
             "--disable-dev-shm-usage",
             "--ignore-certificate-errors",
         ];
 
-        for arg in chrome_args {
-            caps.add_chrome_arg(arg)?;
+        // 绕过系统代理直接访问认证页面
+        if self.config.bypass_proxy_for_portal {
+            args.push("--no-proxy-server");
         }
 
-        // 设置 Chrome 路径
-        let chrome_paths = vec![
-            r"C:\Program Files\Google\Chrome\Application\chrome.exe",
-            r"C:\Program Files (x86)\Google\Chrome\Application\chrome.exe",
-            "./chrome-win32/chrome.exe",  // 相对于当前目录的路径
-            "./chrome-win64/chrome.exe",  // 相对于当前目录的路径
-        ];
-
-        let mut chrome_found = false;
-        for path in chrome_paths {
-            if std::path::Path::new(path).exists() {
-                info!("Found Chrome at: {}", path);
-                caps.set_binary(path)?;
-                chrome_found = true;
-                break;
-            }
-        }
-
-        if !chrome_found {
-            return Err(anyhow!("Chrome browser not found. Please install Chrome or specify its location."));
+        // 无界面模式下没有窗口可最大化，按常见屏幕尺寸渲染页面
+        if self.config.headless_browser {
+            args.push("--headless=new");
+            args.push("--window-size=1920,1080");
+        } else {
+            args.push("--start-maximized");  // 最大化窗口
         }
 
         // 设置超时和其他选项
-        caps.add_chrome_arg("--start-maximized")?;  // 最大化窗口
-        caps.add_chrome_arg("--disable-extensions")?;  // 禁用扩展
-        caps.add_chrome_arg("--disable-popup-blocking")?;  // 禁用弹窗阻止
-        caps.add_chrome_arg("--disable-infobars")?;  // 禁用信息栏
+        args.push("--disable-extensions");  // 禁用扩展
+        args.push("--disable-popup-blocking");  // 禁用弹窗阻止
+        args.push("--disable-infobars");  // 禁用信息栏
+
+        let caps: thirtyfour::Capabilities = match self.config.browser {
+            BrowserKind::Chrome => {
+                let mut caps = DesiredCapabilities::chrome();
+                let chrome = browser::find_chrome(&std::env::current_dir()?).ok_or(Failure::ChromeMissing)?;
+                info!("Found Chrome at: {}", chrome.display());
+                caps.set_binary(&chrome.display().to_string())?;
+                for arg in args {
+                    caps.add_chrome_arg(arg)?;
+                }
+                caps.into()
+            }
+            // Edge 同样基于 Chromium，启动参数相同
+            BrowserKind::Edge => {
+                let edge = browser::find_edge().ok_or(Failure::EdgeMissing)?;
+                info!("Found Edge at: {}", edge.display());
+                let mut caps = DesiredCapabilities::edge();
+                caps.set_binary(&edge.display().to_string())?;
+                for arg in args {
+                    caps.add_arg(arg)?;
+                }
+                caps.into()
+            }
+        };
 
         info!("Creating WebDriver with configured capabilities...");
-        let driver = WebDriver::new("http://localhost:9515", caps).await?;
+        let driver = WebDriver::new(&format!("http://localhost:{}", self.driver_state.port), caps).await?;
         
         // 设置超时
         driver.set_page_load_timeout(Duration::from_secs(30)).await?;
         driver.set_script_timeout(Duration::from_secs(30)).await?;
         driver.set_implicit_wait_timeout(Duration::from_secs(10)).await?;
+
+        // 注入诊断脚本失败不影响登录
+        if let Err(e) = Self::install_diagnostics(&driver).await {
+            log::warn!("Failed to install page diagnostics: {}", e);
+        }
         
         Ok(driver)
     }
@@ -168,8 +508,85 @@ impl Authenticator {
     /// 运营商的xpath路径 //*[@id="login-box"]/div/div[3]/div[1]/div/select
     /// 运营商的值 移动“@cmccn” 联通“@unicomn” 电信“@telecomn” 校园网“”
     /// 登录按钮的js路径 document.querySelector("#login-box > div > div.mt_body > div:nth-child(1) > div > form > input.edit_lobo_cell.sms_login")
+    #[tracing::instrument(name = "login", skip_all, fields(backend = "browser"))]
     pub async fn login(&mut self) -> Result<()> {
+        let result = async {
+            self.ensure_initialized().await?;
+            self.submit_with_diagnostics(false).await
+        }.await;
+        self.close_session(result).await
+    }
+
+    // 一次性的登录或登出结束后关闭浏览器，失败时也关闭，不留下浏览器窗口和 ChromeDriver；
+    // 操作本身的错误优先于关闭时的错误返回
+    async fn close_session(&mut self, result: Result<()>) -> Result<()> {
+        let quit = self.quit().await;
+        if let (Err(_), Err(e)) = (&result, &quit) {
+            log::warn!("Failed to close the browser: {}", e);
+        }
+        result.and(quit)
+    }
+
+    /// 预热浏览器会话：启动 ChromeDriver 和浏览器并打开认证页面，会话仍可用时直接返回
+    pub async fn warm_up(&mut self) -> Result<()> {
+        if self.is_alive().await {
+            return Ok(());
+        }
+        // 浏览器被关闭或崩溃后清理残留进程再重新启动
+        self.quit().await?;
         self.init().await?;
+        self.open_auth_page().await
+    }
+
+    /// 浏览器会话是否仍可用
+    pub async fn is_alive(&self) -> bool {
+        match &self.driver_state.driver {
+            Some(driver) => driver.current_url().await.is_ok(),
+            None => false,
+        }
+    }
+
+    /// 用预热的会话登录，省去启动浏览器的时间；成功后保留会话供下次断线使用
+    #[tracing::instrument(name = "login", skip_all, fields(backend = "browser", warm = true))]
+    pub async fn login_warm(&mut self) -> Result<()> {
+        self.warm_up().await?;
+        self.submit_with_diagnostics(true).await
+    }
+
+    /// 更新预热会话使用的配置（如重新获取的凭据），浏览器相关设置变化时关闭会话，下次预热时按新设置启动
+    pub async fn update_config(&mut self, config: Arc<Config>) -> Result<()> {
+        let restart = config.browser != self.config.browser
+            || config.headless_browser != self.config.headless_browser
+            || config.bypass_proxy_for_portal != self.config.bypass_proxy_for_portal;
+        self.config = config;
+        if restart {
+            self.quit().await?;
+        }
+        Ok(())
+    }
+
+    // 提交登录表单，失败时附带页面诊断信息
+    async fn submit_with_diagnostics(&mut self, warm: bool) -> Result<()> {
+        self.last_diagnostics = None;
+
+        if let Err(e) = self.submit_login_form(warm).await {
+            // 登录失败时附带页面自身的脚本错误和失败请求，便于判断是否为门户问题
+            let diagnostics = match &self.driver_state.driver {
+                Some(driver) => Self::collect_diagnostics(driver).await.unwrap_or_default(),
+                None => PageDiagnostics::default(),
+            };
+            if diagnostics.is_empty() {
+                return Err(e);
+            }
+            let error = diagnostics.attach(e);
+            self.last_diagnostics = Some(diagnostics);
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    // 填写并提交登录表单；预热会话中不再固定等待，输入框出现后立即填写
+    async fn submit_login_form(&mut self, warm: bool) -> Result<()> {
         let driver = self.driver_state.driver.as_ref()
             .ok_or_else(|| anyhow!("WebDriver not initialized"))?;
         
@@ -177,24 +594,42 @@ impl Authenticator {
         info!("Filling login form...");
         
         // 等待页面加载完成
-        std::thread::sleep(Duration::from_secs(3));
+        if warm {
+            // 找不到输入框时交给下面的结构检查报告
+            let _ = driver.query(By::Css(form_selector(USERNAME_SELECTOR, self.config.form_index)))
+                .wait(Duration::from_secs(5), Duration::from_millis(100))
+                .first()
+                .await;
+        } else {
+            std::thread::sleep(Duration::from_secs(3));
+        }
+
+        // 检查登录页结构，避免在找不到元素时报出难以理解的错误
+        let form_index = self.config.form_index;
+        Self::activate_form(driver, form_index).await?;
+        let fingerprint = Self::fingerprint_page(driver, form_index).await?;
+        self.last_fingerprint = Some(fingerprint.clone());
+        if !fingerprint.missing.is_empty() {
+            return Err(PortalLayoutChanged { missing: fingerprint.missing }.into());
+        }
         
         // 输入用户名
-        let username_input = driver.query(By::Css("#login-box > div > div.mt_body > div:nth-child(1) > div > form > input:nth-child(2)"))
+        let username_input = driver.query(By::Css(form_selector(USERNAME_SELECTOR, form_index)))
             .wait(Duration::from_secs(10), Duration::from_millis(500))
             .first()
             .await?;
         username_input.send_keys(&self.config.username).await?;
         
         // 输入密码
-        let password_input = driver.query(By::Css("#login-box > div > div.mt_body > div:nth-child(1) > div > form > input:nth-child(3)"))
+        let password_input = driver.query(By::Css(form_selector(PASSWORD_SELECTOR, form_index)))
             .wait(Duration::from_secs(10), Duration::from_millis(500))
             .first()
             .await?;
-        password_input.send_keys(&self.config.password).await?;     
+        password_input.send_keys(self.config.password.expose()).await?;     
         
          // 使用 XPath 定位 <select> 元素
-        let isp_select = driver.query(By::XPath("//*[@id='login-box']/div/div[3]/div[1]/div/select"))
+        let isp_select_xpath = form_selector(ISP_SELECT_XPATH, form_index);
+        let isp_select = driver.query(By::XPath(&isp_select_xpath))
             .wait(Duration::from_secs(10), Duration::from_millis(500))
             .first()
             .await?;
@@ -203,22 +638,17 @@ impl Authenticator {
         isp_select.click().await?;
 
         // 根据配置选择目标 <option> 元素
-        let isp_value = match self.config.isp {
-            ISP::Mobile => "@cmccn",
-            ISP::Unicom => "@unicomn",
-            ISP::Telecom => "@telecomn",
-            ISP::School => "",
-        };
+        let isp_value = self.config.isp.portal_value();
 
         // 使用 XPath 定位目标 <option> 元素并点击
-        let target_option = driver.query(By::XPath(&format!("//*[@id='login-box']/div/div[3]/div[1]/div/select/option[@value='{}']", isp_value)))
+        let target_option = driver.query(By::XPath(format!("{}/option[@value='{}']", isp_select_xpath, isp_value)))
             .wait(Duration::from_secs(10), Duration::from_millis(500))
             .first()
             .await?;
         target_option.click().await?;
 
         // 点击登录按钮
-        let login_button = driver.query(By::Css("#login-box > div > div.mt_body > div:nth-child(1) > div > form > input.edit_lobo_cell.sms_login"))
+        let login_button = driver.query(By::Css(form_selector(LOGIN_BUTTON_SELECTOR, form_index)))
             .wait(Duration::from_secs(10), Duration::from_millis(500))
             .first()
             .await?;
@@ -227,7 +657,7 @@ impl Authenticator {
         info!("Login button clicked, waiting for network to be ready...");
         
         // 等待登录完成和网络就绪
-        std::thread::sleep(Duration::from_secs(3));
+        std::thread::sleep(if warm { WARM_LOGIN_SETTLE } else { Duration::from_secs(3) });
         
         // 检查登录是否成功
         if let Ok(current_url) = driver.current_url().await {
@@ -238,13 +668,18 @@ impl Authenticator {
             }
         }
         
-        self.quit().await?;
         Ok(())
     }
 
     /// 执行登出操作
     pub async fn logout(&mut self) -> Result<()> {
-        self.init().await?;
+        let result = self.click_logout().await;
+        self.close_session(result).await
+    }
+
+    // 打开认证页面并点击登出按钮和确认按钮
+    async fn click_logout(&mut self) -> Result<()> {
+        self.ensure_initialized().await?;
         // 循环两次才能登出
         for _ in 0..2 {
 
@@ -295,7 +730,6 @@ impl Authenticator {
         }
         // 等待登出完成
         std::thread::sleep(Duration::from_secs(3));
-        self.quit().await?;
         Ok(())
     }
 
@@ -315,6 +749,77 @@ impl Authenticator {
     }
 }
 
+/// Dr.COM eportal 的浏览器实现，在线状态仍通过 HTTP 查询
+impl PortalProvider for Authenticator {
+    fn portal_type(&self) -> PortalType {
+        PortalType::Drcom
+    }
+
+    fn prepare(&mut self) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(self.ensure_initialized())
+    }
+
+    fn login(&mut self) -> LocalBoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            Authenticator::login(self).await?;
+            Ok("Signed in through the browser".to_string())
+        })
+    }
+
+    fn logout(&mut self) -> LocalBoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            Authenticator::logout(self).await?;
+            Ok("Signed out through the browser".to_string())
+        })
+    }
+
+    fn status(&self) -> LocalBoxFuture<'_, Result<OnlineStatus>> {
+        Box::pin(async move { PortalProvider::status(&AuthClient::from_config(&self.config)).await })
+    }
+
+    fn portal_state(&self) -> LocalBoxFuture<'_, Result<PortalState>> {
+        Box::pin(async move { AuthClient::from_config(&self.config).portal_state().await })
+    }
+
+    fn detect(page: &str) -> bool {
+        AuthClient::detect(page)
+    }
+}
+
+impl BrowserPortal for Authenticator {
+    fn open_auth_page(&mut self) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(Authenticator::open_auth_page(self))
+    }
+
+    fn warm_up(&mut self) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(Authenticator::warm_up(self))
+    }
+
+    fn is_alive(&self) -> LocalBoxFuture<'_, bool> {
+        Box::pin(Authenticator::is_alive(self))
+    }
+
+    fn login_warm(&mut self) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(Authenticator::login_warm(self))
+    }
+
+    fn update_config(&mut self, config: Arc<Config>) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(Authenticator::update_config(self, config))
+    }
+
+    fn quit(&mut self) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(Authenticator::quit(self))
+    }
+
+    fn last_diagnostics(&self) -> Option<&PageDiagnostics> {
+        Authenticator::last_diagnostics(self)
+    }
+
+    fn last_fingerprint(&self) -> Option<&PortalFingerprint> {
+        Authenticator::last_fingerprint(self)
+    }
+}
+
 impl Drop for Authenticator {
     fn drop(&mut self) {
         if let Some(mut process) = self.driver_state.chromedriver_process.take() {
@@ -326,17 +831,19 @@ impl Drop for Authenticator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::config::ISP;
     use tokio;
 
     /// 创建测试配置
     fn create_test_config() -> Arc<Config> {
         Arc::new(Config {
             username: "test_user".to_string(),
-            password: "test_pass".to_string(),
+            password: "test_pass".into(),
             auth_url: "http://10.1.1.1".to_string(),
             isp: ISP::School,
             remember_password: true,
             auto_login: false,
+            ..Default::default()
         })
     }
 
@@ -346,6 +853,71 @@ mod tests {
         let auth = Authenticator::new(config);
         assert!(auth.driver_state.driver.is_none());
         assert!(auth.driver_state.chromedriver_process.is_none());
+        assert!(auth.last_fingerprint().is_none());
+    }
+
+    #[test]
+    fn test_portal_fingerprint_from_json() {
+        // 所有元素都存在
+        let value = serde_json::json!({
+            "present": [true, true, true, true],
+            "fields": ["DDDDD", "upass", "ISP_select"],
+        });
+        let fingerprint = PortalFingerprint::from_json(&value);
+        assert!(fingerprint.missing.is_empty());
+        assert_eq!(fingerprint.fields, vec!["DDDDD", "upass", "ISP_select"]);
+
+        // 密码框和登录按钮缺失
+        let value = serde_json::json!({
+            "present": [true, false, true, false],
+            "fields": ["DDDDD"],
+        });
+        let changed = PortalFingerprint::from_json(&value);
+        assert_eq!(changed.missing, vec!["password", "login_button"]);
+        assert_ne!(fingerprint.signature(), changed.signature());
+
+        let error = PortalLayoutChanged { missing: changed.missing };
+        assert!(error.to_string().contains("Portal layout changed"));
+        assert!(error.to_string().contains("password, login_button"));
+    }
+
+    #[test]
+    fn test_form_selector() {
+        assert_eq!(
+            form_selector(USERNAME_SELECTOR, 0),
+            "#login-box > div > div.mt_body > div:nth-child(1) > div > form > input:nth-child(2)"
+        );
+        assert_eq!(form_selector(ISP_SELECT_XPATH, 1), "//*[@id='login-box']/div/div[3]/div[2]/div/select");
+    }
+
+    #[test]
+    fn test_driver_ready() {
+        assert_ne!(free_port().unwrap(), 0);
+        assert!(driver_ready(&serde_json::json!({ "value": { "ready": true, "message": "ChromeDriver ready for new sessions." } })));
+        assert!(!driver_ready(&serde_json::json!({ "value": { "ready": false } })));
+        assert!(!driver_ready(&serde_json::Value::Null));
+    }
+
+    #[test]
+    fn test_page_diagnostics_from_json() {
+        let value = serde_json::json!([
+            { "kind": "console", "text": "Uncaught TypeError: $ is not a function (a41.js:12)" },
+            { "kind": "request", "text": "GET /eportal/portal/online_list -> 502" },
+        ]);
+        let diagnostics = PageDiagnostics::from_json(&value);
+        assert_eq!(diagnostics.console_errors.len(), 1);
+        assert_eq!(diagnostics.failed_requests, vec!["GET /eportal/portal/online_list -> 502"]);
+        assert_eq!(diagnostics.summary(), "portal reported 1 console error(s), 1 failed request(s)");
+        assert!(diagnostics.report().starts_with("[console] Uncaught TypeError"));
+
+        // 附加说明后仍能识别错误链中的故障
+        let error = diagnostics.attach(Failure::WrongPassword.into());
+        assert_eq!(error.to_string(), "Wrong username or password (portal reported 1 console error(s), 1 failed request(s))");
+        assert_eq!(crate::backend::triage::diagnose(error.as_ref()), Some(Failure::WrongPassword));
+        let error = diagnostics.attach(PortalLayoutChanged { missing: vec!["password".to_string()] }.into());
+        assert!(error.downcast_ref::<PortalLayoutChanged>().is_some());
+
+        assert!(PageDiagnostics::from_json(&serde_json::Value::Null).is_empty());
     }
 
     #[tokio::test]
@@ -385,6 +957,25 @@ mod tests {
         assert!(init_result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_warm_standby() {
+        let mut auth = Authenticator::new(create_test_config());
+        assert!(!auth.is_alive().await);
+
+        // 没有 ChromeDriver 时预热失败，不会留下会话
+        let result = auth.login_warm().await;
+        assert!(result.unwrap_err().to_string().contains("ChromeDriver not found"));
+        assert!(auth.driver_state.driver.is_none());
+
+        // 更新凭据不影响会话，切换浏览器时关闭会话
+        let config = Config { password: "new_pass".into(), ..(*create_test_config()).clone() };
+        auth.update_config(Arc::new(config)).await.unwrap();
+        assert_eq!(auth.config.password, "new_pass");
+        let config = Config { browser: BrowserKind::Edge, ..(*create_test_config()).clone() };
+        auth.update_config(Arc::new(config)).await.unwrap();
+        assert!(auth.driver_state.chromedriver_process.is_none());
+    }
+
     #[tokio::test]
     async fn test_logout_process() {
         let config = create_test_config();
@@ -0,0 +1,104 @@
+// 开机自启：在当前用户的 Run 注册表项中登记程序，登录 Windows 后自动启动，自动登录随即开始工作；其他系统交给桌面环境自己的自启设置
+use anyhow::Result;
+#[cfg(windows)]
+use anyhow::anyhow;
+#[cfg(any(windows, test))]
+use std::path::Path;
+#[cfg(windows)]
+use std::process::Command;
+
+/// 开机启动时附带的参数，程序据此切换到程序目录，并按设置最小化启动
+pub const AUTOSTART_ARG: &str = "--autostart";
+#[cfg(windows)]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+// Run 项中的名称
+#[cfg(any(windows, test))]
+const VALUE_NAME: &str = "CSUNetwork";
+
+/// 当前系统是否支持开机自启
+pub fn is_supported() -> bool {
+    cfg!(windows)
+}
+
+/// 写入 Run 项的命令行
+#[cfg(any(windows, test))]
+pub fn command_line(exe: &Path) -> String {
+    format!("\"{}\" {}", exe.display(), AUTOSTART_ARG)
+}
+
+// 登记的命令行，便携模式下带上 --portable，开机启动后仍使用程序目录下的配置
+#[cfg(windows)]
+fn registered_command(exe: &Path) -> String {
+    if crate::backend::paths::is_portable() {
+        format!("{} {}", command_line(exe), crate::backend::paths::PORTABLE_ARG)
+    } else {
+        command_line(exe)
+    }
+}
+
+/// 是否已登记为当前程序开机自启；程序移动过位置后登记的路径失效，视为未登记
+#[cfg(windows)]
+pub fn is_enabled() -> bool {
+    let Ok(exe) = std::env::current_exe() else { return false };
+    let Ok(output) = Command::new("reg").args(["query", RUN_KEY, "/v", VALUE_NAME]).output() else { return false };
+    output.status.success() && parse_registered(&String::from_utf8_lossy(&output.stdout)) == Some(registered_command(&exe))
+}
+
+#[cfg(not(windows))]
+pub fn is_enabled() -> bool {
+    false
+}
+
+/// 登记或取消开机自启
+#[cfg(windows)]
+pub fn set_enabled(enabled: bool) -> Result<()> {
+    let output = if enabled {
+        let command = registered_command(&std::env::current_exe()?);
+        Command::new("reg").args(["add", RUN_KEY, "/v", VALUE_NAME, "/t", "REG_SZ", "/d", &command, "/f"]).output()?
+    } else {
+        Command::new("reg").args(["delete", RUN_KEY, "/v", VALUE_NAME, "/f"]).output()?
+    };
+    if !output.status.success() {
+        return Err(anyhow!("reg exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn set_enabled(_enabled: bool) -> Result<()> {
+    anyhow::bail!("Launch at startup is only available on Windows, use your desktop's autostart settings instead")
+}
+
+/// 开机启动和服务的工作目录不是程序目录（通常是 System32），切换到程序所在目录以使用同一份配置和日志
+pub fn enter_program_dir() -> Result<()> {
+    let exe = std::env::current_exe()?;
+    if let Some(dir) = exe.parent() {
+        std::env::set_current_dir(dir)?;
+    }
+    Ok(())
+}
+
+// 从 reg query 的输出中取出登记的命令行，如 "    CSUNetwork    REG_SZ    \"C:\\sn.exe\" --autostart"
+#[cfg(any(windows, test))]
+fn parse_registered(output: &str) -> Option<String> {
+    output.lines()
+        .find_map(|line| line.trim_start().strip_prefix(VALUE_NAME))
+        .and_then(|rest| rest.trim_start().strip_prefix("REG_SZ"))
+        .map(|command| command.trim().to_string())
+        .filter(|command| !command.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_registered() {
+        let exe = Path::new(r"C:\Program Files\CSUNetwork\sn.exe");
+        let command = command_line(exe);
+        assert_eq!(command, r#""C:\Program Files\CSUNetwork\sn.exe" --autostart"#);
+        let output = format!("\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Run\r\n    CSUNetwork    REG_SZ    {}\r\n\r\n", command);
+        assert_eq!(parse_registered(&output), Some(command));
+        assert_eq!(parse_registered("ERROR: The system was unable to find the specified registry key or value.\r\n"), None);
+    }
+}
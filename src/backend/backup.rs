@@ -0,0 +1,175 @@
+// 配置云备份模块（WebDAV，客户端加密）
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, Context, Result};
+use log::info;
+use rand::RngCore;
+use sha2::Sha256;
+use std::time::Duration;
+use crate::backend::config::Config;
+
+// 远端备份文件名
+const BACKUP_FILE_NAME: &str = "csunetwork-backup.bin";
+// 备份文件头，用于识别格式版本
+const BACKUP_MAGIC: &[u8] = b"CSUB1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+// 口令派生密钥的迭代次数
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+/// WebDAV 备份客户端
+pub struct BackupClient {
+    client: reqwest::Client,
+    url: String,
+    username: String,
+    password: String,
+}
+
+impl BackupClient {
+    /// 创建新的备份客户端，url 为 WebDAV 目录地址
+    pub fn new(url: String, username: String, password: String) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new()),
+            url,
+            username,
+            password,
+        }
+    }
+
+    // 备份文件的完整地址
+    fn file_url(&self) -> String {
+        format!("{}/{}", self.url.trim_end_matches('/'), BACKUP_FILE_NAME)
+    }
+
+    /// 加密并上传配置
+    pub async fn upload(&self, config: &Config, passphrase: &str) -> Result<()> {
+        // 与本地保存一致：不记住密码时不备份密码
+        let mut config_to_backup = config.clone();
+        if !config.remember_password {
+            config_to_backup.password.clear();
+            config_to_backup.auto_login = false;
+        }
+
+        let plain = serde_json::to_vec(&config_to_backup)?;
+        let data = encrypt(&plain, passphrase)?;
+
+        let response = self.client
+            .put(self.file_url())
+            .basic_auth(&self.username, Some(&self.password))
+            .body(data)
+            .send()
+            .await
+            .context("上传备份失败")?;
+        if !response.status().is_success() {
+            return Err(anyhow!("上传备份失败，HTTP状态码: {}", response.status()));
+        }
+
+        info!("Configuration backup uploaded to {}", self.file_url());
+        Ok(())
+    }
+
+    /// 下载并解密配置
+    pub async fn download(&self, passphrase: &str) -> Result<Config> {
+        let response = self.client
+            .get(self.file_url())
+            .basic_auth(&self.username, Some(&self.password))
+            .send()
+            .await
+            .context("下载备份失败")?;
+        if !response.status().is_success() {
+            return Err(anyhow!("下载备份失败，HTTP状态码: {}", response.status()));
+        }
+
+        let data = response.bytes().await?;
+        let plain = decrypt(&data, passphrase)?;
+        let config = serde_json::from_slice(&plain).context("备份内容不是有效的配置")?;
+
+        info!("Configuration backup downloaded from {}", self.file_url());
+        Ok(config)
+    }
+}
+
+// 由口令和盐派生 256 位密钥
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+/// 使用口令加密数据，输出格式为 文件头 + 盐 + nonce + 密文
+pub fn encrypt(plain: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let key = derive_key(passphrase, &salt);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| anyhow!("无效的密钥长度"))?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plain)
+        .map_err(|_| anyhow!("加密备份失败"))?;
+
+    let mut data = Vec::with_capacity(BACKUP_MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    data.extend_from_slice(BACKUP_MAGIC);
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+/// 使用口令解密 encrypt() 生成的数据
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let header_len = BACKUP_MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if data.len() < header_len || !data.starts_with(BACKUP_MAGIC) {
+        return Err(anyhow!("不是有效的备份文件"));
+    }
+
+    let salt = &data[BACKUP_MAGIC.len()..BACKUP_MAGIC.len() + SALT_LEN];
+    let nonce = &data[BACKUP_MAGIC.len() + SALT_LEN..header_len];
+    let key = derive_key(passphrase, salt);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| anyhow!("无效的密钥长度"))?;
+    cipher
+        .decrypt(Nonce::from_slice(nonce), &data[header_len..])
+        .map_err(|_| anyhow!("解密备份失败，口令错误或文件已损坏"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let plain = b"{\"username\":\"test_user\"}";
+        let data = encrypt(plain, "correct horse").unwrap();
+        assert!(data.starts_with(BACKUP_MAGIC));
+        assert_ne!(&data[data.len() - plain.len()..], plain);
+
+        let decrypted = decrypt(&data, "correct horse").unwrap();
+        assert_eq!(decrypted, plain);
+    }
+
+    #[test]
+    fn test_decrypt_wrong_passphrase() {
+        let data = encrypt(b"secret", "correct horse").unwrap();
+        assert!(decrypt(&data, "wrong horse").is_err());
+    }
+
+    #[test]
+    fn test_decrypt_invalid_data() {
+        assert!(decrypt(b"not a backup", "passphrase").is_err());
+        assert!(decrypt(b"CSUB1", "passphrase").is_err());
+    }
+
+    #[test]
+    fn test_file_url() {
+        let client = BackupClient::new(
+            "https://dav.jianguoyun.com/dav/backup/".to_string(),
+            "user".to_string(),
+            "pass".to_string(),
+        );
+        assert_eq!(client.file_url(), "https://dav.jianguoyun.com/dav/backup/csunetwork-backup.bin");
+    }
+}
@@ -0,0 +1,136 @@
+// 使用系统默认浏览器打开认证门户（自动认证全部失败时的手动兜底），以及查找用于网页登录的 Chrome 和 Edge
+#[cfg(any(feature = "webdriver", feature = "downloader"))]
+use crate::backend::platform::Platform;
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+use std::process::Command;
+
+// Chrome 的默认安装位置
+#[cfg(any(feature = "webdriver", feature = "downloader"))]
+#[cfg(windows)]
+const CHROME_PATHS: &[&str] = &[
+    r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+    r"C:\Program Files (x86)\Google\Chrome\Application\chrome.exe",
+];
+#[cfg(any(feature = "webdriver", feature = "downloader"))]
+#[cfg(target_os = "macos")]
+const CHROME_PATHS: &[&str] = &["/Applications/Google Chrome.app/Contents/MacOS/Google Chrome"];
+#[cfg(any(feature = "webdriver", feature = "downloader"))]
+#[cfg(not(any(windows, target_os = "macos")))]
+const CHROME_PATHS: &[&str] = &["/opt/google/chrome/chrome", "/usr/bin/google-chrome-stable", "/usr/bin/google-chrome"];
+
+// Edge 的默认安装位置，新版安装在 Program Files (x86) 下
+#[cfg(any(feature = "webdriver", feature = "downloader"))]
+#[cfg(windows)]
+const EDGE_PATHS: &[&str] = &[
+    r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
+    r"C:\Program Files\Microsoft\Edge\Application\msedge.exe",
+];
+#[cfg(any(feature = "webdriver", feature = "downloader"))]
+#[cfg(target_os = "macos")]
+const EDGE_PATHS: &[&str] = &["/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge"];
+#[cfg(any(feature = "webdriver", feature = "downloader"))]
+#[cfg(not(any(windows, target_os = "macos")))]
+const EDGE_PATHS: &[&str] = &["/opt/microsoft/msedge/msedge", "/usr/bin/microsoft-edge-stable", "/usr/bin/microsoft-edge"];
+
+/// 构造门户地址，并把用户名放在 URL 片段中方便手动填写
+pub fn portal_url_with_username(auth_url: &str, username: &str) -> Result<String> {
+    let auth_url = auth_url.trim();
+    let mut url = if auth_url.contains("://") {
+        Url::parse(auth_url)?
+    } else {
+        Url::parse(&format!("http://{}", auth_url))?
+    };
+
+    if username.is_empty() {
+        url.set_fragment(None);
+    } else {
+        url.set_fragment(Some(&format!("username={}", username)));
+    }
+    Ok(url.to_string())
+}
+
+/// 登录成功后打开的页面地址，没有协议时默认使用 http；为空时返回 None，只允许 http 和 https
+pub fn landing_url(url: &str) -> Result<Option<String>> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Ok(None);
+    }
+    let url = if url.contains("://") { Url::parse(url)? } else { Url::parse(&format!("http://{}", url))? };
+    match url.scheme() {
+        "http" | "https" => Ok(Some(url.to_string())),
+        scheme => Err(anyhow!("Unsupported landing page scheme {}, use http or https", scheme)),
+    }
+}
+
+/// 查找 Chrome：优先使用已安装的 Chrome，其次是 dir 下下载的 Chrome for Testing
+#[cfg(any(feature = "webdriver", feature = "downloader"))]
+pub fn find_chrome(dir: &std::path::Path) -> Option<std::path::PathBuf> {
+    CHROME_PATHS.iter().map(std::path::PathBuf::from)
+        .chain(Platform::current().compatible().iter().map(|platform| dir.join(platform.chrome_dir()).join(platform.chrome_executable())))
+        .find(|path| path.exists())
+}
+
+/// 查找已安装的 Edge
+#[cfg(any(feature = "webdriver", feature = "downloader"))]
+pub fn find_edge() -> Option<std::path::PathBuf> {
+    EDGE_PATHS.iter().map(std::path::PathBuf::from).find(|path| path.exists())
+}
+
+/// 用系统默认浏览器打开地址
+pub fn open_in_default_browser(url: &str) -> Result<()> {
+    // Windows 下通过 rundll32 打开，避免 cmd 对 & 等字符的转义问题
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = Command::new("rundll32");
+        command.args(["url.dll,FileProtocolHandler", url]);
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg(url);
+        command
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut command = {
+        let mut command = Command::new("xdg-open");
+        command.arg(url);
+        command
+    };
+
+    command.spawn()
+        .map(|_| ())
+        .map_err(|e| anyhow!("Failed to open browser: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_portal_url_with_username() {
+        assert_eq!(
+            portal_url_with_username("http://10.1.1.1", "8208190101").unwrap(),
+            "http://10.1.1.1/#username=8208190101"
+        );
+        // 没有协议时默认使用 http，已有的片段会被替换
+        assert_eq!(
+            portal_url_with_username("portal.csu.edu.cn/a79.htm#old", "user name").unwrap(),
+            "http://portal.csu.edu.cn/a79.htm#username=user%20name"
+        );
+        assert_eq!(
+            portal_url_with_username("http://10.1.1.1/", "").unwrap(),
+            "http://10.1.1.1/"
+        );
+    }
+
+    #[test]
+    fn test_landing_url() {
+        assert_eq!(landing_url("  ").unwrap(), None);
+        assert_eq!(landing_url("speedtest.csu.edu.cn").unwrap().as_deref(), Some("http://speedtest.csu.edu.cn/"));
+        assert_eq!(landing_url("https://ecard.csu.edu.cn/self").unwrap().as_deref(), Some("https://ecard.csu.edu.cn/self"));
+        // 不打开本地文件或其他协议
+        assert!(landing_url("file:///C:/Windows/System32/calc.exe").is_err());
+    }
+}
@@ -0,0 +1,240 @@
+// HTTP 交互录制与回放模块，用于离线回归测试门户驱动
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 设置该环境变量为文件路径即开启录制模式
+pub const RECORD_ENV: &str = "CSUNETWORK_RECORD_CASSETTE";
+
+/// 一次 HTTP 请求及其响应
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Interaction {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub query: Vec<(String, String)>,
+    pub status: u16,
+    pub body: String,
+}
+
+impl Interaction {
+    /// 由请求地址和响应内容构造
+    pub fn new(method: &str, url: &reqwest::Url, status: u16, body: &str) -> Self {
+        Self {
+            method: method.to_string(),
+            path: url.path().to_string(),
+            query: url.query_pairs().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            status,
+            body: body.to_string(),
+        }
+    }
+}
+
+/// 录制的交互序列
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// 从文件加载
+    #[cfg(test)]
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// 保存到文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 将敏感内容（账号、密码、IP）替换为占位值
+    pub fn sanitize(&mut self, replacements: &[(String, String)]) {
+        let replace = |text: &str| {
+            replacements.iter()
+                .filter(|(secret, _)| !secret.is_empty())
+                .fold(text.to_string(), |text, (secret, placeholder)| text.replace(secret.as_str(), placeholder))
+        };
+
+        for interaction in &mut self.interactions {
+            interaction.body = replace(&interaction.body);
+            for (_, value) in &mut interaction.query {
+                *value = replace(value);
+            }
+        }
+    }
+}
+
+/// 录制器：每记录一次交互就将脱敏后的内容写入文件
+pub struct Recorder {
+    path: PathBuf,
+    cassette: Cassette,
+    replacements: Vec<(String, String)>,
+}
+
+impl Recorder {
+    /// 如果设置了录制环境变量则创建录制器
+    pub fn from_env() -> Option<Self> {
+        std::env::var(RECORD_ENV).ok()
+            .filter(|path| !path.is_empty())
+            .map(|path| Self::new(PathBuf::from(path)))
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            cassette: Cassette::default(),
+            replacements: Vec::new(),
+        }
+    }
+
+    /// 登记需要脱敏的内容
+    pub fn redact(&mut self, secret: &str, placeholder: &str) {
+        if !self.replacements.iter().any(|(s, _)| s == secret) {
+            self.replacements.push((secret.to_string(), placeholder.to_string()));
+        }
+    }
+
+    /// 记录一次交互并写入文件
+    pub fn record(&mut self, interaction: Interaction) -> Result<()> {
+        self.cassette.interactions.push(interaction);
+        let mut sanitized = self.cassette.clone();
+        sanitized.sanitize(&self.replacements);
+        sanitized.save(&self.path)
+    }
+}
+
+/// 回放服务器，按录制内容响应请求
+#[cfg(test)]
+pub mod replay {
+    use super::Cassette;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 启动回放服务器，返回其根地址（如 http://127.0.0.1:12345）
+    /// 每条交互只会被使用一次，按方法和路径依次匹配
+    pub async fn serve(cassette: Cassette) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind replay server");
+        let addr = listener.local_addr().expect("Failed to get replay server address");
+        let interactions = Arc::new(Mutex::new(cassette.interactions));
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let interactions = Arc::clone(&interactions);
+                tokio::spawn(async move {
+                    // 读取请求头
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 4096];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => request.extend_from_slice(&buf[..n]),
+                        }
+                    }
+
+                    let request = String::from_utf8_lossy(&request);
+                    let mut request_line = request.lines().next().unwrap_or_default().split_whitespace();
+                    let method = request_line.next().unwrap_or_default().to_string();
+                    let path = request_line.next().unwrap_or_default()
+                        .split('?').next().unwrap_or_default().to_string();
+
+                    let interaction = {
+                        let mut interactions = interactions.lock();
+                        interactions.iter()
+                            .position(|i| i.method == method && i.path == path)
+                            .map(|index| interactions.remove(index))
+                    };
+
+                    let (status, body) = match interaction {
+                        Some(interaction) => (interaction.status, interaction.body),
+                        None => (404, format!("No recorded interaction for {} {}", method, path)),
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {} Replay\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_cassette() -> Cassette {
+        Cassette {
+            interactions: vec![Interaction {
+                method: "GET".to_string(),
+                path: "/eportal/portal/login".to_string(),
+                query: vec![
+                    ("user_account".to_string(), ",1,8208190101@cmccn".to_string()),
+                    ("user_password".to_string(), "hunter2".to_string()),
+                    ("wlan_user_ip".to_string(), "10.96.12.34".to_string()),
+                ],
+                status: 200,
+                body: "dr1004({\"result\":1,\"msg\":\"ok 10.96.12.34\"});".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let mut cassette = sample_cassette();
+        cassette.sanitize(&[
+            ("8208190101".to_string(), "test_user".to_string()),
+            ("hunter2".to_string(), "test_pass".to_string()),
+            ("10.96.12.34".to_string(), "10.0.0.1".to_string()),
+        ]);
+
+        let interaction = &cassette.interactions[0];
+        assert_eq!(interaction.query[0].1, ",1,test_user@cmccn");
+        assert_eq!(interaction.query[1].1, "test_pass");
+        assert_eq!(interaction.query[2].1, "10.0.0.1");
+        assert!(interaction.body.contains("10.0.0.1"));
+        assert!(!interaction.body.contains("10.96.12.34"));
+    }
+
+    #[test]
+    fn test_recorder_writes_sanitized_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.json");
+
+        let mut recorder = Recorder::new(path.clone());
+        recorder.redact("hunter2", "test_pass");
+        recorder.record(sample_cassette().interactions.remove(0)).unwrap();
+
+        let saved = Cassette::load(&path).unwrap();
+        assert_eq!(saved.interactions.len(), 1);
+        assert_eq!(saved.interactions[0].query[1].1, "test_pass");
+        assert!(!fs::read_to_string(&path).unwrap().contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn test_replay_server() {
+        let base_url = replay::serve(sample_cassette()).await;
+        let client = reqwest::Client::new();
+
+        let response = client.get(format!("{}/eportal/portal/login?callback=dr1004", base_url)).send().await.unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.text().await.unwrap().contains("\"result\":1"));
+
+        // 每条交互只能使用一次
+        let response = client.get(format!("{}/eportal/portal/login", base_url)).send().await.unwrap();
+        assert_eq!(response.status().as_u16(), 404);
+    }
+}
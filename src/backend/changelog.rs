@@ -0,0 +1,91 @@
+// 内置更新日志，升级后在界面中展示一次
+/// 当前程序版本
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// 一个版本的更新说明
+pub struct Release {
+    pub version: &'static str,
+    pub notes: &'static [&'static str],
+}
+
+/// 按版本从新到旧排列
+pub const RELEASES: &[Release] = &[
+    Release {
+        version: "0.2.0",
+        notes: &[
+            "Command line: sn login, logout, status, daemon, logs and monitor --once with JSON output and exit codes",
+            "Runs as a Windows service or under systemd without the window, and can start with Windows",
+            "Multiple accounts with per-account probes and download mirrors, and CSV import for many accounts",
+            "Settings move to config.toml in the system config folder with a backup and recovery of damaged files",
+            "Passwords are kept in the system keyring, with DPAPI as a fallback on Windows",
+            "Local control API and web dashboard for scripts, Home Assistant or a phone, with an audit trail",
+            "Plain HTTP login and logout without Chrome, falling back to the browser only when needed",
+            "Portal plug-ins, including the Dr.COM web (0.htm) gateway and automatic portal discovery",
+            "Scheduled login and logout rules, semester and holiday presets, and time-zone aware logs",
+            "Session history with filters and CSV export, plus traffic and quota with day/night split",
+            "Webhook, MQTT and desktop notifications with quiet hours",
+            "Account lockout detection, portal maintenance detection and upstream outage hints",
+            "Captive portal and DNS hijack detection, VLAN changes and keep-alive heartbeats trigger re-login",
+            "Latency graph, loss statistics, nightly self-test, traceroute and Prometheus metrics",
+            "Reusable browser sessions with Edge support, headless mode and a warm standby that closes in the background",
+            "Chrome downloads with mirrors, progress, pinned SHA-256 checks and automatic repair",
+            "Log panel filters and search, JSON logs, size-based rotation and a diagnostics bundle",
+            "Smaller builds: control API, speed test, session history, MQTT, browser and downloader are cargo features",
+        ],
+    },
+    Release {
+        version: "0.1.0",
+        notes: &[
+            "Low resource mode: slower monitoring, HTTP-only login and a smaller log buffer",
+            "Encrypted backup and restore of the configuration via WebDAV",
+            "Warning when a system proxy or VPN adapter may break portal login",
+            "Portal layout change detection and console error capture on failed logins",
+            "Background threads are restarted automatically if they stop",
+            "Colored, categorized log entries; click an entry for details",
+            "\"Open portal in my browser\" fallback when automatic login fails",
+            "Configurable ICMP/TCP/HTTP/DNS probes with a network health score",
+            "Portal HTTP requests are rate limited to avoid anti-abuse blocking",
+        ],
+    },
+];
+
+// 将 "1.2.3" 解析为可比较的数字序列
+fn parse_version(version: &str) -> Vec<u32> {
+    version.trim()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// 上次查看的版本之后（不含）到当前版本（含）之间的更新说明
+pub fn releases_since(last_seen: &str) -> Vec<&'static Release> {
+    let current = parse_version(CURRENT_VERSION);
+    let last_seen = if last_seen.is_empty() { Vec::new() } else { parse_version(last_seen) };
+    RELEASES.iter()
+        .filter(|release| {
+            let version = parse_version(release.version);
+            version > last_seen && version <= current
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert!(parse_version("0.10.0") > parse_version("0.9.3"));
+        assert!(parse_version("1.0") < parse_version("1.0.1"));
+    }
+
+    #[test]
+    fn test_releases_since() {
+        // 从未记录过版本时显示全部说明
+        assert_eq!(releases_since("").len(), RELEASES.len());
+        assert!(releases_since(CURRENT_VERSION).is_empty());
+        // 发布新版本时同时添加更新说明
+        assert_eq!(RELEASES[0].version, CURRENT_VERSION);
+        assert_eq!(releases_since("0.1.0").len(), 1);
+    }
+}
@@ -1,70 +1,1005 @@
 // 配置管理模块
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
-use anyhow::Result;
-use log::info;
-
-// 运营商枚举
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
-pub enum ISP {
-    Mobile,
-    Unicom,
-    Telecom,
-    School,
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, bail, Result};
+use log::{debug, error, info, warn};
+
+pub use crate::backend::isp::ISP;
+use crate::backend::config_format::{self, CONFIG_VERSION};
+use crate::backend::ip_stack::StackPreference;
+use crate::backend::keychain::{PasswordStore, SystemKeyring, PROTECTED_PREFIX};
+use crate::backend::logger::{self, LogFormat, LogPolicy, Logger};
+use crate::backend::login_schedule::{ScheduleRule, TimeWindow};
+use crate::backend::notifier::NotificationSettings;
+use crate::backend::paths;
+use crate::backend::portal::{self, PortalType};
+use crate::backend::probe::Probe;
+use crate::backend::rate_limit;
+use crate::backend::retry_prompt::PromptHistory;
+use crate::backend::secret::SecretString;
+use crate::backend::semester::Term;
+use crate::backend::timezone;
+use crate::backend::triage;
+use crate::backend::webhook::Webhook;
+use std::time::Duration;
+
+/// 登录方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AuthBackend {
+    // 用 Chrome 打开门户页面填写表单登录
+    #[default]
+    WebDriver,
+    // 直接调用门户的 eportal HTTP 接口，不需要 Chrome
+    HttpPortal,
+    // 先用 HTTP 接口，门户拒绝接口请求或返回意料之外的页面时再用 Chrome
+    HttpThenBrowser,
+}
+
+impl AuthBackend {
+    pub const ALL: [AuthBackend; 3] = [AuthBackend::WebDriver, AuthBackend::HttpPortal, AuthBackend::HttpThenBrowser];
+
+    /// 界面中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuthBackend::WebDriver => "Browser (Chrome)",
+            AuthBackend::HttpPortal => "HTTP portal",
+            AuthBackend::HttpThenBrowser => "HTTP, then browser",
+        }
+    }
+}
+
+/// 网页登录使用的浏览器
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BrowserKind {
+    // 下载的 Chrome for Testing 或已安装的 Chrome
+    #[default]
+    Chrome,
+    // Windows 自带的 Edge，只需下载 msedgedriver
+    Edge,
+}
+
+#[cfg(any(feature = "webdriver", feature = "downloader"))]
+impl BrowserKind {
+    #[cfg(feature = "webdriver")]
+    pub const ALL: [BrowserKind; 2] = [BrowserKind::Chrome, BrowserKind::Edge];
+
+    /// 界面中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            BrowserKind::Chrome => "Chrome",
+            BrowserKind::Edge => "Microsoft Edge",
+        }
+    }
+
+    /// 程序目录中对应的 WebDriver 文件名，Windows 下带 .exe 后缀
+    pub fn driver_file(&self) -> &'static str {
+        match (self, cfg!(windows)) {
+            (BrowserKind::Chrome, true) => "chromedriver.exe",
+            (BrowserKind::Chrome, false) => "chromedriver",
+            (BrowserKind::Edge, true) => "msedgedriver.exe",
+            (BrowserKind::Edge, false) => "msedgedriver",
+        }
+    }
+}
+
+/// 一项设置的修改，用于保存前预览
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
 }
 
-impl Default for ISP {
-    fn default() -> Self {
-        ISP::School
+// 旧版本配置中唯一账号的名称
+const DEFAULT_PROFILE: &str = "Default";
+// MQTT 和 WebDAV 密码在凭据存储中的条目名前缀，与校园网账号区分
+const MQTT_KEYRING_PREFIX: &str = "mqtt:";
+const WEBDAV_KEYRING_PREFIX: &str = "webdav:";
+
+// 一个需要保存的密码：存入凭据存储时的条目名、密码和保存后的凭据存储引用；
+// account 为校园网账号的密码，只有它受“记住密码”控制
+struct PasswordSlot<'a> {
+    name: String,
+    password: &'a mut SecretString,
+    keyring_account: &'a mut String,
+    account: bool,
+}
+
+impl<'a> PasswordSlot<'a> {
+    // 校园网账号的密码，以用户名为条目名
+    fn account(username: &str, password: &'a mut SecretString, keyring_account: &'a mut String) -> Self {
+        Self { name: username.to_string(), password, keyring_account, account: true }
     }
 }
 
-// 配置文件结构
+/// 一个命名账号
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub username: String,
+    #[serde(default)]
+    pub password: SecretString,
+    pub isp: ISP,
+    pub auth_url: String,
+    // 同 Config::form_index
+    #[serde(default)]
+    pub form_index: usize,
+    // 同 Config::keyring_account
+    #[serde(default)]
+    pub keyring_account: String,
+    // 该账号单独使用的探测目标和下载镜像（不同校区屏蔽的地址不同），为 None 时沿用全局设置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probes: Option<Vec<Probe>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_mirrors: Option<Vec<String>>,
+    // 备注，如账号的来源或用途，在账号列表中悬停显示
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+impl Profile {
+    /// 用该账号替换配置中的账号信息，账号单独设置的探测目标和下载镜像覆盖全局设置
+    pub fn applied_to(&self, config: &Config) -> Config {
+        Config {
+            username: self.username.clone(),
+            password: self.password.clone(),
+            isp: self.isp,
+            auth_url: self.auth_url.clone(),
+            form_index: self.form_index,
+            keyring_account: self.keyring_account.clone(),
+            probes: self.probes.clone().unwrap_or_else(|| config.probes.clone()),
+            download_mirrors: self.download_mirrors.clone().unwrap_or_else(|| config.download_mirrors.clone()),
+            ..config.clone()
+        }
+    }
+}
+
+// 配置文件结构，缺少的字段使用默认值
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
 pub struct Config {
+    // 配置文件格式的版本，保存时写入 config_format::CONFIG_VERSION
+    pub version: u32,
     pub username: String,
-    pub password: String,
+    pub password: SecretString,
     pub remember_password: bool,
     pub auto_login: bool,
     pub auth_url: String,
     pub isp: ISP,
+    // 门户登录页有多个标签页（教师、学生、访客）时使用的登录表单，从 0 开始
+    #[serde(default)]
+    pub form_index: usize,
+    // 门户类型，决定使用哪个门户插件（backend/portal.rs）
+    #[serde(default)]
+    pub portal_type: PortalType,
+    // 登录方式，默认用 Chrome 登录
+    #[serde(default)]
+    pub auth_backend: AuthBackend,
+    // 用无界面的 Chrome 登录，不弹出窗口抢占焦点
+    #[serde(default)]
+    pub headless_browser: bool,
+    // 网页登录使用的浏览器，选择 Edge 时不需要下载 Chrome
+    #[serde(default)]
+    pub browser: BrowserKind,
+    // 在后台保持一个无界面的浏览器会话并打开认证页面，断网时直接提交登录表单
+    #[serde(default)]
+    pub warm_standby: bool,
+    // 上次成功登录时的登录页结构签名
+    #[serde(default)]
+    pub portal_fingerprint: String,
+    // 低资源模式：降低监控频率、仅使用 HTTP 认证、限制日志缓存
+    #[serde(default)]
+    pub low_resource_mode: bool,
+    // 是否已经提示过开启低资源模式
+    #[serde(default)]
+    pub low_resource_prompted: bool,
+    // WebDAV 备份目录地址及账号（如坚果云应用密码）
+    #[serde(default)]
+    pub webdav_url: String,
+    #[serde(default)]
+    pub webdav_username: String,
+    #[serde(default)]
+    pub webdav_password: SecretString,
+    // 同 keyring_account，WebDAV 密码在凭据存储中对应的条目
+    #[serde(default)]
+    pub webdav_keyring_account: String,
+    // 访问认证门户时绕过系统代理
+    #[serde(default)]
+    pub bypass_proxy_for_portal: bool,
+    // 双栈门户上 HTTP 登录使用的协议栈顺序
+    #[serde(default)]
+    pub stack_preference: StackPreference,
+    // 网络监控的探测目标，为空时使用默认的 ICMP 目标；账号可以单独设置
+    #[serde(default)]
+    pub probes: Vec<Probe>,
+    // 外部目标都不可达时用来区分出口故障和本机问题的校内目标，为空时使用默认目标
+    #[serde(default)]
+    pub intranet_probes: Vec<Probe>,
+    // Chrome for Testing 的下载镜像（与官方目录结构相同的地址前缀），按顺序尝试，为空时使用官方地址
+    #[serde(default)]
+    pub download_mirrors: Vec<String>,
+    // 识别门户拦截的 generate_204 地址，为空时使用默认地址
+    #[serde(default)]
+    pub captive_probe_url: String,
+    // 网络检查间隔（秒），未设置时为 30 秒，低资源模式下为 120 秒
+    #[serde(default)]
+    pub check_interval_secs: Option<u64>,
+    // 单次探测的超时（毫秒），未设置时为 3000
+    #[serde(default)]
+    pub probe_timeout_ms: Option<u64>,
+    // 逐个执行探测而不是同时执行，适合对并发连接限流的网络
+    #[serde(default)]
+    pub sequential_probes: bool,
+    // 延迟和丢包统计包含的最近检查次数，未设置时为 20
+    #[serde(default)]
+    pub stats_window: Option<usize>,
+    // 每次检查后写入 Prometheus 指标的 .prom 文件（node_exporter textfile collector 目录下），为空时不导出
+    #[serde(default)]
+    pub metrics_textfile: String,
+    // 用户在自动登录连续失败提示中的选择次数，用于调整提示时机和默认选项
+    #[serde(default)]
+    pub retry_prompt_history: PromptHistory,
+    // 自动登录前不在线状态需持续的时间（秒），未设置时为 5 秒
+    #[serde(default)]
+    pub login_debounce_secs: Option<u64>,
+    // 门户 HTTP 请求的最大并发数和最小间隔（毫秒），未设置时使用默认值
+    #[serde(default)]
+    pub portal_max_concurrent: Option<usize>,
+    #[serde(default)]
+    pub portal_min_interval_ms: Option<u64>,
+    // 外部凭据提供程序的命名管道路径，设置后登录时从该程序获取账号密码
+    #[serde(default)]
+    pub credential_provider: String,
+    // 上次查看更新日志时的程序版本
+    #[serde(default)]
+    pub last_seen_version: String,
+    // 单个日志文件的大小上限（MB）、每月保留的归档个数和日志保留天数，未设置时使用默认值
+    #[serde(default)]
+    pub log_max_file_mb: Option<u64>,
+    #[serde(default)]
+    pub log_max_archives: Option<usize>,
+    #[serde(default)]
+    pub log_retention_days: Option<u64>,
+    // 各模块的日志级别（如 sn::backend::downloader=debug,thirtyfour=warn），未列出的模块使用 info
+    #[serde(default)]
+    pub log_filters: String,
+    // 日志文件的格式，JSON 便于导入 ELK 等日志平台
+    #[serde(default)]
+    pub log_format: LogFormat,
+    // 日志时间和定时任务使用的时区（IANA 名称，如 Asia/Shanghai），为空时使用系统时区
+    #[serde(default)]
+    pub time_zone: String,
+    // 学期日历，配置后假期期间暂停自动登录，为空时不按学期安排
+    #[serde(default)]
+    pub semester_terms: Vec<Term>,
+    // 定时登录登出规则，最近一次生效的是登出时暂停自动登录，为空时不定时
+    #[serde(default)]
+    pub schedule_rules: Vec<ScheduleRule>,
+    // 校园网夜间免费时段的开始和结束时间（HH:MM，配置的时区），用于统计本月夜间和白天的流量，为空时不统计
+    #[serde(default)]
+    pub free_night_start: String,
+    #[serde(default)]
+    pub free_night_end: String,
+    // 匿名延迟统计的汇总服务地址，为空时不上报（默认关闭）
+    #[serde(default)]
+    pub peer_stats_url: String,
+    // 每晚运行一次自检（探测、门户状态、测速），早上汇总显示与一周前相比变差的指标
+    #[serde(default)]
+    pub nightly_self_test: bool,
+    // 自检测速时下载的文件地址，为空时不测速
+    #[serde(default)]
+    pub self_test_speed_url: String,
+    // MQTT 服务器地址（mqtt:// 或 mqtts://），为空时不发布网络状态
+    #[serde(default)]
+    pub mqtt_url: String,
+    #[serde(default)]
+    pub mqtt_username: String,
+    #[serde(default)]
+    pub mqtt_password: SecretString,
+    // 同 keyring_account，MQTT 密码在凭据存储中对应的条目
+    #[serde(default)]
+    pub mqtt_keyring_account: String,
+    // MQTT 主题前缀，为空时使用 csunetwork
+    #[serde(default)]
+    pub mqtt_topic_prefix: String,
+    // 登录成功后用默认浏览器打开的页面（如自助服务或测速页面），为空时不打开
+    #[serde(default)]
+    pub landing_url: String,
+    // 自动登录成功后是否也打开该页面，默认只在手动登录后打开
+    #[serde(default)]
+    pub landing_after_auto_login: bool,
+    // 开机自启时是否最小化启动；是否开机自启以注册表为准，不保存在配置中
+    #[serde(default)]
+    pub start_minimized: bool,
+    // 断网、自动登录成功和失败时是否显示桌面通知
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    // 断网、恢复和自动登录结果发送到的 Webhook（钉钉、企业微信、Server酱、Telegram 或自己的服务）
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    // 通知免打扰时段的开始和结束时间（HH:MM，配置的时区），期间不弹出桌面通知、不发送 Webhook，时段结束后汇总；为空时不设置
+    #[serde(default)]
+    pub quiet_hours_start: String,
+    #[serde(default)]
+    pub quiet_hours_end: String,
+    // 登录成功后连接校园 VPN 的命令（如启动 EasyConnect 客户端），为空时不连接
+    #[serde(default)]
+    pub vpn_connect_command: String,
+    // 判断 VPN 是否已连接的命令，退出码为 0 表示已连接，为空时不检查
+    #[serde(default)]
+    pub vpn_status_command: String,
+    // 登录成功后预先解析的常用域名，为空时不预热
+    #[serde(default)]
+    pub dns_prime_domains: Vec<String>,
+    // 登录后定期发送心跳请求，避免门户因长时间没有流量断开会话
+    #[serde(default)]
+    pub keep_alive: bool,
+    // 心跳间隔（秒），未设置时为 300 秒
+    #[serde(default)]
+    pub keep_alive_secs: Option<u64>,
+    // 心跳请求的地址（如门户的心跳接口），为空时使用识别门户拦截的 generate_204 地址
+    #[serde(default)]
+    pub keep_alive_url: String,
+    // 开启本机控制接口，供脚本、Home Assistant 或局域网内的手机查询状态、登录和登出
+    #[serde(default)]
+    pub control_api: bool,
+    // 控制接口监听的地址，为空时为 127.0.0.1:8787；改为 0.0.0.0:8787 可从局域网访问
+    #[serde(default)]
+    pub control_api_bind: String,
+    // 访问控制接口需要的令牌，为空时不启动
+    #[serde(default)]
+    pub control_api_token: String,
+    // 密码保存在系统凭据存储中时对应的账号，为空表示密码直接写在配置文件里
+    #[serde(default)]
+    pub keyring_account: String,
+    // 保存的账号；当前账号的信息同时保存在 username、password、isp、auth_url 中，登录等功能直接使用这些字段
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    // 当前账号的名称
+    #[serde(default)]
+    pub active_profile: String,
+    // 本次加载时对旧配置做的迁移说明，不保存
+    #[serde(skip)]
+    pub migrations: Vec<String>,
+}
+
+/// 配置文件的内容无法解析（而不是读取失败），只有这种情况才把文件当作损坏的文件另存
+#[derive(Debug)]
+pub struct ConfigCorrupt(String);
+
+impl std::fmt::Display for ConfigCorrupt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigCorrupt {}
+
+#[cfg(any(feature = "control_api", feature = "downloader"))]
+/// 导出配置时代替密码和令牌的文字
+pub const REDACTED: &str = "(redacted)";
+
+// 需要隐去的字段：名称中含有 password、token 或 secret
+fn is_secret_field(name: &str) -> bool {
+    name.contains("password") || name.contains("token") || name.contains("secret")
+}
+
+#[cfg(any(feature = "control_api", feature = "downloader"))]
+// 需要隐去的字段替换为 REDACTED，空值保持为空，便于看出是否填写了
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, value) in fields.iter_mut() {
+                if !is_secret_field(name) {
+                    redact(value);
+                } else if !matches!(value, serde_json::Value::String(text) if text.is_empty()) {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
 }
 
 impl Config {
-    // 获取配置文件路径
+    /// 应用门户请求的并发和频率限制
+    pub fn apply_rate_limits(&self) {
+        rate_limit::portal_limiter().configure(
+            self.portal_max_concurrent.unwrap_or(rate_limit::DEFAULT_MAX_CONCURRENT),
+            self.portal_min_interval_ms
+                .map(Duration::from_millis)
+                .unwrap_or(rate_limit::DEFAULT_MIN_INTERVAL),
+        );
+    }
+
+    /// 应用日志文件的格式、大小上限、保留策略和各模块的日志级别
+    pub fn apply_log_policy(&self) {
+        Logger::set_format(self.log_format);
+        if let Err(e) = Logger::set_filters(&self.log_filters) {
+            log::warn!("{}", e);
+        }
+        Logger::set_policy(self.log_policy());
+    }
+
+    // 日志文件的大小上限和保留策略，未设置的项使用默认值
+    fn log_policy(&self) -> LogPolicy {
+        LogPolicy {
+            max_file_bytes: self.log_max_file_mb.unwrap_or(logger::DEFAULT_MAX_FILE_MB).max(1) * 1024 * 1024,
+            max_archives: self.log_max_archives.unwrap_or(logger::DEFAULT_MAX_ARCHIVES),
+            retention: Duration::from_secs(self.log_retention_days.unwrap_or(logger::DEFAULT_RETENTION_DAYS).max(1) * 24 * 60 * 60),
+        }
+    }
+
+    /// 是否通过 HTTP 接口认证：选择了 HTTP 登录、低资源模式、未启用 webdriver 功能或门户不支持浏览器登录时
+    pub fn uses_http_auth(&self, low_resource: bool) -> bool {
+        low_resource || self.auth_backend == AuthBackend::HttpPortal || !portal::entry(self.portal_type).supports_browser()
+    }
+
+    /// 现在是否在通知免打扰时段内，时段格式错误时视为没有设置
+    pub fn in_quiet_hours(&self) -> bool {
+        TimeWindow::parse(&self.quiet_hours_start, &self.quiet_hours_end)
+            .ok()
+            .flatten()
+            .is_some_and(|window| window.contains(timezone::now().time()))
+    }
+
+    /// 应用配置的时区，时区名无效时保持原设置并返回错误
+    pub fn apply_time_zone(&self) -> Result<()> {
+        timezone::set(timezone::parse(&self.time_zone)?);
+        Ok(())
+    }
+
+    #[cfg(any(feature = "control_api", feature = "downloader"))]
+    /// 隐去所有密码和令牌（包括各账号、WebDAV、MQTT 的密码，控制接口的令牌和 Webhook 的密钥）后的配置；
+    /// Webhook 地址中通常带有机器人的密钥，也一并隐去
+    pub fn redacted(&self) -> Result<serde_json::Value> {
+        let mut value = serde_json::to_value(self)?;
+        redact(&mut value);
+        for webhook in value["webhooks"].as_array_mut().into_iter().flatten() {
+            if webhook["url"].as_str().is_some_and(|url| !url.is_empty()) {
+                webhook["url"] = serde_json::Value::String(REDACTED.to_string());
+            }
+        }
+        Ok(value)
+    }
+
+    /// 与另一份配置比较，列出修改过的设置，密码和令牌只显示是否为空
+    pub fn changes_from(&self, old: &Config) -> Vec<ConfigChange> {
+        let (Ok(serde_json::Value::Object(new)), Ok(serde_json::Value::Object(old))) =
+            (serde_json::to_value(self), serde_json::to_value(old)) else {
+            return Vec::new();
+        };
+        new.into_iter()
+            .filter(|(field, value)| old.get(field) != Some(value))
+            .map(|(field, value)| {
+                let describe = |value: Option<&serde_json::Value>| match value {
+                    Some(serde_json::Value::String(text)) if text.is_empty() => "(empty)".to_string(),
+                    Some(_) if is_secret_field(&field) => "••••••".to_string(),
+                    // 账号列表中含有密码，只显示名称
+                    Some(serde_json::Value::Array(profiles)) if field == "profiles" => profiles.iter()
+                        .filter_map(|profile| profile["name"].as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    // Webhook 的地址和密钥不写入日志，只显示主机名
+                    Some(serde_json::Value::Array(webhooks)) if field == "webhooks" => webhooks.iter()
+                        .filter_map(|webhook| reqwest::Url::parse(webhook["url"].as_str()?).ok())
+                        .filter_map(|url| url.host_str().map(str::to_string))
+                        .reduce(|hosts, host| format!("{}, {}", hosts, host))
+                        .unwrap_or_else(|| "(none)".to_string()),
+                    Some(value @ serde_json::Value::Array(_)) if field == "probes" => serde_json::from_value::<Vec<Probe>>(value.clone())
+                        .map(|probes| match probes.is_empty() {
+                            true => "(default targets)".to_string(),
+                            false => probes.iter().map(Probe::describe).collect::<Vec<_>>().join(", "),
+                        })
+                        .unwrap_or_default(),
+                    Some(serde_json::Value::String(text)) => text.clone(),
+                    Some(value) => value.to_string(),
+                    None => "(none)".to_string(),
+                };
+                ConfigChange { old: describe(old.get(&field)), new: describe(Some(&value)), field }
+            })
+            .collect()
+    }
+
+    // 配置文件的内容，不迁移、不读取密码；读取失败时为 None
+    fn saved_value() -> Option<serde_json::Value> {
+        fs::read_to_string(Self::current_path()).ok()
+            .and_then(|content| Self::parse_value(&content).ok())
+    }
+
+    /// 配置文件中的日志格式；初始化日志时还没有加载完整配置（可能需要读取系统凭据），只读取这一项
+    pub fn saved_log_format() -> LogFormat {
+        Self::saved_value()
+            .and_then(|config| serde_json::from_value(config["log_format"].clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// 配置文件中的日志大小上限和保留策略，同样在初始化日志前读取，启动时按设置的保留天数清理、按上限归档
+    pub fn saved_log_policy() -> LogPolicy {
+        Self::saved_value()
+            .and_then(|config| serde_json::from_value::<Config>(config).ok())
+            .unwrap_or_default()
+            .log_policy()
+    }
+
+    // 获取配置文件路径，所在目录见 paths::config_dir
     fn get_config_path() -> PathBuf {
-        let mut path = PathBuf::from("config");
-        path.push("config.json");
-        path
+        paths::config_dir().join("config.toml")
+    }
+
+    // 旧版本的 JSON 配置文件路径，与 config.toml 在同一目录
+    fn legacy_path(path: &Path) -> PathBuf {
+        path.with_extension("json")
+    }
+
+    // 正在使用的配置文件：还没有转换为 config.toml 时为旧版本的 config.json
+    fn current_path() -> PathBuf {
+        let path = Self::get_config_path();
+        let legacy_path = Self::legacy_path(&path);
+        if !path.exists() && legacy_path.exists() {
+            legacy_path
+        } else {
+            path
+        }
+    }
+
+    // 在文件名后加上后缀，如 config.toml.bak
+    fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(suffix);
+        path.with_file_name(name)
+    }
+
+    // 上一份配置的备份路径
+    fn backup_path(path: &Path) -> PathBuf {
+        Self::with_suffix(path, "bak")
+    }
+
+    // 先写入临时文件再替换，写入中途崩溃不会损坏原配置；替换前把原配置复制为备份
+    fn write_atomic(path: &Path, content: &str) -> Result<()> {
+        let tmp_path = Self::with_suffix(path, "tmp");
+        {
+            let mut file = fs::File::create(&tmp_path).map_err(triage::write_error)?;
+            file.write_all(content.as_bytes()).map_err(triage::write_error)?;
+            file.sync_all()?;
+        }
+        if path.exists() {
+            fs::copy(path, Self::backup_path(path)).map_err(triage::write_error)?;
+        }
+        fs::rename(&tmp_path, path).map_err(triage::write_error)?;
+        Ok(())
+    }
+
+    /// 是否存在上一份配置的备份
+    pub fn has_backup() -> bool {
+        Self::backup_path(&Self::current_path()).exists()
+    }
+
+    /// 从备份中恢复配置
+    pub fn load_backup() -> Result<Self> {
+        let content = fs::read_to_string(Self::backup_path(&Self::current_path()))?;
+        let mut config = Self::parse(&content)?;
+        config.load_password(&SystemKeyring);
+        info!("Configuration restored from backup");
+        Ok(config)
+    }
+
+    /// 把无法解析的配置文件改名为 config.toml.corrupt，避免之后保存时覆盖备份
+    pub fn set_aside_corrupt() -> Result<PathBuf> {
+        let path = Self::current_path();
+        let corrupt_path = Self::with_suffix(&path, "corrupt");
+        fs::rename(&path, &corrupt_path)?;
+        Ok(corrupt_path)
+    }
+
+    /// 是否具备登录所需的凭据（本地保存或由外部程序提供）
+    pub fn has_credentials(&self) -> bool {
+        !self.credential_provider.is_empty()
+            || (!self.username.is_empty() && !self.password.is_empty())
+    }
+
+    /// 配置文件是否存在
+    pub fn exists() -> bool {
+        Self::current_path().exists()
+    }
+
+    // 解析配置文件内容：旧版本的 config.json 以 { 开头，按 JSON 解析，其他按 TOML 解析
+    fn parse_value(content: &str) -> Result<serde_json::Value> {
+        if content.trim_start().starts_with('{') {
+            Ok(serde_json::from_str(content)?)
+        } else {
+            config_format::from_toml(content)
+        }
+    }
+
+    // 解析配置内容，迁移到当前的格式版本，并对旧版本的配置做必要的修正
+    fn parse(content: &str) -> Result<Self> {
+        let corrupt = |e: anyhow::Error| ConfigCorrupt(format!("{:#}", e));
+        let mut value = Self::parse_value(content).map_err(corrupt)?;
+        let migrations = config_format::migrate(&mut value).map_err(corrupt)?;
+        let mut config: Config = serde_json::from_value(value).map_err(|e| corrupt(e.into()))?;
+        config.migrations = migrations;
+
+        // 如果认证URL为空，设置默认值
+        if config.auth_url.is_empty() {
+            config.auth_url = "http://10.1.1.1".to_string();
+            config.migrations.push("Empty auth URL was reset to http://10.1.1.1".to_string());
+        }
+
+        // 如果不记住密码，确保密码被清空
+        if !config.remember_password {
+            for slot in config.password_slots().into_iter().filter(|slot| slot.account) {
+                slot.password.clear();
+            }
+            config.auto_login = false;
+        }
+
+        config.ensure_profile();
+        Ok(config)
+    }
+
+    // 保证当前账号在账号列表中，旧版本配置中的唯一账号作为 Default 加入
+    fn ensure_profile(&mut self) {
+        if self.active_profile.is_empty() {
+            self.active_profile = DEFAULT_PROFILE.to_string();
+        }
+        if !self.profiles.iter().any(|profile| profile.name == self.active_profile) {
+            self.profiles.push(self.active_as_profile());
+        }
+    }
+
+    /// 当前账号
+    pub fn active_as_profile(&self) -> Profile {
+        // 单独设置的探测目标和下载镜像只保存在账号列表中
+        let saved = self.profiles.iter().find(|profile| profile.name == self.active_profile);
+        Profile {
+            name: self.active_profile.clone(),
+            username: self.username.clone(),
+            password: self.password.clone(),
+            isp: self.isp,
+            auth_url: self.auth_url.clone(),
+            form_index: self.form_index,
+            keyring_account: self.keyring_account.clone(),
+            probes: saved.and_then(|profile| profile.probes.clone()),
+            download_mirrors: saved.and_then(|profile| profile.download_mirrors.clone()),
+            notes: saved.map(|profile| profile.notes.clone()).unwrap_or_default(),
+        }
+    }
+
+    /// 账号列表中的当前账号，用于编辑它单独的探测目标和下载镜像
+    pub fn active_profile_mut(&mut self) -> &mut Profile {
+        self.ensure_profile();
+        let active = self.active_profile.clone();
+        self.profiles.iter_mut()
+            .find(|profile| profile.name == active)
+            .expect("ensure_profile keeps the active profile in the list")
+    }
+
+    /// 当前账号使用的探测目标
+    pub fn effective_probes(&self) -> Vec<Probe> {
+        self.active_as_profile().probes.unwrap_or_else(|| self.probes.clone())
+    }
+
+    #[cfg(feature = "downloader")]
+    /// 当前账号使用的下载镜像
+    pub fn effective_download_mirrors(&self) -> Vec<String> {
+        self.active_as_profile().download_mirrors.unwrap_or_else(|| self.download_mirrors.clone())
+    }
+
+    // 把界面中对当前账号的修改写回账号列表
+    fn sync_active_profile(&mut self) {
+        let active = self.active_as_profile();
+        if let Some(profile) = self.profiles.iter_mut().find(|profile| profile.name == active.name) {
+            *profile = active;
+        }
+    }
+
+    // 检查新账号名称，返回去掉首尾空白后的名称
+    fn check_profile_name(&self, name: &str) -> Result<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("Profile name cannot be empty");
+        }
+        if self.profiles.iter().any(|profile| profile.name == name) {
+            bail!("A profile named {} already exists", name);
+        }
+        Ok(name.to_string())
+    }
+
+    /// 切换当前账号
+    pub fn switch_profile(&mut self, name: &str) -> Result<()> {
+        self.ensure_profile();
+        self.sync_active_profile();
+        let profile = self.profiles.iter()
+            .find(|profile| profile.name == name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Profile {} not found", name))?;
+        self.load_profile(profile);
+        Ok(())
+    }
+
+    // 把账号信息放到当前账号的字段中
+    fn load_profile(&mut self, profile: Profile) {
+        self.username = profile.username;
+        self.password = profile.password;
+        self.isp = profile.isp;
+        self.auth_url = profile.auth_url;
+        self.form_index = profile.form_index;
+        self.keyring_account = profile.keyring_account;
+        self.active_profile = profile.name;
+    }
+
+    /// 新建一个空账号并切换过去，门户地址和运营商沿用当前账号
+    pub fn add_profile(&mut self, name: &str) -> Result<()> {
+        self.ensure_profile();
+        let name = self.check_profile_name(name)?;
+        self.profiles.push(Profile {
+            name: name.clone(),
+            isp: self.isp,
+            auth_url: self.auth_url.clone(),
+            ..Default::default()
+        });
+        self.switch_profile(&name)
+    }
+
+    /// 重命名账号
+    pub fn rename_profile(&mut self, name: &str, new_name: &str) -> Result<()> {
+        self.ensure_profile();
+        let new_name = self.check_profile_name(new_name)?;
+        let profile = self.profiles.iter_mut()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| anyhow!("Profile {} not found", name))?;
+        profile.name = new_name.clone();
+        if self.active_profile == name {
+            self.active_profile = new_name;
+        }
+        Ok(())
+    }
+
+    /// 删除账号，删除当前账号时切换到第一个账号；不再被引用的密码从凭据存储中删除
+    pub fn remove_profile(&mut self, name: &str, store: &dyn PasswordStore) -> Result<()> {
+        self.ensure_profile();
+        if self.profiles.len() <= 1 {
+            bail!("Cannot remove the only profile");
+        }
+        let index = self.profiles.iter()
+            .position(|profile| profile.name == name)
+            .ok_or_else(|| anyhow!("Profile {} not found", name))?;
+        let removed = self.profiles.remove(index);
+        if self.active_profile == name {
+            self.load_profile(self.profiles[0].clone());
+        }
+        if !removed.keyring_account.is_empty() && !self.keyring_accounts().contains(removed.keyring_account.as_str()) {
+            if let Err(e) = store.delete(&removed.keyring_account) {
+                debug!("Failed to remove the saved password of {}: {}", removed.keyring_account, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// 从另一份配置复制账号列表和当前账号，其他设置不变
+    pub fn copy_profiles_from(&mut self, other: &Config) {
+        self.username = other.username.clone();
+        self.password = other.password.clone();
+        self.isp = other.isp;
+        self.auth_url = other.auth_url.clone();
+        self.form_index = other.form_index;
+        self.keyring_account = other.keyring_account.clone();
+        self.profiles = other.profiles.clone();
+        self.active_profile = other.active_profile.clone();
+    }
+
+    // 需要保存的密码：当前账号和账号列表中每个账号的密码，以及 MQTT 服务器和 WebDAV 备份的密码
+    fn password_slots(&mut self) -> Vec<PasswordSlot<'_>> {
+        let mut slots = vec![PasswordSlot::account(&self.username, &mut self.password, &mut self.keyring_account)];
+        slots.extend(self.profiles.iter_mut().map(|profile| PasswordSlot::account(&profile.username, &mut profile.password, &mut profile.keyring_account)));
+        slots.push(PasswordSlot {
+            name: format!("{}{}", MQTT_KEYRING_PREFIX, self.mqtt_username),
+            password: &mut self.mqtt_password,
+            keyring_account: &mut self.mqtt_keyring_account,
+            account: false,
+        });
+        slots.push(PasswordSlot {
+            name: format!("{}{}", WEBDAV_KEYRING_PREFIX, self.webdav_username),
+            password: &mut self.webdav_password,
+            keyring_account: &mut self.webdav_keyring_account,
+            account: false,
+        });
+        slots
+    }
+
+    // 引用的凭据存储条目
+    fn keyring_accounts(&self) -> BTreeSet<&str> {
+        [&self.keyring_account, &self.mqtt_keyring_account, &self.webdav_keyring_account].into_iter()
+            .chain(self.profiles.iter().map(|profile| &profile.keyring_account))
+            .filter(|account| !account.is_empty())
+            .map(String::as_str)
+            .collect()
+    }
+
+    // 生成写入文件的配置：不记住密码时清空密码，否则尽量把密码放进系统凭据存储；存储不可用时写在文件里，
+    // 能在本机加密（Windows DPAPI）时写密文，否则写明文
+    fn stored_copy(&self, store: &dyn PasswordStore) -> Config {
+        let mut config_to_save = self.clone();
+        config_to_save.version = CONFIG_VERSION;
+        config_to_save.sync_active_profile();
+        let remember_password = self.remember_password;
+        if !remember_password {
+            config_to_save.auto_login = false;
+        }
+        let mut keyring_available = true;
+        let mut can_protect = true;
+        for PasswordSlot { name, password, keyring_account, account } in config_to_save.password_slots() {
+            keyring_account.clear();
+            if account && !remember_password {
+                password.clear();
+                continue;
+            }
+            if password.is_empty() {
+                continue;
+            }
+            if keyring_available {
+                match store.set(&name, password.expose()) {
+                    Ok(()) => {
+                        password.clear();
+                        *keyring_account = name;
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("System credential store unavailable, saving the password in the config file: {}", e);
+                        keyring_available = false;
+                    }
+                }
+            }
+            if can_protect {
+                match store.protect(password.expose()) {
+                    Ok(protected) => *password = protected.into(),
+                    Err(e) => {
+                        debug!("Password not encrypted in the config file: {}", e);
+                        can_protect = false;
+                    }
+                }
+            }
+        }
+
+        // 取消记住密码或更换了账号后，删除凭据存储中不再使用的条目
+        let mut stale = self.keyring_accounts();
+        if !remember_password {
+            stale.insert(&self.username);
+            stale.extend(self.profiles.iter().map(|profile| profile.username.as_str()));
+        }
+        let in_use = config_to_save.keyring_accounts();
+        for account in stale.difference(&in_use).filter(|account| !account.is_empty()) {
+            if let Err(e) = store.delete(account) {
+                debug!("Failed to remove the saved password of {}: {}", account, e);
+            }
+        }
+        config_to_save
+    }
+
+    // 从系统凭据存储取回密码，解密配置文件中加密保存的密码；返回 true 表示密码仍以明文写在配置文件里，需要迁移。
+    // 不记住密码时只取回 MQTT 和 WebDAV 的密码
+    fn load_password(&mut self, store: &dyn PasswordStore) -> bool {
+        let remember_password = self.remember_password;
+        let mut plain_text = false;
+        let mut undecryptable = BTreeSet::new();
+        for PasswordSlot { name, password, keyring_account, account } in self.password_slots() {
+            if account && !remember_password {
+                continue;
+            }
+            if keyring_account.is_empty() && password.expose().starts_with(PROTECTED_PREFIX) {
+                // 换了电脑或 Windows 用户后无法解密，只能重新输入；在启动提示中告诉用户，而不是静默清空
+                match store.unprotect(password.expose()) {
+                    Ok(saved) => *password = saved.into(),
+                    Err(e) => {
+                        error!("Failed to decrypt the saved password of {}, please enter it again: {}", name, e);
+                        undecryptable.insert(name);
+                        password.clear();
+                    }
+                }
+                continue;
+            }
+            if keyring_account.is_empty() {
+                plain_text |= !password.is_empty();
+                continue;
+            }
+            match store.get(keyring_account) {
+                Ok(saved) => *password = saved.into(),
+                Err(e) => warn!("Failed to read the saved password of {} from the system credential store: {}", keyring_account, e),
+            }
+        }
+        for username in undecryptable {
+            self.migrations.push(format!(
+                "The saved password of {} was encrypted by another Windows user or computer and could not be decrypted, please enter it again",
+                username
+            ));
+        }
+        plain_text
+    }
+
+    // 把明文保存的密码移入系统凭据存储或在本机加密，备份中的明文也一并覆盖；都不可用时保持原样
+    fn migrate_password(&mut self, path: &Path, store: &dyn PasswordStore) -> Result<()> {
+        let mut migrated = self.stored_copy(store);
+        let protected = migrated.password_slots().iter().any(|slot| slot.password.expose().starts_with(PROTECTED_PREFIX));
+        if migrated.keyring_accounts().is_empty() && !protected {
+            return Ok(());
+        }
+        let content = Self::file_content(&migrated)?;
+        Self::write_atomic(path, &content)?;
+        fs::write(Self::backup_path(path), &content)?;
+        self.adopt_keyring_accounts(migrated);
+        if protected {
+            info!("Password in the config file encrypted for the current Windows user");
+        } else {
+            info!("Password moved from the config file to the system credential store");
+        }
+        Ok(())
+    }
+
+    // 记下保存时密码在凭据存储中对应的账号
+    fn adopt_keyring_accounts(&mut self, saved: Config) {
+        self.keyring_account = saved.keyring_account;
+        self.mqtt_keyring_account = saved.mqtt_keyring_account;
+        self.webdav_keyring_account = saved.webdav_keyring_account;
+        for (profile, saved) in self.profiles.iter_mut().zip(saved.profiles) {
+            profile.keyring_account = saved.keyring_account;
+        }
+    }
+
+    // 写入文件的 TOML 内容，config 为 stored_copy 生成的配置
+    fn file_content(config: &Config) -> Result<String> {
+        config_format::to_toml(&serde_json::to_value(config)?)
+    }
+
+    // 读取配置文件，文件不存在时返回 None；只有旧版本的 config.json 时转换为 config.toml，
+    // 转换后删除旧文件和它的备份（其中可能有明文密码）
+    fn read_from(path: &Path, store: &dyn PasswordStore) -> Result<Option<Self>> {
+        let legacy_path = Self::legacy_path(path);
+        if !path.exists() && legacy_path.exists() {
+            let mut config = Self::parse(&fs::read_to_string(&legacy_path)?)?;
+            config.load_password(store);
+            let saved = config.stored_copy(store);
+            Self::write_atomic(path, &Self::file_content(&saved)?)?;
+            config.adopt_keyring_accounts(saved);
+            for old in [Self::backup_path(&legacy_path), legacy_path.clone()] {
+                if old.exists() {
+                    fs::remove_file(old)?;
+                }
+            }
+            info!("Configuration converted from {:?} to {:?}", legacy_path, path);
+            config.migrations.push(format!("Settings moved from {} to {}", legacy_path.display(), path.display()));
+            return Ok(Some(config));
+        }
+        if !path.exists() {
+            return Ok(None);
+        }
+        let mut config = Self::parse(&fs::read_to_string(path)?)?;
+        if config.load_password(store) {
+            config.migrate_password(path, store)?;
+        }
+        Ok(Some(config))
     }
 
     // 加载配置
     pub fn load() -> Result<Self> {
         let path = Self::get_config_path();
-        if path.exists() {
-            let content = fs::read_to_string(&path)?;
-            let mut config: Config = serde_json::from_str(&content)?;
-            
-            // 如果认证URL为空，设置默认值
-            if config.auth_url.is_empty() {
-                config.auth_url = "http://10.1.1.1".to_string();
-            }
-            
-            // 如果不记住密码，确保密码被清空
-            if !config.remember_password {
-                config.password = String::new();
-                config.auto_login = false;
-            }
-            
+        if let Some(config) = Self::read_from(&path, &SystemKeyring)? {
             info!("Configuration loaded successfully from {:?}", path);
             Ok(config)
         } else {
             info!("No configuration file found at {:?}, using defaults", path);
-            Ok(Config {
+            let mut config = Config {
                 auth_url: "http://10.1.1.1".to_string(),
                 ..Default::default()
-            })
+            };
+            config.ensure_profile();
+            Ok(config)
         }
     }
 
@@ -77,78 +1012,144 @@ impl Config {
             fs::create_dir_all(parent)?;
         }
 
-        // 如果不记住密码，则清空密码再保存
-        let mut config_to_save = self.clone();
-        if !self.remember_password {
-            config_to_save.password = String::new();
-            config_to_save.auto_login = false;
-        }
-
-        let content = serde_json::to_string_pretty(&config_to_save)?;
-        fs::write(&path, content)?;
+        let content = Self::file_content(&self.stored_copy(&SystemKeyring))?;
+        Self::write_atomic(&path, &content)?;
         info!("Configuration saved successfully to {:?}", path);
+
+        // 已安装 Windows 服务时同步更新给服务的密码副本，改了密码后服务不会继续用旧密码
+        #[cfg(windows)]
+        if Self::service_credentials_path().exists() {
+            if let Err(e) = self.save_service_credentials() {
+                warn!("Failed to update the password saved for the service: {}", e);
+            }
+        }
         Ok(())
     }
 
-    // 用于测试的直接保存和加载方法
-    #[cfg(test)]
-    fn save_to(&self, path: &PathBuf) -> Result<()> {
+    // 给 Windows 服务保存的密码副本，与配置文件在同一目录
+    #[cfg(windows)]
+    fn service_credentials_path() -> PathBuf {
+        paths::config_dir().join("service_credentials.json")
+    }
+
+    /// 给 Windows 服务保存密码副本：服务以 LocalSystem 运行，读不到安装者的凭据存储，也解不开按用户加密的密码，
+    /// 所以安装时把每个账号的密码按本机范围加密另存一份
+    #[cfg(windows)]
+    pub fn save_service_credentials(&self) -> Result<()> {
+        self.write_service_credentials(&Self::service_credentials_path(), &SystemKeyring)
+    }
+
+    #[cfg(any(windows, test))]
+    fn write_service_credentials(&self, path: &Path, store: &dyn PasswordStore) -> Result<()> {
+        let mut config = self.clone();
+        config.sync_active_profile();
+        let mut credentials = serde_json::Map::new();
+        let mut has_account = false;
+        for PasswordSlot { name, password, account, .. } in config.password_slots() {
+            if !name.is_empty() && !password.is_empty() {
+                credentials.insert(name, store.protect_for_machine(password.expose())?.into());
+                has_account |= account;
+            }
+        }
+        if !has_account {
+            bail!("No saved password for the service, save the username and password with \"Remember password\" checked first");
+        }
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
+        fs::write(path, serde_json::to_string_pretty(&credentials)?)?;
+        Ok(())
+    }
 
-        // 如果不记住密码，则清空密码再保存
-        let mut config_to_save = self.clone();
-        if !self.remember_password {
-            config_to_save.password = String::new();
-            config_to_save.auto_login = false;
+    /// 卸载 Windows 服务时删除给服务保存的密码副本
+    #[cfg(windows)]
+    pub fn remove_service_credentials() -> Result<()> {
+        let path = Self::service_credentials_path();
+        if path.exists() {
+            fs::remove_file(path)?;
         }
+        Ok(())
+    }
 
-        let content = serde_json::to_string_pretty(&config_to_save)?;
-        fs::write(path, content)?;
+    /// Windows 服务加载配置：不读取凭据存储，也不迁移或改写安装者的配置文件，密码只从安装时保存的副本中取回
+    #[cfg(windows)]
+    pub fn load_for_service() -> Result<Self> {
+        let path = Self::current_path();
+        let content = fs::read_to_string(&path).map_err(|e| anyhow!("Failed to read the configuration at {:?}: {}", path, e))?;
+        let mut config = Self::parse(&content)?;
+        if config.credential_provider.is_empty() {
+            config.read_service_credentials(&Self::service_credentials_path(), &SystemKeyring)?;
+        }
+        Ok(config)
+    }
+
+    // 用服务的密码副本替换配置中的密码；副本缺失或无法解密时报错，不以空密码运行
+    #[cfg(any(windows, test))]
+    fn read_service_credentials(&mut self, path: &Path, store: &dyn PasswordStore) -> Result<()> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("No password saved for the service at {:?}, run \"sn service install\" again: {}", path, e))?;
+        let credentials: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&content)?;
+        for PasswordSlot { name, password, keyring_account, .. } in self.password_slots() {
+            keyring_account.clear();
+            password.clear();
+            if let Some(protected) = credentials.get(&name).and_then(|protected| protected.as_str()) {
+                let saved = store.unprotect(protected)
+                    .map_err(|e| anyhow!("Failed to decrypt the password of {} saved for the service, run \"sn service install\" again: {}", name, e))?;
+                *password = saved.into();
+            }
+        }
         Ok(())
     }
 
+    // 用于测试的直接保存和加载方法
     #[cfg(test)]
-    fn load_from(path: &PathBuf) -> Result<Self> {
-        if path.exists() {
-            let content = fs::read_to_string(path)?;
-            let config = serde_json::from_str(&content)?;
-            Ok(config)
-        } else {
-            Ok(Config {
-                auth_url: "http://10.1.1.1".to_string(),
-                ..Default::default()
-            })
+    fn save_to(&self, path: &Path, store: &dyn PasswordStore) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
         }
+
+        let content = Self::file_content(&self.stored_copy(store))?;
+        Self::write_atomic(path, &content)?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn load_from(path: &Path, store: &dyn PasswordStore) -> Result<Self> {
+        Ok(Self::read_from(path, store)?.unwrap_or_else(|| Config {
+            auth_url: "http://10.1.1.1".to_string(),
+            ..Default::default()
+        }))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::keychain::MemoryStore;
     use std::env;
 
     #[test]
     fn test_config_save_load() {
         let test_dir = env::current_dir().unwrap().join("test_config");
         fs::create_dir_all(&test_dir).unwrap();
-        let config_path = test_dir.join("config.json");
+        let config_path = test_dir.join("config.toml");
+        let store = MemoryStore::default();
 
         let config = Config {
             username: "test_user".to_string(),
-            password: "test_pass".to_string(),
+            password: "test_pass".into(),
             remember_password: true,
             auto_login: true,
             auth_url: "http://10.1.1.1".to_string(),
             isp: ISP::School,
+            ..Default::default()
         };
 
         // 保存配置
-        config.save_to(&config_path).unwrap();
+        config.save_to(&config_path, &store).unwrap();
 
         // 读取配置
-        let loaded_config = Config::load_from(&config_path).unwrap();
+        let loaded_config = Config::load_from(&config_path, &store).unwrap();
 
         // 因为remember_password为true，所有字段都应该保持不变
         assert_eq!(config.username, loaded_config.username);
@@ -165,22 +1166,24 @@ mod tests {
     fn test_config_no_remember() {
         let test_dir = env::current_dir().unwrap().join("test_config_no_remember");
         fs::create_dir_all(&test_dir).unwrap();
-        let config_path = test_dir.join("config.json");
+        let config_path = test_dir.join("config.toml");
+        let store = MemoryStore::default();
 
         let config = Config {
             username: "test_user".to_string(),
-            password: "test_pass".to_string(),
+            password: "test_pass".into(),
             remember_password: false,
             auto_login: false,
             auth_url: "http://10.1.1.1".to_string(),
             isp: ISP::Mobile,
+            ..Default::default()
         };
 
         // 保存配置
-        config.save_to(&config_path).unwrap();
+        config.save_to(&config_path, &store).unwrap();
 
         // 读取配置
-        let loaded_config = Config::load_from(&config_path).unwrap();
+        let loaded_config = Config::load_from(&config_path, &store).unwrap();
 
         // 验证结果
         assert_eq!(config.username, loaded_config.username);
@@ -192,4 +1195,261 @@ mod tests {
 
         fs::remove_dir_all(test_dir).unwrap_or_default();
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_config_save_keeps_backup() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let config_path = test_dir.path().join("config.toml");
+        let store = MemoryStore::default();
+
+        let mut config = Config {
+            username: "first".to_string(),
+            remember_password: true,
+            auth_url: "http://10.1.1.1".to_string(),
+            ..Default::default()
+        };
+        config.save_to(&config_path, &store).unwrap();
+        assert!(!Config::backup_path(&config_path).exists());
+
+        config.username = "second".to_string();
+        config.save_to(&config_path, &store).unwrap();
+        assert!(!Config::with_suffix(&config_path, "tmp").exists());
+
+        // 写入中途崩溃导致配置损坏时，备份中仍是上一次的完整配置
+        fs::write(&config_path, "{\"username\": \"sec").unwrap();
+        assert!(Config::load_from(&config_path, &store).is_err());
+        let backup = fs::read_to_string(Config::backup_path(&config_path)).unwrap();
+        assert_eq!(Config::parse(&backup).unwrap().username, "first");
+    }
+
+    #[test]
+    fn test_auth_backend() {
+        let config: Config = serde_json::from_str(r#"{"username":"","password":"","remember_password":false,"auto_login":false,"auth_url":"","isp":"School"}"#).unwrap();
+        assert_eq!(config.auth_backend, AuthBackend::WebDriver);
+        assert_eq!(config.browser, BrowserKind::Chrome);
+        assert!(config.uses_http_auth(true));
+
+        let config = Config { auth_backend: AuthBackend::HttpPortal, ..Default::default() };
+        assert!(config.uses_http_auth(false));
+        assert_eq!(serde_json::to_value(&config).unwrap()["auth_backend"], "HttpPortal");
+
+        // 先 HTTP 后浏览器时由登录流程自行回退，未启用 webdriver 功能时只用 HTTP
+        let config = Config { auth_backend: AuthBackend::HttpThenBrowser, ..Default::default() };
+        assert_eq!(config.uses_http_auth(false), cfg!(not(feature = "webdriver")));
+
+        // 不支持浏览器登录的门户始终用 HTTP
+        let config = Config { portal_type: PortalType::DrcomWeb, ..Default::default() };
+        assert!(config.uses_http_auth(false));
+    }
+
+    #[test]
+    fn test_config_changes() {
+        let old = Config { username: "8208190101".to_string(), password: "secret".into(), ..Default::default() };
+        let mut new = old.clone();
+        assert!(new.changes_from(&old).is_empty());
+
+        new.username = "8208190102".to_string();
+        new.password = "changed".into();
+        new.auto_login = true;
+        let changes = new.changes_from(&old);
+        assert_eq!(changes.len(), 3);
+        assert!(changes.contains(&ConfigChange { field: "username".to_string(), old: "8208190101".to_string(), new: "8208190102".to_string() }));
+        assert!(changes.contains(&ConfigChange { field: "password".to_string(), old: "••••••".to_string(), new: "••••••".to_string() }));
+        assert!(changes.contains(&ConfigChange { field: "auto_login".to_string(), old: "false".to_string(), new: "true".to_string() }));
+
+        // Webhook 地址中的密钥不写入日志
+        let old = new.clone();
+        new.webhooks.push(Webhook { url: "https://oapi.dingtalk.com/robot/send?access_token=abc".to_string(), secret: "SEC1".to_string(), ..Default::default() });
+        assert_eq!(new.changes_from(&old), vec![ConfigChange { field: "webhooks".to_string(), old: "(none)".to_string(), new: "oapi.dingtalk.com".to_string() }]);
+        #[cfg(any(feature = "control_api", feature = "downloader"))]
+        {
+            let redacted = new.redacted().unwrap();
+            assert_eq!(redacted["webhooks"][0]["url"], REDACTED);
+            assert_eq!(redacted["webhooks"][0]["secret"], REDACTED);
+        }
+    }
+
+    #[test]
+    fn test_config_migrations() {
+        let config = Config::parse(r#"{
+            "username": "test_user",
+            "password": "test_pass",
+            "remember_password": true,
+            "auto_login": false,
+            "auth_url": "",
+            "isp": "Mobile"
+        }"#).unwrap();
+
+        assert_eq!(config.auth_url, "http://10.1.1.1");
+        assert_eq!(config.isp, ISP::Mobile);
+        assert_eq!(config.migrations.len(), 1);
+        assert!(config.last_seen_version.is_empty());
+
+        // 无法解析的内容报告为损坏的配置
+        for content in ["username = ", "{\"isp\": 1}", "[1, 2]"] {
+            assert!(Config::parse(content).unwrap_err().downcast_ref::<ConfigCorrupt>().is_some(), "{}", content);
+        }
+    }
+
+    #[test]
+    fn test_password_in_keyring() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let config_path = test_dir.path().join("config.toml");
+        let store = MemoryStore::default();
+
+        // 旧版本明文保存在 config.json 中的密码在加载时移入凭据存储，转换为 config.toml 后删除旧文件和备份
+        let legacy_path = test_dir.path().join("config.json");
+        fs::write(&legacy_path, r#"{"username":"8208190101","password":"secret","remember_password":true,"auto_login":true,"auth_url":"http://10.1.1.1","isp":"School","check_interval_secs":null}"#).unwrap();
+        fs::write(Config::backup_path(&legacy_path), "{}").unwrap();
+        let config = Config::load_from(&config_path, &store).unwrap();
+        assert_eq!(config.password, "secret");
+        assert_eq!(config.keyring_account, "8208190101");
+        assert_eq!(config.migrations.len(), 1);
+        assert_eq!(store.passwords.borrow().get("8208190101").map(String::as_str), Some("secret"));
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert!(saved.starts_with("version = 1\n") && !saved.contains("secret"), "{}", saved);
+        assert!(!legacy_path.exists() && !Config::backup_path(&legacy_path).exists());
+        assert_eq!(Config::load_from(&config_path, &store).unwrap().password, "secret");
+
+        // 取消记住密码后删除凭据存储中的条目
+        let config = Config { remember_password: false, ..config };
+        config.save_to(&config_path, &store).unwrap();
+        assert!(store.passwords.borrow().is_empty());
+
+        // 凭据存储不可用时仍把密码写在配置文件里
+        let store = MemoryStore { unavailable: true, ..Default::default() };
+        let config = Config { remember_password: true, password: "secret".into(), ..config };
+        config.save_to(&config_path, &store).unwrap();
+        let loaded = Config::load_from(&config_path, &store).unwrap();
+        assert_eq!(loaded.password, "secret");
+        assert!(loaded.keyring_account.is_empty());
+
+        // 能在本机加密时，明文密码在加载时改为密文，文件和备份中都不再有明文
+        let store = MemoryStore { unavailable: true, can_protect: true, ..Default::default() };
+        let loaded = Config::load_from(&config_path, &store).unwrap();
+        assert_eq!(loaded.password, "secret");
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert!(saved.contains("password = \"dpapi:terces\""), "{}", saved);
+        assert!(!fs::read_to_string(Config::backup_path(&config_path)).unwrap().contains("secret"));
+        assert_eq!(Config::load_from(&config_path, &store).unwrap().password, "secret");
+
+        // 无法解密（换了电脑或用户）时清空，需要重新输入
+        let loaded = Config::load_from(&config_path, &MemoryStore::default()).unwrap();
+        assert!(loaded.password.is_empty());
+        assert_eq!(loaded.migrations.len(), 1);
+
+        // MQTT 和 WebDAV 密码同样放进凭据存储，不受“记住密码”控制
+        let store = MemoryStore::default();
+        let config = Config {
+            remember_password: false,
+            mqtt_username: "ha".to_string(),
+            mqtt_password: "broker-secret".into(),
+            webdav_username: "me@example.com".to_string(),
+            webdav_password: "dav-secret".into(),
+            ..config
+        };
+        config.save_to(&config_path, &store).unwrap();
+        assert!(!fs::read_to_string(&config_path).unwrap().contains("secret"));
+        assert_eq!(store.passwords.borrow().get("mqtt:ha").map(String::as_str), Some("broker-secret"));
+        assert_eq!(store.passwords.borrow().get("webdav:me@example.com").map(String::as_str), Some("dav-secret"));
+        let loaded = Config::load_from(&config_path, &store).unwrap();
+        assert_eq!(loaded.mqtt_password, "broker-secret");
+        assert_eq!(loaded.webdav_password, "dav-secret");
+        assert!(loaded.password.is_empty());
+    }
+
+    #[test]
+    fn test_service_credentials() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let path = test_dir.path().join("service_credentials.json");
+        let store = MemoryStore { can_protect: true, ..Default::default() };
+        let config = Config { username: "8208190101".to_string(), password: "secret".into(), remember_password: true, ..Default::default() };
+
+        // 没有密码时不安装，密码副本中没有明文
+        assert!(Config { password: Default::default(), ..config.clone() }.write_service_credentials(&path, &store).is_err());
+        config.write_service_credentials(&path, &store).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("secret"));
+
+        // 服务只使用副本中的密码，不读取凭据存储
+        let mut loaded = Config { password: Default::default(), keyring_account: "8208190101".to_string(), ..config.clone() };
+        loaded.read_service_credentials(&path, &store).unwrap();
+        assert_eq!(loaded.password, "secret");
+        assert!(loaded.keyring_account.is_empty());
+
+        // 副本缺失或无法解密时报错，不以空密码运行
+        assert!(loaded.read_service_credentials(&path, &MemoryStore::default()).is_err());
+        assert!(loaded.read_service_credentials(&test_dir.path().join("missing.json"), &store).is_err());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let config_path = test_dir.path().join("config.toml");
+        let store = MemoryStore::default();
+
+        // 旧版本配置中的账号成为 Default
+        let mut config = Config::parse(r#"{"username":"8208190101","password":"secret","remember_password":true,"auto_login":true,"auth_url":"http://10.1.1.1","isp":"School"}"#).unwrap();
+        assert_eq!(config.active_profile, "Default");
+        assert_eq!(config.profiles.len(), 1);
+
+        config.add_profile(" Library ").unwrap();
+        assert_eq!(config.active_profile, "Library");
+        assert!(config.username.is_empty());
+        assert_eq!(config.auth_url, "http://10.1.1.1");
+        assert!(config.add_profile("Default").is_err());
+        config.username = "8208190102".to_string();
+        config.password = "library".into();
+        config.isp = ISP::Mobile;
+        config.form_index = 1;
+
+        // 切换时保留未保存的当前账号修改
+        config.switch_profile("Default").unwrap();
+        assert_eq!((config.username.as_str(), config.password.expose(), config.isp), ("8208190101", "secret", ISP::School));
+        assert_eq!(config.form_index, 0);
+        config.rename_profile("Default", "Dorm").unwrap();
+        assert_eq!(config.active_profile, "Dorm");
+
+        // 每个账号的密码都放进凭据存储
+        config.save_to(&config_path, &store).unwrap();
+        assert!(!fs::read_to_string(&config_path).unwrap().contains("library"));
+        let mut loaded = Config::load_from(&config_path, &store).unwrap();
+        loaded.switch_profile("Library").unwrap();
+        assert_eq!((loaded.username.as_str(), loaded.password.expose(), loaded.isp), ("8208190102", "library", ISP::Mobile));
+        assert_eq!(loaded.form_index, 1);
+
+        // 删除当前账号后切换到第一个账号，其密码从凭据存储中删除
+        loaded.remove_profile("Library", &store).unwrap();
+        assert_eq!(loaded.active_profile, "Dorm");
+        assert_eq!(loaded.password, "secret");
+        assert!(!store.passwords.borrow().contains_key("8208190102"));
+        assert!(loaded.remove_profile("Dorm", &store).is_err());
+    }
+
+    #[test]
+    fn test_profile_overrides() {
+        let mut config = Config {
+            probes: vec![Probe::Icmp { host: "223.5.5.5".to_string() }],
+            download_mirrors: vec!["https://cdn.npmmirror.com/binaries/chrome-for-testing".to_string()],
+            ..Default::default()
+        };
+        config.add_profile("Xiangya").unwrap();
+        assert_eq!(config.effective_probes(), config.probes);
+
+        // 只覆盖探测目标，下载镜像沿用全局设置
+        let campus_probes = vec![Probe::Tcp { host: "www.csu.edu.cn".to_string(), port: 443 }];
+        config.active_profile_mut().probes = Some(campus_probes.clone());
+        config.username = "8208190103".to_string();
+        assert_eq!(config.effective_probes(), campus_probes);
+        #[cfg(feature = "downloader")]
+        assert_eq!(config.effective_download_mirrors(), config.download_mirrors);
+
+        // 切换账号和保存时保留单独的设置
+        config.add_profile("Dorm").unwrap();
+        assert_eq!(config.effective_probes(), config.probes);
+        config.switch_profile("Xiangya").unwrap();
+        assert_eq!(config.username, "8208190103");
+        let applied = config.active_as_profile().applied_to(&config);
+        assert_eq!(applied.probes, campus_probes);
+        assert_eq!(applied.download_mirrors, config.download_mirrors);
+    }
+}
@@ -0,0 +1,200 @@
+// 配置文件格式：配置保存为带版本号的 config.toml，读写时与 serde_json::Value 相互转换；Config 整体带 #[serde(default)]，
+// 版本号保存在 Config::version 中。旧版本的 config.json 没有版本号，视为版本 0；加载时按 MIGRATIONS 逐级迁移到当前版本，
+// 之后新增字段只需带上默认值
+use anyhow::{anyhow, bail, Result};
+use serde_json::{Map, Value};
+use toml_edit::{Array, ArrayOfTables, Document, InlineTable, Item, Table};
+
+/// 当前的配置文件格式版本
+pub const CONFIG_VERSION: u32 = 1;
+/// 配置中记录版本号的字段
+pub const VERSION_KEY: &str = "version";
+
+// 从版本 N 迁移到 N + 1 的步骤，下标为 N；返回给用户看的说明，不需要说明时返回 None
+type Migration = fn(&mut Map<String, Value>) -> Option<String>;
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [
+    // 0 → 1：config.json 改为 config.toml，TOML 没有 null，未设置的可选项改为省略
+    |config| {
+        drop_nulls(config);
+        None
+    },
+];
+
+// 递归删除值为 null 的字段
+fn drop_nulls(map: &mut Map<String, Value>) {
+    map.retain(|_, value| !value.is_null());
+    for value in map.values_mut() {
+        match value {
+            Value::Object(map) => drop_nulls(map),
+            Value::Array(items) => items.iter_mut().filter_map(Value::as_object_mut).for_each(drop_nulls),
+            _ => {}
+        }
+    }
+}
+
+/// 配置的版本号，没有版本号的旧配置为 0
+pub fn version(config: &Value) -> u32 {
+    config[VERSION_KEY].as_u64().map_or(0, |version| version as u32)
+}
+
+/// 把配置迁移到当前版本，返回迁移说明；更新版本的程序写入的配置原样保留，只加载认识的字段
+pub fn migrate(config: &mut Value) -> Result<Vec<String>> {
+    let from = version(config);
+    let Some(map) = config.as_object_mut() else {
+        bail!("The config is not a table");
+    };
+    if from > CONFIG_VERSION {
+        return Ok(vec![format!(
+            "The config was written by a newer version (format {}), settings this version does not know are dropped when it is saved",
+            from
+        )]);
+    }
+    let notes = MIGRATIONS[from as usize..].iter().filter_map(|migration| migration(map)).collect();
+    map.insert(VERSION_KEY.to_string(), CONFIG_VERSION.into());
+    Ok(notes)
+}
+
+// JSON 值转为 TOML 中的值，用于数组和内联表中；null 没有对应的值
+fn to_value(value: &Value) -> Result<Option<toml_edit::Value>> {
+    Ok(Some(match value {
+        Value::Null => return Ok(None),
+        Value::Bool(value) => (*value).into(),
+        Value::Number(number) => match (number.as_i64(), number.as_f64()) {
+            (Some(integer), _) => integer.into(),
+            (None, Some(float)) if !number.is_u64() => float.into(),
+            _ => bail!("Number {} is too large for TOML", number),
+        },
+        Value::String(value) => value.as_str().into(),
+        Value::Array(items) => {
+            let mut array = Array::new();
+            for item in items {
+                array.extend(to_value(item)?);
+            }
+            array.into()
+        }
+        Value::Object(map) => {
+            let mut table = InlineTable::new();
+            for (key, value) in map {
+                if let Some(value) = to_value(value)? {
+                    table.insert(key, value);
+                }
+            }
+            table.into()
+        }
+    }))
+}
+
+// JSON 值转为 TOML 中的项：对象为表，对象组成的数组为表数组，其他为值
+fn to_item(value: &Value) -> Result<Item> {
+    match value {
+        Value::Object(map) => Ok(Item::Table(to_table(map)?)),
+        Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object) => {
+            let mut tables = ArrayOfTables::new();
+            for item in items.iter().filter_map(Value::as_object) {
+                tables.push(to_table(item)?);
+            }
+            Ok(Item::ArrayOfTables(tables))
+        }
+        value => Ok(to_value(value)?.map_or(Item::None, Item::Value)),
+    }
+}
+
+fn to_table(map: &Map<String, Value>) -> Result<Table> {
+    let mut table = Table::new();
+    for (key, value) in map {
+        let item = to_item(value)?;
+        if !item.is_none() {
+            table.insert(key, item);
+        }
+    }
+    Ok(table)
+}
+
+/// 把配置转为 TOML 文本，版本号写在开头
+pub fn to_toml(config: &Value) -> Result<String> {
+    let map = config.as_object().ok_or_else(|| anyhow!("The config is not a table"))?;
+    let mut document = Document::new();
+    if let Some(version) = map.get(VERSION_KEY) {
+        document.insert(VERSION_KEY, to_item(version)?);
+    }
+    for (key, value) in map.iter().filter(|(key, _)| *key != VERSION_KEY) {
+        let item = to_item(value)?;
+        if !item.is_none() {
+            document.insert(key, item);
+        }
+    }
+    Ok(document.to_string())
+}
+
+// TOML 中的值转为 JSON 值，日期时间按文本处理
+fn from_value(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(value) => value.value().clone().into(),
+        toml_edit::Value::Integer(value) => (*value.value()).into(),
+        toml_edit::Value::Float(value) => (*value.value()).into(),
+        toml_edit::Value::Boolean(value) => (*value.value()).into(),
+        toml_edit::Value::Datetime(value) => value.value().to_string().into(),
+        toml_edit::Value::Array(array) => array.iter().map(from_value).collect(),
+        toml_edit::Value::InlineTable(table) => {
+            table.iter().map(|(key, value)| (key.to_string(), from_value(value))).collect::<Map<_, _>>().into()
+        }
+    }
+}
+
+fn from_item(item: &Item) -> Value {
+    match item {
+        Item::None => Value::Null,
+        Item::Value(value) => from_value(value),
+        Item::Table(table) => from_table(table).into(),
+        Item::ArrayOfTables(tables) => tables.iter().map(|table| Value::from(from_table(table))).collect(),
+    }
+}
+
+fn from_table(table: &Table) -> Map<String, Value> {
+    table.iter().map(|(key, item)| (key.to_string(), from_item(item))).collect()
+}
+
+/// 解析 TOML 配置文件
+pub fn from_toml(content: &str) -> Result<Value> {
+    let document: Document = content.parse()?;
+    Ok(from_table(document.as_table()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_toml_roundtrip() {
+        let mut config = json!({
+            "username": "8208190101",
+            "check_interval_secs": null,
+            "probe_timeout_ms": 2000,
+            "download_mirrors": ["https://mirror.example/cft"],
+            "webhooks": [],
+            "notifications": {"network_down": true},
+            "profiles": [
+                {"name": "Default", "username": "8208190101", "probes": [{"Icmp": {"host": "114.114.114.114"}}]},
+                {"name": "Dorm", "username": "8208190102", "download_mirrors": null},
+            ],
+        });
+        assert_eq!(version(&config), 0);
+        assert!(migrate(&mut config).unwrap().is_empty());
+        assert_eq!(version(&config), CONFIG_VERSION);
+        assert!(config.get("check_interval_secs").is_none());
+        assert!(config["profiles"][1].get("download_mirrors").is_none());
+
+        let toml = to_toml(&config).unwrap();
+        assert!(toml.starts_with("version = 1\n"), "{}", toml);
+        assert!(toml.contains("[[profiles]]\nname = \"Default\""), "{}", toml);
+        assert_eq!(from_toml(&toml).unwrap(), config);
+
+        // 更新版本的程序写入的配置不降级
+        let mut newer = json!({"version": CONFIG_VERSION + 1, "username": "8208190101"});
+        assert_eq!(migrate(&mut newer).unwrap().len(), 1);
+        assert_eq!(version(&newer), CONFIG_VERSION + 1);
+        assert!(to_toml(&json!({"big": u64::MAX})).is_err());
+        assert!(from_toml("username = ").is_err());
+    }
+}
@@ -0,0 +1,72 @@
+// 运行中的配置：设置保存后发布新的配置，网络监控和自动登录线程订阅后在下一轮使用新值（检查间隔、探测目标、指标输出、
+// 防抖时间、学期日历、预热浏览器等），不需要重启程序或后台线程
+use crate::backend::config::Config;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// 共享的配置句柄，克隆后发布到同一处
+#[derive(Clone)]
+pub struct ConfigHandle {
+    sender: Arc<watch::Sender<Arc<Config>>>,
+}
+
+impl ConfigHandle {
+    pub fn new(config: Config) -> Self {
+        Self { sender: Arc::new(watch::Sender::new(Arc::new(config))) }
+    }
+
+    /// 发布新的配置，与当前配置相同时不通知订阅者；返回发生变化的字段
+    pub fn publish(&self, config: &Config) -> Vec<String> {
+        let mut changed = Vec::new();
+        self.sender.send_if_modified(|current| {
+            changed = config.changes_from(current).into_iter().map(|change| change.field).collect();
+            if !changed.is_empty() {
+                *current = Arc::new(config.clone());
+            }
+            !changed.is_empty()
+        });
+        changed
+    }
+
+    /// 订阅配置变化，订阅时的配置视为已读
+    pub fn subscribe(&self) -> watch::Receiver<Arc<Config>> {
+        self.sender.subscribe()
+    }
+}
+
+/// 等待配置变化，最多等待 timeout；返回是否有新的配置。后台线程用它代替 sleep，修改间隔后不必等上一轮结束
+pub async fn changed_within(settings: &mut watch::Receiver<Arc<Config>>, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    match tokio::time::timeout_at(deadline, settings.changed()).await {
+        Ok(Ok(())) => true,
+        // 句柄已释放时不会再有新的配置，照常等到超时
+        Ok(Err(_)) => {
+            tokio::time::sleep_until(deadline).await;
+            false
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_config_handle() {
+        let handle = ConfigHandle::new(Config::default());
+        let mut settings = handle.subscribe();
+        assert!(!settings.has_changed().unwrap());
+
+        // 内容没有变化时不唤醒订阅者
+        assert!(handle.publish(&Config::default()).is_empty());
+        assert!(!changed_within(&mut settings, Duration::from_millis(20)).await);
+
+        let config = Config { check_interval_secs: Some(10), ..Config::default() };
+        assert_eq!(handle.publish(&config), vec!["check_interval_secs".to_string()]);
+        assert!(changed_within(&mut settings, Duration::from_secs(1)).await);
+        assert_eq!(settings.borrow_and_update().check_interval_secs, Some(10));
+    }
+}
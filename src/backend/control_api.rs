@@ -0,0 +1,458 @@
+// 本机控制接口：在 127.0.0.1:8787（可改为 0.0.0.0 供局域网访问）提供一个很小的 HTTP 接口，脚本、Home Assistant
+// 或手机可以查询状态（GET /status）、触发登录登出（POST /login、/logout）和读取配置（GET /config，隐去密码和令牌）。
+// GET / 是内置的网页控制台（dashboard.html），在浏览器中输入令牌后显示状态、最近的会话历史和请求记录，可以登录登出，
+// sn daemon 不装图形界面也能管理；页面本身不含数据，其余接口照常需要令牌。
+// 每个请求都要在 Authorization 请求头中带上配置中的令牌；接口很简单，直接在 TcpListener 上解析请求，不引入 Web 框架。
+// 每个请求（包括令牌错误的）的来源、命令、参数和结果都记入会话历史旁的 api_audit.jsonl
+use crate::backend::config::{AuthBackend, Config, REDACTED};
+use crate::backend::config_handle::ConfigHandle;
+use crate::backend::history::{self, Action, ApiCall, HistoryEntry, Trigger};
+use crate::backend::timezone;
+use crate::backend::network_monitor::NetworkMonitor;
+use crate::backend::portal;
+use crate::backend::watchdog::{self, StopHandle};
+use anyhow::{anyhow, Result};
+use log::warn;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// 未配置监听地址时使用，只接受本机的请求
+pub const DEFAULT_BIND: &str = "127.0.0.1:8787";
+// 请求头的大小上限，超过时直接关闭连接
+const MAX_REQUEST_BYTES: usize = 16 * 1024;
+// 读取请求头的超时，避免不发数据的连接一直占用
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+// 网页控制台
+const DASHBOARD: &str = include_str!("dashboard.html");
+// GET /history 返回的会话历史和请求记录条数
+const RECENT_ENTRIES: usize = 20;
+
+/// 启动接口所需的设置，变化时重新启动
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlApiSettings {
+    pub bind: String,
+    pub token: String,
+    // 总是通过 HTTP 接口登录登出（daemon 本身就只用 HTTP），为 false 时按配置的登录方式，只用浏览器时接口不代为登录
+    pub http_only: bool,
+    // 记录每个请求的文件
+    pub audit_path: PathBuf,
+    // 会话历史文件，/login、/logout 记入其中，/history 从中读取
+    pub history_path: PathBuf,
+}
+
+impl ControlApiSettings {
+    /// 从配置读取，未开启或没有设置令牌时返回 None
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let token = config.control_api_token.trim();
+        if !config.control_api || token.is_empty() {
+            return None;
+        }
+        let bind = config.control_api_bind.trim();
+        Some(Self {
+            bind: if bind.is_empty() { DEFAULT_BIND } else { bind }.to_string(),
+            token: token.to_string(),
+            http_only: false,
+            audit_path: history::api_audit_path(),
+            history_path: history::default_path(),
+        })
+    }
+}
+
+/// 随机生成的令牌
+pub fn generate_token() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// 解析出的请求，只关心方法、路径、查询参数、令牌和 User-Agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    // 地址中 ? 之后的部分，没有时为空
+    pub query: String,
+    // 取自 Authorization: Bearer 请求头；不接受查询参数中的令牌，地址会出现在浏览器历史和代理日志中
+    pub token: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl Request {
+    /// 解析请求行和请求头，格式不对时返回 None
+    pub fn parse(head: &str) -> Option<Self> {
+        let mut lines = head.lines();
+        let mut request_line = lines.next()?.split_whitespace();
+        let method = request_line.next()?.to_ascii_uppercase();
+        let target = request_line.next()?;
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let headers: Vec<(&str, &str)> = lines
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .collect();
+        let header = |wanted: &str| headers.iter().find(|(name, _)| name.eq_ignore_ascii_case(wanted)).map(|(_, value)| *value);
+        let token = header("authorization")
+            .and_then(|value| value.split_once(' '))
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+            .map(|(_, token)| token.trim().to_string());
+        let user_agent = header("user-agent").filter(|agent| !agent.is_empty()).map(str::to_string);
+        Some(Self { method, path: path.to_string(), query: query.to_string(), token, user_agent })
+    }
+
+    /// 记入审计记录的查询参数，隐去其中的令牌
+    pub fn redacted_query(&self) -> String {
+        self.query.split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _)) if name.eq_ignore_ascii_case("token") => format!("{}={}", name, REDACTED),
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+// 比较令牌，耗时与第几个字符不同无关
+fn token_matches(expected: &str, provided: &str) -> bool {
+    expected.len() == provided.len()
+        && expected.bytes().zip(provided.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// 处理请求时共用的状态
+struct ApiState {
+    config: ConfigHandle,
+    monitor: Arc<NetworkMonitor>,
+    token: String,
+    http_only: bool,
+    audit_path: PathBuf,
+    history_path: PathBuf,
+}
+
+fn error(status: u16, message: &str) -> (u16, Value) {
+    (status, json!({ "error": message }))
+}
+
+// 按路径处理请求，返回状态码和 JSON 内容
+async fn handle(state: &ApiState, request: &Request) -> (u16, Value) {
+    if !request.token.as_deref().is_some_and(|token| token_matches(&state.token, token)) {
+        return error(401, "Missing or wrong token");
+    }
+    let config = state.config.subscribe().borrow().clone();
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/status") => (200, status(&config, &state.monitor).await),
+        ("POST", "/login" | "/logout") if !state.http_only && config.auth_backend == AuthBackend::WebDriver && !config.uses_http_auth(false) => {
+            error(501, "The login method is Browser, which the control API cannot drive; switch it to HTTP portal or HTTP, then browser")
+        }
+        ("POST", "/login") => (200, action(&config, Action::Login, &state.history_path).await),
+        ("POST", "/logout") => (200, action(&config, Action::Logout, &state.history_path).await),
+        ("GET", "/history") => (200, recent(state)),
+        ("GET", "/config") => match config.redacted() {
+            Ok(value) => (200, value),
+            Err(e) => error(500, &e.to_string()),
+        },
+        (_, "/" | "/status" | "/config" | "/history") => error(405, "Use GET"),
+        (_, "/login" | "/logout") => error(405, "Use POST"),
+        _ => error(404, "Unknown endpoint, available: /status, /login, /logout, /config, /history"),
+    }
+}
+
+// 外网连通性和门户记录的会话
+async fn status(config: &Config, monitor: &NetworkMonitor) -> Value {
+    let mut report = json!({
+        "connectivity": monitor.connectivity(),
+        "connected": monitor.is_connected(),
+        "health_score": monitor.health_score(),
+        "latency_ms": monitor.latency_ms(),
+    });
+    match portal::http_provider(config).status().await {
+        Ok(status) => {
+            report["signed_in"] = json!(status.online);
+            report["account"] = json!(status.account);
+            report["ip"] = json!(status.ip);
+            report["used_bytes"] = json!(status.used_bytes);
+            report["duration_secs"] = json!(status.duration.map(|duration| duration.as_secs()));
+        }
+        Err(e) => report["portal_error"] = json!(e.to_string()),
+    }
+    report
+}
+
+// 最近的会话历史和请求记录，新的在前，附上按配置的时区显示的时间
+fn recent(state: &ApiState) -> Value {
+    fn latest<T: serde::Serialize>(entries: Vec<T>, time: impl Fn(&T) -> String) -> Vec<Value> {
+        entries.iter().rev().take(RECENT_ENTRIES).map(|entry| {
+            let mut value = json!(entry);
+            value["time"] = json!(time(entry));
+            value
+        }).collect()
+    }
+    json!({
+        "history": latest(history::load(&state.history_path), HistoryEntry::time),
+        "api_calls": latest(history::load_api_calls(&state.audit_path), ApiCall::time),
+    })
+}
+
+// 通过门户的 HTTP 接口登录或登出，结果格式与 sn login 的输出相同；先 HTTP 后浏览器的登录方式下不再换用浏览器
+async fn action(config: &Config, action: Action, history_path: &Path) -> Value {
+    let result = portal::http_action(config, action, Trigger::Api, history_path).await;
+    json!({
+        "action": action.label().to_lowercase(),
+        "success": result.is_ok(),
+        "message": result.unwrap_or_else(|e| e),
+    })
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        501 => "Not Implemented",
+        _ => "Internal Server Error",
+    }
+}
+
+// 一个请求的审计记录：结果取门户返回的消息或错误说明，其他请求为状态码的说明
+fn audit_entry(peer: SocketAddr, request: &Request, status: u16, body: &Value) -> ApiCall {
+    let result = body["message"].as_str().or_else(|| body["error"].as_str()).unwrap_or(reason(status));
+    ApiCall {
+        timestamp: timezone::now().timestamp(),
+        source: match &request.user_agent {
+            Some(agent) => format!("{} ({})", peer.ip(), agent),
+            None => peer.ip().to_string(),
+        },
+        command: format!("{} {}", request.method, request.path),
+        parameters: request.redacted_query(),
+        status,
+        success: status == 200 && body["success"].as_bool() != Some(false),
+        result: result.to_string(),
+    }
+}
+
+// 读取请求头，处理后写回响应并记入审计记录；请求体不需要，不读取。GET / 返回网页控制台，其余返回 JSON
+async fn serve(state: &ApiState, mut stream: TcpStream, peer: SocketAddr) {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    let read = tokio::time::timeout(READ_TIMEOUT, async {
+        while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+            match stream.read(&mut buf).await {
+                Ok(n) if n > 0 && head.len() + n <= MAX_REQUEST_BYTES => head.extend_from_slice(&buf[..n]),
+                _ => return false,
+            }
+        }
+        true
+    }).await;
+    if read != Ok(true) {
+        return;
+    }
+    let Some(request) = Request::parse(&String::from_utf8_lossy(&head)) else { return };
+    let (status, content_type, body) = if request.method == "GET" && request.path == "/" {
+        history::record_api_call(&state.audit_path, &audit_entry(peer, &request, 200, &Value::Null));
+        (200, "text/html; charset=utf-8", DASHBOARD.to_string())
+    } else {
+        let (status, body) = handle(state, &request).await;
+        history::record_api_call(&state.audit_path, &audit_entry(peer, &request, status, &body));
+        (status, "application/json", body.to_string())
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// 在后台线程中运行的控制接口，释放时停止监听
+pub struct ControlApiServer {
+    address: SocketAddr,
+    stop: Option<StopHandle>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ControlApiServer {
+    /// 监听设置的地址并在后台处理请求，地址无效或已被占用时返回错误
+    pub fn start(settings: ControlApiSettings, config: ConfigHandle, monitor: Arc<NetworkMonitor>) -> Result<Self> {
+        let listener = std::net::TcpListener::bind(&settings.bind)
+            .map_err(|e| anyhow!("Failed to start the control API on {}: {}", settings.bind, e))?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        let state = ApiState {
+            config,
+            monitor,
+            token: settings.token,
+            http_only: settings.http_only,
+            audit_path: settings.audit_path,
+            history_path: settings.history_path,
+        };
+        let (stop, mut stopped) = watchdog::stop_signal();
+
+        let thread = watchdog::spawn("control-api", move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    warn!("Failed to start the control API: {}", e);
+                    return;
+                }
+            };
+            // 门户插件的请求不能跨线程，所有连接在这个线程上并发处理
+            let local = tokio::task::LocalSet::new();
+            local.block_on(&runtime, async move {
+                let listener = match TcpListener::from_std(listener) {
+                    Ok(listener) => listener,
+                    Err(e) => {
+                        warn!("Failed to start the control API: {}", e);
+                        return;
+                    }
+                };
+                let state = Rc::new(state);
+                loop {
+                    tokio::select! {
+                        _ = stopped.stopped() => break,
+                        accepted = listener.accept() => match accepted {
+                            Ok((stream, peer)) => {
+                                let state = Rc::clone(&state);
+                                tokio::task::spawn_local(async move { serve(&state, stream, peer).await });
+                            }
+                            Err(e) => {
+                                warn!("Control API failed to accept a connection: {}", e);
+                                tokio::time::sleep(Duration::from_millis(100)).await;
+                            }
+                        },
+                    }
+                }
+            });
+        });
+
+        Ok(Self { address, stop: Some(stop), thread: Some(thread) })
+    }
+
+    /// 实际监听的地址
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for ControlApiServer {
+    // 等待后台线程释放端口，修改设置后可以立即在同一端口重新启动
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop.stop();
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let request = Request::parse("POST /login HTTP/1.1\r\nHost: 127.0.0.1\r\nauthorization: bearer  abc123 \r\nUser-Agent: curl/8.4.0\r\n\r\n").unwrap();
+        assert_eq!(request, Request {
+            method: "POST".to_string(),
+            path: "/login".to_string(),
+            query: String::new(),
+            token: Some("abc123".to_string()),
+            user_agent: Some("curl/8.4.0".to_string()),
+        });
+        let request = Request::parse("get /status?verbose=1&token=abc123 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str(), request.token.as_deref()), ("GET", "/status", None));
+        assert_eq!(request.redacted_query(), format!("verbose=1&token={}", REDACTED));
+        assert_eq!(Request::parse("GET /status HTTP/1.1\r\nAuthorization: Basic dXNlcg==\r\n\r\n").unwrap().token, None);
+        assert_eq!(Request::parse(""), None);
+
+        assert!(token_matches("abc123", "abc123"));
+        assert!(!token_matches("abc123", "abc124"));
+        assert!(!token_matches("abc123", "abc"));
+        assert_eq!(generate_token().len(), 32);
+    }
+
+    #[test]
+    fn test_control_api() {
+        let mut config = Config { control_api: true, control_api_bind: "127.0.0.1:0".to_string(), ..Config::default() };
+        assert_eq!(ControlApiSettings::from_config(&config), None);
+        config.control_api_token = " secret-token ".to_string();
+        config.password.edit().push_str("portal-password");
+        let mut settings = ControlApiSettings::from_config(&config).unwrap();
+        assert_eq!(settings.token, "secret-token");
+        let dir = tempfile::tempdir().unwrap();
+        settings.audit_path = dir.path().join("api_audit.jsonl");
+        settings.history_path = dir.path().join("history.jsonl");
+        let audit_path = settings.audit_path.clone();
+        history::append(&settings.history_path, &HistoryEntry {
+            timestamp: 1_700_000_000,
+            action: Action::Logout,
+            trigger: Trigger::Manual,
+            method: "HTTP".to_string(),
+            success: true,
+            error: None,
+            latency_ms: 120,
+        }).unwrap();
+        assert_eq!(ControlApiSettings::from_config(&Config { control_api_bind: String::new(), ..config.clone() }).unwrap().bind, DEFAULT_BIND);
+
+        let server = ControlApiServer::start(settings, ConfigHandle::new(config), Arc::new(NetworkMonitor::new())).unwrap();
+        let address = server.address();
+        let send = |request: &str| {
+            use std::io::{Read, Write};
+            let mut stream = std::net::TcpStream::connect(address).unwrap();
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+
+        assert!(send("GET /config HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 401 Unauthorized"));
+        assert!(send("GET /config?token=secret-token HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 401"));
+        assert!(send("DELETE /login HTTP/1.1\r\nAuthorization: Bearer secret-token\r\n\r\n").starts_with("HTTP/1.1 405"));
+        // 只用浏览器登录时不代为登录
+        assert!(send("POST /login HTTP/1.1\r\nAuthorization: Bearer secret-token\r\n\r\n").starts_with("HTTP/1.1 501"));
+        assert!(send("GET /reboot HTTP/1.1\r\nAuthorization: Bearer secret-token\r\n\r\n").starts_with("HTTP/1.1 404"));
+
+        let response = send("GET /config HTTP/1.1\r\nAuthorization: Bearer secret-token\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        let body: Value = serde_json::from_str(response.split_once("\r\n\r\n").unwrap().1).unwrap();
+        assert_eq!(body["password"], REDACTED);
+        assert_eq!(body["control_api_token"], REDACTED);
+        assert!(!response.contains("secret"));
+
+        // 网页控制台不需要令牌，页面中没有数据；最近的记录需要令牌，新的在前
+        let response = send("GET / HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/html"), "{}", response);
+        assert!(response.contains("Authorization") && !response.contains("secret"));
+        assert!(send("POST / HTTP/1.1\r\nAuthorization: Bearer secret-token\r\n\r\n").starts_with("HTTP/1.1 405"));
+        assert!(send("GET /history HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 401"));
+        let response = send("GET /history HTTP/1.1\r\nAuthorization: Bearer secret-token\r\n\r\n");
+        let body: Value = serde_json::from_str(response.split_once("\r\n\r\n").unwrap().1).unwrap();
+        assert_eq!(body["history"][0]["action"], "logout");
+        assert_eq!(body["history"][0]["time"], timezone::at(1_700_000_000).format("%Y-%m-%d %H:%M:%S").to_string());
+        assert_eq!(body["api_calls"][0]["command"], "GET /history");
+        assert_eq!(body["api_calls"][1]["command"], "POST /");
+
+        // 每个请求都记入审计记录，令牌不写入
+        let calls = history::load_api_calls(&audit_path);
+        assert_eq!(calls.len(), 10);
+        assert_eq!((calls[0].command.as_str(), calls[0].status, calls[0].success), ("GET /config", 401, false));
+        assert_eq!(calls[0].result, "Missing or wrong token");
+        assert_eq!(calls[1].parameters, format!("token={}", REDACTED));
+        assert_eq!((calls[3].command.as_str(), calls[3].status), ("POST /login", 501));
+        assert_eq!((calls[5].source.as_str(), calls[5].success), ("127.0.0.1", true));
+        assert!(!std::fs::read_to_string(&audit_path).unwrap().contains("secret-token"));
+
+        // 停止后端口被释放
+        drop(server);
+        assert!(std::net::TcpStream::connect(address).is_err());
+    }
+}
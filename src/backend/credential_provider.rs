@@ -0,0 +1,247 @@
+// 外部凭据提供程序：机房部署时按机器或 Windows 登录用户获取校园网账号，程序本身不保存密码
+//
+// 协议：连接到命名管道（Windows，如 \\.\pipe\csunetwork-credentials）或 Unix 套接字，
+// 写入一行 JSON 请求，读取一行 JSON 响应：
+//   请求 {"machine": "LAB-PC-01", "user": "student01", "domain": "CSU"}
+//   响应 {"username": "8208190101", "password": "...", "isp": "Mobile"}
+//   出错 {"error": "no account mapped for student01"}
+use crate::backend::config::{Config, ISP};
+use crate::backend::secret::SecretString;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+// 等待提供程序响应的时间，超时后登录失败，而不是让登录线程一直卡住
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 向提供程序说明当前机器和登录用户。用户名和机器名取自系统（Windows 为 GetUserNameW/GetComputerNameW，
+/// Unix 为当前进程的 uid），不读环境变量；但任何本机进程都能连上管道并自称任意用户，
+/// 提供程序只能把这些字段当作查找账号的提示，授权须以连接对端的身份为准
+/// （Windows 用 GetNamedPipeClientProcessId 或 ImpersonateNamedPipeClient，Unix 用 SO_PEERCRED）
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CredentialRequest {
+    pub machine: String,
+    pub user: String,
+    pub domain: String,
+}
+
+impl CredentialRequest {
+    /// 根据当前登录用户构造请求
+    pub fn current() -> Self {
+        Self {
+            machine: os::machine(),
+            user: os::user(),
+            // 域名没有不依赖环境变量的简单取法，仅供参考
+            domain: std::env::var("USERDOMAIN").unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(windows)]
+mod os {
+    use winapi::um::winbase::{GetComputerNameW, GetUserNameW};
+
+    // 调用 GetUserNameW 这类把 UTF-16 字符串写入缓冲区的函数，size 传入时为缓冲区长度
+    fn query(call: unsafe extern "system" fn(*mut u16, *mut u32) -> i32) -> String {
+        let mut buffer = [0u16; 257];
+        let mut size = buffer.len() as u32;
+        if unsafe { call(buffer.as_mut_ptr(), &mut size) } == 0 {
+            return String::new();
+        }
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        String::from_utf16_lossy(&buffer[..len])
+    }
+
+    pub fn user() -> String {
+        query(GetUserNameW)
+    }
+
+    pub fn machine() -> String {
+        query(GetComputerNameW)
+    }
+}
+
+#[cfg(not(windows))]
+mod os {
+    use std::os::unix::fs::MetadataExt;
+
+    // 当前进程的 uid 对应的用户名，/etc/passwd 中没有时为 uid 本身
+    pub fn user() -> String {
+        let Ok(uid) = std::fs::metadata("/proc/self").map(|meta| meta.uid()) else {
+            return String::new();
+        };
+        let passwd = std::fs::read_to_string("/etc/passwd").unwrap_or_default();
+        passwd_user(&passwd, uid).unwrap_or_else(|| uid.to_string())
+    }
+
+    pub fn machine() -> String {
+        std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .map(|name| name.trim().to_string())
+            .unwrap_or_default()
+    }
+
+    // 在 /etc/passwd 中查找 uid 对应的用户名（name:password:uid:...）
+    pub fn passwd_user(passwd: &str, uid: u32) -> Option<String> {
+        passwd.lines()
+            .map(|line| line.split(':').collect::<Vec<_>>())
+            .find(|fields| fields.get(2).and_then(|id| id.parse::<u32>().ok()) == Some(uid))
+            .map(|fields| fields[0].to_string())
+    }
+}
+
+/// 提供程序返回的凭据
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct CredentialResponse {
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: SecretString,
+    #[serde(default)]
+    pub isp: Option<ISP>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// 通过命名管道或 Unix 套接字获取凭据
+pub struct PipeCredentialProvider {
+    path: String,
+}
+
+impl PipeCredentialProvider {
+    pub fn new(path: &str) -> Self {
+        Self { path: path.to_string() }
+    }
+
+    /// 发送请求并读取凭据，提供程序在 PROVIDER_TIMEOUT 内没有响应时返回错误
+    pub async fn fetch(&self, request: &CredentialRequest) -> Result<CredentialResponse> {
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+
+        let response = tokio::time::timeout(PROVIDER_TIMEOUT, self.exchange(&line))
+            .await
+            .map_err(|_| anyhow!("Credential provider {} did not respond within {} seconds", self.path, PROVIDER_TIMEOUT.as_secs()))?
+            .map_err(|e| anyhow!("Credential provider {} unavailable: {}", self.path, e))?;
+        let response: CredentialResponse = serde_json::from_str(response.trim())?;
+
+        if let Some(error) = &response.error {
+            return Err(anyhow!("Credential provider error: {}", error));
+        }
+        if response.username.is_empty() || response.password.is_empty() {
+            return Err(anyhow!("Credential provider returned an empty username or password"));
+        }
+        Ok(response)
+    }
+
+    #[cfg(windows)]
+    async fn exchange(&self, line: &str) -> std::io::Result<String> {
+        let pipe = tokio::net::windows::named_pipe::ClientOptions::new().open(&self.path)?;
+        Self::send_line(pipe, line).await
+    }
+
+    #[cfg(unix)]
+    async fn exchange(&self, line: &str) -> std::io::Result<String> {
+        let stream = tokio::net::UnixStream::connect(&self.path).await?;
+        Self::send_line(stream, line).await
+    }
+
+    // 写入一行请求并读取一行响应
+    async fn send_line(mut stream: impl AsyncRead + AsyncWrite + Unpin, line: &str) -> std::io::Result<String> {
+        stream.write_all(line.as_bytes()).await?;
+        stream.flush().await?;
+        let mut response = String::new();
+        BufReader::new(stream).read_line(&mut response).await?;
+        Ok(response)
+    }
+}
+
+/// 配置了凭据提供程序时，返回填入了外部凭据的配置副本；否则原样返回
+pub async fn resolve(config: &Config) -> Result<Config> {
+    let mut config = config.clone();
+    if config.credential_provider.is_empty() {
+        return Ok(config);
+    }
+
+    let response = PipeCredentialProvider::new(&config.credential_provider)
+        .fetch(&CredentialRequest::current())
+        .await?;
+    config.username = response.username;
+    config.password = response.password;
+    if let Some(isp) = response.isp {
+        config.isp = isp;
+    }
+    Ok(config)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, Write};
+    use std::os::unix::net::UnixListener;
+
+    // 启动一个只响应一次的模拟提供程序
+    fn serve_once(response: &'static str) -> (tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("provider.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            assert!(request.contains("\"machine\""));
+            (&stream).write_all(response.as_bytes()).unwrap();
+        });
+        (dir, path.to_string_lossy().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_credentials() {
+        let (_dir, path) = serve_once("{\"username\":\"8208190101\",\"password\":\"secret\",\"isp\":\"Telecom\"}\n");
+        let config = Config {
+            credential_provider: path,
+            ..Default::default()
+        };
+
+        let resolved = resolve(&config).await.unwrap();
+        assert_eq!(resolved.username, "8208190101");
+        assert_eq!(resolved.password, "secret");
+        assert_eq!(resolved.isp, ISP::Telecom);
+        // 原配置不保存外部凭据
+        assert!(config.password.is_empty());
+    }
+
+    #[test]
+    fn test_current_user() {
+        let passwd = "root:x:0:0:root:/root:/bin/bash\n# comment\nstudent01:x:1000:1000::/home/student01:/bin/sh\n";
+        assert_eq!(os::passwd_user(passwd, 1000).as_deref(), Some("student01"));
+        assert_eq!(os::passwd_user(passwd, 0).as_deref(), Some("root"));
+        assert_eq!(os::passwd_user(passwd, 1001), None);
+
+        // 用户名不受环境变量影响
+        std::env::set_var("USER", "someone-else");
+        assert_ne!(CredentialRequest::current().user, "someone-else");
+    }
+
+    #[tokio::test]
+    async fn test_provider_error() {
+        let (_dir, path) = serve_once("{\"error\":\"no account mapped\"}\n");
+        let err = PipeCredentialProvider::new(&path).fetch(&CredentialRequest::current()).await.unwrap_err();
+        assert!(err.to_string().contains("no account mapped"));
+
+        assert!(PipeCredentialProvider::new("/nonexistent/provider.sock")
+            .fetch(&CredentialRequest::current())
+            .await
+            .is_err());
+
+        // 提供程序接受连接后不响应时超时
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("silent.sock");
+        let _listener = UnixListener::bind(&path).unwrap();
+        let err = PipeCredentialProvider::new(&path.to_string_lossy())
+            .fetch(&CredentialRequest::current())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("did not respond"), "{}", err);
+    }
+}
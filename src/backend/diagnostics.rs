@@ -0,0 +1,137 @@
+// 诊断包：把当前配置（隐去密码）、最近的日志、网络统计、Chrome/ChromeDriver 版本和系统信息打包成一个 zip，
+// 报告登录失败时附上这一个文件即可
+use crate::backend::config::Config;
+use crate::backend::downloader::{BundleStatus, Downloader};
+use crate::backend::logger::Logger;
+use crate::backend::{paths, system_info, timezone, triage};
+use anyhow::Result;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+/// 诊断包保存的目录，位于配置目录下
+pub const DIAGNOSTICS_DIR: &str = "diagnostics";
+/// 日志文件只收集最后这么多字节
+pub const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// 隐去所有密码和令牌后的配置
+pub fn redacted_config(config: &Config) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&config.redacted()?)?)
+}
+
+/// 文件最后不超过 max_bytes 字节的内容，截断时从下一个完整的行开始
+pub fn tail(path: &Path, max_bytes: u64) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(max_bytes);
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(match (start > 0, text.split_once('\n')) {
+        (true, Some((_, rest))) => rest.to_string(),
+        _ => text.into_owned(),
+    })
+}
+
+/// 程序版本、操作系统、内存和网络环境
+pub fn system_report() -> String {
+    let now = timezone::now();
+    [
+        format!("CSUNetwork {}", env!("CARGO_PKG_VERSION")),
+        format!("Generated: {}", now.format("%Y-%m-%d %H:%M:%S %:z")),
+        format!("OS: {} ({} {})", system_info::os_version().unwrap_or_else(|| "unknown".to_string()), std::env::consts::OS, std::env::consts::ARCH),
+        format!("Memory: {}", system_info::total_memory_mb().map_or("unknown".to_string(), |mb| format!("{} MB", mb))),
+        format!("Local IPv4: {}", system_info::local_ipv4().map_or("none".to_string(), |ip| ip.to_string())),
+        format!("Wi-Fi: {}", system_info::wifi_ssid().unwrap_or_else(|| "none".to_string())),
+    ].join("\n")
+}
+
+/// Chrome/ChromeDriver 和 Edge/msedgedriver 的版本及校验结果
+pub fn browser_report(dir: &Path) -> String {
+    let describe = |status: BundleStatus| match status {
+        BundleStatus::Healthy { version } => format!("OK, version {}", version),
+        BundleStatus::NeedsRepair(reason) => format!("needs repair: {}", reason),
+    };
+    [
+        format!("Chrome and ChromeDriver: {}", describe(Downloader::verify_bundle(dir))),
+        format!("Edge and msedgedriver: {}", describe(Downloader::verify_edge(dir))),
+    ].join("\n")
+}
+
+/// 新诊断包的默认位置，文件名带有生成时间
+pub fn default_path() -> PathBuf {
+    paths::config_dir().join(DIAGNOSTICS_DIR).join(format!("csunetwork-diagnostics-{}.zip", timezone::now().format("%Y%m%d-%H%M%S")))
+}
+
+/// 把各个文本文件写入 zip
+pub fn write_bundle(path: &Path, files: &[(&str, String)]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(triage::write_error)?;
+    }
+    let mut zip = ZipWriter::new(File::create(path).map_err(triage::write_error)?);
+    for (name, content) in files {
+        zip.start_file(*name, FileOptions::default())?;
+        zip.write_all(content.as_bytes())?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// 收集诊断信息并写入 path，network_report 为界面中的网络状态、各目标统计和最近的日志，
+/// browser_dir 为登录时查找 Chrome 和驱动的目录
+pub fn export(path: &Path, config: &Config, network_report: &str, browser_dir: &Path) -> Result<()> {
+    let log = tail(&Logger::current_log_path(), MAX_LOG_BYTES)
+        .unwrap_or_else(|e| format!("Cannot read {}: {}", Logger::current_log_path().display(), e));
+    write_bundle(path, &[
+        ("config.json", redacted_config(config)?),
+        ("network.txt", network_report.to_string()),
+        ("browser.txt", browser_report(browser_dir)),
+        ("system.txt", system_report()),
+        ("log.txt", log),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::config::{Profile, REDACTED};
+    use serde_json::Value;
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_redacted_bundle() {
+        let mut config = Config {
+            username: "8208000000".to_string(),
+            webdav_password: "dav-secret".into(),
+            profiles: vec![Profile { name: "Dorm".to_string(), ..Profile::default() }],
+            ..Config::default()
+        };
+        config.password.edit().push_str("portal-secret");
+        config.profiles[0].password.edit().push_str("profile-secret");
+        let redacted = redacted_config(&config).unwrap();
+        assert!(!redacted.contains("secret"), "{}", redacted);
+        assert!(redacted.contains("8208000000"));
+        let value: Value = serde_json::from_str(&redacted).unwrap();
+        assert_eq!(value["password"], REDACTED);
+        assert_eq!(value["profiles"][0]["password"], REDACTED);
+        // 未填写的密码保持为空
+        assert_eq!(value["mqtt_password"], "");
+
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("campus_network_2024-09.log");
+        fs::write(&log, "first line\nsecond line\nthird line\n").unwrap();
+        assert_eq!(tail(&log, 1024).unwrap(), "first line\nsecond line\nthird line\n");
+        // 截断时丢掉不完整的第一行
+        assert_eq!(tail(&log, 15).unwrap(), "third line\n");
+
+        let path = dir.path().join("bundle").join("diagnostics.zip");
+        write_bundle(&path, &[("config.json", redacted), ("log.txt", tail(&log, 15).unwrap())]).unwrap();
+        let mut archive = ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 2);
+        let mut content = String::new();
+        archive.by_name("log.txt").unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "third line\n");
+    }
+}
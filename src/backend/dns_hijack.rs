@@ -0,0 +1,73 @@
+// 门户 DNS 劫持检测：未认证时校园网 DNS 把所有域名都解析到门户地址，DNS 探测和部分 HTTP 探测因此看起来"成功"，
+// 浏览器和系统也会缓存这些解析结果和门户页面。解析一个不存在的域名，得到门户地址或门户所在网段的地址就说明 DNS 被劫持；
+// 运营商 DNS 对不存在的域名返回的搜索页、广告页地址不算
+use rand::random;
+use reqwest::Url;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// 未配置门户地址时使用的门户主机
+pub const DEFAULT_PORTAL_HOST: &str = "10.1.1.1";
+
+/// 本次检测使用的不存在的域名，每次随机生成，避免命中缓存
+pub fn canary_domain() -> String {
+    format!("csunetwork-nx-{:08x}.com", random::<u32>())
+}
+
+/// 认证地址中的主机名或 IP，没有协议时按 http 解析
+pub fn portal_host(auth_url: &str) -> Option<String> {
+    let auth_url = auth_url.trim();
+    let url = if auth_url.contains("://") { Url::parse(auth_url) } else { Url::parse(&format!("http://{}", auth_url)) };
+    url.ok()?.host_str().map(|host| host.trim_matches(|c| c == '[' || c == ']').to_string())
+}
+
+// 是否与门户地址相同或在同一网段（IPv4 /24，IPv6 /64）
+fn in_portal_subnet(ip: IpAddr, portal: IpAddr) -> bool {
+    match (ip, portal) {
+        (IpAddr::V4(ip), IpAddr::V4(portal)) => ip.octets()[..3] == portal.octets()[..3],
+        (IpAddr::V6(ip), IpAddr::V6(portal)) => ip.segments()[..4] == portal.segments()[..4],
+        _ => false,
+    }
+}
+
+/// 不存在的域名被解析到的门户地址；解析失败或解析到与门户无关的地址时返回 None
+pub fn hijacked_address(addrs: impl IntoIterator<Item = SocketAddr>, portal: &[IpAddr]) -> Option<IpAddr> {
+    addrs.into_iter()
+        .map(|addr| addr.ip())
+        .find(|ip| portal.iter().any(|portal| in_portal_subnet(*ip, *portal)))
+}
+
+/// 解析不存在的域名，DNS 被劫持到门户时返回劫持到的地址；解析失败、超时或门户地址无法解析时视为正常
+pub async fn detect(portal_host: &str, timeout: Duration) -> Option<IpAddr> {
+    let portal: Vec<IpAddr> = match portal_host.parse() {
+        Ok(ip) => vec![ip],
+        Err(_) => tokio::time::timeout(timeout, tokio::net::lookup_host((portal_host, 0))).await.ok()?.ok()?
+            .map(|addr| addr.ip())
+            .collect(),
+    };
+    let domain = canary_domain();
+    let addrs = tokio::time::timeout(timeout, tokio::net::lookup_host((domain.as_str(), 0))).await.ok()?.ok()?;
+    hijacked_address(addrs, &portal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hijacked_address() {
+        assert_ne!(canary_domain(), canary_domain());
+        let portal = [IpAddr::from([10, 1, 1, 1])];
+        assert_eq!(hijacked_address(Vec::new(), &portal), None);
+        let answer: SocketAddr = "10.1.1.1:0".parse().unwrap();
+        assert_eq!(hijacked_address(vec![answer], &portal), Some(answer.ip()));
+        let answer: SocketAddr = "10.1.1.20:0".parse().unwrap();
+        assert_eq!(hijacked_address(vec![answer], &portal), Some(answer.ip()));
+        // 运营商 DNS 把不存在的域名解析到搜索页，不是门户劫持
+        let answer: SocketAddr = "223.5.5.5:0".parse().unwrap();
+        assert_eq!(hijacked_address(vec![answer], &portal), None);
+
+        assert_eq!(portal_host("http://10.1.1.1/eportal").as_deref(), Some("10.1.1.1"));
+        assert_eq!(portal_host("portal.csu.edu.cn:801").as_deref(), Some("portal.csu.edu.cn"));
+    }
+}
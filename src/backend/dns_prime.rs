@@ -0,0 +1,89 @@
+// 登录后的 DNS 预热：预先解析常用域名（镜像站、教务系统、GitHub 等）填充系统 DNS 缓存，同时确认校园网 DNS 能正常解析；
+// 有域名解析失败时报告为 DNS 异常，与断网区分开
+use futures_util::future::join_all;
+use std::time::{Duration, Instant};
+
+/// 界面中一键填入的常用域名
+pub const SUGGESTED_DOMAINS: [&str; 3] = ["www.csu.edu.cn", "mirrors.ustc.edu.cn", "github.com"];
+// 单个域名的解析超时
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 一次预热的结果，顺序与配置的域名相同
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsReport {
+    // 每个域名的解析耗时（毫秒）或失败原因
+    pub results: Vec<(String, Result<u64, String>)>,
+}
+
+impl DnsReport {
+    /// 解析失败的域名
+    pub fn failed(&self) -> Vec<&str> {
+        self.results.iter()
+            .filter(|(_, result)| result.is_err())
+            .map(|(domain, _)| domain.as_str())
+            .collect()
+    }
+
+    /// 有域名解析失败
+    pub fn is_degraded(&self) -> bool {
+        self.results.iter().any(|(_, result)| result.is_err())
+    }
+
+    /// 状态栏中显示的简短说明
+    pub fn summary(&self) -> String {
+        let failed = self.failed();
+        if failed.is_empty() {
+            let slowest = self.results.iter().filter_map(|(_, result)| result.as_ref().ok()).max().copied().unwrap_or(0);
+            format!("OK ({} domains, slowest {} ms)", self.results.len(), slowest)
+        } else {
+            format!("Degraded ({} of {} failed: {})", failed.len(), self.results.len(), failed.join(", "))
+        }
+    }
+}
+
+/// 配置的域名，去掉空行和首尾空白
+pub fn domains(configured: &[String]) -> Vec<String> {
+    configured.iter()
+        .map(|domain| domain.trim())
+        .filter(|domain| !domain.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 同时解析所有域名
+pub async fn prime(domains: &[String]) -> DnsReport {
+    let results = join_all(domains.iter().map(|domain| async move {
+        (domain.clone(), resolve(domain).await)
+    })).await;
+    DnsReport { results }
+}
+
+// 通过系统解析器解析，结果进入系统 DNS 缓存
+async fn resolve(domain: &str) -> Result<u64, String> {
+    let start = Instant::now();
+    let mut addrs = tokio::time::timeout(RESOLVE_TIMEOUT, tokio::net::lookup_host((domain, 0))).await
+        .map_err(|_| "timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    addrs.next().ok_or("no address returned")?;
+    Ok(start.elapsed().as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_prime() {
+        assert_eq!(domains(&[" localhost ".to_string(), String::new()]), vec!["localhost".to_string()]);
+
+        // .invalid 保留域名不会被解析
+        let report = prime(&["localhost".to_string(), "csunetwork.invalid".to_string()]).await;
+        assert!(report.results[0].1.is_ok());
+        assert!(report.is_degraded());
+        assert_eq!(report.failed(), vec!["csunetwork.invalid"]);
+        assert_eq!(report.summary(), "Degraded (1 of 2 failed: csunetwork.invalid)");
+
+        let report = DnsReport { results: vec![("github.com".to_string(), Ok(42)), ("www.csu.edu.cn".to_string(), Ok(7))] };
+        assert_eq!(report.summary(), "OK (2 domains, slowest 42 ms)");
+    }
+}
@@ -1,62 +1,401 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use tokio::fs;
 use tokio::task;
+use tokio::sync::mpsc::UnboundedSender;
 use reqwest;
 use zip::ZipArchive;
-use std::io::copy;
+use std::io::{copy, Read};
 use anyhow::{Result, Context, anyhow};
 use log::{debug, info, warn, error};
 use tokio::time::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use futures_util::StreamExt;
 use bytes::{BytesMut, Buf};
+use sha2::{Digest, Sha256};
+use crate::backend::browser;
+use crate::backend::platform::{self, Platform};
+use crate::backend::triage::{self, Failure};
 
 // Chrome和ChromeDriver版本
 const CHROMEDRIVER_VERSION: &str = "131.0.6778.204";
 const CHROME_VERSION: &str = "131.0.6778.204";
-// Chrome下载地址
-const CHROME_DOWNLOAD_URL: &str = "https://storage.googleapis.com/chrome-for-testing-public/131.0.6778.204/win32/chrome-win32.zip";
-const CHROMEDRIVER_DOWNLOAD_URL: &str = "https://storage.googleapis.com/chrome-for-testing-public/131.0.6778.204/win32/chromedriver-win32.zip";
+// CHROME_VERSION 各平台压缩包的 SHA-256（平台，chrome 或 chromedriver，小写十六进制）。Chrome for Testing 的清单
+// 不公布校验值，更新 CHROME_VERSION 时运行 cargo test -- --ignored test_pinned_sha256，从官方地址下载各压缩包，
+// 按输出更新这里；镜像下载的文件同样按官方地址对应的哈希校验，没有固定哈希的压缩包不安装
+const PINNED_SHA256: &[(Platform, &str, &str)] = &[];
+// 官方下载地址的前缀，镜像需与其目录结构相同
+pub const DEFAULT_MIRROR: &str = "https://storage.googleapis.com/chrome-for-testing-public";
+/// 界面中可一键添加的国内镜像（名称，地址前缀）
+pub const KNOWN_MIRRORS: [(&str, &str); 1] = [
+    ("npmmirror", "https://cdn.npmmirror.com/binaries/chrome-for-testing"),
+];
+// Chrome for Testing 各构建号最新补丁版本的下载地址列表
+const CHROME_VERSIONS_URL: &str = "https://googlechromelabs.github.io/chrome-for-testing/latest-patch-versions-per-build-with-downloads.json";
+// msedgedriver 的下载地址前缀，按 Edge 的完整版本号存放
+const EDGEDRIVER_DOWNLOAD_URL: &str = "https://msedgedriver.microsoft.com";
 // 最大重试次数
 const MAX_RETRIES: u32 = 3;
 // 重试等待时间（秒）
 const RETRY_WAIT_TIME: u64 = 5;
 
+// 运行 --version 的超时时间
+const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+// 等待 --version 退出时检查进程状态的间隔
+const VERSION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 下载进度，安装时发送给界面显示进度条
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadProgress {
+    pub file: String,
+    pub downloaded: u64,
+    // 服务器没有返回文件大小时为 0
+    pub total: u64,
+}
+
+impl DownloadProgress {
+    /// 已下载的比例，文件大小未知时为 None
+    pub fn fraction(&self) -> Option<f32> {
+        (self.total > 0).then(|| (self.downloaded as f64 / self.total as f64).min(1.0) as f32)
+    }
+}
+
+/// Chrome 和 ChromeDriver 的校验结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleStatus {
+    Healthy { version: String },
+    NeedsRepair(String),
+}
+
+// 从版本输出中提取形如 131.0.6778.204 的版本号
+fn parse_version(text: &str) -> Option<String> {
+    text.split_whitespace()
+        .find(|token| token.split('.').count() == 4 && token.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit())))
+        .map(str::to_string)
+}
+
+fn major_version(version: &str) -> &str {
+    version.split('.').next().unwrap_or_default()
+}
+
+// 运行程序的 --version，超时视为程序损坏；超时后结束并回收进程，不留下卡住的进程
+fn binary_version(path: &Path) -> Result<String> {
+    let mut child = Command::new(path)
+        .arg("--version")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to run {}", path.display()))?;
+
+    let deadline = Instant::now() + VERSION_TIMEOUT;
+    while child.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!("{} --version timed out", path.display()));
+        }
+        std::thread::sleep(VERSION_POLL_INTERVAL);
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!("{} --version exited with {}", path.display(), output.status));
+    }
+    parse_version(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow!("{} --version printed no version", path.display()))
+}
+
+// 用于比较版本号新旧
+fn version_key(version: &str) -> Vec<u32> {
+    version.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+}
+
+// Windows 下 chrome.exe 和 msedge.exe 的 --version 不输出内容，改从安装目录读取：Chrome for Testing 目录中有 <版本号>.manifest 文件，
+// 安装版的 Chrome 和 Edge 目录中有以版本号命名的子目录；升级期间新旧版本同时存在，取较新的
+fn installed_version(path: &Path) -> Result<String> {
+    binary_version(path).or_else(|e| {
+        std::fs::read_dir(path.parent().ok_or_else(|| anyhow!("{} has no parent directory", path.display()))?)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                match name.strip_suffix(".manifest") {
+                    Some(version) => parse_version(version),
+                    None if entry.path().is_dir() => parse_version(&name),
+                    None => None,
+                }
+            })
+            .max_by_key(|version| version_key(version))
+            .ok_or(e)
+    })
+}
+
+// 没有安装 Chrome 时下载的 Chrome for Testing，及与之对应的 ChromeDriver
+fn chrome_download_url() -> String {
+    Platform::current().download_url(DEFAULT_MIRROR, CHROME_VERSION, "chrome")
+}
+
+fn chromedriver_download_url() -> String {
+    Platform::current().download_url(DEFAULT_MIRROR, CHROMEDRIVER_VERSION, "chromedriver")
+}
+
+// 从 Chrome for Testing 的版本列表中找出与 Chrome 同一构建号（前三段）、本平台的 ChromeDriver 下载地址
+fn chromedriver_url_for(versions: &serde_json::Value, chrome_version: &str, platform: Platform) -> Option<String> {
+    let build = chrome_version.rsplit_once('.').map_or(chrome_version, |(build, _)| build);
+    versions["builds"][build]["downloads"]["chromedriver"].as_array()?
+        .iter()
+        .find(|download| download["platform"] == platform.name())
+        .and_then(|download| download["url"].as_str())
+        .map(str::to_string)
+}
+
+// 官方下载地址对应的固定 SHA-256
+fn pinned_sha256(official_url: &str) -> Option<&'static str> {
+    PINNED_SHA256.iter()
+        .find(|(platform, component, _)| platform.download_url(DEFAULT_MIRROR, CHROME_VERSION, component) == official_url)
+        .map(|(_, _, sha256)| *sha256)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// 下载前取得压缩包的固定 SHA-256，没有时拒绝下载，不安装未经校验的文件
+fn expected_sha256(official_url: &str) -> Result<&'static str> {
+    pinned_sha256(official_url).ok_or_else(|| {
+        error!("{} 没有固定的SHA-256，拒绝下载安装", archive_name(official_url));
+        Failure::ChecksumUnpinned(archive_name(official_url).to_string()).into()
+    })
+}
+
+// 解压前校验压缩包，与固定的 SHA-256 不一致时拒绝安装
+fn verify_archive(official_url: &str, expected: &str, bytes: &[u8]) -> Result<()> {
+    let file = archive_name(official_url);
+    if sha256_hex(bytes) != expected {
+        error!("{} 的SHA-256与固定值不一致，拒绝安装", file);
+        return Err(Failure::ChecksumMismatch(file.to_string()).into());
+    }
+    info!("{} SHA-256校验通过", file);
+    Ok(())
+}
+
+// 下载地址中的文件名
+fn archive_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+// 与 Edge 版本完全相同的 msedgedriver 下载地址
+fn edgedriver_url(version: &str, platform: Platform) -> String {
+    format!("{}/{}/{}", EDGEDRIVER_DOWNLOAD_URL, version, platform.edgedriver_archive())
+}
+
+// 把官方下载地址换成各镜像上的地址，按顺序尝试；所有镜像都失败时最后再试官方地址
+fn mirror_urls(official_url: &str, mirrors: &[String]) -> Vec<String> {
+    let mut urls: Vec<String> = mirrors.iter()
+        .map(|mirror| mirror.trim().trim_end_matches('/'))
+        .filter(|mirror| !mirror.is_empty())
+        .map(|mirror| official_url.replacen(DEFAULT_MIRROR, mirror, 1))
+        .collect();
+    if !urls.iter().any(|url| url == official_url) {
+        urls.push(official_url.to_string());
+    }
+    urls
+}
+
 pub struct Downloader;
 
 impl Downloader {
-    pub async fn ensure_chrome_and_driver_async() -> Result<()> {
+    pub async fn ensure_chrome_and_driver_async(mirrors: &[String], progress: Option<&UnboundedSender<DownloadProgress>>) -> Result<()> {
         info!("开始确保Chrome和ChromeDriver存在");
         let current_dir = std::env::current_dir()?;
         
-        // 确保 Chrome 目录存在
-        let chrome_dir = current_dir.join("chrome-win32");
-        if !chrome_dir.exists() {
-            info!("Chrome目录不存在，开始下载");
-            if let Err(e) = Self::download_and_install_chrome_async(&current_dir).await {
-                error!("下载Chrome失败: {}", e);
-                return Err(anyhow!("Chrome下载失败: {}. 请检查网络连接或手动下载", e));
+        // 已安装 Chrome 时直接使用，否则下载固定版本的 Chrome for Testing
+        let chrome = match browser::find_chrome(&current_dir) {
+            Some(chrome) => {
+                info!("使用已有的Chrome: {}", chrome.display());
+                chrome
             }
-        } else {
-            info!("Chrome目录已存在");
-        }
-        
-        // 确保 ChromeDriver 存在
-        let chromedriver_path = current_dir.join("chromedriver.exe");
-        if !chromedriver_path.exists() {
-            info!("ChromeDriver不存在，开始下载");
-            if let Err(e) = Self::download_and_install_chromedriver_async(&current_dir).await {
-                error!("下载ChromeDriver失败: {}", e);
-                return Err(anyhow!("ChromeDriver下载失败: {}. 请检查网络连接或手动下载", e));
+            None => {
+                info!("没有找到Chrome，开始下载");
+                if let Err(e) = Self::download_and_install_chrome_async(&current_dir, mirrors, progress).await {
+                    error!("下载Chrome失败: {}", e);
+                    // 校验失败时保留故障类型，界面据此提示重新下载
+                    if e.downcast_ref::<Failure>().is_some() {
+                        return Err(e);
+                    }
+                    return Err(anyhow!("Chrome下载失败: {}. 请检查网络连接或手动下载", e));
+                }
+                let platform = Platform::current();
+                current_dir.join(platform.chrome_dir()).join(platform.chrome_executable())
             }
+        };
+
+        // ChromeDriver 缺失或与 Chrome 主版本不一致时，下载与 Chrome 对应的版本
+        if let BundleStatus::Healthy { version } = Self::verify_bundle(&current_dir) {
+            info!("ChromeDriver {} 已存在", version);
+            return Ok(());
+        }
+        let version = installed_version(&chrome).context("读取Chrome版本失败")?;
+        let url = if version == CHROMEDRIVER_VERSION {
+            chromedriver_download_url()
         } else {
-            info!("ChromeDriver已存在");
+            Self::resolve_chromedriver_url(&version).await?
+        };
+        info!("ChromeDriver不存在或版本不匹配，开始下载与Chrome {}对应的版本", version);
+        if let Err(e) = Self::download_and_install_chromedriver_async(&current_dir, &url, mirrors, progress).await {
+            error!("下载ChromeDriver失败: {}", e);
+            // 校验失败时保留故障类型，界面据此提示重新下载
+            if e.downcast_ref::<Failure>().is_some() {
+                return Err(e);
+            }
+            return Err(anyhow!("ChromeDriver下载失败: {}. 请检查网络连接或手动下载", e));
         }
-        
+
         info!("Chrome和ChromeDriver检查完成");
         Ok(())
     }
 
+    // 查询 Chrome for Testing 的版本列表，取得与 Chrome 对应的 ChromeDriver 下载地址
+    async fn resolve_chromedriver_url(chrome_version: &str) -> Result<String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("创建HTTP客户端失败")?;
+        let body = client.get(CHROME_VERSIONS_URL)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("获取ChromeDriver版本列表失败")?
+            .text()
+            .await
+            .context("获取ChromeDriver版本列表失败")?;
+        let versions: serde_json::Value = serde_json::from_str(&body).context("解析ChromeDriver版本列表失败")?;
+        // Chrome for Testing 从 115 开始提供 ChromeDriver
+        chromedriver_url_for(&versions, chrome_version, Platform::current())
+            .ok_or_else(|| anyhow!("没有与Chrome {}对应的ChromeDriver，Chrome 115及以上版本才能自动下载", chrome_version))
+    }
+
+    /// 校验使用的 Chrome（优先已安装的 Chrome）和 ChromeDriver：文件存在、能输出版本号且主版本一致
+    pub fn verify_bundle(dir: &Path) -> BundleStatus {
+        let chromedriver_path = dir.join(platform::executable("chromedriver"));
+        let Some(chrome) = browser::find_chrome(dir) else {
+            let platform = Platform::current();
+            let chrome = Path::new(&platform.chrome_dir()).join(platform.chrome_executable());
+            return BundleStatus::NeedsRepair(format!("{} is missing", chrome.display()));
+        };
+        if !chromedriver_path.exists() {
+            return BundleStatus::NeedsRepair(format!("{} is missing", platform::executable("chromedriver")));
+        }
+
+        let chrome = match installed_version(&chrome) {
+            Ok(version) => version,
+            Err(e) => return BundleStatus::NeedsRepair(format!("Chrome is corrupted: {}", e)),
+        };
+        let driver = match binary_version(&chromedriver_path) {
+            Ok(version) => version,
+            Err(e) => return BundleStatus::NeedsRepair(format!("ChromeDriver is corrupted: {}", e)),
+        };
+
+        if major_version(&chrome) != major_version(&driver) {
+            warn!("Chrome {} 与 ChromeDriver {} 版本不匹配", chrome, driver);
+            return BundleStatus::NeedsRepair(format!("Chrome {} does not match ChromeDriver {}", chrome, driver));
+        }
+        info!("Chrome和ChromeDriver校验通过，版本 {}", chrome);
+        BundleStatus::Healthy { version: chrome }
+    }
+
+    /// 校验 msedgedriver 与已安装的 Edge 主版本一致
+    pub fn verify_edge(dir: &Path) -> BundleStatus {
+        let Some(edge) = browser::find_edge() else {
+            return BundleStatus::NeedsRepair("Microsoft Edge is not installed".to_string());
+        };
+        Self::verify_edge_driver(&edge, &dir.join(platform::executable("msedgedriver")))
+    }
+
+    fn verify_edge_driver(edge: &Path, driver_path: &Path) -> BundleStatus {
+        if !driver_path.exists() {
+            return BundleStatus::NeedsRepair(format!("{} is missing", platform::executable("msedgedriver")));
+        }
+        let edge = match installed_version(edge) {
+            Ok(version) => version,
+            Err(e) => return BundleStatus::NeedsRepair(format!("Cannot read the Edge version: {}", e)),
+        };
+        let driver = match binary_version(driver_path) {
+            Ok(version) => version,
+            Err(e) => return BundleStatus::NeedsRepair(format!("msedgedriver is corrupted: {}", e)),
+        };
+
+        // Edge 会自动更新，版本不一致时需要重新下载 msedgedriver
+        if major_version(&edge) != major_version(&driver) {
+            warn!("Edge {} 与 msedgedriver {} 版本不匹配", edge, driver);
+            return BundleStatus::NeedsRepair(format!("Edge {} does not match msedgedriver {}", edge, driver));
+        }
+        info!("Edge和msedgedriver校验通过，版本 {}", edge);
+        BundleStatus::Healthy { version: edge }
+    }
+
+    /// 下载与已安装 Edge 版本相同的 msedgedriver，已有匹配的版本时跳过；Edge 更新后也用它修复
+    pub async fn ensure_edgedriver_async(progress: Option<&UnboundedSender<DownloadProgress>>) -> Result<()> {
+        let current_dir = std::env::current_dir()?;
+        let edge = browser::find_edge().ok_or(Failure::EdgeMissing)?;
+        if let BundleStatus::Healthy { version } = Self::verify_edge(&current_dir) {
+            info!("msedgedriver {} 已存在", version);
+            return Ok(());
+        }
+
+        let version = installed_version(&edge).context("读取Edge版本失败")?;
+        info!("开始下载msedgedriver {}", version);
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(300))
+            .build()
+            .context("创建HTTP客户端失败")?;
+        let bytes = Self::download_from_mirrors(&client, &edgedriver_url(&version, Platform::current()), &[], progress)
+            .await
+            .context("下载msedgedriver失败")?;
+
+        // 在阻塞线程中解压，只取出 msedgedriver 可执行文件
+        match task::spawn_blocking(move || -> Result<()> {
+            let driver = platform::executable("msedgedriver");
+            let mut archive = ZipArchive::new(std::io::Cursor::new(bytes))
+                .context("创建ZIP存档失败")?;
+            let mut file = archive.by_name(&driver)
+                .with_context(|| format!("ZIP中没有{}", driver))?;
+            let driver_path = current_dir.join(&driver);
+            let mut outfile = std::fs::File::create(&driver_path)
+                .map_err(triage::write_error)
+                .context("创建msedgedriver可执行文件失败")?;
+            copy(&mut file, &mut outfile)
+                .map_err(triage::write_error)
+                .context("复制msedgedriver可执行文件失败")?;
+            platform::make_executable(&driver_path)
+                .context("设置msedgedriver可执行权限失败")?;
+            Ok(())
+        }).await {
+            Ok(result) => result?,
+            Err(e) => return Err(anyhow!("解压msedgedriver时发生错误: {}", e)),
+        }
+
+        info!("msedgedriver安装完成");
+        Ok(())
+    }
+
+    /// 删除已损坏的 Chrome 和 ChromeDriver 后重新下载
+    pub async fn repair_bundle_async(mirrors: &[String], progress: Option<&UnboundedSender<DownloadProgress>>) -> Result<()> {
+        let current_dir = std::env::current_dir()?;
+        info!("开始修复Chrome和ChromeDriver");
+
+        for platform in Platform::current().compatible() {
+            let chrome_dir = current_dir.join(platform.chrome_dir());
+            if chrome_dir.exists() {
+                fs::remove_dir_all(&chrome_dir).await.context("删除Chrome目录失败")?;
+            }
+        }
+        let chromedriver_path = current_dir.join(platform::executable("chromedriver"));
+        if chromedriver_path.exists() {
+            fs::remove_file(&chromedriver_path).await.context("删除ChromeDriver失败")?;
+        }
+
+        Self::ensure_chrome_and_driver_async(mirrors, progress).await
+    }
+
     async fn check_url_accessibility(url: &str) -> Result<bool> {
         debug!("检查URL可访问性: {}", url);
         
@@ -66,7 +405,7 @@ impl Downloader {
         
         // 使用 ping 命令检查主机是否可访问
         let output = std::process::Command::new("ping")
-            .arg("-n")  // Windows 平台使用 -n
+            .arg(if cfg!(windows) { "-n" } else { "-c" })  // Windows 平台使用 -n，Linux 和 macOS 使用 -c
             .arg("1")   // 只 ping 一次
             .arg(host)
             .output()
@@ -83,7 +422,8 @@ impl Downloader {
         Ok(success)
     }
 
-    async fn download_with_retry(client: &reqwest::Client, url: &str, retry_count: u32) -> Result<bytes::Bytes> {
+    async fn download_with_retry(client: &reqwest::Client, url: &str, retry_count: u32, progress: Option<&UnboundedSender<DownloadProgress>>) -> Result<bytes::Bytes> {
+        let file = url.rsplit('/').next().unwrap_or(url).to_string();
         let mut attempts = 0;
         loop {
             attempts += 1;
@@ -115,6 +455,11 @@ impl Downloader {
                                         downloaded += data.len() as u64;
                                         bytes.extend_from_slice(&data);
                                         
+                                        // 界面已关闭时发送失败，不影响下载
+                                        if let Some(progress) = progress {
+                                            let _ = progress.send(DownloadProgress { file: file.clone(), downloaded, total: total_size });
+                                        }
+
                                         // 计算下载进度
                                         if total_size > 0 {
                                             let percentage = (downloaded as f64 / total_size as f64 * 100.0) as u32;
@@ -160,13 +505,29 @@ impl Downloader {
         }
     }
 
-    pub async fn download_and_install_chrome_async(current_dir: &PathBuf) -> Result<()> {
-        info!("开始下载Chrome");
-        
-        // 检查URL是否可访问
-        if !Self::check_url_accessibility(CHROME_DOWNLOAD_URL).await? {
-            return Err(anyhow!("无法访问Chrome下载地址，请检查网络连接"));
+    // 依次从各镜像下载，跳过无法访问或下载失败的镜像
+    async fn download_from_mirrors(client: &reqwest::Client, official_url: &str, mirrors: &[String], progress: Option<&UnboundedSender<DownloadProgress>>) -> Result<bytes::Bytes> {
+        let mut last_error = None;
+        for url in mirror_urls(official_url, mirrors) {
+            // 检查URL是否可访问
+            if !Self::check_url_accessibility(&url).await? {
+                last_error = Some(anyhow!("无法访问下载地址 {}，请检查网络连接", url));
+                continue;
+            }
+            match Self::download_with_retry(client, &url, MAX_RETRIES, progress).await {
+                Ok(bytes) => return Ok(bytes),
+                Err(e) => {
+                    warn!("从 {} 下载失败: {}", url, e);
+                    last_error = Some(e);
+                }
+            }
         }
+        let reason = last_error.map_or("没有可用的下载地址".to_string(), |e| e.to_string());
+        Err(Failure::DownloadFailed { file: archive_name(official_url).to_string(), reason }.into())
+    }
+
+    pub async fn download_and_install_chrome_async(current_dir: &Path, mirrors: &[String], progress: Option<&UnboundedSender<DownloadProgress>>) -> Result<()> {
+        info!("开始下载Chrome");
         
         // 创建 HTTP 客户端
         let client = reqwest::Client::builder()
@@ -176,19 +537,23 @@ impl Downloader {
         
         // 下载 Chrome ZIP 文件
         debug!("开始下载Chrome ZIP文件");
-        let bytes = Self::download_with_retry(&client, CHROME_DOWNLOAD_URL, MAX_RETRIES)
+        let url = chrome_download_url();
+        let expected = expected_sha256(&url)?;
+        let bytes = Self::download_from_mirrors(&client, &url, mirrors, progress)
             .await
             .context("下载Chrome失败")?;
+        verify_archive(&url, expected, &bytes)?;
             
         let zip_path = current_dir.join("chrome.zip");
         fs::write(&zip_path, &bytes)
             .await
+            .map_err(triage::write_error)
             .context("写入Chrome zip文件失败")?;
         
         info!("Chrome下载完成，开始解压");
         
         // 在阻塞线程中解压文件
-        let current_dir = current_dir.clone();
+        let current_dir = current_dir.to_path_buf();
         match task::spawn_blocking(move || -> Result<()> {
             // 解压 Chrome
             let file = std::fs::File::open(&zip_path)
@@ -209,18 +574,39 @@ impl Downloader {
                 
                 if file.name().ends_with('/') {
                     std::fs::create_dir_all(&outpath)
+                        .map_err(triage::write_error)
                         .context("创建目录失败")?;
                 } else {
                     if let Some(p) = outpath.parent() {
                         if !p.exists() {
                             std::fs::create_dir_all(p)
+                                .map_err(triage::write_error)
                                 .context("创建父目录失败")?;
                         }
                     }
-                    let mut outfile = std::fs::File::create(&outpath)
-                        .context("创建文件失败")?;
-                    copy(&mut file, &mut outfile)
-                        .context("复制文件失败")?;
+                    match file.unix_mode() {
+                        // macOS 的 .app 包中有符号链接，条目内容为链接目标
+                        Some(mode) if platform::is_symlink(mode) => {
+                            let mut target = String::new();
+                            file.read_to_string(&mut target)
+                                .context("读取符号链接失败")?;
+                            platform::symlink(Path::new(&target), &outpath)
+                                .context("创建符号链接失败")?;
+                        }
+                        mode => {
+                            let mut outfile = std::fs::File::create(&outpath)
+                                .map_err(triage::write_error)
+                                .context("创建文件失败")?;
+                            copy(&mut file, &mut outfile)
+                                .map_err(triage::write_error)
+                                .context("复制文件失败")?;
+                            // 保留压缩包中的权限，Linux 和 macOS 下 Chrome 及其辅助程序需要可执行权限
+                            if let Some(mode) = mode {
+                                platform::set_mode(&outpath, mode & 0o777)
+                                    .context("设置文件权限失败")?;
+                            }
+                        }
+                    }
                 }
             }
             
@@ -239,14 +625,9 @@ impl Downloader {
         Ok(())
     }
 
-    pub async fn download_and_install_chromedriver_async(current_dir: &PathBuf) -> Result<()> {
+    pub async fn download_and_install_chromedriver_async(current_dir: &Path, url: &str, mirrors: &[String], progress: Option<&UnboundedSender<DownloadProgress>>) -> Result<()> {
         info!("开始下载ChromeDriver");
         
-        // 检查URL是否可访问
-        if !Self::check_url_accessibility(CHROMEDRIVER_DOWNLOAD_URL).await? {
-            return Err(anyhow!("无法访问ChromeDriver下载地址，请检查网络连接"));
-        }
-        
         // 创建 HTTP 客户端
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(300))
@@ -255,19 +636,22 @@ impl Downloader {
         
         // 下载 ChromeDriver ZIP 文件
         debug!("开始下载ChromeDriver ZIP文件");
-        let bytes = Self::download_with_retry(&client, CHROMEDRIVER_DOWNLOAD_URL, MAX_RETRIES)
+        let expected = expected_sha256(url)?;
+        let bytes = Self::download_from_mirrors(&client, url, mirrors, progress)
             .await
             .context("下载ChromeDriver失败")?;
+        verify_archive(url, expected, &bytes)?;
             
         let zip_path = current_dir.join("chromedriver.zip");
         fs::write(&zip_path, &bytes)
             .await
+            .map_err(triage::write_error)
             .context("写入ChromeDriver zip文件失败")?;
         
         info!("ChromeDriver下载完成，开始解压");
         
         // 在阻塞线程中解压文件
-        let current_dir = current_dir.clone();
+        let current_dir = current_dir.to_path_buf();
         match task::spawn_blocking(move || -> Result<()> {
             // 解压 ChromeDriver
             let file = std::fs::File::open(&zip_path)
@@ -281,11 +665,18 @@ impl Downloader {
                 let mut file = archive.by_index(i)
                     .context("从存档中获取文件失败")?;
                     
-                if file.name().contains("chromedriver.exe") {
-                    let mut outfile = std::fs::File::create(current_dir.join("chromedriver.exe"))
+                // 压缩包中为 chromedriver-<平台>/chromedriver(.exe)
+                let driver = platform::executable("chromedriver");
+                if file.name().rsplit('/').next() == Some(driver.as_str()) {
+                    let driver_path = current_dir.join(&driver);
+                    let mut outfile = std::fs::File::create(&driver_path)
+                        .map_err(triage::write_error)
                         .context("创建ChromeDriver可执行文件失败")?;
                     copy(&mut file, &mut outfile)
+                        .map_err(triage::write_error)
                         .context("复制ChromeDriver可执行文件失败")?;
+                    platform::make_executable(&driver_path)
+                        .context("设置ChromeDriver可执行权限失败")?;
                     break;
                 }
             }
@@ -319,6 +710,83 @@ mod tests {
             .try_init();
     }
 
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("ChromeDriver 131.0.6778.204 (52183f9e99a6)").as_deref(), Some("131.0.6778.204"));
+        assert_eq!(parse_version("Google Chrome for Testing 131.0.6778.204 \n").as_deref(), Some("131.0.6778.204"));
+        assert_eq!(parse_version("131.0.6778.204.manifest".strip_suffix(".manifest").unwrap()).as_deref(), Some("131.0.6778.204"));
+        assert_eq!(parse_version("Segmentation fault"), None);
+    }
+
+    #[test]
+    fn test_verify_bundle_missing() {
+        let temp_dir = tempdir().unwrap();
+        assert!(matches!(Downloader::verify_bundle(temp_dir.path()), BundleStatus::NeedsRepair(reason) if reason.contains("chrome")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_bundle_version_mismatch() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // 用输出版本号的脚本代替真实程序
+        let temp_dir = tempdir().unwrap();
+        let write_script = |path: &Path, output: &str| {
+            std::fs::write(path, format!("#!/bin/sh\necho '{}'\n", output)).unwrap();
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        };
+        let platform = Platform::current();
+        let chrome = temp_dir.path().join(platform.chrome_dir()).join(platform.chrome_executable());
+        std::fs::create_dir_all(chrome.parent().unwrap()).unwrap();
+        write_script(&chrome, "Google Chrome for Testing 131.0.6778.204");
+        let chromedriver = temp_dir.path().join(platform::executable("chromedriver"));
+        write_script(&chromedriver, "ChromeDriver 131.0.6778.108 (abc)");
+        assert_eq!(
+            Downloader::verify_bundle(temp_dir.path()),
+            BundleStatus::Healthy { version: "131.0.6778.204".to_string() }
+        );
+
+        write_script(&chromedriver, "ChromeDriver 130.0.6723.116 (abc)");
+        assert!(matches!(Downloader::verify_bundle(temp_dir.path()), BundleStatus::NeedsRepair(reason) if reason.contains("does not match")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_edge_driver() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // 没有 msedge.exe 时从版本号目录读取版本
+        let temp_dir = tempdir().unwrap();
+        std::fs::create_dir(temp_dir.path().join("130.0.2849.80")).unwrap();
+        std::fs::create_dir(temp_dir.path().join("131.0.2903.86")).unwrap();
+        std::fs::create_dir(temp_dir.path().join("Installer")).unwrap();
+        let edge = temp_dir.path().join("msedge.exe");
+        assert_eq!(installed_version(&edge).unwrap(), "131.0.2903.86");
+        assert_eq!(
+            edgedriver_url("131.0.2903.86", Platform::Win64),
+            "https://msedgedriver.microsoft.com/131.0.2903.86/edgedriver_win64.zip"
+        );
+        assert_eq!(
+            edgedriver_url("131.0.2903.86", Platform::MacArm64),
+            "https://msedgedriver.microsoft.com/131.0.2903.86/edgedriver_mac64_m1.zip"
+        );
+
+        let driver_path = temp_dir.path().join(platform::executable("msedgedriver"));
+        assert!(matches!(Downloader::verify_edge_driver(&edge, &driver_path), BundleStatus::NeedsRepair(reason) if reason.contains("missing")));
+
+        let write_driver = |output: &str| {
+            std::fs::write(&driver_path, format!("#!/bin/sh\necho '{}'\n", output)).unwrap();
+            std::fs::set_permissions(&driver_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        };
+        write_driver("Microsoft Edge WebDriver 131.0.2903.86 (abc)");
+        assert_eq!(
+            Downloader::verify_edge_driver(&edge, &driver_path),
+            BundleStatus::Healthy { version: "131.0.2903.86".to_string() }
+        );
+        write_driver("Microsoft Edge WebDriver 130.0.2849.80 (abc)");
+        assert!(matches!(Downloader::verify_edge_driver(&edge, &driver_path), BundleStatus::NeedsRepair(reason) if reason.contains("does not match")));
+    }
+
     #[test]
     fn test_path_construction() {
         init_test_logger();
@@ -326,26 +794,27 @@ mod tests {
         let temp_path = temp_dir.path().to_path_buf();
         
         // 测试Chrome路径构造
-        let chrome_dir = temp_path.join("chrome-win32");
+        let chrome_dir = temp_path.join(Platform::Win32.chrome_dir());
         assert_eq!(chrome_dir.file_name().unwrap(), "chrome-win32");
         
         // 测试ChromeDriver路径构造
-        let chromedriver_path = temp_path.join("chromedriver.exe");
-        assert_eq!(chromedriver_path.file_name().unwrap(), "chromedriver.exe");
+        let chromedriver_path = temp_path.join(platform::executable("chromedriver"));
+        assert_eq!(chromedriver_path.file_name().unwrap(), if cfg!(windows) { "chromedriver.exe" } else { "chromedriver" });
     }
 
     #[test]
     fn test_url_parsing() {
         init_test_logger();
         // 测试Chrome下载URL
-        let chrome_url = reqwest::Url::parse(CHROME_DOWNLOAD_URL).unwrap();
+        let platform = Platform::current().name();
+        let chrome_url = reqwest::Url::parse(&chrome_download_url()).unwrap();
         assert_eq!(chrome_url.host_str().unwrap(), "storage.googleapis.com");
-        assert!(chrome_url.path().contains("chrome-win32.zip"));
+        assert!(chrome_url.path().contains(&format!("chrome-{}.zip", platform)));
         
         // 测试ChromeDriver下载URL
-        let chromedriver_url = reqwest::Url::parse(CHROMEDRIVER_DOWNLOAD_URL).unwrap();
+        let chromedriver_url = reqwest::Url::parse(&chromedriver_download_url()).unwrap();
         assert_eq!(chromedriver_url.host_str().unwrap(), "storage.googleapis.com");
-        assert!(chromedriver_url.path().contains("chromedriver-win32.zip"));
+        assert!(chromedriver_url.path().contains(&format!("chromedriver-{}.zip", platform)));
     }
 
     #[test]
@@ -363,12 +832,110 @@ mod tests {
     fn test_download_urls() {
         init_test_logger();
         // 测试URL中包含正确的版本号
-        assert!(CHROME_DOWNLOAD_URL.contains(CHROME_VERSION), "Chrome下载URL应该包含正确的版本号");
-        assert!(CHROMEDRIVER_DOWNLOAD_URL.contains(CHROMEDRIVER_VERSION), "ChromeDriver下载URL应该包含正确的版本号");
+        assert!(chrome_download_url().contains(CHROME_VERSION), "Chrome下载URL应该包含正确的版本号");
+        assert!(chromedriver_download_url().contains(CHROMEDRIVER_VERSION), "ChromeDriver下载URL应该包含正确的版本号");
         
         // 测试URL中包含正确的平台信息
-        assert!(CHROME_DOWNLOAD_URL.contains("win32"), "Chrome下载URL应该包含平台信息");
-        assert!(CHROMEDRIVER_DOWNLOAD_URL.contains("win32"), "ChromeDriver下载URL应该包含平台信息");
+        let platform = Platform::current().name();
+        assert!(chrome_download_url().contains(platform), "Chrome下载URL应该包含平台信息");
+        assert!(chromedriver_download_url().contains(platform), "ChromeDriver下载URL应该包含平台信息");
+    }
+
+    #[test]
+    fn test_chromedriver_url_for() {
+        let versions = serde_json::json!({
+            "builds": {
+                "133.0.6943": {
+                    "version": "133.0.6943.141",
+                    "downloads": {
+                        "chrome": [{ "platform": "win32", "url": "https://storage.googleapis.com/chrome-for-testing-public/133.0.6943.141/win32/chrome-win32.zip" }],
+                        "chromedriver": [
+                            { "platform": "linux64", "url": "https://storage.googleapis.com/chrome-for-testing-public/133.0.6943.141/linux64/chromedriver-linux64.zip" },
+                            { "platform": "win32", "url": "https://storage.googleapis.com/chrome-for-testing-public/133.0.6943.141/win32/chromedriver-win32.zip" }
+                        ]
+                    }
+                },
+                "113.0.5672": { "version": "113.0.5672.63", "downloads": { "chrome": [] } }
+            }
+        });
+        // 已安装的 Chrome 补丁版本与列表中不同时，使用同一构建号的最新补丁版本
+        assert_eq!(
+            chromedriver_url_for(&versions, "133.0.6943.127", Platform::Win32).as_deref(),
+            Some("https://storage.googleapis.com/chrome-for-testing-public/133.0.6943.141/win32/chromedriver-win32.zip")
+        );
+        assert_eq!(
+            chromedriver_url_for(&versions, "133.0.6943.127", Platform::Linux64).as_deref(),
+            Some("https://storage.googleapis.com/chrome-for-testing-public/133.0.6943.141/linux64/chromedriver-linux64.zip")
+        );
+        assert_eq!(chromedriver_url_for(&versions, "133.0.6943.127", Platform::MacArm64), None);
+        assert_eq!(chromedriver_url_for(&versions, "113.0.5672.127", Platform::Win32), None);
+        assert_eq!(chromedriver_url_for(&versions, "134.0.6998.35", Platform::Win32), None);
+    }
+
+    #[test]
+    fn test_download_progress() {
+        let progress = DownloadProgress { file: "chrome-win32.zip".to_string(), downloaded: 38 * 1024 * 1024, total: 152 * 1024 * 1024 };
+        assert_eq!(progress.fraction(), Some(0.25));
+        assert_eq!(DownloadProgress { total: 0, ..progress }.fraction(), None);
+    }
+
+    #[test]
+    fn test_mirror_urls() {
+        let chrome_url = Platform::Win32.download_url(DEFAULT_MIRROR, CHROME_VERSION, "chrome");
+        let chromedriver_url = Platform::Win32.download_url(DEFAULT_MIRROR, CHROMEDRIVER_VERSION, "chromedriver");
+        assert_eq!(mirror_urls(&chrome_url, &[]), vec![chrome_url.clone()]);
+        assert_eq!(archive_name(&chromedriver_url), "chromedriver-win32.zip");
+        let mirrors = vec!["https://cdn.npmmirror.com/binaries/chrome-for-testing/".to_string(), " ".to_string()];
+        assert_eq!(
+            mirror_urls(&chromedriver_url, &mirrors),
+            vec![
+                "https://cdn.npmmirror.com/binaries/chrome-for-testing/131.0.6778.204/win32/chromedriver-win32.zip".to_string(),
+                chromedriver_url.clone(),
+            ]
+        );
+        // 列表中已有官方地址时按配置的顺序尝试，不再重复
+        let mirrors = vec![DEFAULT_MIRROR.to_string(), KNOWN_MIRRORS[0].1.to_string()];
+        assert_eq!(mirror_urls(&chrome_url, &mirrors)[0], chrome_url);
+        assert_eq!(mirror_urls(&chrome_url, &mirrors).len(), 2);
+    }
+
+    #[test]
+    fn test_verify_archive() {
+        let url = Platform::Win64.download_url(DEFAULT_MIRROR, CHROME_VERSION, "chromedriver");
+        let expected = sha256_hex(b"chromedriver");
+        assert!(verify_archive(&url, &expected, b"chromedriver").is_ok());
+        let error = verify_archive(&url, &expected, b"tampered").unwrap_err();
+        assert_eq!(triage::diagnose(error.as_ref()), Some(Failure::ChecksumMismatch("chromedriver-win64.zip".to_string())));
+        assert_eq!(sha256_hex(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+
+        // 与已安装的 Chrome 匹配的其他版本没有固定哈希，不下载
+        let other = Platform::Win64.download_url(DEFAULT_MIRROR, "133.0.6943.141", "chromedriver");
+        let error = expected_sha256(&other).unwrap_err();
+        assert_eq!(triage::diagnose(error.as_ref()), Some(Failure::ChecksumUnpinned("chromedriver-win64.zip".to_string())));
+        for (platform, component, sha256) in PINNED_SHA256 {
+            assert_eq!(pinned_sha256(&platform.download_url(DEFAULT_MIRROR, CHROME_VERSION, component)), Some(*sha256));
+            assert!(sha256.len() == 64 && sha256.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+        }
+    }
+
+    #[test]
+    #[ignore] // 需要网络连接：下载 CHROME_VERSION 各平台的压缩包，与 PINNED_SHA256 比较并输出实际的哈希
+    fn test_pinned_sha256() {
+        let platforms = [Platform::Win32, Platform::Win64, Platform::Linux64, Platform::MacX64, Platform::MacArm64];
+        let rt = Runtime::new().unwrap();
+        let client = reqwest::Client::new();
+        let mut actual = Vec::new();
+        for platform in platforms {
+            for component in ["chrome", "chromedriver"] {
+                let url = platform.download_url(DEFAULT_MIRROR, CHROME_VERSION, component);
+                let bytes = rt.block_on(async { client.get(&url).send().await?.error_for_status()?.bytes().await }).unwrap();
+                let sha256 = sha256_hex(&bytes);
+                println!("(Platform::{:?}, \"{}\", \"{}\"),", platform, component, sha256);
+                actual.push((platform, component, sha256));
+            }
+        }
+        let pinned: Vec<_> = PINNED_SHA256.iter().map(|(platform, component, sha256)| (*platform, *component, sha256.to_string())).collect();
+        assert_eq!(pinned, actual);
     }
 
     #[test]
@@ -380,10 +947,10 @@ mod tests {
         let temp_path = temp_dir.path().to_path_buf();
 
         rt.block_on(async {
-            let result = Downloader::download_and_install_chrome_async(&temp_path).await;
+            let result = Downloader::download_and_install_chrome_async(&temp_path, &[], None).await;
             match result {
                 Ok(_) => {
-                    assert!(temp_path.join("chrome-win32").exists());
+                    assert!(temp_path.join(Platform::current().chrome_dir()).exists());
                 }
                 Err(e) => {
                     warn!("Chrome下载失败（这可能是正常的）: {:?}", e);
@@ -401,10 +968,10 @@ mod tests {
         let temp_path = temp_dir.path().to_path_buf();
 
         rt.block_on(async {
-            let result = Downloader::download_and_install_chromedriver_async(&temp_path).await;
+            let result = Downloader::download_and_install_chromedriver_async(&temp_path, &chromedriver_download_url(), &[], None).await;
             match result {
                 Ok(_) => {
-                    assert!(temp_path.join("chromedriver.exe").exists());
+                    assert!(temp_path.join(platform::executable("chromedriver")).exists());
                 }
                 Err(e) => {
                     warn!("ChromeDriver下载失败（这可能是正常的）: {:?}", e);
@@ -420,7 +987,7 @@ mod tests {
         let rt = Runtime::new().unwrap();
 
         rt.block_on(async {
-            let result = Downloader::ensure_chrome_and_driver_async().await;
+            let result = Downloader::ensure_chrome_and_driver_async(&[], None).await;
             match result {
                 Ok(_) => info!("Chrome和ChromeDriver安装成功"),
                 Err(e) => warn!("Chrome和ChromeDriver安装失败（这可能是正常的）: {:?}", e),
@@ -435,7 +1002,7 @@ mod tests {
         
         rt.block_on(async {
             // 测试 Chrome 下载 URL
-            let chrome_accessible = Downloader::check_url_accessibility(CHROME_DOWNLOAD_URL).await;
+            let chrome_accessible = Downloader::check_url_accessibility(&chrome_download_url()).await;
             match chrome_accessible {
                 Ok(accessible) => {
                     if accessible {
@@ -448,7 +1015,7 @@ mod tests {
             }
 
             // 测试 ChromeDriver 下载 URL
-            let chromedriver_accessible = Downloader::check_url_accessibility(CHROMEDRIVER_DOWNLOAD_URL).await;
+            let chromedriver_accessible = Downloader::check_url_accessibility(&chromedriver_download_url()).await;
             match chromedriver_accessible {
                 Ok(accessible) => {
                     if accessible {
@@ -0,0 +1,80 @@
+// Windows DPAPI：没有可用的系统凭据存储时，用 CryptProtectData 以当前用户的身份加密密码后写在配置文件中，
+// 只有同一台电脑上的同一个 Windows 用户能解密；给 Windows 服务（LocalSystem）的副本按本机范围加密，本机的任何账号都能解密。
+// 其他系统不支持，密码仍以明文保存
+use anyhow::Result;
+#[cfg(windows)]
+use base64::engine::general_purpose::STANDARD;
+#[cfg(windows)]
+use base64::Engine;
+
+/// 以当前用户的身份加密密码，返回 Base64 编码的密文
+#[cfg(windows)]
+pub fn protect(password: &str) -> Result<String> {
+    Ok(STANDARD.encode(imp::transform(password.as_bytes(), imp::Operation::Protect)?))
+}
+
+/// 按本机范围加密密码，本机的任何账号（包括 LocalSystem）都能解密
+#[cfg(windows)]
+pub fn protect_for_machine(password: &str) -> Result<String> {
+    Ok(STANDARD.encode(imp::transform(password.as_bytes(), imp::Operation::ProtectForMachine)?))
+}
+
+/// 解密 protect 或 protect_for_machine 的结果
+#[cfg(windows)]
+pub fn unprotect(encoded: &str) -> Result<String> {
+    let bytes = imp::transform(&STANDARD.decode(encoded.trim())?, imp::Operation::Unprotect)?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+#[cfg(not(windows))]
+pub fn protect(_password: &str) -> Result<String> {
+    anyhow::bail!("DPAPI is only available on Windows")
+}
+
+#[cfg(all(not(windows), test))]
+pub fn protect_for_machine(_password: &str) -> Result<String> {
+    anyhow::bail!("DPAPI is only available on Windows")
+}
+
+#[cfg(not(windows))]
+pub fn unprotect(_encoded: &str) -> Result<String> {
+    anyhow::bail!("DPAPI is only available on Windows")
+}
+
+#[cfg(windows)]
+mod imp {
+    use anyhow::Result;
+    use std::ptr;
+    use zeroize::Zeroize;
+    use winapi::um::dpapi::{CryptProtectData, CryptUnprotectData, CRYPTPROTECT_LOCAL_MACHINE, CRYPTPROTECT_UI_FORBIDDEN};
+    use winapi::um::winbase::LocalFree;
+    use winapi::um::wincrypt::DATA_BLOB;
+
+    pub enum Operation {
+        Protect,
+        ProtectForMachine,
+        Unprotect,
+    }
+
+    // 加密或解密一段数据，不弹出任何提示
+    pub fn transform(input: &[u8], operation: Operation) -> Result<Vec<u8>> {
+        let mut input = DATA_BLOB { cbData: input.len() as u32, pbData: input.as_ptr() as *mut u8 };
+        let mut output = DATA_BLOB { cbData: 0, pbData: ptr::null_mut() };
+        let ok = unsafe {
+            match operation {
+                Operation::Protect => CryptProtectData(&mut input, ptr::null(), ptr::null_mut(), ptr::null_mut(), ptr::null_mut(), CRYPTPROTECT_UI_FORBIDDEN, &mut output),
+                Operation::ProtectForMachine => CryptProtectData(&mut input, ptr::null(), ptr::null_mut(), ptr::null_mut(), ptr::null_mut(), CRYPTPROTECT_UI_FORBIDDEN | CRYPTPROTECT_LOCAL_MACHINE, &mut output),
+                Operation::Unprotect => CryptUnprotectData(&mut input, ptr::null_mut(), ptr::null_mut(), ptr::null_mut(), ptr::null_mut(), CRYPTPROTECT_UI_FORBIDDEN, &mut output),
+            }
+        };
+        if ok == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        // 输出由系统分配，复制后清零（与 SecureZeroMemory 相同，不会被编译器优化掉）再释放，解密出的密码不留在已释放的内存里
+        let output = unsafe { std::slice::from_raw_parts_mut(output.pbData, output.cbData as usize) };
+        let bytes = output.to_vec();
+        output.zeroize();
+        unsafe { LocalFree(output.as_mut_ptr() as _) };
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,292 @@
+// Dr.COM 5.2 / AC 网页认证：部分校区的网关没有 eportal JSONP 接口，而是向网关的 0.htm 提交 DDDDD、upass 表单，
+// 结果页面中的 Msg 变量表示失败原因；F.htm 注销，网关首页的 uid、flow、time 变量表示在线状态
+use crate::backend::auth::{js_var, OnlineStatus};
+use crate::backend::config::Config;
+use crate::backend::isp::ISP;
+use crate::backend::maintenance::{self, PortalState};
+use crate::backend::portal::{PortalProvider, PortalType};
+use crate::backend::rate_limit::{self, RateLimiter};
+use crate::backend::secret::SecretString;
+use crate::backend::triage::Failure;
+use anyhow::{anyhow, Result};
+use futures_util::future::LocalBoxFuture;
+use reqwest::Client;
+
+// 登录表单中固定的字段：0MKKey 为登录按钮，R1、R3、R6 为认证方式和终端类型，para 为扩展参数
+const FIXED_FIELDS: [(&str, &str); 5] = [("0MKKey", "123456"), ("R1", "0"), ("R3", "0"), ("R6", "0"), ("para", "00")];
+// 登录成功页面的特征
+const SUCCESS_MARKERS: [&str; 3] = ["Dr.COMWebLoginID_3", "You have successfully logged", "您已经成功登录"];
+// 注销成功时结果页面中的 Msg
+const LOGOUT_MSG: &str = "14";
+
+/// 结果页面中 Msg 变量对应的提示
+pub fn describe_msg(msg: &str) -> Option<&'static str> {
+    match msg {
+        "01" => Some("Wrong username or password"),
+        "02" => Some("This account is already signed in elsewhere"),
+        "03" => Some("This account can only sign in from its registered address"),
+        "04" => Some("This account has run out of balance or traffic"),
+        "05" => Some("This account is suspended"),
+        "11" => Some("This account can only sign in from its registered device"),
+        "14" => Some("Signed out"),
+        _ => None,
+    }
+}
+
+// Msg=01 时 msga 为网关附加的原因；msga 为空才是账号或密码错误（页面显示"账号或密码不对，请重新输入"）
+fn describe_msga(msga: &str) -> Option<&'static str> {
+    match msga {
+        "error0" => Some("This address is not allowed to sign in on the web portal"),
+        "error1" => Some("This account is not allowed to sign in on the web portal"),
+        "error2" => Some("This account is not allowed to change its password"),
+        _ => None,
+    }
+}
+
+// 结果页面中 Msg=NN; 形式的变量（不带引号）
+fn page_msg(page: &str) -> Option<String> {
+    let start = page.find("Msg=")? + "Msg=".len();
+    let value: String = page[start..].chars().take_while(|c| c.is_ascii_digit()).collect();
+    (!value.is_empty()).then_some(value)
+}
+
+/// 解析登录结果页面：成功时返回提示，失败时返回门户的原因，账号或密码错误时附带 Failure::WrongPassword
+pub fn parse_login_page(page: &str) -> Result<String> {
+    if SUCCESS_MARKERS.iter().any(|marker| page.contains(marker)) {
+        return Ok("Signed in to the Dr.COM web portal".to_string());
+    }
+    let Some(msg) = page_msg(page) else {
+        return Err(anyhow!("Unexpected Dr.COM response: no result in the page"));
+    };
+    let msga = js_var(page, "msga").filter(|msga| !msga.is_empty());
+    if msg == "01" {
+        return Err(match msga {
+            Some(msga) => anyhow!(describe_msga(&msga).map_or_else(|| format!("Dr.COM login failed: {}", msga), str::to_string)),
+            None => anyhow::Error::new(Failure::WrongPassword).context("Wrong username or password"),
+        });
+    }
+    // 账号已在别处登录时 xip 为正在使用的地址，其他情况 msga 为网关附加的说明
+    let detail = if msg == "02" { js_var(page, "xip") } else { msga }.filter(|detail| !detail.is_empty());
+    Err(anyhow!(match (describe_msg(&msg), detail) {
+        (Some(text), Some(detail)) => format!("{} ({})", text, detail),
+        (Some(text), None) => text.to_string(),
+        (None, Some(detail)) => format!("Dr.COM login failed: Msg={} ({})", msg, detail),
+        (None, None) => format!("Dr.COM login failed: Msg={}", msg),
+    }))
+}
+
+/// Dr.COM 网页认证的门户识别：页面含有 DDDDD、upass 表单和 0MKKey 按钮，没有 eportal 接口
+pub fn detect(page: &str) -> bool {
+    page.contains("0MKKey") || (page.contains("DDDDD") && page.contains("upass") && !page.contains("/eportal/"))
+}
+
+/// Dr.COM 5.2 / AC 网页认证
+pub struct DrComPortal {
+    client: Client,
+    // 网关地址，使用账号的认证地址（auth_url）
+    gateway: String,
+    username: String,
+    password: SecretString,
+    isp: ISP,
+    limiter: &'static RateLimiter,
+}
+
+impl DrComPortal {
+    pub fn from_config(config: &Config) -> Self {
+        let mut builder = Client::builder().danger_accept_invalid_certs(true);
+        if config.bypass_proxy_for_portal {
+            builder = builder.no_proxy();
+        }
+        Self {
+            client: builder.build().unwrap_or_else(|_| Client::new()),
+            gateway: config.auth_url.trim_end_matches('/').to_string(),
+            username: config.username.clone(),
+            password: config.password.clone(),
+            isp: config.isp,
+            limiter: rate_limit::portal_limiter(),
+        }
+    }
+
+    /// 使用单独的限制器（用于回放测试），不受其他测试修改的共用限制器影响
+    #[cfg(test)]
+    pub fn with_limiter(mut self, limiter: &'static RateLimiter) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    // 登录表单：账号带上运营商后缀
+    fn login_form(&self) -> Vec<(&'static str, String)> {
+        let mut form = vec![
+            ("DDDDD", format!("{}{}", self.username, self.isp.portal_value())),
+            ("upass", self.password.expose().to_string()),
+        ];
+        form.extend(FIXED_FIELDS.iter().map(|(name, value)| (*name, value.to_string())));
+        form
+    }
+
+    // 发送请求并返回页面，连不上网关时返回 Failure::PortalUnreachable，网关维护或出错时返回 PortalState
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<String> {
+        let _permit = self.limiter.acquire().await;
+        let response = request.send().await.map_err(|e| {
+            if e.is_connect() || e.is_timeout() {
+                anyhow::Error::new(Failure::PortalUnreachable(e.to_string()))
+            } else {
+                e.into()
+            }
+        })?;
+        let status = response.status().as_u16();
+        let text = response.text().await?;
+        match maintenance::classify(status, &text) {
+            PortalState::Healthy => Ok(text),
+            state => Err(state.into()),
+        }
+    }
+}
+
+impl PortalProvider for DrComPortal {
+    fn portal_type(&self) -> PortalType {
+        PortalType::DrcomWeb
+    }
+
+    fn login(&mut self) -> LocalBoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            let request = self.client.post(format!("{}/0.htm", self.gateway)).form(&self.login_form());
+            parse_login_page(&self.send(request).await?)
+        })
+    }
+
+    fn logout(&mut self) -> LocalBoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            let page = self.send(self.client.get(format!("{}/F.htm", self.gateway))).await?;
+            match page_msg(&page) {
+                Some(msg) if msg == LOGOUT_MSG => Ok("Signed out of the Dr.COM web portal".to_string()),
+                Some(msg) => Err(anyhow!("Dr.COM logout failed: {}", describe_msg(&msg).unwrap_or(&msg))),
+                None => Err(anyhow!("Unexpected Dr.COM response: no result in the page")),
+            }
+        })
+    }
+
+    fn status(&self) -> LocalBoxFuture<'_, Result<OnlineStatus>> {
+        Box::pin(async move {
+            let page = self.send(self.client.get(format!("{}/", self.gateway))).await?;
+            Ok(OnlineStatus::parse(&page))
+        })
+    }
+
+    fn portal_state(&self) -> LocalBoxFuture<'_, Result<PortalState>> {
+        Box::pin(async move {
+            match self.send(self.client.get(format!("{}/", self.gateway))).await {
+                Ok(_) => Ok(PortalState::Healthy),
+                Err(e) => e.downcast::<PortalState>(),
+            }
+        })
+    }
+
+    fn detect(page: &str) -> bool {
+        detect(page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::cassette::{replay, Cassette};
+    use crate::backend::triage;
+    use std::sync::OnceLock;
+
+    #[test]
+    fn test_parse_login_page() {
+        let success = "<html><title>登录成功窗</title><script>window.location='Dr.COMWebLoginID_3.htm'</script></html>";
+        assert_eq!(parse_login_page(success).unwrap(), "Signed in to the Dr.COM web portal");
+
+        let wrong_password = "<script>Msg=01;time='0';msga='';</script>";
+        let error = parse_login_page(wrong_password).unwrap_err();
+        assert_eq!(error.to_string(), "Wrong username or password");
+        assert_eq!(triage::diagnose(error.as_ref()), Some(Failure::WrongPassword));
+
+        // Msg=01 附带 msga 时是网关拒绝 Web 登录，不是密码错误
+        let refused = parse_login_page("<script>Msg=01;time='0';msga='error0';</script>").unwrap_err();
+        assert_eq!(refused.to_string(), "This address is not allowed to sign in on the web portal");
+        assert_eq!(triage::diagnose(refused.as_ref()), None);
+
+        let in_use = "<script>Msg=02;time='0';xip='10.96.12.34';msga='';</script>";
+        assert_eq!(parse_login_page(in_use).unwrap_err().to_string(), "This account is already signed in elsewhere (10.96.12.34)");
+        assert_eq!(parse_login_page("<script>Msg=09;</script>").unwrap_err().to_string(), "Dr.COM login failed: Msg=09");
+        assert!(parse_login_page("<html>502 Bad Gateway</html>").unwrap_err().to_string().starts_with("Unexpected"));
+    }
+
+    #[test]
+    fn test_login_form() {
+        let config = Config {
+            username: "8201200101".to_string(),
+            password: "secret".into(),
+            isp: ISP::Telecom,
+            auth_url: "http://10.1.1.1/".to_string(),
+            ..Config::default()
+        };
+        let portal = DrComPortal::from_config(&config);
+        assert_eq!(portal.gateway, "http://10.1.1.1");
+        let form = portal.login_form();
+        assert_eq!(form[0], ("DDDDD", "8201200101@telecomn".to_string()));
+        assert_eq!(form[1], ("upass", "secret".to_string()));
+        assert!(form.contains(&("0MKKey", "123456".to_string())));
+
+        assert!(detect("<form name='f1' method='post' action='0.htm'><input name='DDDDD'><input name='upass'><input name='0MKKey'></form>"));
+        assert!(!detect("<script>v46ip='10.0.0.1';</script><a href='/eportal/portal'>DDDDD upass</a>"));
+    }
+
+    // 用录制的网关响应离线回放，与 AuthClient 的回放测试相同
+    async fn replay_portal(fixture: &str) -> DrComPortal {
+        // 回放时不需要请求间隔
+        static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+        let limiter = LIMITER.get_or_init(|| RateLimiter::new(rate_limit::DEFAULT_MAX_CONCURRENT, std::time::Duration::ZERO));
+        let cassette: Cassette = serde_json::from_str(fixture).unwrap();
+        let config = Config {
+            username: "test_user".to_string(),
+            password: "test_pass".into(),
+            isp: ISP::Mobile,
+            auth_url: replay::serve(cassette).await,
+            bypass_proxy_for_portal: true,
+            ..Config::default()
+        };
+        DrComPortal::from_config(&config).with_limiter(limiter)
+    }
+
+    #[tokio::test]
+    async fn test_replay_login_success() {
+        let mut portal = replay_portal(include_str!("../../fixtures/cassettes/drcom_login_success.json")).await;
+        assert_eq!(portal.login().await.unwrap(), "Signed in to the Dr.COM web portal");
+        let status = portal.status().await.unwrap();
+        assert!(status.online);
+        assert_eq!(status.account.as_deref(), Some("test_user"));
+        assert_eq!(status.ip.as_deref(), Some("10.0.0.1"));
+        assert_eq!(status.used_bytes, Some(20480 * 1024));
+
+        // 回放记录已用完，返回 404 视为网关正常响应
+        assert_eq!(portal.portal_state().await.unwrap(), PortalState::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_replay_wrong_password() {
+        let mut portal = replay_portal(include_str!("../../fixtures/cassettes/drcom_wrong_password.json")).await;
+        let error = portal.login().await.unwrap_err();
+        assert_eq!(error.to_string(), "Wrong username or password");
+        assert_eq!(triage::diagnose(error.as_ref()), Some(Failure::WrongPassword));
+    }
+
+    #[tokio::test]
+    async fn test_replay_logout() {
+        let mut portal = replay_portal(include_str!("../../fixtures/cassettes/drcom_logout.json")).await;
+        assert_eq!(portal.logout().await.unwrap(), "Signed out of the Dr.COM web portal");
+        assert!(!portal.status().await.unwrap().online);
+    }
+
+    #[tokio::test]
+    async fn test_replay_maintenance() {
+        let mut cassette: Cassette = serde_json::from_str(include_str!("../../fixtures/cassettes/drcom_logout.json")).unwrap();
+        cassette.interactions[1].status = 503;
+        cassette.interactions[1].body = "<h1>系统维护中</h1>".to_string();
+        let portal = replay_portal(&serde_json::to_string(&cassette).unwrap()).await;
+        assert_eq!(portal.portal_state().await.unwrap(), PortalState::MaintenancePage);
+    }
+}
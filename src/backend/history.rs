@@ -0,0 +1,372 @@
+// 会话历史：每次登录、登出和自动登录尝试追加一行 JSON 到 config/history.jsonl，记录时间、触发方式、登录方式、
+// 结果、错误和耗时。界面的 History 窗口可以筛选并导出 CSV，便于向网络中心说明断线和登录失败发生的时间。
+// 控制接口收到的每个请求另外记入 config/api_audit.jsonl（来源、命令、参数和结果），公用电脑的主人可以看到是谁远程登出的。
+// 文件超过 MAX_FILE_BYTES 时只保留最近一年内的最后 KEEP_ENTRIES 条
+use crate::backend::{paths, timezone};
+use anyhow::Result;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+#[cfg(feature = "history")]
+/// 导出的 CSV 文件所在目录，位于配置目录下
+pub const EXPORT_DIR: &str = "exports";
+// 历史文件的大小上限，超过时删除旧记录
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+// 删除旧记录后最多保留的条数，约为上限的一半，不必每次追加都重写文件
+const KEEP_ENTRIES: usize = 5000;
+// 删除旧记录时同时删除超过此时长的记录（秒）
+const MAX_AGE_SECS: i64 = 365 * 24 * 60 * 60;
+// 多个线程同时记录时逐行写入
+static WRITE_LOCK: Mutex<()> = parking_lot::const_mutex(());
+
+/// 操作
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Login,
+    Logout,
+}
+
+/// 触发方式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+    // 界面中点击按钮
+    Manual,
+    // 自动登录线程
+    Auto,
+    // sn login / sn logout 命令
+    Command,
+    // 本机控制接口的 /login、/logout
+    Api,
+    // 定时登录登出规则
+    Schedule,
+}
+
+impl Action {
+    pub const ALL: [Action; 2] = [Action::Login, Action::Logout];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Login => "Login",
+            Action::Logout => "Logout",
+        }
+    }
+}
+
+#[cfg(feature = "history")]
+impl Trigger {
+    pub const ALL: [Trigger; 5] = [Trigger::Manual, Trigger::Auto, Trigger::Command, Trigger::Api, Trigger::Schedule];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Trigger::Manual => "Manual",
+            Trigger::Auto => "Auto",
+            Trigger::Command => "Command line",
+            Trigger::Api => "Control API",
+            Trigger::Schedule => "Schedule",
+        }
+    }
+}
+
+/// 一次尝试的记录
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HistoryEntry {
+    // 开始时间（Unix 时间戳，秒）
+    pub timestamp: i64,
+    pub action: Action,
+    pub trigger: Trigger,
+    // 使用的方式，如 HTTP、browser
+    pub method: String,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // 从开始到得出结果的耗时（毫秒）
+    pub latency_ms: u64,
+}
+
+#[cfg(any(feature = "history", feature = "control_api"))]
+impl HistoryEntry {
+    /// 按配置的时区显示的开始时间
+    pub fn time(&self) -> String {
+        timezone::at(self.timestamp).format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+}
+
+#[cfg(feature = "control_api")]
+/// 控制接口收到的一个请求
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ApiCall {
+    // 收到请求的时间（Unix 时间戳，秒）
+    pub timestamp: i64,
+    // 请求来自的地址，带有 User-Agent 时附在后面，如 192.168.1.20 (HomeAssistant/2024.10)
+    pub source: String,
+    // 方法和路径，如 POST /logout
+    pub command: String,
+    // 查询参数，其中的令牌已隐去
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub parameters: String,
+    pub status: u16,
+    pub success: bool,
+    // 门户返回的消息或错误
+    pub result: String,
+}
+
+#[cfg(feature = "control_api")]
+impl ApiCall {
+    /// 按配置的时区显示的时间
+    pub fn time(&self) -> String {
+        timezone::at(self.timestamp).format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+}
+
+// 历史文件中的一行记录
+trait Record: Serialize + DeserializeOwned {
+    fn timestamp(&self) -> i64;
+}
+
+impl Record for HistoryEntry {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+#[cfg(feature = "control_api")]
+impl Record for ApiCall {
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+/// 进行中的一次尝试，得出结果时写入历史
+pub struct Attempt {
+    // 写入的历史文件
+    path: PathBuf,
+    action: Action,
+    trigger: Trigger,
+    timestamp: i64,
+    started: Instant,
+}
+
+impl Attempt {
+    /// 开始一次尝试，结果写入 path 处的历史文件
+    pub fn start(path: PathBuf, action: Action, trigger: Trigger) -> Self {
+        Self { path, action, trigger, timestamp: timezone::now().timestamp(), started: Instant::now() }
+    }
+
+    /// 记录结果并写入历史文件，写入失败只记日志
+    pub fn finish(&self, method: &str, result: Result<(), String>) {
+        let entry = HistoryEntry {
+            timestamp: self.timestamp,
+            action: self.action,
+            trigger: self.trigger,
+            method: method.to_string(),
+            success: result.is_ok(),
+            error: result.err(),
+            latency_ms: self.started.elapsed().as_millis() as u64,
+        };
+        if let Err(e) = append(&self.path, &entry) {
+            log::warn!("Failed to save session history: {}", e);
+        }
+    }
+}
+
+/// 历史文件的默认位置
+pub fn default_path() -> PathBuf {
+    paths::config_dir().join("history.jsonl")
+}
+
+/// 追加一条记录，文件超过大小上限时删除旧记录
+pub fn append(path: &Path, entry: &HistoryEntry) -> Result<()> {
+    append_record(path, entry)
+}
+
+#[cfg(feature = "control_api")]
+/// 控制接口请求记录的默认位置
+pub fn api_audit_path() -> PathBuf {
+    paths::config_dir().join("api_audit.jsonl")
+}
+
+#[cfg(feature = "control_api")]
+/// 追加一条控制接口请求的记录，写入失败只记日志
+pub fn record_api_call(path: &Path, call: &ApiCall) {
+    if let Err(e) = append_record(path, call) {
+        log::warn!("Failed to save the control API audit trail: {}", e);
+    }
+}
+
+#[cfg(feature = "control_api")]
+/// 读取全部控制接口请求的记录，跳过无法解析的行
+pub fn load_api_calls(path: &Path) -> Vec<ApiCall> {
+    load_records(path)
+}
+
+fn append_record<T: Record>(path: &Path, entry: &T) -> Result<()> {
+    let _guard = WRITE_LOCK.lock();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    if file.metadata()?.len() > MAX_FILE_BYTES {
+        drop(file);
+        trim_records::<T>(path, KEEP_ENTRIES, timezone::now().timestamp())?;
+    }
+    Ok(())
+}
+
+// 只保留 now 之前一年内的最后 keep 条记录，先写临时文件再替换，无法解析的行一并删除
+fn trim_records<T: Record>(path: &Path, keep: usize, now: i64) -> Result<()> {
+    let entries: Vec<T> = load_records(path);
+    let recent: Vec<&T> = entries.iter().filter(|entry| now - entry.timestamp() <= MAX_AGE_SECS).collect();
+    let mut content = String::new();
+    for entry in &recent[recent.len().saturating_sub(keep)..] {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    let temp = path.with_extension("jsonl.tmp");
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// 读取全部记录，跳过无法解析的行；文件不存在时为空
+pub fn load(path: &Path) -> Vec<HistoryEntry> {
+    load_records(path)
+}
+
+fn load_records<T: DeserializeOwned>(path: &Path) -> Vec<T> {
+    fs::read_to_string(path)
+        .map(|content| content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "history")]
+/// 历史窗口的筛选条件
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryFilter {
+    pub action: Option<Action>,
+    pub trigger: Option<Trigger>,
+    pub failures_only: bool,
+    // 在方式和错误中搜索，不区分大小写
+    pub search: String,
+}
+
+#[cfg(feature = "history")]
+impl HistoryFilter {
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        let search = self.search.trim().to_lowercase();
+        self.action.is_none_or(|action| entry.action == action)
+            && self.trigger.is_none_or(|trigger| entry.trigger == trigger)
+            && !(self.failures_only && entry.success)
+            && (search.is_empty()
+                || entry.method.to_lowercase().contains(&search)
+                || entry.error.as_deref().is_some_and(|error| error.to_lowercase().contains(&search)))
+    }
+}
+
+#[cfg(feature = "history")]
+// CSV 字段，含逗号、引号或换行时加引号
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(feature = "history")]
+/// 转为 CSV，时间按配置的时区
+pub fn to_csv<'a>(entries: impl IntoIterator<Item = &'a HistoryEntry>) -> String {
+    let mut csv = String::from("time,action,trigger,method,result,latency_ms,error\n");
+    for entry in entries {
+        let fields = [
+            entry.time(),
+            entry.action.label().to_string(),
+            entry.trigger.label().to_string(),
+            entry.method.clone(),
+            if entry.success { "success" } else { "failure" }.to_string(),
+            entry.latency_ms.to_string(),
+            entry.error.clone().unwrap_or_default(),
+        ];
+        csv.push_str(&fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(feature = "history")]
+/// 新导出文件的默认位置，文件名带有导出时间
+pub fn export_path() -> PathBuf {
+    paths::config_dir().join(EXPORT_DIR).join(format!("csunetwork-history-{}.csv", timezone::now().format("%Y%m%d-%H%M%S")))
+}
+
+#[cfg(feature = "history")]
+/// 把记录导出为 CSV 文件
+pub fn export<'a>(path: &Path, entries: impl IntoIterator<Item = &'a HistoryEntry>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, to_csv(entries))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        assert!(load(&path).is_empty());
+
+        let login = HistoryEntry {
+            timestamp: 1_730_000_000,
+            action: Action::Login,
+            trigger: Trigger::Auto,
+            method: "HTTP".to_string(),
+            success: false,
+            error: Some("Portal rejected the login request: \"ldap auth error\", retry".to_string()),
+            latency_ms: 420,
+        };
+        let logout = HistoryEntry { action: Action::Logout, trigger: Trigger::Manual, success: true, error: None, ..login.clone() };
+        append(&path, &login).unwrap();
+        append(&path, &logout).unwrap();
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"not json\n").unwrap();
+        let entries = load(&path);
+        assert_eq!(entries, vec![login.clone(), logout.clone()]);
+
+        #[cfg(feature = "history")]
+        {
+            let filter = HistoryFilter { failures_only: true, search: "LDAP".to_string(), ..Default::default() };
+            assert!(filter.matches(&login));
+            assert!(!filter.matches(&logout));
+            assert!(!HistoryFilter { trigger: Some(Trigger::Command), ..Default::default() }.matches(&login));
+
+            let csv = to_csv(&entries);
+            let lines: Vec<&str> = csv.lines().collect();
+            assert_eq!(lines[0], "time,action,trigger,method,result,latency_ms,error");
+            assert!(lines[1].ends_with(",Login,Auto,HTTP,failure,420,\"Portal rejected the login request: \"\"ldap auth error\"\", retry\""));
+            assert!(lines[2].ends_with(",Logout,Manual,HTTP,success,420,"));
+        }
+
+        // 删除旧记录：超过一年的和最后 keep 条之前的
+        let recent = HistoryEntry { timestamp: login.timestamp + MAX_AGE_SECS, ..logout.clone() };
+        append(&path, &recent).unwrap();
+        trim_records::<HistoryEntry>(&path, 5, recent.timestamp + 1).unwrap();
+        assert_eq!(load(&path), vec![recent.clone()]);
+        append(&path, &logout).unwrap();
+        trim_records::<HistoryEntry>(&path, 1, recent.timestamp).unwrap();
+        assert_eq!(load(&path), vec![logout]);
+
+        // Attempt 写入传入的文件
+        Attempt::start(path.clone(), Action::Login, Trigger::Command).finish("HTTP", Ok(()));
+        assert_eq!(load(&path).last().map(|entry| (entry.trigger, entry.success)), Some((Trigger::Command, true)));
+    }
+}
@@ -0,0 +1,226 @@
+// 本地登录统计：记录自动登录在各时段、各 Wi-Fi 下的成败，第几次重试，以及断线是否在防抖时间内自行恢复，
+// 据此在设置中给出调整建议。数据只保存在本机配置目录中的 insights.json，不上传
+use crate::backend::login_trigger::DEFAULT_DEBOUNCE;
+use crate::backend::paths;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 保存的断线记录条数
+pub const MAX_DISCONNECTS: usize = 50;
+// 某一项的尝试次数少于此值时不给出建议
+const MIN_ATTEMPTS: u32 = 5;
+// 断线记录少于此条数时不建议调整防抖时间
+const MIN_DISCONNECTS: usize = 10;
+/// 未连接 Wi-Fi（有线或无法查询）时的网络名
+pub const WIRED: &str = "Wired or unknown";
+
+/// 一组登录尝试的次数
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoginCounts {
+    pub attempts: u32,
+    pub failures: u32,
+}
+
+impl LoginCounts {
+    fn record(&mut self, success: bool) {
+        self.attempts += 1;
+        if !success {
+            self.failures += 1;
+        }
+    }
+
+    /// 失败的百分比
+    pub fn failure_percent(&self) -> u32 {
+        (self.failures * 100).checked_div(self.attempts).unwrap_or(0)
+    }
+}
+
+/// 一次断线：在防抖时间内自行恢复时记录恢复用时，否则为触发了自动登录
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Disconnect {
+    // 自行恢复的用时（秒），触发登录时为 None
+    pub recovered_after: Option<u64>,
+    // 当时的防抖时间（秒）
+    pub debounce: u64,
+}
+
+/// 累计的登录统计
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct LoginInsights {
+    // 按开始登录时的小时统计
+    #[serde(default)]
+    pub by_hour: [LoginCounts; 24],
+    // 按 Wi-Fi 名称统计
+    #[serde(default)]
+    pub by_network: BTreeMap<String, LoginCounts>,
+    // 按连续第几次尝试统计，第 4 次及以后合并
+    #[serde(default)]
+    pub by_retry: [LoginCounts; 4],
+    // 最近的断线，最多 MAX_DISCONNECTS 条
+    #[serde(default)]
+    pub disconnects: VecDeque<Disconnect>,
+}
+
+impl LoginInsights {
+    /// 统计文件的默认位置
+    pub fn default_path() -> PathBuf {
+        paths::config_dir().join("insights.json")
+    }
+
+    /// 读取统计，文件不存在或无法解析时从头开始
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path).ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 记录一次自动登录，retry 为此前连续失败的次数
+    pub fn record_login(&mut self, hour: u32, network: Option<&str>, retry: u32, success: bool) {
+        self.by_hour[hour as usize % 24].record(success);
+        self.by_network.entry(network.unwrap_or(WIRED).to_string()).or_default().record(success);
+        self.by_retry[(retry as usize).min(3)].record(success);
+    }
+
+    /// 记录一次断线，recovered_after 为在防抖时间内自行恢复的用时
+    pub fn record_disconnect(&mut self, recovered_after: Option<Duration>, debounce: Duration) {
+        self.disconnects.push_back(Disconnect {
+            recovered_after: recovered_after.map(|elapsed| elapsed.as_secs()),
+            debounce: debounce.as_secs(),
+        });
+        while self.disconnects.len() > MAX_DISCONNECTS {
+            self.disconnects.pop_front();
+        }
+    }
+
+    /// 是否还没有任何记录
+    pub fn is_empty(&self) -> bool {
+        self.disconnects.is_empty() && self.by_hour.iter().all(|counts| counts.attempts == 0)
+    }
+
+    fn total(&self) -> LoginCounts {
+        self.by_hour.iter().fold(LoginCounts::default(), |total, counts| LoginCounts {
+            attempts: total.attempts + counts.attempts,
+            failures: total.failures + counts.failures,
+        })
+    }
+
+    /// 根据统计给出调整建议，debounce 为当前的防抖时间
+    pub fn suggestions(&self, debounce: Duration, max_debounce_secs: u64) -> Vec<String> {
+        let mut suggestions = Vec::new();
+        let debounce = debounce.as_secs();
+
+        // 防抖：自行恢复的断线多且多在防抖快结束时恢复，说明再多等一会儿还能少登录几次；几乎都不会自行恢复时等待只是拖慢登录
+        if self.disconnects.len() >= MIN_DISCONNECTS {
+            let recovered: Vec<&Disconnect> = self.disconnects.iter().filter(|d| d.recovered_after.is_some()).collect();
+            let recovered_percent = recovered.len() * 100 / self.disconnects.len();
+            let late = recovered.iter()
+                .filter(|d| d.recovered_after.is_some_and(|secs| secs * 3 >= d.debounce * 2))
+                .count();
+            if recovered_percent >= 30 && late * 2 >= recovered.len() && debounce < max_debounce_secs {
+                suggestions.push(format!(
+                    "Raise the login debounce to {}s: {}% of your recent disconnects recovered on their own, most of them just before the {}s debounce ended",
+                    (debounce * 2).clamp(2, max_debounce_secs), recovered_percent, debounce
+                ));
+            } else if recovered_percent < 10 && debounce > DEFAULT_DEBOUNCE.as_secs() {
+                suggestions.push(format!(
+                    "Lower the login debounce to {}s: only {}% of your recent disconnects recovered on their own, so waiting {}s mostly delays the login",
+                    DEFAULT_DEBOUNCE.as_secs(), recovered_percent, debounce
+                ));
+            }
+        }
+
+        let total = self.total();
+        // 某个时段失败明显偏多，通常是门户在该时段维护或拥挤
+        if let Some((hour, counts)) = self.by_hour.iter().enumerate()
+            .filter(|(_, counts)| counts.attempts >= MIN_ATTEMPTS)
+            .max_by_key(|(_, counts)| counts.failure_percent())
+            .filter(|(_, counts)| counts.failure_percent() >= 50 && counts.failure_percent() >= total.failure_percent() * 2)
+        {
+            suggestions.push(format!(
+                "Logins between {:02}:00 and {:02}:00 fail {}% of the time ({} of {}), against {}% overall; the portal may be busy or under maintenance then",
+                hour, (hour + 1) % 24, counts.failure_percent(), counts.failures, counts.attempts, total.failure_percent()
+            ));
+        }
+
+        // 某个 Wi-Fi 下失败明显偏多
+        if self.by_network.len() > 1 {
+            if let Some((network, counts)) = self.by_network.iter()
+                .filter(|(_, counts)| counts.attempts >= MIN_ATTEMPTS)
+                .max_by_key(|(_, counts)| counts.failure_percent())
+                .filter(|(_, counts)| counts.failure_percent() >= 50 && counts.failure_percent() >= total.failure_percent() * 2)
+            {
+                suggestions.push(format!(
+                    "Logins on \"{}\" fail {}% of the time ({} of {}), against {}% overall; prefer another network or check its signal",
+                    network, counts.failure_percent(), counts.failures, counts.attempts, total.failure_percent()
+                ));
+            }
+        }
+
+        // 多次失败后的重试几乎都不成功，继续重试只是浪费
+        let late_retries = self.by_retry[3];
+        if late_retries.attempts >= MIN_ATTEMPTS && late_retries.failure_percent() >= 90 {
+            suggestions.push(format!(
+                "Retries after the third failure almost never succeed ({} of {} failed); when the prompt appears, pausing or opening the portal manually saves attempts",
+                late_retries.failures, late_retries.attempts
+            ));
+        }
+        suggestions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggestions() {
+        let debounce = Duration::from_secs(10);
+        let mut insights = LoginInsights::default();
+        assert!(insights.is_empty());
+        assert!(insights.suggestions(debounce, 600).is_empty());
+
+        // 一半的断线在防抖快结束时自行恢复
+        for _ in 0..6 {
+            insights.record_disconnect(Some(Duration::from_secs(8)), debounce);
+            insights.record_disconnect(None, debounce);
+        }
+        // 23 点的登录大多失败，第 4 次以后的重试全部失败
+        for retry in 0..8 {
+            insights.record_login(23, Some("CSU-WiFi"), retry, retry == 0);
+        }
+        for _ in 0..20 {
+            insights.record_login(9, None, 0, true);
+        }
+        let suggestions = insights.suggestions(debounce, 600);
+        assert_eq!(suggestions.len(), 4, "{:?}", suggestions);
+        assert!(suggestions[0].starts_with("Raise the login debounce to 20s: 50%"));
+        assert!(suggestions[1].starts_with("Logins between 23:00 and 00:00 fail 87% of the time (7 of 8), against 25% overall"));
+        assert!(suggestions[2].starts_with("Logins on \"CSU-WiFi\" fail 87%"));
+        assert!(suggestions[3].contains("(5 of 5 failed)"));
+
+        // 只保存最近的断线
+        for _ in 0..MAX_DISCONNECTS {
+            insights.record_disconnect(None, Duration::from_secs(30));
+        }
+        assert_eq!(insights.disconnects.len(), MAX_DISCONNECTS);
+        assert!(insights.suggestions(Duration::from_secs(30), 600)[0].starts_with("Lower the login debounce to 5s"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("insights.json");
+        insights.save(&path).unwrap();
+        assert_eq!(LoginInsights::load(&path), insights);
+        assert!(LoginInsights::load(&dir.path().join("missing.json")).is_empty());
+    }
+}
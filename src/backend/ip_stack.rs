@@ -0,0 +1,90 @@
+// 双栈登录：门户同时接受 IPv4 和 IPv6 登录时，按设置的顺序选择 HTTP 登录使用的协议栈，并记录会话最终使用的协议栈
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// 登录使用的协议栈
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpStack {
+    V4,
+    V6,
+}
+
+impl IpStack {
+    pub fn label(&self) -> &'static str {
+        match self {
+            IpStack::V4 => "IPv4",
+            IpStack::V6 => "IPv6",
+        }
+    }
+
+    /// 地址所属的协议栈，无法解析时为 None
+    pub fn of(address: &str) -> Option<Self> {
+        match address.trim().parse::<IpAddr>().ok()? {
+            IpAddr::V4(_) => Some(IpStack::V4),
+            IpAddr::V6(_) => Some(IpStack::V6),
+        }
+    }
+
+    /// HTTP 客户端绑定的本地地址，绑定未指定地址即可限定连接使用的协议栈
+    pub fn bind_address(&self) -> IpAddr {
+        match self {
+            IpStack::V4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpStack::V6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        }
+    }
+}
+
+/// 双栈登录的顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StackPreference {
+    // 先用 IPv4 登录，失败时再用 IPv6
+    #[default]
+    V4First,
+    V6First,
+    V4Only,
+    V6Only,
+}
+
+impl StackPreference {
+    pub const ALL: [StackPreference; 4] = [
+        StackPreference::V4First,
+        StackPreference::V6First,
+        StackPreference::V4Only,
+        StackPreference::V6Only,
+    ];
+
+    /// 界面中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            StackPreference::V4First => "IPv4 first",
+            StackPreference::V6First => "IPv6 first",
+            StackPreference::V4Only => "IPv4 only",
+            StackPreference::V6Only => "IPv6 only",
+        }
+    }
+
+    /// 依次尝试的协议栈
+    pub fn order(&self) -> &'static [IpStack] {
+        match self {
+            StackPreference::V4First => &[IpStack::V4, IpStack::V6],
+            StackPreference::V6First => &[IpStack::V6, IpStack::V4],
+            StackPreference::V4Only => &[IpStack::V4],
+            StackPreference::V6Only => &[IpStack::V6],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack_preference() {
+        assert_eq!(StackPreference::default().order(), &[IpStack::V4, IpStack::V6]);
+        assert_eq!(StackPreference::V6Only.order(), &[IpStack::V6]);
+        assert_eq!(IpStack::of("10.96.12.34"), Some(IpStack::V4));
+        assert_eq!(IpStack::of(" 2001:250:4400::1234 "), Some(IpStack::V6));
+        assert_eq!(IpStack::of(""), None);
+        assert!(IpStack::V6.bind_address().is_ipv6());
+    }
+}
@@ -0,0 +1,67 @@
+// 运营商定义，HTTP 认证和浏览器认证共用
+use serde::{Deserialize, Serialize};
+
+// 运营商枚举
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ISP {
+    Mobile,    // 移动 @cmccn
+    Unicom,    // 联通 @unicomn
+    Telecom,   // 电信 @telecomn
+    #[default]
+    #[serde(alias = "Campus")]
+    School,    // 校园网 ""
+}
+
+impl ISP {
+    /// 所有运营商，按界面显示顺序排列
+    pub const ALL: [ISP; 4] = [ISP::Mobile, ISP::Unicom, ISP::Telecom, ISP::School];
+
+    /// 门户账号后缀（不含 @）
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            ISP::Mobile => "cmccn",
+            ISP::Unicom => "unicomn",
+            ISP::Telecom => "telecomn",
+            ISP::School => "",
+        }
+    }
+
+    /// 登录页运营商下拉框中对应选项的 value
+    pub fn portal_value(&self) -> String {
+        match self.suffix() {
+            "" => String::new(),
+            suffix => format!("@{}", suffix),
+        }
+    }
+
+    /// 界面显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ISP::Mobile => "Mobile",
+            ISP::Unicom => "Unicom",
+            ISP::Telecom => "Telecom",
+            ISP::School => "School",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_portal_mapping() {
+        assert_eq!(ISP::Mobile.suffix(), "cmccn");
+        assert_eq!(ISP::Unicom.portal_value(), "@unicomn");
+        assert_eq!(ISP::Telecom.portal_value(), "@telecomn");
+        assert_eq!(ISP::School.portal_value(), "");
+    }
+
+    #[test]
+    fn test_serde_compatibility() {
+        // 与旧配置文件保持兼容
+        assert_eq!(serde_json::to_string(&ISP::School).unwrap(), "\"School\"");
+        assert_eq!(serde_json::from_str::<ISP>("\"Mobile\"").unwrap(), ISP::Mobile);
+        assert_eq!(serde_json::from_str::<ISP>("\"Campus\"").unwrap(), ISP::School);
+    }
+}
@@ -0,0 +1,121 @@
+// 登录后的心跳保活：门户在一段时间没有流量后会断开会话，开启后定期发送一个很小的 HTTP 请求（或门户的心跳接口），
+// 记录最近一次心跳的时间、耗时和结果；请求被重定向到门户说明会话已经断开
+use crate::backend::config::Config;
+use crate::backend::probe::DEFAULT_CAPTIVE_PROBE_URL;
+use crate::backend::timezone;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset};
+use reqwest::redirect::Policy;
+use reqwest::Client;
+use std::time::{Duration, Instant};
+
+/// 未设置时的心跳间隔
+pub const DEFAULT_INTERVAL_SECS: u64 = 300;
+/// 心跳间隔的范围
+pub const MIN_INTERVAL_SECS: u64 = 30;
+pub const MAX_INTERVAL_SECS: u64 = 3600;
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 配置的心跳间隔
+pub fn interval(config: &Config) -> Duration {
+    Duration::from_secs(config.keep_alive_secs.unwrap_or(DEFAULT_INTERVAL_SECS).clamp(MIN_INTERVAL_SECS, MAX_INTERVAL_SECS))
+}
+
+/// 心跳请求的地址，未配置时使用识别门户拦截的 generate_204 地址
+pub fn target(config: &Config) -> &str {
+    [config.keep_alive_url.trim(), config.captive_probe_url.trim()]
+        .into_iter()
+        .find(|url| !url.is_empty())
+        .unwrap_or(DEFAULT_CAPTIVE_PROBE_URL)
+}
+
+/// 一次心跳
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heartbeat {
+    // 按配置的时区
+    pub at: DateTime<FixedOffset>,
+    // 成功时为耗时（毫秒），失败时为原因
+    pub result: Result<u64, String>,
+}
+
+/// 本次运行的心跳统计
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeepAliveStats {
+    pub sent: u32,
+    pub failed: u32,
+    pub last: Option<Heartbeat>,
+}
+
+impl KeepAliveStats {
+    pub fn record(&mut self, heartbeat: Heartbeat) {
+        self.sent += 1;
+        if heartbeat.result.is_err() {
+            self.failed += 1;
+        }
+        self.last = Some(heartbeat);
+    }
+
+    /// 状态栏中显示的简短说明
+    pub fn summary(&self) -> Option<String> {
+        let last = self.last.as_ref()?;
+        let result = match &last.result {
+            Ok(ms) => format!("OK, {} ms", ms),
+            Err(e) => format!("failed: {}", e),
+        };
+        Some(format!("{} ({}), {} sent, {} failed", last.at.format("%H:%M:%S"), result, self.sent, self.failed))
+    }
+}
+
+// 按响应状态判断心跳是否成功：重定向表示请求被门户拦截
+fn check_status(status: u16) -> Result<()> {
+    match status {
+        300..=399 => Err(anyhow!("redirected to the portal, the session may have ended")),
+        500..=599 => Err(anyhow!("server returned HTTP {}", status)),
+        _ => Ok(()),
+    }
+}
+
+/// 发送一次心跳
+pub async fn beat(config: &Config) -> Heartbeat {
+    let at = timezone::now();
+    let started = Instant::now();
+    let result = send(config).await
+        .map(|()| started.elapsed().as_millis() as u64)
+        .map_err(|e| e.to_string());
+    Heartbeat { at, result }
+}
+
+async fn send(config: &Config) -> Result<()> {
+    let mut builder = Client::builder().redirect(Policy::none()).timeout(HEARTBEAT_TIMEOUT);
+    if config.bypass_proxy_for_portal {
+        builder = builder.no_proxy();
+    }
+    let response = builder.build()?.get(target(config)).send().await?;
+    check_status(response.status().as_u16())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_alive_stats() {
+        let mut config = Config::default();
+        assert_eq!(interval(&config), Duration::from_secs(DEFAULT_INTERVAL_SECS));
+        assert_eq!(target(&config), DEFAULT_CAPTIVE_PROBE_URL);
+        config.keep_alive_secs = Some(5);
+        config.keep_alive_url = " http://10.1.1.1/heartbeat ".to_string();
+        assert_eq!(interval(&config), Duration::from_secs(MIN_INTERVAL_SECS));
+        assert_eq!(target(&config), "http://10.1.1.1/heartbeat");
+
+        assert!(check_status(204).is_ok());
+        assert!(check_status(302).unwrap_err().to_string().starts_with("redirected"));
+
+        let mut stats = KeepAliveStats::default();
+        assert_eq!(stats.summary(), None);
+        let at = timezone::now();
+        stats.record(Heartbeat { at, result: Ok(12) });
+        stats.record(Heartbeat { at, result: Err("timed out".to_string()) });
+        assert_eq!(stats.summary().unwrap(), format!("{} (failed: timed out), 2 sent, 1 failed", at.format("%H:%M:%S")));
+    }
+}
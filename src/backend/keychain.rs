@@ -0,0 +1,103 @@
+// 系统凭据存储：记住的密码保存在 Windows 凭据管理器、macOS 钥匙串或 Secret Service 中，配置文件里只保留账号作为引用；
+// 凭据存储不可用时在 Windows 上用 DPAPI 加密后写在配置文件里
+use crate::backend::dpapi;
+use anyhow::Result;
+
+// 凭据存储中的服务名
+const SERVICE: &str = "CSUNetwork";
+/// 配置文件中加密保存的密码的前缀
+pub const PROTECTED_PREFIX: &str = "dpapi:";
+
+/// 按账号保存密码的存储
+pub trait PasswordStore {
+    fn get(&self, account: &str) -> Result<String>;
+    fn set(&self, account: &str, password: &str) -> Result<()>;
+    /// 删除账号对应的密码，条目不存在时视为成功
+    fn delete(&self, account: &str) -> Result<()>;
+
+    /// 加密要写在配置文件中的密码，返回带 PROTECTED_PREFIX 的密文；默认使用当前 Windows 用户的 DPAPI，其他系统返回错误
+    fn protect(&self, password: &str) -> Result<String> {
+        Ok(format!("{}{}", PROTECTED_PREFIX, dpapi::protect(password)?))
+    }
+
+    /// 加密给 Windows 服务（LocalSystem）使用的密码副本，返回带 PROTECTED_PREFIX 的密文；默认使用本机范围的 DPAPI
+    #[cfg(any(windows, test))]
+    fn protect_for_machine(&self, password: &str) -> Result<String> {
+        Ok(format!("{}{}", PROTECTED_PREFIX, dpapi::protect_for_machine(password)?))
+    }
+
+    /// 解密 protect 或 protect_for_machine 的结果
+    fn unprotect(&self, protected: &str) -> Result<String> {
+        dpapi::unprotect(protected.strip_prefix(PROTECTED_PREFIX).unwrap_or(protected))
+    }
+}
+
+/// 操作系统的凭据存储
+pub struct SystemKeyring;
+
+impl PasswordStore for SystemKeyring {
+    fn get(&self, account: &str) -> Result<String> {
+        Ok(keyring::Entry::new(SERVICE, account)?.get_password()?)
+    }
+
+    fn set(&self, account: &str, password: &str) -> Result<()> {
+        keyring::Entry::new(SERVICE, account)?.set_password(password)?;
+        Ok(())
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        match keyring::Entry::new(SERVICE, account)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// 测试用的内存存储
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryStore {
+    pub passwords: std::cell::RefCell<std::collections::HashMap<String, String>>,
+    // 模拟凭据存储不可用（如没有 Secret Service 的 Linux）
+    pub unavailable: bool,
+    // 模拟可以在本机加密密码（Windows DPAPI），密文为倒序的密码
+    pub can_protect: bool,
+}
+
+#[cfg(test)]
+impl PasswordStore for MemoryStore {
+    fn get(&self, account: &str) -> Result<String> {
+        self.passwords.borrow().get(account).cloned().ok_or_else(|| anyhow::anyhow!("No matching entry found"))
+    }
+
+    fn set(&self, account: &str, password: &str) -> Result<()> {
+        if self.unavailable {
+            anyhow::bail!("Platform secure storage failure");
+        }
+        self.passwords.borrow_mut().insert(account.to_string(), password.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        self.passwords.borrow_mut().remove(account);
+        Ok(())
+    }
+
+    fn protect(&self, password: &str) -> Result<String> {
+        if !self.can_protect {
+            anyhow::bail!("DPAPI is only available on Windows");
+        }
+        Ok(format!("{}{}", PROTECTED_PREFIX, password.chars().rev().collect::<String>()))
+    }
+
+    fn protect_for_machine(&self, password: &str) -> Result<String> {
+        self.protect(password)
+    }
+
+    fn unprotect(&self, protected: &str) -> Result<String> {
+        match protected.strip_prefix(PROTECTED_PREFIX) {
+            Some(reversed) if self.can_protect => Ok(reversed.chars().rev().collect()),
+            _ => anyhow::bail!("The key is not valid for use in specified state"),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// 账号锁定检测：密码错误次数过多被门户临时锁定时暂停自动登录，避免锁定时间被不断延长
+use std::time::{Duration, Instant};
+
+// 门户锁定提示中常见的关键字
+const LOCKOUT_KEYWORDS: [&str; 6] = ["锁定", "错误次数过多", "尝试次数过多", "登录过于频繁", "locked", "too many"];
+/// 无法从提示中解析出锁定时长时使用的冷却时间
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(15 * 60);
+// 进入冷却时记录的提示，界面和 daemon 共用
+const PAUSED_MESSAGE: &str = "Account temporarily locked, auto login paused";
+
+/// 进入冷却时的日志，带上按分钟向上取整的冷却时长
+pub fn paused_message(cooldown: Duration) -> String {
+    format!("{} for {} minutes", PAUSED_MESSAGE, cooldown.as_secs().div_ceil(60))
+}
+
+/// 门户提示是否表示账号被临时锁定
+pub fn is_lockout_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    LOCKOUT_KEYWORDS.iter().any(|keyword| message.contains(keyword))
+}
+
+/// 从提示中解析锁定时长，如"请10分钟后再试"、"try again in 5 minutes"
+pub fn parse_cooldown(message: &str) -> Option<Duration> {
+    let message = message.to_lowercase();
+    let units: [(&str, u64); 4] = [("分钟", 60), ("minute", 60), ("秒", 1), ("second", 1)];
+    units.iter().find_map(|(unit, seconds)| {
+        let before = &message[..message.find(unit)?];
+        let digits: String = before.trim_end()
+            .chars()
+            .rev()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+        let value = digits.parse::<u64>().ok()?;
+        Some(Duration::from_secs(value * seconds))
+    })
+}
+
+/// 记录账号锁定的冷却时间
+#[derive(Debug, Default)]
+pub struct LockoutTracker {
+    until: Option<Instant>,
+}
+
+impl LockoutTracker {
+    /// 记录一次登录失败的提示，是锁定提示时进入冷却并返回冷却时长
+    pub fn record(&mut self, message: &str) -> Option<Duration> {
+        if !is_lockout_message(message) {
+            return None;
+        }
+        let cooldown = parse_cooldown(message).unwrap_or(DEFAULT_COOLDOWN);
+        self.until = Some(Instant::now() + cooldown);
+        Some(cooldown)
+    }
+
+    /// 剩余冷却时间，冷却结束后返回 None
+    pub fn remaining(&self) -> Option<Duration> {
+        self.until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.remaining().is_some()
+    }
+
+    pub fn reset(&mut self) {
+        self.until = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_lockout() {
+        assert!(is_lockout_message("密码错误次数过多，账号已被锁定，请10分钟后再试"));
+        assert!(is_lockout_message("Account locked: too many failed attempts"));
+        assert!(!is_lockout_message("密码错误"));
+        assert_eq!(paused_message(Duration::from_secs(90)), "Account temporarily locked, auto login paused for 2 minutes");
+
+        assert_eq!(parse_cooldown("账号已被锁定，请10分钟后再试"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_cooldown("Try again in 5 minutes"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_cooldown("账号已被锁定"), None);
+    }
+
+    #[test]
+    fn test_tracker() {
+        let mut tracker = LockoutTracker::default();
+        assert_eq!(tracker.record("ldap auth error"), None);
+        assert!(!tracker.is_active());
+
+        assert_eq!(tracker.record("账号已被锁定"), Some(DEFAULT_COOLDOWN));
+        assert!(tracker.is_active());
+        assert!(tracker.remaining().unwrap() <= DEFAULT_COOLDOWN);
+
+        tracker.reset();
+        assert!(!tracker.is_active());
+    }
+}
@@ -0,0 +1,92 @@
+// 日志总线：后台模块通过 log 宏输出的日志经由日志系统发布到这里，界面每帧取出显示
+use log::Level;
+use std::cell::Cell;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+// 界面来不及取出时最多缓存的条数，超出后丢弃最旧的
+const CAPACITY: usize = 1024;
+
+/// 一条总线上的日志
+#[derive(Debug, Clone)]
+pub struct BusRecord {
+    pub level: Level,
+    // 日志来源模块，如 sn::backend::downloader
+    pub target: String,
+    pub message: String,
+}
+
+impl BusRecord {
+    /// 来源模块名，如 downloader
+    pub fn module(&self) -> &str {
+        self.target.rsplit("::").next().unwrap_or(&self.target)
+    }
+}
+
+thread_local! {
+    // 界面线程自己调用的操作结果由界面直接显示，不再经过总线
+    static MUTED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn bus() -> &'static broadcast::Sender<BusRecord> {
+    static BUS: OnceLock<broadcast::Sender<BusRecord>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(CAPACITY).0)
+}
+
+/// 是否发布到总线：只转发后台模块的日志；网络监控每次检查都会输出，其状态已在界面显示，只转发警告和错误
+pub fn should_publish(level: Level, target: &str) -> bool {
+    if !target.contains("::backend::") || target.ends_with("::log_bus") {
+        return false;
+    }
+    level <= Level::Warn || (level == Level::Info && !target.ends_with("::network_monitor"))
+}
+
+/// 当前线程输出的日志不再发布到总线
+pub fn mute_current_thread() {
+    MUTED.with(|muted| muted.set(true));
+}
+
+/// 发布一条日志，没有订阅者时直接丢弃
+pub fn publish(level: Level, target: &str, message: String) {
+    if should_publish(level, target) && !MUTED.with(Cell::get) {
+        let _ = bus().send(BusRecord { level, target: target.to_string(), message });
+    }
+}
+
+/// 订阅总线，只能收到订阅之后发布的日志
+pub fn subscribe() -> broadcast::Receiver<BusRecord> {
+    bus().subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_and_filter() {
+        let mut receiver = subscribe();
+        publish(Level::Info, "sn::backend::downloader", "Downloading Chrome...".to_string());
+        publish(Level::Info, "sn::backend::network_monitor", "Probing 223.5.5.5".to_string());
+        publish(Level::Info, "reqwest::connect", "starting new connection".to_string());
+        publish(Level::Error, "sn::backend::network_monitor", "Probe failed".to_string());
+
+        // 其他测试也可能同时发布日志，只检查这里发布的
+        let records: Vec<BusRecord> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        let messages: Vec<&str> = records.iter().map(|record| record.message.as_str()).collect();
+        assert!(messages.contains(&"Downloading Chrome..."));
+        assert!(messages.contains(&"Probe failed"));
+        assert!(!messages.contains(&"Probing 223.5.5.5"));
+        assert!(!messages.contains(&"starting new connection"));
+        assert_eq!(records.iter().find(|record| record.message == "Probe failed").unwrap().module(), "network_monitor");
+    }
+
+    #[test]
+    fn test_muted_thread() {
+        let mut receiver = subscribe();
+        std::thread::spawn(|| {
+            mute_current_thread();
+            publish(Level::Info, "sn::backend::config", "Configuration saved".to_string());
+        }).join().unwrap();
+        assert!(std::iter::from_fn(|| receiver.try_recv().ok()).all(|record| record.message != "Configuration saved"));
+    }
+}
@@ -1,13 +1,114 @@
-use chrono::Local;
-use std::fs::{self, OpenOptions};
-use env_logger::{Builder, fmt::Color};
-use std::io::Write;
-use std::path::Path;
-use log::LevelFilter;
-use std::sync::Once;
+// 日志系统：通过 tracing 输出，log 宏的记录经 tracing-log 转发；同时写入控制台和日志文件，后台模块的日志发布到日志总线。
+// 日志文件可选用 JSON 格式（每行一个对象），便于导入 ELK 等日志平台；控制台始终为文本
+// 各模块的日志级别可在配置中设置（如 sn::backend::downloader=debug,thirtyfour=warn），登录过程记录为 span，结束时输出耗时
+use crate::backend::log_bus;
+use crate::backend::paths;
+use crate::backend::timezone;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Once, OnceLock};
+use std::time::{Duration, SystemTime};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::fmt::format::{FmtSpan, Writer};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
 
 static LOGGER_INIT: Once = Once::new();
 
+// 日志文件名前缀，归档和清理只处理以此开头的文件
+const LOG_PREFIX: &str = "campus_network_";
+// 日志目录（paths::log_dir）中始终指向当前日志文件的固定文件名，供外部工具跟踪
+const CURRENT_LOG: &str = "current.log";
+// 跟踪日志时检查新内容的间隔
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 未配置时单个日志文件的大小上限（MB）
+pub const DEFAULT_MAX_FILE_MB: u64 = 5;
+/// 未配置时每个月保留的归档个数
+pub const DEFAULT_MAX_ARCHIVES: usize = 5;
+/// 未配置时删除多少天前的日志
+pub const DEFAULT_RETENTION_DAYS: u64 = 90;
+
+/// 日志文件的大小上限和保留策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogPolicy {
+    // 当前文件超过此大小时归档，另起新文件
+    pub max_file_bytes: u64,
+    // 每个月最多保留的归档个数，超出时删除最旧的
+    pub max_archives: usize,
+    // 最后修改时间早于此时长的日志文件在启动和修改设置时删除
+    pub retention: Duration,
+}
+
+impl Default for LogPolicy {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: DEFAULT_MAX_FILE_MB * 1024 * 1024,
+            max_archives: DEFAULT_MAX_ARCHIVES,
+            retention: Duration::from_secs(DEFAULT_RETENTION_DAYS * 24 * 60 * 60),
+        }
+    }
+}
+
+static POLICY: Mutex<Option<LogPolicy>> = Mutex::new(None);
+// 日志文件是否使用 JSON 格式
+static JSON_FILE: AtomicBool = AtomicBool::new(false);
+// 运行中修改各模块日志级别的句柄
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// 日志文件的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    // 与控制台相同的文本行
+    #[default]
+    Text,
+    // 每行一个 JSON 对象：timestamp、level、target、spans、fields
+    Json,
+}
+
+impl LogFormat {
+    pub const ALL: [LogFormat; 2] = [LogFormat::Text, LogFormat::Json];
+
+    /// 界面中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogFormat::Text => "Text",
+            LogFormat::Json => "JSON lines",
+        }
+    }
+}
+
+/// 未配置模块级别时的默认级别
+pub const DEFAULT_LEVEL: &str = "info";
+
+/// 解析各模块的日志级别，如 "sn::backend::downloader=debug,thirtyfour=warn"；未列出的模块使用 info
+pub fn parse_filters(directives: &str) -> Result<EnvFilter, String> {
+    let directives = directives.trim().trim_matches(',');
+    let directives = if directives.is_empty() {
+        DEFAULT_LEVEL.to_string()
+    } else {
+        format!("{},{}", DEFAULT_LEVEL, directives)
+    };
+    EnvFilter::try_new(&directives).map_err(|e| format!("Invalid log levels \"{}\": {}", directives, e))
+}
+
+// 当前的日志策略，未设置时使用默认值
+fn policy() -> LogPolicy {
+    POLICY.lock().unwrap_or_default()
+}
+
 pub struct Logger;
 
 impl Logger {
@@ -22,17 +123,46 @@ impl Logger {
         Ok(())
     }
 
+    /// 设置日志文件的大小上限和保留策略，并立即清理过期的日志
+    pub fn set_policy(policy: LogPolicy) {
+        *POLICY.lock() = Some(policy);
+        if let Err(e) = prune_old_logs(&paths::log_dir(), policy.retention, SystemTime::now()) {
+            eprintln!("Failed to remove old logs: {}", e);
+        }
+    }
+
+    /// 设置日志文件的格式，初始化前调用时从第一行起生效；运行中修改时另起新文件，同一个文件中不混用两种格式
+    pub fn set_format(format: LogFormat) {
+        JSON_FILE.store(format == LogFormat::Json, Ordering::Relaxed);
+    }
+
+    /// 修改各模块的日志级别，格式无效时保持原设置并返回错误
+    pub fn set_filters(directives: &str) -> Result<(), String> {
+        let filter = parse_filters(directives)?;
+        if let Some(handle) = FILTER.get() {
+            handle.reload(filter).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// 当月日志文件的路径
+    pub fn current_log_path() -> PathBuf {
+        PathBuf::from(Self::log_file_name())
+    }
+
+    // 当月日志文件的路径
+    fn log_file_name() -> String {
+        paths::log_dir().join(format!("{}{}.log", LOG_PREFIX, timezone::now().format("%Y-%m"))).display().to_string()
+    }
+
     /// 获取日志文件路径和句柄
     fn get_log_file() -> Result<(std::fs::File, String), Box<dyn std::error::Error>> {
         // 创建日志目录
-        fs::create_dir_all("./logs")?;
+        fs::create_dir_all(paths::log_dir())?;
 
         // 生成当月的日志文件名
-        let current_time = Local::now();
-        let log_file_name = format!(
-            "./logs/campus_network_{}.log",
-            current_time.format("%Y-%m")
-        );
+        let current_time = timezone::now();
+        let log_file_name = Self::log_file_name();
 
         // 检查文件是否已存在
         let file_exists = Path::new(&log_file_name).exists();
@@ -43,90 +173,341 @@ impl Logger {
             .append(true)
             .open(&log_file_name)?;
 
-        // 如果是新文件，写入文件头
-        if !file_exists {
-            writeln!(log_file, "\n=== 日志开始于 {} ===\n", 
-                current_time.format("%Y-%m-%d %H:%M:%S"))?;
+        if let Err(e) = Self::link_current(&log_file_name) {
+            eprintln!("Failed to update {:?}: {}", Self::current_link(), e);
+        }
+
+        // 如果是新文件，写入文件头；JSON 格式下文件头也是一个 JSON 对象
+        let header = match file_exists {
+            false => format!("=== 日志开始于 {} ===", current_time.format("%Y-%m-%d %H:%M:%S")),
+            true => format!("=== 程序启动于 {} ===", current_time.format("%Y-%m-%d %H:%M:%S")),
+        };
+        if json_file() {
+            let mut fields = Map::new();
+            fields.insert("message".to_string(), Value::String(header));
+            writeln!(log_file, "{}", json_line(&tracing::Level::INFO, module_path!(), Vec::new(), fields))?;
         } else {
-            writeln!(log_file, "\n=== 程序启动于 {} ===\n",
-                current_time.format("%Y-%m-%d %H:%M:%S"))?;
+            writeln!(log_file, "\n{}\n", header)?;
         }
 
         Ok((log_file, log_file_name))
     }
 
-    /// 内部初始化函数
-    fn init_logger_internal() -> Result<(), Box<dyn std::error::Error>> {
-        // 获取日志文件
-        let (log_file, _) = Self::get_log_file()?;
+    // 始终指向当前日志文件的 logs/current.log
+    fn current_link() -> PathBuf {
+        paths::log_dir().join(CURRENT_LOG)
+    }
 
-        // 创建多重写入器
-        let multi_writer = MultiWriter::new(vec![
-            Box::new(log_file),
-            Box::new(std::io::stderr()),
-        ]);
+    // 让 logs/current.log 指向当前日志文件
+    fn link_current(log_file_name: &str) -> io::Result<()> {
+        let link = Self::current_link();
+        let link = link.as_path();
+        if link.symlink_metadata().is_ok() {
+            fs::remove_file(link)?;
+        }
 
-        // 创建日志构建器
-        let mut builder = Builder::new();
-        
-        // 设置日志格式
-        builder.format(|buf, record| {
-            let mut style = buf.style();
-            let level_color = match record.level() {
-                log::Level::Error => Color::Red,
-                log::Level::Warn => Color::Yellow,
-                log::Level::Info => Color::Green,
-                log::Level::Debug => Color::Blue,
-                log::Level::Trace => Color::Cyan,
+        #[cfg(unix)]
+        {
+            // 使用相对路径，日志目录整体移动后链接仍然有效
+            let target = Path::new(log_file_name).file_name().unwrap_or_default();
+            std::os::unix::fs::symlink(target, link)
+        }
+        #[cfg(windows)]
+        {
+            // Windows 创建符号链接需要管理员权限，改用硬链接
+            fs::hard_link(log_file_name, link)
+        }
+    }
+
+    /// 输出当前日志文件内容，follow 为 true 时持续输出新写入的内容
+    pub fn print_current(follow: bool) -> io::Result<()> {
+        let path = Self::current_link();
+        let path = path.as_path();
+        let mut offset = 0;
+        loop {
+            let chunk = match Self::read_from(path, &mut offset) {
+                // 程序重启时链接会被重新创建，短暂不存在
+                Err(e) if follow && e.kind() == io::ErrorKind::NotFound => Vec::new(),
+                result => result?,
             };
-            style.set_color(level_color).set_bold(true);
-
-            writeln!(
-                buf,
-                "[{}] {} [{}] {}",
-                Local::now().format("%Y-%m-%d %H:%M:%S"),
-                style.value(record.level()),
-                record.target(),
-                record.args()
-            )
-        })
-        .filter(None, LevelFilter::Info)
-        .target(env_logger::Target::Pipe(Box::new(multi_writer)));
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(&chunk)?;
+            stdout.flush()?;
 
-        // 初始化日志系统
-        builder.init();
+            if !follow {
+                return Ok(());
+            }
+            std::thread::sleep(FOLLOW_INTERVAL);
+        }
+    }
+
+    // 读取文件中 offset 之后的新内容，文件变短时视为已被替换，从头读取
+    fn read_from(path: &Path, offset: &mut u64) -> io::Result<Vec<u8>> {
+        let mut file = File::open(path)?;
+        if file.metadata()?.len() < *offset {
+            *offset = 0;
+        }
+        file.seek(SeekFrom::Start(*offset))?;
+
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        *offset += buf.len() as u64;
+        Ok(buf)
+    }
+
+    /// 内部初始化函数
+    fn init_logger_internal() -> Result<(), Box<dyn std::error::Error>> {
+        // 获取日志文件，先清理过期的日志
+        let _ = prune_old_logs(&paths::log_dir(), policy().retention, SystemTime::now());
+        let (log_file, log_file_name) = Self::get_log_file()?;
+
+        // 按模块过滤的级别，可在运行中修改
+        let (filter, handle) = reload::Layer::new(parse_filters("")?);
+        let _ = FILTER.set(handle);
+        let console = tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_span_events(FmtSpan::CLOSE)
+            .event_format(LineFormat)
+            .with_writer(io::stderr);
+        let file = tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_span_events(FmtSpan::CLOSE)
+            .event_format(FileFormat(None))
+            .with_writer(std::sync::Mutex::new(RotatingFile::new(log_file, PathBuf::from(log_file_name))));
+
+        // 初始化日志系统，测试中其他模块可能已经设置了全局日志器
+        let _ = tracing_subscriber::registry().with(filter).with(BusLayer).with(console).with(file).try_init();
 
         Ok(())
     }
 }
 
-/// 多重写入器结构体，用于同时写入多个输出目标
-struct MultiWriter {
-    writers: Vec<Box<dyn Write + Send + Sync>>,
+// 每条日志一行："[时间] 级别 [模块] span: 消息"，与之前的格式一致，span 为所在的登录等过程
+struct LineFormat;
+
+impl<S, N> FormatEvent<S, N> for LineFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        // log 宏转发的记录取原来的模块名
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        write!(writer, "[{}] {} [{}] ", timezone::now().format("%Y-%m-%d %H:%M:%S"), metadata.level(), metadata.target())?;
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                write!(writer, "{}: ", span.name())?;
+            }
+        }
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
 }
 
-impl MultiWriter {
-    fn new(writers: Vec<Box<dyn Write + Send + Sync>>) -> Self {
-        Self { writers }
+// 日志文件的格式，为 None 时按当前设置选择文本行或 JSON
+struct FileFormat(Option<LogFormat>);
+
+impl FileFormat {
+    fn is_json(&self) -> bool {
+        self.0.map_or_else(json_file, |format| format == LogFormat::Json)
     }
 }
 
-impl Write for MultiWriter {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        for writer in &mut self.writers {
-            writer.write_all(buf)?;
+// 当前设置是否为 JSON 格式
+fn json_file() -> bool {
+    JSON_FILE.load(Ordering::Relaxed)
+}
+
+impl<S, N> FormatEvent<S, N> for FileFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        if !self.is_json() {
+            return LineFormat.format_event(ctx, writer, event);
         }
-        Ok(buf.len())
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let spans = ctx.event_scope()
+            .map(|scope| scope.from_root().map(|span| span.name()).collect())
+            .unwrap_or_default();
+        let mut fields = JsonVisitor(Map::new());
+        event.record(&mut fields);
+        writeln!(writer, "{}", json_line(metadata.level(), metadata.target(), spans, fields.0))
     }
+}
 
-    fn flush(&mut self) -> std::io::Result<()> {
-        for writer in &mut self.writers {
-            writer.flush()?;
+// 一行 JSON 日志，时间带时区偏移，精确到毫秒
+fn json_line(level: &tracing::Level, target: &str, spans: Vec<&str>, fields: Map<String, Value>) -> Value {
+    serde_json::json!({
+        "timestamp": timezone::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string(),
+        "level": level.as_str(),
+        "target": target,
+        "spans": spans,
+        "fields": fields,
+    })
+}
+
+// 收集事件的全部字段，数值和布尔值保持原类型；跳过 tracing-log 附加的 log.* 元数据
+struct JsonVisitor(Map<String, Value>);
+
+impl JsonVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        if !field.name().starts_with("log.") {
+            self.0.insert(field.name().to_string(), value);
         }
+    }
+}
+
+impl Visit for JsonVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, Value::from(format!("{:?}", value)));
+    }
+}
+
+// 把后台模块的日志发布到日志总线，由界面显示
+struct BusLayer;
+
+impl<S: Subscriber> Layer<S> for BusLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let level = match *metadata.level() {
+            tracing::Level::ERROR => log::Level::Error,
+            tracing::Level::WARN => log::Level::Warn,
+            tracing::Level::INFO => log::Level::Info,
+            tracing::Level::DEBUG => log::Level::Debug,
+            tracing::Level::TRACE => log::Level::Trace,
+        };
+        let mut message = MessageVisitor(String::new());
+        event.record(&mut message);
+        log_bus::publish(level, metadata.target(), message.0);
+    }
+}
+
+// 取出事件的消息字段
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0 = value.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+// 按月份和大小切换的日志文件：跨月时改写新月份的文件，超过大小上限或修改了日志格式时
+// 把当前文件归档为 .1.log（原有归档依次后移）后重新创建
+struct RotatingFile {
+    file: File,
+    path: PathBuf,
+    size: u64,
+    // 文件中的日志是否为 JSON 格式
+    json: bool,
+}
+
+impl RotatingFile {
+    fn new(file: File, path: PathBuf) -> Self {
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        Self { file, path, size, json: json_file() }
+    }
+
+    // 重新打开当月的日志文件
+    fn reopen(&mut self) -> io::Result<()> {
+        let (file, path) = Logger::get_log_file().map_err(|e| io::Error::other(e.to_string()))?;
+        *self = Self::new(file, PathBuf::from(path));
         Ok(())
     }
 }
 
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let policy = policy();
+        if Path::new(&Logger::log_file_name()) != self.path {
+            self.reopen()?;
+        } else if self.json != json_file() || self.size > 0 && self.size + buf.len() as u64 > policy.max_file_bytes {
+            rotate(&self.path, policy.max_archives)?;
+            self.reopen()?;
+        }
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+// 第 index 个归档的路径，如 campus_network_2024-05.2.log
+fn archive_path(path: &Path, index: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.{}.log", stem, index))
+}
+
+// 归档当前日志文件，最多保留 keep 个归档
+fn rotate(path: &Path, keep: usize) -> io::Result<()> {
+    if keep == 0 {
+        return fs::remove_file(path);
+    }
+    let _ = fs::remove_file(archive_path(path, keep));
+    for index in (1..keep).rev() {
+        let from = archive_path(path, index);
+        if from.exists() {
+            fs::rename(&from, archive_path(path, index + 1))?;
+        }
+    }
+    fs::rename(path, archive_path(path, 1))
+}
+
+/// 删除日志目录中最后修改时间早于 retention 的日志文件（包括归档），返回删除的个数
+pub fn prune_old_logs(dir: &Path, retention: Duration, now: SystemTime) -> io::Result<usize> {
+    let Some(cutoff) = now.checked_sub(retention) else {
+        return Ok(0);
+    };
+    let mut removed = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with(LOG_PREFIX) || !name.ends_with(".log") {
+            continue;
+        }
+        if entry.metadata()?.modified()? < cutoff {
+            fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,33 +543,91 @@ mod tests {
         let log_file = Path::new(&file_name);
         assert!(log_file.exists());
         
+        // 当前日志链接指向同一个文件
+        assert!(Logger::current_link().exists());
+
         // 清理测试文件
         let _ = fs::remove_file(log_file);
+        let _ = fs::remove_file(Logger::current_link());
         let _ = fs::remove_dir("./logs");
     }
 
     #[test]
-    fn test_multi_writer() {
-        // 创建测试文件
+    fn test_read_from_offset() {
         let test_file = tempfile::NamedTempFile::new().unwrap();
-        let test_file2 = tempfile::NamedTempFile::new().unwrap();
-        
-        // 创建多重写入器
-        let mut writer = MultiWriter::new(vec![
-            Box::new(test_file.reopen().unwrap()),
-            Box::new(test_file2.reopen().unwrap()),
-        ]);
-        
-        // 写入测试数据
-        let test_data = b"Test message\n";
-        let write_result = writer.write(test_data);
-        assert!(write_result.is_ok());
-        assert_eq!(write_result.unwrap(), test_data.len());
-        
-        // 验证数据写入
-        let content1 = fs::read(test_file.path()).unwrap();
-        let content2 = fs::read(test_file2.path()).unwrap();
-        assert_eq!(content1, test_data);
-        assert_eq!(content2, test_data);
+        let mut offset = 0;
+
+        fs::write(test_file.path(), "line 1\n").unwrap();
+        assert_eq!(Logger::read_from(test_file.path(), &mut offset).unwrap(), b"line 1\n");
+
+        // 只读取新追加的内容
+        let mut file = OpenOptions::new().append(true).open(test_file.path()).unwrap();
+        writeln!(file, "line 2").unwrap();
+        assert_eq!(Logger::read_from(test_file.path(), &mut offset).unwrap(), b"line 2\n");
+        assert!(Logger::read_from(test_file.path(), &mut offset).unwrap().is_empty());
+
+        // 文件被替换后从头读取
+        fs::write(test_file.path(), "new\n").unwrap();
+        assert_eq!(Logger::read_from(test_file.path(), &mut offset).unwrap(), b"new\n");
+    }
+
+    #[test]
+    fn test_parse_filters() {
+        assert_eq!(parse_filters("").unwrap().to_string(), "info");
+        let filter = parse_filters(" sn::backend::downloader=debug,thirtyfour=warn, ").unwrap().to_string();
+        assert!(filter.contains("sn::backend::downloader=debug"));
+        assert!(filter.contains("thirtyfour=warn"));
+        assert!(parse_filters("downloader=loud").is_err());
+    }
+
+    #[test]
+    fn test_rotate_and_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("campus_network_2024-05.log");
+
+        // 最多保留 2 个归档，最旧的被删除
+        for content in ["first", "second", "third"] {
+            fs::write(&path, content).unwrap();
+            rotate(&path, 2).unwrap();
+        }
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(archive_path(&path, 1)).unwrap(), "third");
+        assert_eq!(fs::read_to_string(archive_path(&path, 2)).unwrap(), "second");
+        assert!(!archive_path(&path, 3).exists());
+        assert_eq!(archive_path(&path, 1).file_name().unwrap(), "campus_network_2024-05.1.log");
+
+        // 只删除过期的日志文件，其他文件不动
+        fs::write(dir.path().join("notes.txt"), "").unwrap();
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert_eq!(prune_old_logs(dir.path(), day * 30, SystemTime::now()).unwrap(), 0);
+        assert_eq!(prune_old_logs(dir.path(), day * 30, SystemTime::now() + day * 31).unwrap(), 2);
+        assert!(dir.path().join("notes.txt").exists());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_json_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("json.log");
+        let writer = std::sync::Mutex::new(File::create(&path).unwrap());
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .event_format(FileFormat(Some(LogFormat::Json)))
+                .with_writer(writer)
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("login", backend = "http");
+            let _entered = span.enter();
+            tracing::warn!(attempt = 2, online = false, "Portal did not answer");
+        });
+
+        let content = fs::read_to_string(&path).unwrap();
+        let line: Value = serde_json::from_str(content.trim()).unwrap();
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["target"], module_path!());
+        assert_eq!(line["spans"], serde_json::json!(["login"]));
+        assert_eq!(line["fields"], serde_json::json!({"message": "Portal did not answer", "attempt": 2, "online": false}));
+        assert!(line["timestamp"].as_str().unwrap().contains('T'));
+        assert_eq!(serde_json::to_string(&LogFormat::Json).unwrap(), "\"json\"");
+    }
+}
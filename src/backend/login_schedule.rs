@@ -0,0 +1,176 @@
+// 定时登录登出：按配置的规则在每天（或每周的某几天）的固定时间登录或登出，如 07:00 登录、23:30 登出，适合按时段
+// 计费或限速的宿舍网络。最近一次生效的规则是登出时暂停自动登录，直到下一条登录规则生效。
+// 这里也定义每天的时段（TimeWindow），夜间免费时段和通知免打扰时段都用它表示
+use crate::backend::history::Action;
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+const TIME_FORMAT: &str = "%H:%M";
+// 查找上一次和下一次生效的规则时向前、向后看的天数，每周的规则一周内必然生效一次
+const LOOKAROUND_DAYS: i64 = 7;
+/// 规则中星期的写法，1 为周一，7 为周日
+pub const WEEKDAYS: [(u8, &str); 7] = [(1, "Mon"), (2, "Tue"), (3, "Wed"), (4, "Thu"), (5, "Fri"), (6, "Sat"), (7, "Sun")];
+
+/// 一条定时规则
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ScheduleRule {
+    pub action: Action,
+    // 生效时间 HH:MM（配置的时区）
+    pub time: String,
+    // 生效的星期（1 为周一，7 为周日），为空时每天生效
+    #[serde(default)]
+    pub weekdays: Vec<u8>,
+}
+
+impl ScheduleRule {
+    /// 解析生效时间，格式错误时返回错误
+    pub fn parse_time(&self) -> Result<NaiveTime> {
+        NaiveTime::parse_from_str(self.time.trim(), TIME_FORMAT)
+            .map_err(|_| anyhow!("Invalid time \"{}\", expected HH:MM", self.time))
+    }
+}
+
+/// 每天的一个时段，开始时间晚于结束时间时跨过午夜（如 23:00–07:00）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    /// 解析配置的开始和结束时间（HH:MM），都为空时表示没有设置时段
+    pub fn parse(start: &str, end: &str) -> Result<Option<Self>> {
+        let (start, end) = (start.trim(), end.trim());
+        if start.is_empty() && end.is_empty() {
+            return Ok(None);
+        }
+        let time = |value: &str| NaiveTime::parse_from_str(value, TIME_FORMAT)
+            .map_err(|_| anyhow!("Invalid time \"{}\", expected HH:MM", value));
+        let window = Self { start: time(start)?, end: time(end)? };
+        if window.start == window.end {
+            return Err(anyhow!("A time period must not start and end at the same time"));
+        }
+        Ok(Some(window))
+    }
+
+    /// 界面中显示的时段
+    pub fn label(&self) -> String {
+        format!("{}–{}", self.start.format(TIME_FORMAT), self.end.format(TIME_FORMAT))
+    }
+
+    /// 该时间是否在时段内（含开始，不含结束）
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        match self.start < self.end {
+            true => self.start <= time && time < self.end,
+            false => self.start <= time || time < self.end,
+        }
+    }
+
+    /// [from, to) 中落在时段内的秒数
+    pub fn overlap(&self, from: NaiveDateTime, to: NaiveDateTime) -> i64 {
+        let mut seconds = 0;
+        let mut date = from.date() - Duration::days(1);
+        while date <= to.date() {
+            let start = date.and_time(self.start);
+            let end = if self.end > self.start { date.and_time(self.end) } else { (date + Duration::days(1)).and_time(self.end) };
+            seconds += (end.min(to) - start.max(from)).num_seconds().max(0);
+            date += Duration::days(1);
+        }
+        seconds
+    }
+}
+
+// around 所在日期前后 LOOKAROUND_DAYS 天内所有有效规则的生效时间，按时间排序；同一时间有多条规则时后配置的排在后面
+fn occurrences(rules: &[ScheduleRule], around: NaiveDateTime) -> Vec<(NaiveDateTime, Action)> {
+    let mut occurrences: Vec<(NaiveDateTime, Action)> = (-LOOKAROUND_DAYS..=LOOKAROUND_DAYS)
+        .map(|offset| around.date() + Duration::days(offset))
+        .flat_map(|date| {
+            let weekday = date.weekday().number_from_monday() as u8;
+            rules.iter()
+                .filter(move |rule| rule.weekdays.is_empty() || rule.weekdays.contains(&weekday))
+                .filter_map(move |rule| rule.parse_time().ok().map(|time| (date.and_time(time), rule.action)))
+        })
+        .collect();
+    occurrences.sort_by_key(|(at, _)| *at);
+    occurrences
+}
+
+/// 在 (since, now] 之间到时间的规则中最后一条的操作；间隔中跨过多条规则时只执行最后一条
+pub fn due(rules: &[ScheduleRule], since: NaiveDateTime, now: NaiveDateTime) -> Option<Action> {
+    occurrences(rules, now).into_iter()
+        .rfind(|(at, _)| since < *at && *at <= now)
+        .map(|(_, action)| action)
+}
+
+/// 最近一次生效的规则是否为登出，是则暂停自动登录
+pub fn paused(rules: &[ScheduleRule], now: NaiveDateTime) -> bool {
+    occurrences(rules, now).into_iter()
+        .rfind(|(at, _)| *at <= now)
+        .is_some_and(|(_, action)| action == Action::Logout)
+}
+
+/// 下一条生效的规则
+pub fn next(rules: &[ScheduleRule], now: NaiveDateTime) -> Option<(NaiveDateTime, Action)> {
+    occurrences(rules, now).into_iter().find(|(at, _)| *at > now)
+}
+
+/// 下一条登录规则的时间说明，用于暂停自动登录时的提示
+pub fn resumes(rules: &[ScheduleRule], now: NaiveDateTime) -> String {
+    occurrences(rules, now).into_iter()
+        .find(|(at, action)| *at > now && *action == Action::Login)
+        .map(|(at, _)| format!("until {}", at.format("%a %H:%M")))
+        .unwrap_or_else(|| "until a login rule is added".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_login_schedule() {
+        let rules = vec![
+            ScheduleRule { action: Action::Login, time: "07:00".to_string(), weekdays: Vec::new() },
+            ScheduleRule { action: Action::Logout, time: "23:30".to_string(), weekdays: Vec::new() },
+            // 周六中午也登出
+            ScheduleRule { action: Action::Logout, time: "12:00".to_string(), weekdays: vec![6] },
+            ScheduleRule { action: Action::Login, time: "25:00".to_string(), weekdays: Vec::new() },
+        ];
+        assert!(rules[3].parse_time().is_err());
+
+        // 2026-10-16 为周五
+        assert_eq!(due(&rules, at("2026-10-16 06:59"), at("2026-10-16 07:00")), Some(Action::Login));
+        assert_eq!(due(&rules, at("2026-10-16 07:00"), at("2026-10-16 07:01")), None);
+        // 休眠跨过多条规则时执行最后一条
+        assert_eq!(due(&rules, at("2026-10-16 06:00"), at("2026-10-17 08:00")), Some(Action::Login));
+
+        assert!(!paused(&rules, at("2026-10-16 12:00")));
+        assert!(paused(&rules, at("2026-10-16 23:45")));
+        assert!(paused(&rules, at("2026-10-17 06:59")));
+        assert_eq!(resumes(&rules, at("2026-10-16 23:45")), "until Sat 07:00");
+        assert!(paused(&rules, at("2026-10-17 12:30")));
+        assert_eq!(resumes(&rules, at("2026-10-17 12:30")), "until Sun 07:00");
+        assert_eq!(next(&rules, at("2026-10-17 08:00")), Some((at("2026-10-17 12:00"), Action::Logout)));
+        assert!(!paused(&[], at("2026-10-16 23:45")));
+    }
+
+    #[test]
+    fn test_time_window() {
+        let night = TimeWindow::parse("23:00", " 07:00").unwrap().unwrap();
+        assert_eq!(night.label(), "23:00–07:00");
+        assert!(night.contains(at("2026-10-16 03:00").time()));
+        assert!(!night.contains(at("2026-10-16 07:00").time()));
+        assert_eq!(night.overlap(at("2026-10-16 22:00"), at("2026-10-17 08:00")), 8 * 3600);
+        let lunch = TimeWindow::parse("12:00", "13:30").unwrap().unwrap();
+        assert!(lunch.contains(at("2026-10-16 12:00").time()));
+        assert!(!lunch.contains(at("2026-10-16 23:00").time()));
+        assert_eq!(TimeWindow::parse(" ", "").unwrap(), None);
+        assert!(TimeWindow::parse("23:00", "7am").is_err());
+        assert!(TimeWindow::parse("23:00", "").is_err());
+        assert!(TimeWindow::parse("23:00", "23:00").is_err());
+    }
+}
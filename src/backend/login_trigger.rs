@@ -0,0 +1,164 @@
+// 自动登录的触发条件：启动时或从在线变为断开时尝试一次，被门户拦截时按失败次数退避后持续尝试；状态需持续一段时间（防抖）才登录，避免网络短暂抖动时反复登录
+use crate::backend::probe::Connectivity;
+use std::time::{Duration, Instant};
+
+/// 未配置时的防抖时间
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(5);
+/// 配置值的上限（秒）
+pub const MAX_DEBOUNCE_SECS: u64 = 600;
+
+/// 连续失败 failures 次后到下次重试的等待时间：前 3 次 30 秒，之后 2 分钟
+pub fn backoff(failures: u32) -> Duration {
+    Duration::from_secs(if failures > 3 { 120 } else { 30 })
+}
+
+#[derive(Debug)]
+pub struct LoginTrigger {
+    debounce: Duration,
+    // 上一次看到的状态，启动后还没有检查过时为 None
+    last: Option<Connectivity>,
+    // 满足登录条件的时间，等防抖时间过去后登录
+    pending_since: Option<Instant>,
+    // 最近一次在防抖时间内自行恢复的断线时长，取出后清除
+    recovered: Option<Duration>,
+    // 本次断线中是否已尝试过登录，登录过后恢复在线不算自行恢复
+    attempted: bool,
+    // 登录失败后的退避结束时间，之前被门户拦截也不再重试
+    retry_at: Option<Instant>,
+}
+
+impl LoginTrigger {
+    pub fn new(debounce: Duration) -> Self {
+        Self { debounce, last: None, pending_since: None, recovered: None, attempted: false, retry_at: None }
+    }
+
+    /// 修改防抖时间，已在等待的登录按新的时间计算
+    pub fn set_debounce(&mut self, debounce: Duration) {
+        self.debounce = debounce;
+    }
+
+    /// 登录失败后调用，until 之前被门户拦截也不再重试；从在线变为断开时仍会尝试
+    pub fn back_off(&mut self, until: Instant) {
+        self.retry_at = Some(until);
+        self.pending_since = None;
+    }
+
+    /// 记录一次检查结果，返回是否应该登录
+    pub fn observe(&mut self, connectivity: Connectivity, now: Instant) -> bool {
+        let previous = self.last.replace(connectivity);
+        if connectivity == Connectivity::Online {
+            self.retry_at = None;
+            if let Some(since) = self.pending_since.take().filter(|_| !self.attempted) {
+                self.recovered = Some(now.duration_since(since));
+            }
+            self.attempted = false;
+            return false;
+        }
+        // 断开后只尝试一次，之后交给状态变化；被门户拦截说明确实需要登录，失败后等退避结束再重试
+        let backed_off = self.retry_at.is_some_and(|retry_at| now < retry_at);
+        let arm = matches!(previous, None | Some(Connectivity::Online)) || (connectivity == Connectivity::PortalRedirect && !backed_off);
+        if arm && self.pending_since.is_none() {
+            self.pending_since = Some(now);
+        }
+        match self.pending_since {
+            Some(since) if now.duration_since(since) >= self.debounce => {
+                self.pending_since = None;
+                self.attempted = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 距离防抖结束还需等待的时间，没有待登录时为 None
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.pending_since.map(|since| self.debounce.saturating_sub(now.duration_since(since)))
+    }
+
+    /// 不经 observe 触发的登录（如本机地址变化）开始时调用，之后恢复在线不算自行恢复
+    pub fn record_attempt(&mut self) {
+        self.attempted = true;
+    }
+
+    /// 取出最近一次不登录也自行恢复的断线时长
+    pub fn take_recovery(&mut self) -> Option<Duration> {
+        self.recovered.take()
+    }
+
+    /// 当前的防抖时间
+    pub fn debounce(&self) -> Duration {
+        self.debounce
+    }
+
+    /// 暂停期间（维护、锁定、假期）按在线处理，恢复后仍不在线会再尝试登录
+    pub fn suspend(&mut self) {
+        self.last = Some(Connectivity::Online);
+        self.pending_since = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trigger() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // 启动时已被门户拦截，防抖时间过去后登录，失败后继续重试
+        let mut trigger = LoginTrigger::new(Duration::from_secs(5));
+        assert!(!trigger.observe(Connectivity::PortalRedirect, at(0)));
+        assert_eq!(trigger.remaining(at(2)), Some(Duration::from_secs(3)));
+        assert!(trigger.observe(Connectivity::PortalRedirect, at(5)));
+        assert!(!trigger.observe(Connectivity::PortalRedirect, at(6)));
+        assert!(trigger.observe(Connectivity::PortalRedirect, at(11)));
+
+        // 登录失败后一直被拦截时，退避结束前不再重试
+        trigger.back_off(at(11) + backoff(1));
+        assert!(!trigger.observe(Connectivity::PortalRedirect, at(16)));
+        assert!(!trigger.observe(Connectivity::PortalRedirect, at(31)));
+        assert_eq!(trigger.remaining(at(31)), None);
+        assert!(!trigger.observe(Connectivity::PortalRedirect, at(41)));
+        assert!(trigger.observe(Connectivity::PortalRedirect, at(46)));
+        assert_eq!(backoff(4), Duration::from_secs(120));
+
+        // 短暂断开在防抖时间内恢复时不登录
+        assert!(!trigger.observe(Connectivity::Online, at(20)));
+        assert!(!trigger.observe(Connectivity::Offline, at(30)));
+        assert!(!trigger.observe(Connectivity::Online, at(32)));
+        assert_eq!(trigger.remaining(at(40)), None);
+        assert_eq!(trigger.take_recovery(), Some(Duration::from_secs(2)));
+        assert_eq!(trigger.take_recovery(), None);
+
+        // 断开只尝试一次
+        assert!(!trigger.observe(Connectivity::Offline, at(50)));
+        assert!(trigger.observe(Connectivity::Offline, at(55)));
+        assert!(!trigger.observe(Connectivity::Offline, at(70)));
+
+        // 登录过后恢复在线不算自行恢复，即使之后又在等待重试
+        assert!(!trigger.observe(Connectivity::PortalRedirect, at(71)));
+        assert!(!trigger.observe(Connectivity::Online, at(72)));
+        assert_eq!(trigger.take_recovery(), None);
+        assert!(!trigger.observe(Connectivity::Offline, at(73)));
+        trigger.record_attempt();
+        assert!(!trigger.observe(Connectivity::Online, at(74)));
+        assert_eq!(trigger.take_recovery(), None);
+
+        // 暂停结束后仍断开时再尝试
+        trigger.suspend();
+        assert!(!trigger.observe(Connectivity::Offline, at(80)));
+        assert!(trigger.observe(Connectivity::Offline, at(85)));
+
+        // 不防抖时立即登录
+        let mut trigger = LoginTrigger::new(Duration::ZERO);
+        assert!(trigger.observe(Connectivity::Offline, at(0)));
+
+        // 修改防抖时间后，已在等待的登录按新的时间计算
+        let mut trigger = LoginTrigger::new(Duration::from_secs(60));
+        assert!(!trigger.observe(Connectivity::Offline, at(0)));
+        trigger.set_debounce(Duration::from_secs(10));
+        assert_eq!(trigger.remaining(at(4)), Some(Duration::from_secs(6)));
+        assert!(trigger.observe(Connectivity::Offline, at(10)));
+    }
+}
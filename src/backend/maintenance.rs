@@ -0,0 +1,118 @@
+// 门户维护检测：门户返回维护页面或持续返回 5xx 时暂停自动登录，恢复后继续
+use chrono::{DateTime, FixedOffset};
+use crate::backend::timezone;
+use std::fmt;
+
+// 维护页面中常见的关键字
+const MAINTENANCE_KEYWORDS: [&str; 5] = ["系统维护", "正在维护", "维护中", "系统升级中", "under maintenance"];
+/// 连续多少次 5xx 后认为门户在维护
+pub const SERVER_ERROR_THRESHOLD: u32 = 3;
+
+/// 门户响应的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortalState {
+    Healthy,
+    ServerError(u16),
+    MaintenancePage,
+}
+
+impl fmt::Display for PortalState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortalState::Healthy => write!(f, "Portal is healthy"),
+            PortalState::ServerError(status) => write!(f, "Portal returned HTTP {}", status),
+            PortalState::MaintenancePage => write!(f, "Portal is showing a maintenance page"),
+        }
+    }
+}
+
+impl std::error::Error for PortalState {}
+
+/// 根据状态码和页面内容判断门户状态
+pub fn classify(status: u16, body: &str) -> PortalState {
+    let lower = body.to_lowercase();
+    if MAINTENANCE_KEYWORDS.iter().any(|keyword| lower.contains(keyword)) {
+        PortalState::MaintenancePage
+    } else if status >= 500 {
+        PortalState::ServerError(status)
+    } else {
+        PortalState::Healthy
+    }
+}
+
+/// 记录门户是否处于维护状态
+#[derive(Debug, Default)]
+pub struct MaintenanceTracker {
+    consecutive_errors: u32,
+    active_since: Option<DateTime<FixedOffset>>,
+}
+
+impl MaintenanceTracker {
+    /// 记录一次门户状态，返回是否刚刚进入维护状态
+    pub fn record(&mut self, state: PortalState) -> bool {
+        let entering = match state {
+            PortalState::Healthy => {
+                self.reset();
+                return false;
+            }
+            // 维护页面直接认定，5xx 需要连续多次
+            PortalState::MaintenancePage => true,
+            PortalState::ServerError(_) => {
+                self.consecutive_errors += 1;
+                self.consecutive_errors >= SERVER_ERROR_THRESHOLD
+            }
+        };
+
+        if entering && self.active_since.is_none() {
+            self.active_since = Some(timezone::now());
+            return true;
+        }
+        false
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active_since.is_some()
+    }
+
+    /// 进入维护状态的时间
+    pub fn active_since(&self) -> Option<DateTime<FixedOffset>> {
+        self.active_since
+    }
+
+    pub fn reset(&mut self) {
+        self.consecutive_errors = 0;
+        self.active_since = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(200, "<html>v46ip='10.0.0.1'</html>"), PortalState::Healthy);
+        assert_eq!(classify(502, "Bad Gateway"), PortalState::ServerError(502));
+        assert_eq!(classify(200, "<h1>系统维护中，请稍后再试</h1>"), PortalState::MaintenancePage);
+        assert_eq!(classify(503, "Site Under Maintenance"), PortalState::MaintenancePage);
+    }
+
+    #[test]
+    fn test_tracker() {
+        let mut tracker = MaintenanceTracker::default();
+
+        // 偶发的 5xx 不会进入维护状态
+        assert!(!tracker.record(PortalState::ServerError(502)));
+        assert!(!tracker.record(PortalState::ServerError(502)));
+        assert!(!tracker.is_active());
+        assert!(tracker.record(PortalState::ServerError(502)));
+        assert!(tracker.is_active());
+        // 已经处于维护状态时不再重复通知
+        assert!(!tracker.record(PortalState::MaintenancePage));
+
+        // 门户恢复后清除状态
+        tracker.record(PortalState::Healthy);
+        assert!(!tracker.is_active());
+        assert!(tracker.record(PortalState::MaintenancePage));
+    }
+}
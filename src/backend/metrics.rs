@@ -0,0 +1,120 @@
+// Prometheus 指标导出（可选）：每次检查后把连接状态、健康度、时延和丢包以文本格式写入文件，供 node_exporter 的 textfile collector 读取
+use crate::backend::probe::Connectivity;
+use crate::backend::stats::{NetworkStats, TargetStats};
+use anyhow::{anyhow, Result};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// 一次检查后导出的数据
+pub struct MetricsSnapshot<'a> {
+    pub connectivity: Connectivity,
+    pub health_score: u8,
+    pub latency_ms: Option<u64>,
+    pub stats: &'a NetworkStats,
+    // 检查完成的时间（Unix 秒），用于判断数据是否过期
+    pub timestamp: u64,
+}
+
+/// node_exporter 只读取 .prom 文件
+pub fn validate_path(path: &str) -> Result<()> {
+    let path = path.trim();
+    if !path.is_empty() && !path.ends_with(".prom") {
+        return Err(anyhow!("Metrics file {} must end with .prom, node_exporter ignores other files", path));
+    }
+    Ok(())
+}
+
+/// 按 Prometheus 文本格式输出
+pub fn render(snapshot: &MetricsSnapshot) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, values: &[(Option<String>, f64)]| {
+        let _ = writeln!(out, "# HELP csunetwork_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE csunetwork_{} gauge", name);
+        for (labels, value) in values {
+            let _ = writeln!(out, "csunetwork_{}{} {}", name, labels.as_deref().unwrap_or(""), value);
+        }
+    };
+
+    let states = [
+        (Connectivity::Online, "online"),
+        (Connectivity::PortalRedirect, "portal_redirect"),
+        (Connectivity::Offline, "offline"),
+    ];
+    let state_values: Vec<_> = states.iter()
+        .map(|(state, name)| (Some(format!("{{state=\"{}\"}}", name)), if *state == snapshot.connectivity { 1.0 } else { 0.0 }))
+        .collect();
+    gauge("connectivity", "Result of the last check, 1 for the current state.", &state_values);
+    gauge("health_score_percent", "Share of probe targets reachable in the last check.", &[(None, snapshot.health_score as f64)]);
+    if let Some(ms) = snapshot.latency_ms {
+        gauge("latency_milliseconds", "Fastest probe in the last check.", &[(None, ms as f64)]);
+    }
+
+    let per_target = |value: fn(&TargetStats) -> Option<f64>| -> Vec<(Option<String>, f64)> {
+        snapshot.stats.targets.iter()
+            .filter_map(|target| value(target).map(|v| (Some(format!("{{target=\"{}\"}}", escape(&target.target))), v)))
+            .collect()
+    };
+    gauge("probe_latency_milliseconds", "Average latency of successful probes over the statistics window.", &per_target(|t| t.avg_latency_ms));
+    gauge("probe_jitter_milliseconds", "Average latency difference between consecutive probes.", &per_target(|t| t.jitter_ms));
+    gauge("probe_loss_ratio", "Share of failed probes over the statistics window.", &per_target(|t| Some(t.loss_rate)));
+    gauge("last_check_timestamp_seconds", "Unix time of the last check.", &[(None, snapshot.timestamp as f64)]);
+    out
+}
+
+/// 先写临时文件再改名，node_exporter 不会读到写了一半的文件
+pub fn write_textfile(path: &Path, contents: &str) -> Result<()> {
+    let temp = path.with_extension("prom.tmp");
+    fs::write(&temp, contents).map_err(|e| anyhow!("Failed to write {}: {}", temp.display(), e))?;
+    fs::rename(&temp, path).map_err(|e| anyhow!("Failed to replace {}: {}", path.display(), e))
+}
+
+// 标签值中的反斜杠、引号和换行需要转义
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let stats = NetworkStats {
+            targets: vec![TargetStats {
+                target: "HTTP \"portal\"".to_string(),
+                samples: 4,
+                avg_latency_ms: Some(12.5),
+                jitter_ms: None,
+                loss_rate: 0.25,
+            }],
+        };
+        let text = render(&MetricsSnapshot {
+            connectivity: Connectivity::PortalRedirect,
+            health_score: 50,
+            latency_ms: None,
+            stats: &stats,
+            timestamp: 1_700_000_000,
+        });
+        assert!(text.contains("csunetwork_connectivity{state=\"portal_redirect\"} 1\n"));
+        assert!(text.contains("csunetwork_connectivity{state=\"online\"} 0\n"));
+        assert!(text.contains("csunetwork_health_score_percent 50\n"));
+        assert!(!text.contains("csunetwork_latency_milliseconds"));
+        assert!(text.contains("csunetwork_probe_latency_milliseconds{target=\"HTTP \\\"portal\\\"\"} 12.5\n"));
+        assert!(text.contains("csunetwork_probe_loss_ratio{target=\"HTTP \\\"portal\\\"\"} 0.25\n"));
+        assert!(text.contains("csunetwork_last_check_timestamp_seconds 1700000000\n"));
+
+        assert!(validate_path("").is_ok());
+        assert!(validate_path("/var/lib/node_exporter/csunetwork.prom").is_ok());
+        assert!(validate_path("/var/lib/node_exporter/csunetwork.txt").is_err());
+    }
+
+    #[test]
+    fn test_write_textfile() {
+        let path = std::env::temp_dir().join(format!("csunetwork-{}.prom", rand::random::<u32>()));
+        write_textfile(&path, "csunetwork_health_score_percent 100\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "csunetwork_health_score_percent 100\n");
+        assert!(!path.with_extension("prom.tmp").exists());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,5 +1,70 @@
+pub mod address_watch;
 pub mod auth;
+#[cfg(feature = "webdriver")]
 pub mod authentication;
+pub mod autostart;
+pub mod backup;
+pub mod browser;
+pub mod cassette;
+pub mod changelog;
 pub mod config;
+pub mod config_format;
+pub mod config_handle;
+#[cfg(feature = "control_api")]
+pub mod control_api;
+pub mod credential_provider;
+#[cfg(feature = "downloader")]
+pub mod diagnostics;
+pub mod dns_hijack;
+pub mod dns_prime;
+#[cfg(feature = "downloader")]
 pub mod downloader;
-pub mod network_monitor;
\ No newline at end of file
+pub mod dpapi;
+pub mod drcom;
+pub mod history;
+pub mod insights;
+pub mod ip_stack;
+pub mod isp;
+pub mod keep_alive;
+pub mod keychain;
+pub mod lockout;
+pub mod log_bus;
+pub mod logger;
+pub mod login_schedule;
+pub mod login_trigger;
+pub mod maintenance;
+pub mod metrics;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod network_monitor;
+pub mod notifier;
+#[cfg(feature = "webdriver")]
+pub mod orchestrator;
+pub mod outage;
+pub mod paths;
+pub mod peer_stats;
+#[cfg(any(feature = "webdriver", feature = "downloader"))]
+pub mod platform;
+pub mod portal;
+pub mod portal_discovery;
+pub mod portal_session;
+pub mod probe;
+pub mod profile_import;
+pub mod proxy_detect;
+pub mod rate_limit;
+pub mod retry_prompt;
+pub mod schedule;
+pub mod secret;
+pub mod self_test;
+pub mod semester;
+#[cfg(feature = "webdriver")]
+pub mod session;
+pub mod stats;
+pub mod system_info;
+pub mod timezone;
+pub mod traceroute;
+pub mod traffic;
+pub mod triage;
+pub mod vpn;
+pub mod watchdog;
+pub mod webhook;
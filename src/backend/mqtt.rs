@@ -0,0 +1,262 @@
+// MQTT 状态发布（可选）：把外网连通性、门户会话和已用流量发布到 MQTT 服务器，供宿舍的 Home Assistant 等面板显示，断网时触发自动化
+use crate::backend::auth::OnlineStatus;
+use crate::backend::config::Config;
+use crate::backend::watchdog;
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// 未配置主题前缀时使用
+const DEFAULT_TOPIC_PREFIX: &str = "csunetwork";
+// 连不上服务器时重试的间隔
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+/// state 消息的格式版本，随消息一起发布。只增加字段时不变；删除、改名或改变字段含义时加一，并在 Readme 中说明
+pub const STATE_SCHEMA_VERSION: u32 = 1;
+
+/// 连接和发布所需的设置，变化时重新连接
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttSettings {
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    pub topic_prefix: String,
+}
+
+impl MqttSettings {
+    /// 从配置读取，没有配置服务器地址时返回 None
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let url = config.mqtt_url.trim();
+        if url.is_empty() {
+            return None;
+        }
+        let prefix = config.mqtt_topic_prefix.trim().trim_end_matches('/');
+        Some(Self {
+            url: url.to_string(),
+            username: config.mqtt_username.clone(),
+            password: config.mqtt_password.expose().to_string(),
+            topic_prefix: if prefix.is_empty() { DEFAULT_TOPIC_PREFIX } else { prefix }.to_string(),
+        })
+    }
+
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{}", self.topic_prefix, name)
+    }
+
+    // mqtt:// 为明文连接，mqtts:// 使用 TLS 和系统根证书；只写主机名时按 mqtt:// 处理
+    fn options(&self) -> Result<MqttOptions> {
+        let mut url = if self.url.contains("://") { self.url.clone() } else { format!("mqtt://{}", self.url) };
+        if !url.contains("client_id=") {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            url = format!("{}{}client_id=csunetwork-{:08x}", url, separator, rand::random::<u32>());
+        }
+        let mut options = MqttOptions::parse_url(url)
+            .map_err(|e| anyhow!("Invalid MQTT broker address {}: {}", self.url, e))?;
+        if !self.username.is_empty() {
+            options.set_credentials(&self.username, &self.password);
+        }
+        options.set_keep_alive(Duration::from_secs(30));
+        // 程序退出或断网时由服务器发布 offline
+        options.set_last_will(LastWill::new(self.topic("availability"), "offline", QoS::AtLeastOnce, true));
+        Ok(options)
+    }
+}
+
+/// 发布的网络状态
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct NetworkState {
+    // 外网是否连通
+    pub connected: bool,
+    // 门户是否显示已认证，未查询到时为空
+    pub signed_in: Option<bool>,
+    pub account: Option<String>,
+    pub ip: Option<String>,
+    // 本次登录已用流量（MB）
+    pub used_mb: Option<u64>,
+    // 本次登录在线时长（分钟）
+    pub online_minutes: Option<u64>,
+}
+
+impl NetworkState {
+    pub fn new(connected: bool, status: Option<&OnlineStatus>) -> Self {
+        let Some(status) = status else {
+            return Self { connected, ..Default::default() };
+        };
+        Self {
+            connected,
+            signed_in: Some(status.online),
+            account: status.account.clone(),
+            ip: status.ip.clone(),
+            used_mb: status.used_bytes.map(|bytes| bytes / 1024 / 1024),
+            online_minutes: status.duration.map(|duration| duration.as_secs() / 60),
+        }
+    }
+}
+
+// state 消息的内容：格式版本加上网络状态的各字段，脚本和面板依赖这些字段名，修改 NetworkState 时注意兼容
+#[derive(Serialize)]
+struct StatePayload<'a> {
+    schema_version: u32,
+    #[serde(flatten)]
+    state: &'a NetworkState,
+}
+
+// 一次状态对应的消息：完整状态的 JSON，以及可直接用作 binary_sensor 的 connected（ON/OFF）
+fn messages(settings: &MqttSettings, state: &NetworkState) -> Vec<(String, String)> {
+    let payload = StatePayload { schema_version: STATE_SCHEMA_VERSION, state };
+    vec![
+        (settings.topic("state"), serde_json::to_string(&payload).unwrap_or_default()),
+        (settings.topic("connected"), if state.connected { "ON" } else { "OFF" }.to_string()),
+    ]
+}
+
+/// 后台保持与 MQTT 服务器的连接，状态变化时发布
+pub struct MqttPublisher {
+    client: Client,
+    settings: MqttSettings,
+    last: Option<NetworkState>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MqttPublisher {
+    /// 在后台线程中连接服务器，断开后自动重连；地址无效时返回错误
+    pub fn connect(settings: MqttSettings) -> Result<Self> {
+        let (client, mut connection) = Client::new(settings.options()?, 16);
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let stop = stopped.clone();
+        let availability_client = client.clone();
+        let availability = settings.topic("availability");
+        let broker = settings.url.clone();
+        watchdog::spawn("mqtt", move || {
+            let mut failing = false;
+            for event in connection.iter() {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                match event {
+                    // 每次连上（包括重连）后重新声明在线，覆盖遗嘱消息
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        if failing {
+                            info!("MQTT broker {} reconnected", broker);
+                        }
+                        failing = false;
+                        let _ = availability_client.try_publish(availability.as_str(), QoS::AtLeastOnce, true, "online");
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        if !failing {
+                            warn!("MQTT broker {} unreachable: {}", broker, e);
+                        }
+                        failing = true;
+                        std::thread::sleep(RETRY_DELAY);
+                    }
+                }
+            }
+        });
+
+        Ok(Self { client, settings, last: None, stopped })
+    }
+
+    /// 状态有变化时发布；消息带保留标记，面板订阅后立即拿到最新状态
+    pub fn publish(&mut self, state: &NetworkState) {
+        if self.last.as_ref() == Some(state) {
+            return;
+        }
+        let sent = messages(&self.settings, state).into_iter().all(|(topic, payload)| {
+            match self.client.try_publish(topic, QoS::AtLeastOnce, true, payload) {
+                Ok(()) => true,
+                Err(e) => {
+                    // 长时间连不上服务器时队列会满，之后的状态变化会再次尝试
+                    debug!("MQTT publish dropped: {}", e);
+                    false
+                }
+            }
+        });
+        if sent {
+            self.last = Some(state.clone());
+        }
+    }
+}
+
+impl Drop for MqttPublisher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_and_messages() {
+        let mut config = Config::default();
+        assert_eq!(MqttSettings::from_config(&config), None);
+
+        config.mqtt_url = "mqtts://broker.local:8883".to_string();
+        config.mqtt_topic_prefix = "dorm/network/".to_string();
+        let settings = MqttSettings::from_config(&config).unwrap();
+        assert_eq!(settings.topic("state"), "dorm/network/state");
+        assert_eq!(settings.options().unwrap().broker_address(), ("broker.local".to_string(), 8883));
+
+        config.mqtt_url = "192.168.1.10".to_string();
+        config.mqtt_topic_prefix.clear();
+        let settings = MqttSettings::from_config(&config).unwrap();
+        assert_eq!(settings.topic("state"), "csunetwork/state");
+        assert_eq!(settings.options().unwrap().broker_address(), ("192.168.1.10".to_string(), 1883));
+
+        let status = OnlineStatus {
+            online: true,
+            ip: Some("10.96.12.34".to_string()),
+            account: Some("8208190101".to_string()),
+            used_bytes: Some(512 * 1024 * 1024),
+            duration: Some(Duration::from_secs(90 * 60)),
+            stack: None,
+        };
+        let state = NetworkState::new(false, Some(&status));
+        assert_eq!(state.used_mb, Some(512));
+        assert_eq!(state.online_minutes, Some(90));
+
+        let messages = messages(&settings, &state);
+        assert_eq!(messages[1], ("csunetwork/connected".to_string(), "OFF".to_string()));
+        let json: serde_json::Value = serde_json::from_str(&messages[0].1).unwrap();
+        assert_eq!(json["signed_in"], true);
+        assert_eq!(json["account"], "8208190101");
+    }
+
+    // 已发布的字段名和类型不能改变；确需改变时增加 STATE_SCHEMA_VERSION 并更新这里和 Readme
+    #[test]
+    fn test_state_schema_compatibility() {
+        let settings = MqttSettings::from_config(&Config { mqtt_url: "localhost".to_string(), ..Config::default() }).unwrap();
+        let state = NetworkState {
+            connected: true,
+            signed_in: Some(true),
+            account: Some("8208190101".to_string()),
+            ip: Some("10.96.12.34".to_string()),
+            used_mb: Some(512),
+            online_minutes: Some(90),
+        };
+        let json: serde_json::Value = serde_json::from_str(&messages(&settings, &state)[0].1).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "schema_version": 1,
+                "connected": true,
+                "signed_in": true,
+                "account": "8208190101",
+                "ip": "10.96.12.34",
+                "used_mb": 512,
+                "online_minutes": 90,
+            })
+        );
+
+        // 未查询到门户状态时字段为 null，不省略
+        let json: serde_json::Value = serde_json::from_str(&messages(&settings, &NetworkState::default())[0].1).unwrap();
+        assert_eq!(json.as_object().unwrap().len(), 7);
+        assert!(json["signed_in"].is_null());
+    }
+}
@@ -0,0 +1,181 @@
+// 桌面通知：断网、自动登录成功和失败时通过系统通知（Windows 下为 toast）提示，窗口最小化时也能看到；每类事件可在设置中单独关闭。
+// 设置了免打扰时段时，期间的通知和 Webhook 先暂存，时段结束后显示一条汇总通知并一起发送 Webhook，日志照常记录
+use crate::backend::webhook::{self, Notice, Webhook};
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+
+// 通知中显示的程序名
+const APP_NAME: &str = "CSUNetwork";
+// 免打扰时段结束后汇总通知的标题
+const QUIET_SUMMARY_TITLE: &str = "During quiet hours";
+// 免打扰时段内最多暂存的 Webhook 事件数，更早的丢弃
+const MAX_HELD_NOTICES: usize = 50;
+
+/// 发送通知的事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyEvent {
+    // 网络从在线变为断开或需要登录
+    Disconnected,
+    AutoLoginSucceeded,
+    // 连续失败中只通知第一次，之后的重试由 "Auto Login Keeps Failing" 窗口处理
+    LoginFailed,
+}
+
+impl NotifyEvent {
+    pub const ALL: [NotifyEvent; 3] = [NotifyEvent::Disconnected, NotifyEvent::AutoLoginSucceeded, NotifyEvent::LoginFailed];
+
+    /// 通知的标题
+    pub fn title(&self) -> &'static str {
+        match self {
+            NotifyEvent::Disconnected => "Network disconnected",
+            NotifyEvent::AutoLoginSucceeded => "Auto login succeeded",
+            NotifyEvent::LoginFailed => "Auto login failed",
+        }
+    }
+
+    /// 设置中开关的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotifyEvent::Disconnected => "Network disconnected",
+            NotifyEvent::AutoLoginSucceeded => "Auto login succeeded",
+            NotifyEvent::LoginFailed => "Auto login failed (first failure in a row)",
+        }
+    }
+}
+
+/// 各类事件是否发送通知，默认全部开启
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub disconnected: bool,
+    pub auto_login_succeeded: bool,
+    pub login_failed: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self { disconnected: true, auto_login_succeeded: true, login_failed: true }
+    }
+}
+
+impl NotificationSettings {
+    /// 该事件的开关
+    pub fn enabled_mut(&mut self, event: NotifyEvent) -> &mut bool {
+        match event {
+            NotifyEvent::Disconnected => &mut self.disconnected,
+            NotifyEvent::AutoLoginSucceeded => &mut self.auto_login_succeeded,
+            NotifyEvent::LoginFailed => &mut self.login_failed,
+        }
+    }
+
+    pub fn enabled(&self, event: NotifyEvent) -> bool {
+        match event {
+            NotifyEvent::Disconnected => self.disconnected,
+            NotifyEvent::AutoLoginSucceeded => self.auto_login_succeeded,
+            NotifyEvent::LoginFailed => self.login_failed,
+        }
+    }
+}
+
+/// 事件开启通知时显示系统通知，失败（如没有通知服务）只记录日志
+pub fn notify(settings: &NotificationSettings, event: NotifyEvent, body: &str) {
+    if settings.enabled(event) {
+        show(event.title(), body);
+    }
+}
+
+fn show(title: &str, body: &str) {
+    if let Err(e) = Notification::new().appname(APP_NAME).summary(title).body(body).show() {
+        log::warn!("Failed to show the \"{}\" notification: {}", title, e);
+    }
+}
+
+// 汇总通知的内容，按事件计数，如 "Network disconnected ×2, Auto login succeeded"
+fn summary(events: &[NotifyEvent]) -> String {
+    NotifyEvent::ALL.iter()
+        .map(|event| (event, events.iter().filter(|held| *held == event).count()))
+        .filter(|(_, count)| *count > 0)
+        .map(|(event, count)| match count {
+            1 => event.title().to_string(),
+            _ => format!("{} ×{}", event.title(), count),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 免打扰时段内暂存的桌面通知和 Webhook，各线程共用一份
+#[derive(Debug, Default)]
+pub struct QuietHours {
+    events: Vec<NotifyEvent>,
+    notices: Vec<Notice>,
+}
+
+impl QuietHours {
+    /// 显示通知；quiet 为 true（免打扰时段内）时只暂存开启了通知的事件
+    pub fn notify(&mut self, quiet: bool, settings: &NotificationSettings, event: NotifyEvent, body: &str) {
+        match quiet {
+            true if settings.enabled(event) => self.events.push(event),
+            true => {}
+            false => notify(settings, event, body),
+        }
+    }
+
+    /// 在后台发送 Webhook；免打扰时段内先暂存
+    pub fn dispatch(&mut self, quiet: bool, webhooks: &[Webhook], notices: Vec<Notice>) {
+        if !quiet {
+            webhook::dispatch(webhooks, notices);
+            return;
+        }
+        self.notices.extend(notices);
+        let excess = self.notices.len().saturating_sub(MAX_HELD_NOTICES);
+        self.notices.drain(..excess);
+    }
+
+    /// 免打扰时段结束后发送暂存的 Webhook，并显示一条汇总通知；返回汇总的内容，没有暂存的通知时为 None
+    pub fn release(&mut self, quiet: bool, webhooks: &[Webhook]) -> Option<String> {
+        if quiet {
+            return None;
+        }
+        if !self.notices.is_empty() {
+            webhook::dispatch(webhooks, std::mem::take(&mut self.notices));
+        }
+        if self.events.is_empty() {
+            return None;
+        }
+        let body = summary(&std::mem::take(&mut self.events));
+        show(QUIET_SUMMARY_TITLE, &body);
+        Some(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_settings() {
+        let mut settings = NotificationSettings::default();
+        assert!(NotifyEvent::ALL.iter().all(|&event| settings.enabled(event)));
+        *settings.enabled_mut(NotifyEvent::AutoLoginSucceeded) = false;
+        assert!(!settings.enabled(NotifyEvent::AutoLoginSucceeded));
+        assert!(settings.enabled(NotifyEvent::LoginFailed));
+
+        // 旧配置中没有的开关默认开启
+        let loaded: NotificationSettings = serde_json::from_str(r#"{"disconnected": false}"#).unwrap();
+        assert_eq!(loaded, NotificationSettings { disconnected: false, ..NotificationSettings::default() });
+
+        // 关闭的事件不会发送通知
+        notify(&loaded, NotifyEvent::Disconnected, "Offline");
+
+        // 免打扰时段内只暂存开启了通知的事件，时段内不发出
+        let mut quiet = QuietHours::default();
+        quiet.notify(true, &loaded, NotifyEvent::Disconnected, "Offline");
+        quiet.notify(true, &loaded, NotifyEvent::LoginFailed, "timeout");
+        quiet.notify(true, &loaded, NotifyEvent::LoginFailed, "timeout");
+        quiet.dispatch(true, &[], vec![Notice::new(webhook::WebhookEvent::NetworkDown, "Offline", "")]);
+        assert_eq!(quiet.events, vec![NotifyEvent::LoginFailed, NotifyEvent::LoginFailed]);
+        assert_eq!(quiet.notices.len(), 1);
+        assert_eq!(quiet.release(true, &[]), None);
+        assert_eq!(summary(&[NotifyEvent::LoginFailed, NotifyEvent::AutoLoginSucceeded, NotifyEvent::LoginFailed]), "Auto login succeeded, Auto login failed ×2");
+    }
+}
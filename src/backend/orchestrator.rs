@@ -0,0 +1,116 @@
+// 登录方式回退：先用轻量的 HTTP 接口登录，门户拒绝接口请求或返回意料之外的页面时再用浏览器填写表单。
+// 账号密码错误、账号被锁定或连不上门户时浏览器也无济于事，直接报告，不再启动浏览器
+use crate::backend::config::Config;
+use crate::backend::lockout;
+use crate::backend::portal;
+use crate::backend::session::SessionManager;
+use crate::backend::triage::{self, Failure};
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+
+/// 登录成功时使用的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthPath {
+    Http,
+    Browser,
+}
+
+impl AuthPath {
+    /// 日志中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuthPath::Http => "HTTP portal",
+            AuthPath::Browser => "browser",
+        }
+    }
+}
+
+/// HTTP 登录的结果
+#[derive(Debug)]
+pub enum HttpAttempt {
+    // 登录成功，附带门户的提示信息
+    SignedIn(String),
+    // 换用浏览器也不会成功的失败
+    Failed(anyhow::Error),
+    // 值得改用浏览器再试的失败，附带原因
+    FallBack(String),
+}
+
+/// HTTP 登录失败后是否改用浏览器：账号密码错误、被锁定或连不上门户时不改用
+pub fn should_fall_back(failure: Option<&Failure>, message: &str) -> bool {
+    !matches!(failure, Some(Failure::WrongPassword | Failure::PortalUnreachable(_))) && !lockout::is_lockout_message(message)
+}
+
+// 按 HTTP 登录失败的原因决定是否回退
+fn classify(failure: Option<Failure>, message: String) -> HttpAttempt {
+    if should_fall_back(failure.as_ref(), &message) {
+        return HttpAttempt::FallBack(message);
+    }
+    HttpAttempt::Failed(match failure {
+        Some(failure) => anyhow::Error::new(failure).context(message),
+        None => anyhow!(message),
+    })
+}
+
+/// 通过配置的门户插件的 HTTP 接口登录，并判断失败时是否值得改用浏览器
+pub async fn try_http(config: &Config) -> HttpAttempt {
+    let mut provider = portal::http_provider(config);
+    debug!("Trying the {} HTTP interface", provider.portal_type().label());
+    match provider.login().await {
+        Ok(msg) => HttpAttempt::SignedIn(msg),
+        Err(e) => classify(triage::diagnose(e.as_ref()), e.to_string()),
+    }
+}
+
+/// 先 HTTP 后浏览器的登录流程，浏览器会话由 sessions 复用
+pub struct AuthOrchestrator<'a> {
+    sessions: &'a mut SessionManager,
+}
+
+impl<'a> AuthOrchestrator<'a> {
+    pub fn new(sessions: &'a mut SessionManager) -> Self {
+        Self { sessions }
+    }
+
+    /// 登录，返回成功时使用的方式；browser_config 为改用浏览器时的配置（如预热会话不显示窗口）
+    pub async fn login(&mut self, config: &Config, browser_config: &Config) -> Result<AuthPath> {
+        match try_http(config).await {
+            HttpAttempt::SignedIn(msg) => {
+                info!("Signed in through the HTTP portal: {}", msg);
+                Ok(AuthPath::Http)
+            }
+            HttpAttempt::Failed(e) => Err(e),
+            HttpAttempt::FallBack(reason) => {
+                warn!("HTTP login did not go through, falling back to the browser: {}", reason);
+                self.sessions.login(browser_config).await?;
+                info!("Signed in with the browser after HTTP login failed");
+                Ok(AuthPath::Browser)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_fall_back() {
+        // 门户拒绝接口请求或页面无法解析时改用浏览器
+        assert!(should_fall_back(None, "Portal rejected the login request: 参数错误"));
+        assert!(should_fall_back(None, "Unexpected portal response: expected value at line 1 column 1"));
+        assert!(matches!(classify(None, "no address".to_string()), HttpAttempt::FallBack(reason) if reason == "no address"));
+
+        // 浏览器也无济于事的失败直接报告，保留故障类型
+        assert!(!should_fall_back(Some(&Failure::PortalUnreachable("timed out".to_string())), ""));
+        assert!(!should_fall_back(None, "账号已被锁定，请10分钟后再试"));
+        match classify(Some(Failure::WrongPassword), "Portal rejected the login request: bGRhcCBhdXRoIGVycm9y".to_string()) {
+            HttpAttempt::Failed(e) => {
+                assert_eq!(triage::diagnose(e.as_ref()), Some(Failure::WrongPassword));
+                assert!(e.to_string().starts_with("Portal rejected"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(AuthPath::Browser.label(), "browser");
+    }
+}
@@ -0,0 +1,98 @@
+// 出口故障判断：门户认为本机已在线、外部目标却都不可达时，看校内目标是否仍可达。校内可达说明校园网到运营商的出口故障，
+// 重新登录没有用，暂停自动登录直到恢复；校内也不可达说明是本机连接或认证出了问题，照常重新登录
+use chrono::{DateTime, FixedOffset};
+use crate::backend::timezone;
+
+/// 外部目标不可达的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutageKind {
+    // 校内目标可达，运营商出口故障
+    Upstream,
+    // 校内目标也不可达，本机连接或认证问题
+    Local,
+}
+
+impl OutageKind {
+    /// 状态栏中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            OutageKind::Upstream => "Upstream ISP outage",
+            OutageKind::Local => "Local connection or authentication problem",
+        }
+    }
+
+    /// 鼠标悬停时显示的说明
+    pub fn advice(&self) -> &'static str {
+        match self {
+            OutageKind::Upstream => "The portal says you are signed in and campus hosts respond, but nothing outside campus does. Logging in again will not help, so auto login waits until the internet comes back",
+            OutageKind::Local => "The portal says you are signed in, but campus hosts do not respond either. Check the cable or Wi-Fi; auto login signs in again in case the session is stale",
+        }
+    }
+}
+
+/// 外部目标都不可达时判断原因；门户不认为本机已在线时是普通的未登录，返回 None
+pub fn classify(portal_online: bool, intranet_reachable: bool) -> Option<OutageKind> {
+    match (portal_online, intranet_reachable) {
+        (false, _) => None,
+        (true, true) => Some(OutageKind::Upstream),
+        (true, false) => Some(OutageKind::Local),
+    }
+}
+
+/// 记录当前的判断结果及开始时间，与界面共享
+#[derive(Debug, Default)]
+pub struct OutageTracker {
+    current: Option<(OutageKind, DateTime<FixedOffset>)>,
+}
+
+impl OutageTracker {
+    /// 记录一次判断结果，返回结果是否变化
+    pub fn record(&mut self, kind: Option<OutageKind>) -> bool {
+        if self.current.map(|(current, _)| current) == kind {
+            return false;
+        }
+        self.current = kind.map(|kind| (kind, timezone::now()));
+        true
+    }
+
+    pub fn current(&self) -> Option<(OutageKind, DateTime<FixedOffset>)> {
+        self.current
+    }
+
+    /// 出口故障期间不重新登录
+    pub fn suppresses_login(&self) -> bool {
+        matches!(self.current, Some((OutageKind::Upstream, _)))
+    }
+
+    /// 外部目标恢复后清除，返回之前是否有记录
+    pub fn reset(&mut self) -> bool {
+        self.current.take().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outage_tracker() {
+        assert_eq!(classify(false, true), None);
+        assert_eq!(classify(true, true), Some(OutageKind::Upstream));
+        assert_eq!(classify(true, false), Some(OutageKind::Local));
+
+        let mut tracker = OutageTracker::default();
+        assert!(!tracker.record(None));
+        assert!(tracker.record(Some(OutageKind::Upstream)));
+        assert!(tracker.suppresses_login());
+        // 结果不变时不重复通知，开始时间保持不变
+        let since = tracker.current().unwrap().1;
+        assert!(!tracker.record(Some(OutageKind::Upstream)));
+        assert_eq!(tracker.current().unwrap().1, since);
+
+        // 校内也不可达时照常重新登录
+        assert!(tracker.record(Some(OutageKind::Local)));
+        assert!(!tracker.suppresses_login());
+        assert!(tracker.reset());
+        assert!(!tracker.reset());
+    }
+}
@@ -0,0 +1,171 @@
+// 配置和日志的存放位置：默认放在系统的配置目录（Windows 为 %APPDATA%\CSUNetwork，Linux 为 ~/.config/csunetwork），
+// 从快捷方式、开机启动或其他工作目录启动时读到的都是同一份配置；--portable 时沿用工作目录下的 config 和 logs，
+// 适合放在 U 盘上使用。Windows 服务以 SYSTEM 身份运行，安装时用 --config-dir 指定安装者的配置目录。
+// 导出的会话历史和诊断包也放在配置目录下
+use anyhow::{anyhow, Result};
+use directories::BaseDirs;
+use log::info;
+use parking_lot::RwLock;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 使用工作目录下的 config 和 logs（旧版本的行为）
+#[cfg(any(windows, test))]
+pub const PORTABLE_ARG: &str = "--portable";
+/// 指定配置目录，日志写在其中的 logs 下
+#[cfg(any(windows, test))]
+pub const CONFIG_DIR_ARG: &str = "--config-dir";
+// 便携模式下配置和日志所在的目录，相对于工作目录
+const PORTABLE_CONFIG_DIR: &str = "config";
+const PORTABLE_LOG_DIR: &str = "logs";
+// 系统配置目录下的程序目录名，Linux 上按惯例用小写
+const APP_DIR: &str = if cfg!(any(windows, target_os = "macos")) { "CSUNetwork" } else { "csunetwork" };
+// 配置目录中判断已有配置的文件
+const CONFIG_FILES: [&str; 2] = ["config.toml", "config.json"];
+
+/// 配置和日志的存放位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    // 系统的配置目录
+    Platform,
+    // 工作目录下的 config 和 logs
+    Portable,
+    // 命令行指定的目录
+    Dir(PathBuf),
+}
+
+// 启动时由命令行参数设置；测试中沿用工作目录，不写入用户的配置目录
+static LOCATION: RwLock<Option<Location>> = parking_lot::const_rwlock(None);
+
+/// 设置存放位置，在读取配置和初始化日志之前调用
+pub fn set_location(location: Location) {
+    *LOCATION.write() = Some(location);
+}
+
+/// 当前的存放位置
+pub fn location() -> Location {
+    LOCATION.read().clone().unwrap_or(if cfg!(test) { Location::Portable } else { Location::Platform })
+}
+
+/// 是否为便携模式
+#[cfg(any(windows, test))]
+pub fn is_portable() -> bool {
+    location() == Location::Portable
+}
+
+// 系统配置目录下的程序目录，无法确定用户目录时为 None
+fn platform_dir() -> Option<PathBuf> {
+    BaseDirs::new().map(|dirs| dirs.config_dir().join(APP_DIR))
+}
+
+/// 配置文件、会话历史等数据所在的目录
+pub fn config_dir() -> PathBuf {
+    match location() {
+        Location::Platform => platform_dir().unwrap_or_else(|| PathBuf::from(PORTABLE_CONFIG_DIR)),
+        Location::Portable => PathBuf::from(PORTABLE_CONFIG_DIR),
+        Location::Dir(dir) => dir,
+    }
+}
+
+/// 日志文件所在的目录
+pub fn log_dir() -> PathBuf {
+    match location() {
+        Location::Portable => PathBuf::from(PORTABLE_LOG_DIR),
+        _ => config_dir().join(PORTABLE_LOG_DIR),
+    }
+}
+
+/// 让另一个进程（如 Windows 服务）使用同一份配置的命令行参数
+#[cfg(any(windows, test))]
+pub fn location_args() -> Vec<String> {
+    match location() {
+        Location::Portable => vec![PORTABLE_ARG.to_string()],
+        _ => vec![CONFIG_DIR_ARG.to_string(), config_dir().display().to_string()],
+    }
+}
+
+// 目录中是否已有配置文件
+fn has_config(dir: &Path) -> bool {
+    CONFIG_FILES.iter().any(|file| dir.join(file).exists())
+}
+
+// 把旧目录中的文件复制到新目录，不覆盖新目录中已有的文件，不复制子目录；返回复制的文件数
+fn copy_files(from: &Path, to: &Path) -> Result<usize> {
+    fs::create_dir_all(to)?;
+    let mut copied = 0;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_file() && !target.exists() {
+            fs::copy(entry.path(), target)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+// 删除旧目录中的配置文件及其备份（config.json、config.toml.bak 等），其中可能有明文密码；返回删除的文件数
+fn remove_configs(dir: &Path) -> Result<usize> {
+    let mut removed = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() && entry.file_name().to_string_lossy().starts_with("config.") {
+            fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// 旧版本把配置放在程序目录的 config 下；系统配置目录中还没有配置时复制过来，返回复制来源。
+/// 旧配置可能写有明文密码，复制后删除原来的配置文件，删除失败时返回提醒用户手动删除的错误；会话历史等其他文件保留
+pub fn adopt_program_dir_config() -> Result<Option<PathBuf>> {
+    if location() != Location::Platform {
+        return Ok(None);
+    }
+    let Some(exe_dir) = std::env::current_exe()?.parent().map(|dir| dir.join(PORTABLE_CONFIG_DIR)) else {
+        return Ok(None);
+    };
+    let target = config_dir();
+    if has_config(&target) || !has_config(&exe_dir) || exe_dir == target {
+        return Ok(None);
+    }
+    let copied = copy_files(&exe_dir, &target)?;
+    info!("Copied {} files from {:?} to {:?}", copied, exe_dir, target);
+    remove_configs(&exe_dir).map_err(|e| {
+        anyhow!("cannot remove the old config in {:?}, it may contain a plaintext password, delete it by hand: {}", exe_dir, e)
+    })?;
+    Ok(Some(exe_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_files() {
+        assert!(is_portable());
+        assert_eq!(config_dir(), PathBuf::from("config"));
+        assert_eq!(log_dir(), PathBuf::from("logs"));
+        assert_eq!(location_args(), vec![PORTABLE_ARG.to_string()]);
+
+        let old = tempfile::tempdir().unwrap();
+        let new = tempfile::tempdir().unwrap();
+        let target = new.path().join(APP_DIR);
+        fs::write(old.path().join("config.json"), "{}").unwrap();
+        fs::write(old.path().join("history.jsonl"), "").unwrap();
+        fs::create_dir(old.path().join("exports")).unwrap();
+        assert!(has_config(old.path()) && !has_config(&target));
+        assert_eq!(copy_files(old.path(), &target).unwrap(), 2);
+        assert!(has_config(&target));
+        assert!(!target.join("exports").exists());
+        // 已有的文件不覆盖
+        assert_eq!(copy_files(old.path(), &target).unwrap(), 0);
+
+        // 复制后删除旧的配置文件和备份，其他文件保留
+        fs::write(old.path().join("config.json.bak"), "{}").unwrap();
+        assert_eq!(remove_configs(old.path()).unwrap(), 2);
+        assert!(!has_config(old.path()));
+        assert!(old.path().join("history.jsonl").exists());
+    }
+}
@@ -0,0 +1,174 @@
+// 匿名延迟统计（可选）：向可自建的汇总服务上报匿名的连通状态和时延，并获取同一网段的汇总，判断是自己断网还是整栋楼都断了
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+/// 上报和刷新汇总的间隔
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// 没有本机 IPv4 地址（如未连接网络）时，隔这么久再尝试上报
+pub const NO_ADDRESS_RETRY: Duration = Duration::from_secs(60);
+// 同一网段上报的人数少于此值时不给出判断，也避免汇总暴露个人状态
+const MIN_REPORTERS: u32 = 3;
+
+/// 上报的匿名样本，不包含账号、IP 等可识别信息
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Sample {
+    // 网段标识（/24 网段加上汇总服务的盐后的哈希），同一栋楼通常在同一网段
+    pub group: String,
+    pub online: bool,
+    pub latency_ms: Option<u64>,
+}
+
+/// 汇总服务返回的同一网段最近一段时间的统计
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Aggregate {
+    pub reporters: u32,
+    pub online: u32,
+    pub median_latency_ms: Option<u64>,
+}
+
+/// 自己的状态与同网段其他人的对比
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    // 上报人数太少
+    NotEnoughData,
+    // 大家都正常
+    AllGood,
+    // 只有自己断网或时延明显偏高
+    JustMe,
+    // 大部分人都断网
+    Widespread,
+}
+
+impl Comparison {
+    /// 状态栏中显示的说明
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Comparison::NotEnoughData => "Not enough reports from your network yet",
+            Comparison::AllGood => "Your network looks normal",
+            Comparison::JustMe => "Others on your network are fine, the problem is probably on your side",
+            Comparison::Widespread => "Most people on your network are offline too",
+        }
+    }
+}
+
+/// 与汇总对比：超过一半的人断网视为大范围故障，自己断网或时延超过中位数 3 倍视为只有自己
+pub fn compare(online: bool, latency_ms: Option<u64>, aggregate: &Aggregate) -> Comparison {
+    if aggregate.reporters < MIN_REPORTERS {
+        return Comparison::NotEnoughData;
+    }
+    if aggregate.online * 2 < aggregate.reporters {
+        return Comparison::Widespread;
+    }
+    let slow = match (latency_ms, aggregate.median_latency_ms) {
+        (Some(own), Some(median)) => own > median.max(1) * 3,
+        _ => false,
+    };
+    if !online || slow {
+        Comparison::JustMe
+    } else {
+        Comparison::AllGood
+    }
+}
+
+/// 由本机地址得到网段标识；盐由汇总服务在安装时随机生成，不知道盐就无法由标识穷举出网段
+pub fn network_group(ip: Ipv4Addr, salt: &str) -> String {
+    let [a, b, c, _] = ip.octets();
+    let digest = Sha256::digest(format!("{}:{}.{}.{}.0/24", salt, a, b, c).as_bytes());
+    digest.iter().take(8).map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 汇总服务客户端：GET {url}/salt 获取网段标识的盐，POST {url}/samples 上报样本，GET {url}/aggregate?group= 获取汇总
+pub struct PeerStatsClient {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl PeerStatsClient {
+    pub fn new(url: String) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new()),
+            url,
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.url.trim_end_matches('/'), path)
+    }
+
+    // 汇总服务的盐，同一服务的所有用户相同，同网段的样本才能汇总在一起
+    async fn salt(&self) -> Result<String> {
+        let response = self.client.get(self.endpoint("salt")).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Fetch salt failed: HTTP {}", response.status()));
+        }
+        let salt = response.text().await?.trim().to_string();
+        if salt.is_empty() {
+            return Err(anyhow!("The aggregation service returned an empty salt"));
+        }
+        Ok(salt)
+    }
+
+    /// 上报本机状态并获取同网段的汇总
+    pub async fn exchange(&self, ip: Ipv4Addr, online: bool, latency_ms: Option<u64>) -> Result<Aggregate> {
+        let sample = Sample {
+            group: network_group(ip, &self.salt().await?),
+            online,
+            latency_ms,
+        };
+        let response = self.client
+            .post(self.endpoint("samples"))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(&sample)?)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Submit failed: HTTP {}", response.status()));
+        }
+
+        let response = self.client
+            .get(self.endpoint("aggregate"))
+            .query(&[("group", &sample.group)])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Fetch aggregate failed: HTTP {}", response.status()));
+        }
+        Ok(serde_json::from_str(&response.text().await?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aggregate(reporters: u32, online: u32, median_latency_ms: Option<u64>) -> Aggregate {
+        Aggregate { reporters, online, median_latency_ms }
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(compare(false, None, &aggregate(2, 0, None)), Comparison::NotEnoughData);
+        assert_eq!(compare(false, None, &aggregate(10, 2, None)), Comparison::Widespread);
+        assert_eq!(compare(false, None, &aggregate(10, 9, Some(20))), Comparison::JustMe);
+        assert_eq!(compare(true, Some(200), &aggregate(10, 9, Some(20))), Comparison::JustMe);
+        assert_eq!(compare(true, Some(25), &aggregate(10, 9, Some(20))), Comparison::AllGood);
+    }
+
+    #[test]
+    fn test_network_group() {
+        let group = network_group(Ipv4Addr::new(10, 96, 12, 34), "k3Xq");
+        assert_eq!(group.len(), 16);
+        // 同一 /24 网段得到相同的标识，且不包含原始地址
+        assert_eq!(group, network_group(Ipv4Addr::new(10, 96, 12, 200), "k3Xq"));
+        assert_ne!(group, network_group(Ipv4Addr::new(10, 96, 13, 34), "k3Xq"));
+        assert!(!group.contains("10.96"));
+        // 不同汇总服务的盐不同，标识也不同
+        assert_ne!(group, network_group(Ipv4Addr::new(10, 96, 12, 34), "Zp7w"));
+    }
+}
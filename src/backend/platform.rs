@@ -0,0 +1,154 @@
+// 运行平台：Chrome for Testing 和 msedgedriver 按平台提供不同的压缩包，Windows 下的可执行文件带 .exe 后缀，
+// macOS 下的 Chrome 在 .app 包内；Linux 和 macOS 下解压出的程序需要加上可执行权限
+use std::env::consts::EXE_SUFFIX;
+use std::path::{Path, PathBuf};
+
+/// Chrome for Testing 支持的平台
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Win32,
+    Win64,
+    Linux64,
+    MacX64,
+    MacArm64,
+}
+
+impl Platform {
+    /// 编译目标对应的平台
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            if cfg!(target_pointer_width = "64") { Platform::Win64 } else { Platform::Win32 }
+        } else if cfg!(target_os = "macos") {
+            if cfg!(target_arch = "aarch64") { Platform::MacArm64 } else { Platform::MacX64 }
+        } else {
+            Platform::Linux64
+        }
+    }
+
+    /// Chrome for Testing 中的平台名，用在下载地址、压缩包名和解压目录名中
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Win32 => "win32",
+            Platform::Win64 => "win64",
+            Platform::Linux64 => "linux64",
+            Platform::MacX64 => "mac-x64",
+            Platform::MacArm64 => "mac-arm64",
+        }
+    }
+
+    /// 可以在本平台运行的 Chrome for Testing，旧版本在 64 位 Windows 下也下载 win32 版，已下载的仍可使用
+    pub fn compatible(&self) -> &'static [Platform] {
+        match self {
+            Platform::Win64 => &[Platform::Win64, Platform::Win32],
+            Platform::Win32 => &[Platform::Win32],
+            Platform::Linux64 => &[Platform::Linux64],
+            Platform::MacX64 => &[Platform::MacX64],
+            Platform::MacArm64 => &[Platform::MacArm64],
+        }
+    }
+
+    /// Chrome for Testing 的下载地址，component 为 chrome 或 chromedriver
+    pub fn download_url(&self, prefix: &str, version: &str, component: &str) -> String {
+        format!("{}/{}/{}/{}-{}.zip", prefix, version, self.name(), component, self.name())
+    }
+
+    /// 解压后的 Chrome 目录名，如 chrome-win64
+    pub fn chrome_dir(&self) -> String {
+        format!("chrome-{}", self.name())
+    }
+
+    /// Chrome 可执行文件相对于 Chrome 目录的路径
+    pub fn chrome_executable(&self) -> PathBuf {
+        match self {
+            Platform::Win32 | Platform::Win64 => PathBuf::from("chrome.exe"),
+            Platform::Linux64 => PathBuf::from("chrome"),
+            Platform::MacX64 | Platform::MacArm64 => {
+                Path::new("Google Chrome for Testing.app/Contents/MacOS").join("Google Chrome for Testing")
+            }
+        }
+    }
+
+    /// 与 Edge 版本对应的 msedgedriver 压缩包名
+    pub fn edgedriver_archive(&self) -> &'static str {
+        match self {
+            Platform::Win32 => "edgedriver_win32.zip",
+            Platform::Win64 => "edgedriver_win64.zip",
+            Platform::Linux64 => "edgedriver_linux64.zip",
+            Platform::MacX64 => "edgedriver_mac64.zip",
+            Platform::MacArm64 => "edgedriver_mac64_m1.zip",
+        }
+    }
+}
+
+/// 本平台的可执行文件名，Windows 下加上 .exe
+pub fn executable(name: &str) -> String {
+    format!("{}{}", name, EXE_SUFFIX)
+}
+
+/// Linux 和 macOS 下设置文件权限，mode 为压缩包中记录的权限；Windows 下不需要
+pub fn set_mode(path: &Path, mode: u32) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+    Ok(())
+}
+
+/// 压缩包中记录的权限是否表示符号链接
+pub fn is_symlink(mode: u32) -> bool {
+    mode & 0o170000 == 0o120000
+}
+
+/// 创建符号链接；只有 macOS 的压缩包中有符号链接，Windows 下不需要支持
+pub fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, link);
+    #[cfg(not(unix))]
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("cannot create symlink {} -> {}", link.display(), target.display()),
+    ))
+}
+
+/// 给解压出的驱动程序加上可执行权限
+pub fn make_executable(path: &Path) -> std::io::Result<()> {
+    set_mode(path, 0o755)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_names() {
+        assert_eq!(
+            Platform::Linux64.download_url("https://storage.googleapis.com/chrome-for-testing-public", "131.0.6778.204", "chromedriver"),
+            "https://storage.googleapis.com/chrome-for-testing-public/131.0.6778.204/linux64/chromedriver-linux64.zip"
+        );
+        assert_eq!(Platform::MacArm64.chrome_dir(), "chrome-mac-arm64");
+        assert_eq!(
+            Platform::MacX64.chrome_executable(),
+            PathBuf::from("Google Chrome for Testing.app/Contents/MacOS/Google Chrome for Testing")
+        );
+        assert_eq!(Platform::Win64.chrome_executable(), PathBuf::from("chrome.exe"));
+        assert_eq!(Platform::Win64.compatible(), &[Platform::Win64, Platform::Win32]);
+        assert!(Platform::current().compatible().contains(&Platform::current()));
+        assert!(is_symlink(0o120755));
+        assert!(!is_symlink(0o100755));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_make_executable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(executable("chromedriver"));
+        std::fs::write(&path, "").unwrap();
+        make_executable(&path).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o755);
+    }
+}
@@ -0,0 +1,221 @@
+// 门户协议插件：不同学校的认证门户（Dr.COM、深澜 Srun、锐捷等）各自实现 PortalProvider，按配置中的 portal_type
+// 在注册表中查找。添加新的门户只需实现该 trait 并在 REGISTRY 中登记，界面和自动登录不需要改动
+use crate::backend::auth::{AuthClient, OnlineStatus, Usage};
+use crate::backend::config::Config;
+use crate::backend::credential_provider;
+use crate::backend::drcom::DrComPortal;
+use crate::backend::history::{Action, Attempt, Trigger};
+use crate::backend::maintenance::PortalState;
+use anyhow::{anyhow, Result};
+use futures_util::future::LocalBoxFuture;
+use log::debug;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "webdriver")]
+use crate::backend::authentication::{PageDiagnostics, PortalFingerprint};
+use std::path::Path;
+#[cfg(feature = "webdriver")]
+use std::sync::Arc;
+
+/// 门户类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PortalType {
+    // 城市热点 Dr.COM eportal（中南大学）
+    #[default]
+    Drcom,
+    // Dr.COM 5.2 / AC 网页认证（0.htm 表单），部分校区使用
+    DrcomWeb,
+}
+
+impl PortalType {
+    pub const ALL: [PortalType; 2] = [PortalType::Drcom, PortalType::DrcomWeb];
+
+    /// 界面中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            PortalType::Drcom => "Dr.COM eportal",
+            PortalType::DrcomWeb => "Dr.COM web (0.htm)",
+        }
+    }
+}
+
+/// 一种门户的认证协议
+pub trait PortalProvider {
+    /// 实现的门户类型
+    fn portal_type(&self) -> PortalType;
+
+    /// 准备登录登出所需的资源，如浏览器实现启动浏览器；HTTP 实现不需要准备
+    fn prepare(&mut self) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// 登录，返回门户的提示信息；门户拒绝时错误信息为门户的提示，能识别的故障附带 triage::Failure
+    fn login(&mut self) -> LocalBoxFuture<'_, Result<String>>;
+
+    /// 登出，返回门户的提示信息
+    fn logout(&mut self) -> LocalBoxFuture<'_, Result<String>>;
+
+    /// 查询门户记录的在线状态
+    fn status(&self) -> LocalBoxFuture<'_, Result<OnlineStatus>>;
+
+    /// 轻量检查门户是否正常响应：维护或服务器出错时返回对应的状态，连不上门户时返回错误
+    fn portal_state(&self) -> LocalBoxFuture<'_, Result<PortalState>>;
+
+    /// 查询账号的流量和余额，门户没有用量接口时返回错误
+    fn usage(&self) -> LocalBoxFuture<'_, Result<Usage>> {
+        let portal = self.portal_type();
+        Box::pin(async move { Err(anyhow!("The {} portal does not report account usage", portal.label())) })
+    }
+
+    /// 门户首页是否为本实现支持的门户
+    fn detect(page: &str) -> bool where Self: Sized;
+}
+
+/// 用浏览器填写门户表单的实现：除登录登出外，自动登录在重试之间复用同一个浏览器会话，失败时附带页面诊断信息
+#[cfg(feature = "webdriver")]
+pub trait BrowserPortal: PortalProvider {
+    /// 打开认证页面
+    fn open_auth_page(&mut self) -> LocalBoxFuture<'_, Result<()>>;
+
+    /// 启动浏览器并打开认证页面，会话仍可用时直接返回
+    fn warm_up(&mut self) -> LocalBoxFuture<'_, Result<()>>;
+
+    /// 浏览器会话是否仍可用
+    fn is_alive(&self) -> LocalBoxFuture<'_, bool>;
+
+    /// 用预热的会话登录，成功后保留会话
+    fn login_warm(&mut self) -> LocalBoxFuture<'_, Result<()>>;
+
+    /// 更新凭据和浏览器设置，浏览器设置变化时关闭会话
+    fn update_config(&mut self, config: Arc<Config>) -> LocalBoxFuture<'_, Result<()>>;
+
+    /// 关闭浏览器和驱动
+    fn quit(&mut self) -> LocalBoxFuture<'_, Result<()>>;
+
+    /// 最近一次登录失败时采集到的页面诊断信息
+    fn last_diagnostics(&self) -> Option<&PageDiagnostics>;
+
+    /// 最近一次登录时采集到的登录页指纹
+    fn last_fingerprint(&self) -> Option<&PortalFingerprint>;
+}
+
+// 创建用浏览器认证的实现
+#[cfg(feature = "webdriver")]
+type BrowserFactory = fn(Arc<Config>) -> Box<dyn BrowserPortal>;
+
+/// 注册表中的一种门户
+pub struct PortalEntry {
+    pub portal_type: PortalType,
+    // 根据门户首页识别
+    detect: fn(&str) -> bool,
+    // 通过 HTTP 接口认证的实现
+    http: fn(&Config) -> Box<dyn PortalProvider>,
+    // 用浏览器填写门户表单的实现，没有时只能用 HTTP
+    #[cfg(feature = "webdriver")]
+    browser: Option<BrowserFactory>,
+}
+
+// 已支持的门户，识别时按顺序检查；Dr.COM 网页认证的页面也带有 Dr.COM 字样，排在 eportal 之前
+const REGISTRY: &[PortalEntry] = &[
+    PortalEntry {
+        portal_type: PortalType::DrcomWeb,
+        detect: DrComPortal::detect,
+        http: |config| Box::new(DrComPortal::from_config(config)),
+        #[cfg(feature = "webdriver")]
+        browser: None,
+    },
+    PortalEntry {
+        portal_type: PortalType::Drcom,
+        detect: AuthClient::detect,
+        http: |config| Box::new(AuthClient::from_config(config)),
+        #[cfg(feature = "webdriver")]
+        browser: Some(|config| Box::new(crate::backend::authentication::Authenticator::new(config))),
+    },
+];
+
+/// 查找门户类型的注册项
+pub fn entry(portal_type: PortalType) -> &'static PortalEntry {
+    REGISTRY.iter()
+        .find(|entry| entry.portal_type == portal_type)
+        .expect("every portal type is registered")
+}
+
+impl PortalEntry {
+    /// 是否能用浏览器登录，不能时只用 HTTP
+    pub fn supports_browser(&self) -> bool {
+        #[cfg(feature = "webdriver")]
+        return self.browser.is_some();
+        #[cfg(not(feature = "webdriver"))]
+        false
+    }
+}
+
+/// 配置的门户通过 HTTP 接口认证的实现
+pub fn http_provider(config: &Config) -> Box<dyn PortalProvider> {
+    (entry(config.portal_type).http)(config)
+}
+
+/// 配置的门户用浏览器认证的实现，该门户不支持浏览器时返回 None
+#[cfg(feature = "webdriver")]
+pub fn browser_provider(config: Arc<Config>) -> Option<Box<dyn BrowserPortal>> {
+    entry(config.portal_type).browser.map(|browser| browser(config))
+}
+
+/// 通过配置的门户的 HTTP 接口登录或登出，返回门户的提示信息，并记录到 history 处的会话历史
+pub async fn http_action(config: &Config, action: Action, trigger: Trigger, history: &Path) -> Result<String, String> {
+    let attempt = Attempt::start(history.to_path_buf(), action, trigger);
+    let result = send_http_action(config, action).await;
+    attempt.finish("HTTP portal", result.as_ref().map(|_| ()).map_err(String::clone));
+    result
+}
+
+// 配置了外部凭据提供程序时先获取账号
+async fn send_http_action(config: &Config, action: Action) -> Result<String, String> {
+    let config = credential_provider::resolve(config)
+        .await
+        .map_err(|e| format!("Failed to get credentials: {}", e))?;
+    if !config.has_credentials() {
+        return Err("Username or password is empty".to_string());
+    }
+    let mut provider = http_provider(&config);
+    debug!("Using the {} portal", provider.portal_type().label());
+    provider.prepare().await.map_err(|e| e.to_string())?;
+    let response = match action {
+        Action::Login => provider.login().await,
+        Action::Logout => provider.logout().await,
+    };
+    response.map_err(|e| e.to_string())
+}
+
+/// 根据门户首页识别门户类型
+pub fn detect(page: &str) -> Option<PortalType> {
+    REGISTRY.iter().find(|entry| (entry.detect)(page)).map(|entry| entry.portal_type)
+}
+
+/// 访问门户首页并识别当前网络的门户类型
+pub async fn detect_current(config: &Config) -> Result<Option<PortalType>> {
+    let page = AuthClient::from_config(config).portal_page().await
+        .map_err(|e| anyhow::anyhow!("Failed to load the portal page: {}", e))?;
+    Ok(detect(&page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        for portal_type in PortalType::ALL {
+            assert_eq!(entry(portal_type).portal_type, portal_type);
+        }
+        let config = Config::default();
+        assert_eq!(http_provider(&config).portal_type(), PortalType::Drcom);
+        #[cfg(feature = "webdriver")]
+        assert_eq!(browser_provider(Arc::new(config)).unwrap().portal_type(), PortalType::Drcom);
+
+        assert_eq!(detect("<script>var v46ip='10.0.0.1'; var uid='';</script><title>Dr.COMWebLoginID_0.htm</title>"), Some(PortalType::Drcom));
+        assert_eq!(detect("<form action='0.htm'><input name='DDDDD'><input name='upass'><input name='0MKKey'></form><title>Dr.COM</title>"), Some(PortalType::DrcomWeb));
+        assert!(!entry(PortalType::DrcomWeb).supports_browser());
+        assert_eq!(detect("<html><title>Srun Portal</title></html>"), None);
+        assert_eq!(serde_json::to_value(PortalType::Drcom).unwrap(), "Drcom");
+    }
+}
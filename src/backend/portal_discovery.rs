@@ -0,0 +1,175 @@
+// 门户自动发现：未认证时访问一个只走 HTTP 的网站，校园网会把请求重定向到认证门户，顺着重定向（包括页面中的
+// meta refresh 和 JS 跳转）找到门户地址和首页，推断 auth_url 和门户类型，用户不需要事先知道 http://10.1.1.1
+use crate::backend::config::Config;
+use crate::backend::portal::{self, PortalType};
+use anyhow::{anyhow, Result};
+use reqwest::redirect::Policy;
+use reqwest::{Client, Url};
+use std::time::Duration;
+
+/// 用于触发门户重定向的网站，只提供 HTTP，不会因 HTTPS 证书错误而看不到重定向
+pub const DISCOVERY_PROBE_URL: &str = "http://neverssl.com/";
+// 最多跟随的跳转次数
+const MAX_REDIRECTS: usize = 5;
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+// 页面中跳转语句的前缀，后面跟带引号的地址
+const SCRIPT_REDIRECTS: [&str; 3] = ["location.href=", "location.replace(", "location="];
+
+/// 发现的门户
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discovery {
+    // 门户地址（协议、主机、端口和路径），作为 auth_url
+    pub auth_url: String,
+    // 识别出的门户类型，不支持的门户为 None
+    pub portal_type: Option<PortalType>,
+    // 是否由重定向发现；已认证或网络不拦截时为 false，auth_url 沿用配置
+    pub redirected: bool,
+}
+
+/// 门户地址：保留路径（如 http://10.1.1.1:801/eportal/），去掉查询参数和片段，其中的终端 IP 等参数每次重定向都不同
+pub fn portal_address(url: &Url) -> Option<String> {
+    url.host_str()?;
+    let mut address = url.clone();
+    address.set_query(None);
+    address.set_fragment(None);
+    // 门户在根路径时与默认配置一样写成 http://10.1.1.1
+    if address.path() == "/" {
+        return Some(address.as_str().trim_end_matches('/').to_string());
+    }
+    Some(address.to_string())
+}
+
+// 跳转语句后带引号的地址
+fn quoted(rest: &str) -> Option<&str> {
+    let rest = rest.trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let rest = &rest[1..];
+    rest.find(quote).map(|end| &rest[..end])
+}
+
+/// 页面中的跳转地址：meta refresh 或 location.href 等 JS 跳转，门户常用它们代替 302
+pub fn page_redirect(page: &str) -> Option<String> {
+    let lower = page.to_ascii_lowercase();
+    if let Some(meta) = lower.find("http-equiv=\"refresh\"").or_else(|| lower.find("http-equiv='refresh'")).or_else(|| lower.find("http-equiv=refresh")) {
+        // meta 标签的 content="0;url=..."
+        let tag_end = lower[meta..].find('>').map_or(lower.len(), |end| meta + end);
+        if let Some(start) = lower[meta..tag_end].find("url=").map(|start| meta + start + "url=".len()) {
+            let url: String = page[start..tag_end].chars()
+                .take_while(|c| !matches!(c, '"' | '\'' | '>' | ' '))
+                .collect();
+            if !url.is_empty() {
+                return Some(url);
+            }
+        }
+    }
+    let compact: String = page.chars().filter(|c| !c.is_whitespace()).collect();
+    SCRIPT_REDIRECTS.iter()
+        .filter_map(|prefix| compact.find(prefix).map(|start| &compact[start + prefix.len()..]))
+        .find_map(quoted)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+}
+
+// 是否仍在探测网站上（neverssl 会跳转到自己的随机子域名）
+fn same_site(probe: &Url, landed: &Url) -> bool {
+    match (probe.host_str(), landed.host_str()) {
+        (Some(probe), Some(landed)) => landed == probe || landed.ends_with(&format!(".{}", probe)),
+        _ => false,
+    }
+}
+
+/// 根据探测地址、最后到达的地址和页面得出发现结果：到达其他网站时视为被门户重定向，否则返回 None
+pub fn discovery_from(probe: &Url, landed: &Url, page: &str) -> Option<Discovery> {
+    if same_site(probe, landed) {
+        return None;
+    }
+    Some(Discovery { auth_url: portal_address(landed)?, portal_type: portal::detect(page), redirected: true })
+}
+
+/// 通过重定向发现当前网络的认证门户
+pub struct PortalDetector {
+    client: Client,
+    probe_url: String,
+}
+
+impl PortalDetector {
+    pub fn new(config: &Config) -> Self {
+        // 自己跟随重定向，才能处理页面中的跳转并限制次数
+        let mut builder = Client::builder()
+            .redirect(Policy::none())
+            .timeout(DISCOVERY_TIMEOUT)
+            .danger_accept_invalid_certs(true);
+        if config.bypass_proxy_for_portal {
+            builder = builder.no_proxy();
+        }
+        Self {
+            client: builder.build().unwrap_or_else(|_| Client::new()),
+            probe_url: DISCOVERY_PROBE_URL.to_string(),
+        }
+    }
+
+    // 跟随 HTTP 重定向和页面跳转，返回最后到达的地址和页面；跳转次数用完时不再跟随页面中的跳转
+    async fn follow(&self, mut url: Url) -> Result<(Url, String)> {
+        for hop in 0..=MAX_REDIRECTS {
+            let response = self.client.get(url.clone()).send().await?;
+            if response.status().is_redirection() {
+                let location = response.headers().get(reqwest::header::LOCATION)
+                    .and_then(|value| value.to_str().ok())
+                    .ok_or_else(|| anyhow!("Redirect from {} has no location", url))?;
+                url = url.join(location)?;
+                continue;
+            }
+            let page = response.text().await?;
+            match page_redirect(&page).and_then(|target| url.join(&target).ok()) {
+                Some(target) if target != url && hop < MAX_REDIRECTS => url = target,
+                _ => return Ok((url, page)),
+            }
+        }
+        Err(anyhow!("Too many redirects while looking for the portal (last at {})", url))
+    }
+
+    /// 访问探测网站并顺着重定向找到门户，网络没有拦截请求（已认证或不在校园网）时返回 None
+    pub async fn discover(&self) -> Result<Option<Discovery>> {
+        let probe = Url::parse(&self.probe_url)?;
+        let (landed, page) = self.follow(probe.clone()).await
+            .map_err(|e| anyhow!("Failed to reach {}: {}", self.probe_url, e))?;
+        Ok(discovery_from(&probe, &landed, &page))
+    }
+}
+
+/// 发现当前网络的门户：优先由重定向推断地址和类型；没有被重定向时访问配置的门户首页识别类型
+pub async fn discover(config: &Config) -> Result<Discovery> {
+    if let Some(discovery) = PortalDetector::new(config).discover().await? {
+        return Ok(discovery);
+    }
+    Ok(Discovery {
+        auth_url: config.auth_url.clone(),
+        portal_type: portal::detect_current(config).await?,
+        redirected: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_from() {
+        assert_eq!(page_redirect("<META HTTP-EQUIV=\"Refresh\" CONTENT=\"0; URL=http://10.1.1.1/a79.htm?wlanuserip=10.0.0.2\">").unwrap(), "http://10.1.1.1/a79.htm?wlanuserip=10.0.0.2");
+        assert_eq!(page_redirect("<script>top.self.location.href = 'http://10.1.1.1:801/eportal/';</script>").unwrap(), "http://10.1.1.1:801/eportal/");
+        assert_eq!(page_redirect("<script>window.location.replace(\"/0.htm\")</script>").unwrap(), "/0.htm");
+        assert_eq!(page_redirect("<html><h1>NeverSSL</h1></html>"), None);
+
+        let probe = Url::parse(DISCOVERY_PROBE_URL).unwrap();
+        let landed = Url::parse("http://10.1.1.1:801/eportal/?wlanuserip=10.0.0.2").unwrap();
+        let discovery = discovery_from(&probe, &landed, "<form action='0.htm'><input name='DDDDD'><input name='upass'><input name='0MKKey'></form>").unwrap();
+        assert_eq!(discovery.auth_url, "http://10.1.1.1:801/eportal/");
+        assert_eq!(discovery.portal_type, Some(PortalType::DrcomWeb));
+        assert!(discovery.redirected);
+        assert_eq!(portal_address(&Url::parse("http://10.1.1.1/?wlanuserip=10.0.0.2").unwrap()).unwrap(), "http://10.1.1.1");
+
+        // 没有被重定向时沿用配置
+        assert_eq!(discovery_from(&probe, &probe.join("/online").unwrap(), "<h1>NeverSSL</h1>"), None);
+        assert_eq!(discovery_from(&probe, &Url::parse("http://x7k2.neverssl.com/online").unwrap(), "<h1>NeverSSL</h1>"), None);
+    }
+}
@@ -0,0 +1,126 @@
+// 门户会话：HTTP 登录成功后保存门户下发的 Cookie 和登录时的地址，登出和查询状态时直接复用，不必重新获取地址
+use crate::backend::paths;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// 超过此时间的会话视为过期，登出时重新获取地址
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 一次 HTTP 登录得到的会话
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PortalSession {
+    // 登录时使用的本机地址
+    pub ip: String,
+    // 按主机保存的 Cookie
+    pub cookies: BTreeMap<String, BTreeMap<String, String>>,
+    // 登录时间（Unix 秒），未登录时为 0
+    pub saved_at: u64,
+}
+
+impl PortalSession {
+    /// 会话文件的默认位置
+    pub fn default_path() -> PathBuf {
+        paths::config_dir().join("portal_session.json")
+    }
+
+    /// 读取保存的会话，文件不存在、无法解析或会话已过期时返回 None
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let session: Self = serde_json::from_str(&content).ok()?;
+        (!session.is_expired(now_secs())).then_some(session)
+    }
+
+    /// 写入会话文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 删除会话文件
+    pub fn remove(path: &Path) {
+        let _ = fs::remove_file(path);
+    }
+
+    /// 是否已过期，没有登录记录的会话视为过期
+    pub fn is_expired(&self, now: u64) -> bool {
+        now.saturating_sub(self.saved_at) >= MAX_AGE.as_secs()
+    }
+
+    /// 登录成功后记录地址和时间
+    pub fn mark_login(&mut self, ip: &str) {
+        self.ip = ip.to_string();
+        self.saved_at = now_secs();
+    }
+
+    /// 记录一条 Set-Cookie 响应头，值为空或 Max-Age=0 时删除该 Cookie
+    pub fn store_cookie(&mut self, host: &str, set_cookie: &str) {
+        let mut parts = set_cookie.split(';');
+        let Some((name, value)) = parts.next().and_then(|pair| pair.split_once('=')) else { return };
+        let (name, value) = (name.trim(), value.trim());
+        let removed = value.is_empty() || parts.any(|attr| attr.trim().eq_ignore_ascii_case("max-age=0"));
+
+        let cookies = self.cookies.entry(host.to_string()).or_default();
+        if removed {
+            cookies.remove(name);
+        } else {
+            cookies.insert(name.to_string(), value.to_string());
+        }
+    }
+
+    /// 发往该主机的 Cookie 请求头
+    pub fn cookie_header(&self, host: &str) -> Option<String> {
+        let cookies = self.cookies.get(host).filter(|cookies| !cookies.is_empty())?;
+        Some(cookies.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>().join("; "))
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cookies() {
+        let mut session = PortalSession::default();
+        session.store_cookie("portal.csu.edu.cn", "PHPSESSID=abc123; Path=/; HttpOnly");
+        session.store_cookie("portal.csu.edu.cn", "lang=zh-CN");
+        session.store_cookie("10.1.1.1", "md5_login=xyz");
+        assert_eq!(session.cookie_header("portal.csu.edu.cn").as_deref(), Some("PHPSESSID=abc123; lang=zh-CN"));
+        assert_eq!(session.cookie_header("10.1.1.1").as_deref(), Some("md5_login=xyz"));
+
+        // 门户让 Cookie 失效
+        session.store_cookie("portal.csu.edu.cn", "PHPSESSID=deleted; Max-Age=0");
+        session.store_cookie("portal.csu.edu.cn", "lang=");
+        assert_eq!(session.cookie_header("portal.csu.edu.cn"), None);
+    }
+
+    #[test]
+    fn test_save_and_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("portal_session.json");
+
+        let mut session = PortalSession::default();
+        session.store_cookie("10.1.1.1", "md5_login=xyz");
+        session.save(&path).unwrap();
+        // 没有登录记录的会话不会被读取
+        assert_eq!(PortalSession::load(&path), None);
+
+        session.mark_login("10.96.12.34");
+        session.save(&path).unwrap();
+        assert_eq!(PortalSession::load(&path), Some(session.clone()));
+        assert!(session.is_expired(session.saved_at + MAX_AGE.as_secs()));
+
+        PortalSession::remove(&path);
+        assert_eq!(PortalSession::load(&path), None);
+    }
+}
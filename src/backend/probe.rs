@@ -0,0 +1,221 @@
+// 网络探测目标定义，支持 ICMP / TCP / HTTP / DNS 多种协议
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 未配置时单次探测的超时时间
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 未配置时用于识别门户拦截的地址，联网时返回 204，被门户拦截时返回登录页或重定向
+pub const DEFAULT_CAPTIVE_PROBE_URL: &str = "http://connect.rom.miui.com/generate_204";
+
+/// 综合各项探测得到的连接状态
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Connectivity {
+    // 所有探测目标都不可达
+    #[default]
+    Offline,
+    // 请求被门户拦截，需要登录
+    PortalRedirect,
+    Online,
+}
+
+/// 一个探测目标及其参数
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "protocol", rename_all = "lowercase")]
+pub enum Probe {
+    // ICMP ping，host 可以是域名或 IP
+    Icmp { host: String },
+    // TCP 连接指定端口
+    Tcp { host: String, port: u16 },
+    // HTTP GET，状态码符合预期才算成功（被门户劫持时会返回 200/302）
+    Http {
+        url: String,
+        #[serde(default = "default_expected_status")]
+        expected_status: u16,
+    },
+    // DNS 解析指定域名
+    Dns { name: String },
+}
+
+fn default_expected_status() -> u16 {
+    204
+}
+
+/// 设置界面中可选的协议
+pub const PROTOCOLS: [&str; 4] = ["icmp", "tcp", "http", "dns"];
+
+impl Probe {
+    pub fn protocol(&self) -> &'static str {
+        match self {
+            Probe::Icmp { .. } => "icmp",
+            Probe::Tcp { .. } => "tcp",
+            Probe::Http { .. } => "http",
+            Probe::Dns { .. } => "dns",
+        }
+    }
+
+    // 目标的主机名，HTTP 取 URL 中的主机
+    fn host(&self) -> String {
+        match self {
+            Probe::Icmp { host } | Probe::Tcp { host, .. } => host.clone(),
+            Probe::Dns { name } => name.clone(),
+            Probe::Http { url, .. } => Url::parse(url).ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 换成另一种协议，保留目标主机
+    pub fn with_protocol(&self, protocol: &str) -> Probe {
+        let host = self.host();
+        match protocol {
+            "tcp" => Probe::Tcp { host, port: 443 },
+            "http" => Probe::Http {
+                url: if host.is_empty() { String::new() } else { format!("http://{}/generate_204", host) },
+                expected_status: default_expected_status(),
+            },
+            "dns" => Probe::Dns { name: host },
+            _ => Probe::Icmp { host },
+        }
+    }
+
+    /// 检查目标是否填写完整
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Probe::Http { url, .. } => {
+                Url::parse(url).map_err(|e| anyhow!("Invalid probe URL {}: {}", url, e))?;
+            }
+            _ if self.host().trim().is_empty() => return Err(anyhow!("{} probe target is empty", self.protocol().to_uppercase())),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 用于日志显示的描述
+    pub fn describe(&self) -> String {
+        match self {
+            Probe::Icmp { host } => format!("ICMP {}", host),
+            Probe::Tcp { host, port } => format!("TCP {}:{}", host, port),
+            Probe::Http { url, expected_status } => format!("HTTP {} (expect {})", url, expected_status),
+            Probe::Dns { name } => format!("DNS {}", name),
+        }
+    }
+}
+
+/// 单个探测的结果
+#[derive(Serialize, Clone, Debug)]
+pub struct ProbeOutcome {
+    #[serde(flatten)]
+    pub probe: Probe,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl ProbeOutcome {
+    pub fn is_reachable(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// 配置中没有指定探测目标时使用的默认目标
+pub fn default_probes() -> Vec<Probe> {
+    [
+        "www.baidu.com",
+        "www.opendns.com",
+        "1.1.1.1",
+        "114.114.114.114",  // 114 DNS
+        "8.8.8.8",          // Google DNS
+        "223.5.5.5",        // AliDNS
+    ]
+    .iter()
+    .map(|host| Probe::Icmp { host: host.to_string() })
+    .collect()
+}
+
+/// 没有配置校内目标时使用的默认目标：学校主页在校内，未认证或出口故障时仍可连接
+pub fn default_intranet_probes() -> Vec<Probe> {
+    vec![Probe::Tcp { host: "www.csu.edu.cn".to_string(), port: 80 }]
+}
+
+/// 根据 generate_204 探测的状态码（请求失败时为 None）、其他探测目标是否可达以及 DNS 是否被门户劫持判断连接状态；
+/// 门户放行 ICMP 或内网地址时 ping 仍然成功，因此只要返回了登录页或重定向就视为需要登录；
+/// DNS 被劫持时域名都解析到门户，DNS 和 HTTP 探测的"成功"不可信，同样视为需要登录
+pub fn classify(captive_status: Option<u16>, any_reachable: bool, dns_hijacked: bool) -> Connectivity {
+    match captive_status {
+        Some(204) => Connectivity::Online,
+        Some(200..=399) => Connectivity::PortalRedirect,
+        _ if dns_hijacked => Connectivity::PortalRedirect,
+        _ if any_reachable => Connectivity::Online,
+        _ => Connectivity::Offline,
+    }
+}
+
+/// 根据探测结果计算健康度（0-100）
+pub fn health_score(results: &[bool]) -> u8 {
+    if results.is_empty() {
+        return 0;
+    }
+    let succeeded = results.iter().filter(|ok| **ok).count();
+    (succeeded * 100 / results.len()) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_config_format() {
+        let json = r#"[
+            {"protocol": "icmp", "host": "223.5.5.5"},
+            {"protocol": "tcp", "host": "www.baidu.com", "port": 443},
+            {"protocol": "http", "url": "http://connect.rom.miui.com/generate_204"},
+            {"protocol": "dns", "name": "www.qq.com"}
+        ]"#;
+        let probes: Vec<Probe> = serde_json::from_str(json).unwrap();
+        assert_eq!(probes[1], Probe::Tcp { host: "www.baidu.com".to_string(), port: 443 });
+        assert_eq!(probes[2], Probe::Http {
+            url: "http://connect.rom.miui.com/generate_204".to_string(),
+            expected_status: 204,
+        });
+        assert_eq!(probes[3].describe(), "DNS www.qq.com");
+    }
+
+    #[test]
+    fn test_with_protocol() {
+        let icmp = Probe::Icmp { host: "223.5.5.5".to_string() };
+        assert_eq!(icmp.with_protocol("tcp"), Probe::Tcp { host: "223.5.5.5".to_string(), port: 443 });
+        let http = icmp.with_protocol("http");
+        assert_eq!(http.describe(), "HTTP http://223.5.5.5/generate_204 (expect 204)");
+        assert_eq!(http.with_protocol("dns"), Probe::Dns { name: "223.5.5.5".to_string() });
+
+        assert!(http.validate().is_ok());
+        assert!(Probe::Icmp { host: " ".to_string() }.validate().is_err());
+        assert!(Probe::Http { url: "generate_204".to_string(), expected_status: 204 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(Some(204), false, false), Connectivity::Online);
+        // 门户返回登录页或重定向，即使 ping 成功也需要登录
+        assert_eq!(classify(Some(200), true, false), Connectivity::PortalRedirect);
+        assert_eq!(classify(Some(302), false, false), Connectivity::PortalRedirect);
+        // 探测地址本身不可用时按其他探测目标判断
+        assert_eq!(classify(Some(503), true, false), Connectivity::Online);
+        assert_eq!(classify(None, true, false), Connectivity::Online);
+        assert_eq!(classify(None, false, false), Connectivity::Offline);
+        // DNS 被劫持时其他探测的成功不可信
+        assert_eq!(classify(None, true, true), Connectivity::PortalRedirect);
+        assert_eq!(classify(None, false, true), Connectivity::PortalRedirect);
+        assert_eq!(classify(Some(204), true, true), Connectivity::Online);
+    }
+
+    #[test]
+    fn test_health_score() {
+        assert_eq!(health_score(&[]), 0);
+        assert_eq!(health_score(&[true, true]), 100);
+        assert_eq!(health_score(&[true, false, false]), 33);
+    }
+}
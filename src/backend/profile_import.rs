@@ -0,0 +1,211 @@
+// 从 CSV 批量导入账号：每行为用户名、密码、运营商、备注，第一行可以是表头。逐行校验格式，与已有账号或文件中
+// 前面的行重复（用户名和运营商相同）的跳过，其余加入账号列表，适合社团等需要管理大量账号的场合。
+// 密码随配置保存，记住密码时放进系统凭据存储（与手动添加的账号相同）；也可以不导入密码，之后再逐个填写
+use crate::backend::config::{Config, Profile};
+use crate::backend::isp::ISP;
+use anyhow::{anyhow, bail, Result};
+
+/// 导入的结果
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    // 新建的账号名称
+    pub imported: Vec<String>,
+    // 跳过的行及原因，如 "Line 3: ..."
+    pub skipped: Vec<String>,
+}
+
+impl ImportReport {
+    /// 写入日志的摘要
+    pub fn summary(&self) -> String {
+        format!("Imported {} profiles from CSV, skipped {} lines", self.imported.len(), self.skipped.len())
+    }
+}
+
+// 把 CSV 内容拆成行和字段：字段可用双引号括起，其中的 "" 表示一个引号，引号内可以有逗号和换行；
+// 返回每行的起始行号和字段，跳过空行
+fn parse_csv(content: &str) -> Result<Vec<(usize, Vec<String>)>> {
+    let mut rows = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                fields.push(std::mem::take(&mut field));
+                let row = std::mem::take(&mut fields);
+                if row.iter().any(|field| !field.trim().is_empty()) {
+                    rows.push((row_line, row));
+                }
+                line += 1;
+                row_line = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if quoted {
+        bail!("Line {}: unterminated quoted field", row_line);
+    }
+    fields.push(field);
+    if fields.iter().any(|field| !field.trim().is_empty()) {
+        rows.push((row_line, fields));
+    }
+    Ok(rows)
+}
+
+// 运营商列：界面中的名称、门户账号后缀或中文名称，不区分大小写；为空时为校园网
+fn parse_isp(value: &str) -> Result<ISP> {
+    let value = value.trim().trim_start_matches('@').to_lowercase();
+    if value.is_empty() {
+        return Ok(ISP::School);
+    }
+    let chinese = |isp: ISP| match isp {
+        ISP::Mobile => "移动",
+        ISP::Unicom => "联通",
+        ISP::Telecom => "电信",
+        ISP::School => "校园网",
+    };
+    ISP::ALL.into_iter()
+        .find(|isp| value == isp.label().to_lowercase() || value == isp.suffix() || value == chinese(*isp))
+        .ok_or_else(|| anyhow!("unknown ISP \"{}\", expected Mobile, Unicom, Telecom or School", value))
+}
+
+// 校验一行并生成账号（名称稍后确定）
+fn parse_row(fields: &[String]) -> Result<Profile> {
+    if fields.len() > 4 {
+        bail!("expected at most 4 columns (username, password, ISP, notes), found {}", fields.len());
+    }
+    let field = |index: usize| fields.get(index).map(String::as_str).unwrap_or_default();
+    let username = field(0).trim();
+    if username.is_empty() {
+        bail!("username is empty");
+    }
+    if username.chars().any(|c| c.is_whitespace() || c == '@') {
+        bail!("username \"{}\" must not contain spaces or @, put the carrier in the ISP column", username);
+    }
+    Ok(Profile {
+        username: username.to_string(),
+        password: field(1).into(),
+        isp: parse_isp(field(2))?,
+        notes: field(3).trim().to_string(),
+        ..Default::default()
+    })
+}
+
+// 第一行是否为表头
+fn is_header(fields: &[String]) -> bool {
+    fields.first().is_some_and(|field| matches!(field.trim().to_lowercase().as_str(), "username" | "用户名" | "账号"))
+}
+
+/// 把 CSV 中的账号加入配置的账号列表，账号名称为用户名（重名时加序号），门户地址沿用当前账号；
+/// save_passwords 为 false 时不导入密码。整个文件无法解析时返回错误，单行有误时跳过该行
+pub fn import(config: &mut Config, content: &str, save_passwords: bool) -> Result<ImportReport> {
+    let mut rows = parse_csv(content)?;
+    if rows.first().is_some_and(|(_, fields)| is_header(fields)) {
+        rows.remove(0);
+    }
+    if rows.is_empty() {
+        bail!("The CSV file contains no accounts");
+    }
+    let mut report = ImportReport::default();
+    let mut accounts: Vec<(String, ISP)> = config.profiles.iter()
+        .map(|profile| (profile.username.clone(), profile.isp))
+        .chain([(config.username.clone(), config.isp)])
+        .collect();
+    for (line, fields) in rows {
+        let mut profile = match parse_row(&fields) {
+            Ok(profile) => profile,
+            Err(e) => {
+                report.skipped.push(format!("Line {}: {}", line, e));
+                continue;
+            }
+        };
+        let account = (profile.username.clone(), profile.isp);
+        if accounts.contains(&account) {
+            report.skipped.push(format!("Line {}: {} ({}) already exists", line, profile.username, profile.isp.label()));
+            continue;
+        }
+        accounts.push(account);
+        let mut name = profile.username.clone();
+        for index in 2.. {
+            if !config.profiles.iter().any(|existing| existing.name == name) {
+                break;
+            }
+            name = format!("{} ({})", profile.username, index);
+        }
+        if !save_passwords {
+            profile.password.clear();
+        }
+        profile.name = name.clone();
+        profile.auth_url = config.auth_url.clone();
+        config.profiles.push(profile);
+        report.imported.push(name);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let mut config = Config {
+            username: "8208190101".to_string(),
+            auth_url: "http://10.1.1.1".to_string(),
+            active_profile: "8208190102".to_string(),
+            ..Default::default()
+        };
+        config.profiles.push(config.active_as_profile());
+        let csv = "\u{feff}username,password,isp,notes\r\n\
+            8208190102,\"pa,ss\",Mobile,\"Sponsor \"\"A\"\"\"\r\n\
+            \n\
+            8208190101,secret,,already the active account\n\
+            8208190103,secret,@telecomn\n\
+            8208190103,secret,电信,duplicate in the file\n\
+            ,secret,School\n\
+            8208190104,secret,Satellite\n\
+            8208190105 x,secret\n\
+            8208190106,\"two\nlines\",unicomn,a,b\n\
+            8208190107";
+        let report = import(&mut config, csv, true).unwrap();
+        assert_eq!(report.imported, vec!["8208190102 (2)", "8208190103", "8208190107"]);
+        assert_eq!(report.skipped.len(), 6);
+        assert_eq!(report.skipped[0], "Line 4: 8208190101 (School) already exists");
+        assert!(report.skipped[1].starts_with("Line 6: 8208190103 (Telecom)"));
+        assert!(report.skipped[2].starts_with("Line 7: username is empty"));
+        assert!(report.skipped[3].contains("unknown ISP \"satellite\""));
+        assert!(report.skipped[4].starts_with("Line 9:"));
+        // 引号中的换行不影响之后的行号
+        assert!(report.skipped[5].starts_with("Line 10: expected at most 4 columns"));
+        assert_eq!(report.summary(), "Imported 3 profiles from CSV, skipped 6 lines");
+
+        let imported = &config.profiles[1];
+        assert_eq!((imported.username.as_str(), imported.password.expose(), imported.isp), ("8208190102", "pa,ss", ISP::Mobile));
+        assert_eq!(imported.notes, "Sponsor \"A\"");
+        assert_eq!(imported.auth_url, "http://10.1.1.1");
+        assert_eq!(config.profiles[3].isp, ISP::School);
+
+        // 不导入密码；导入过的账号不会重复加入
+        let report = import(&mut config, "8208190108,secret\n8208190103,secret,Telecom", false).unwrap();
+        assert_eq!(report.imported, vec!["8208190108"]);
+        assert!(config.profiles.last().unwrap().password.is_empty());
+        assert!(import(&mut config, "username,password\n", true).is_err());
+        assert!(import(&mut config, "\"8208190109,secret", true).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+// 系统代理/VPN 检测模块
+#[cfg(windows)]
+use std::process::Command;
+
+// 常见 VPN/代理虚拟网卡名称中的关键字
+const VPN_KEYWORDS: [&str; 10] = [
+    "tap", "tun", "wintun", "wireguard", "clash", "easyconnect", "sangfor", "openvpn", "zerotier", "vpn",
+];
+// 系统自带的隧道网卡，不属于 VPN
+const IGNORED_ADAPTERS: [&str; 3] = ["isatap", "teredo", "6to4"];
+
+/// 代理/VPN 检测结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProxyStatus {
+    pub proxy: Option<String>,
+    pub vpn_adapters: Vec<String>,
+}
+
+impl ProxyStatus {
+    /// 是否存在可能干扰门户认证的代理或 VPN
+    pub fn is_active(&self) -> bool {
+        self.proxy.is_some() || !self.vpn_adapters.is_empty()
+    }
+
+    /// 用于界面显示的简短描述
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(proxy) = &self.proxy {
+            parts.push(format!("proxy {}", proxy));
+        }
+        if !self.vpn_adapters.is_empty() {
+            parts.push(format!("VPN adapter {}", self.vpn_adapters.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+/// 检测当前系统的代理和 VPN 状态
+pub fn detect() -> ProxyStatus {
+    ProxyStatus {
+        proxy: env_proxy().or_else(system_proxy),
+        vpn_adapters: vpn_adapters(),
+    }
+}
+
+// 从环境变量中读取代理设置
+fn env_proxy() -> Option<String> {
+    ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY", "http_proxy", "https_proxy", "all_proxy"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+}
+
+// 读取 Windows 系统代理设置（Internet 选项）
+#[cfg(windows)]
+fn system_proxy() -> Option<String> {
+    let output = Command::new("reg")
+        .args(["query", r"HKCU\Software\Microsoft\Windows\CurrentVersion\Internet Settings"])
+        .output()
+        .ok()?;
+    parse_registry_proxy(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(not(windows))]
+fn system_proxy() -> Option<String> {
+    None
+}
+
+// 从 reg query 的输出中解析已启用的代理服务器
+#[cfg(any(windows, test))]
+fn parse_registry_proxy(output: &str) -> Option<String> {
+    let value_of = |name: &str| {
+        output.lines()
+            .map(str::split_whitespace)
+            .find_map(|mut fields| {
+                if fields.next() == Some(name) {
+                    fields.nth(1).map(String::from)
+                } else {
+                    None
+                }
+            })
+    };
+
+    match value_of("ProxyEnable").as_deref() {
+        Some("0x1") => value_of("ProxyServer"),
+        _ => None,
+    }
+}
+
+// 列出处于连接状态的 VPN/代理虚拟网卡
+#[cfg(windows)]
+fn vpn_adapters() -> Vec<String> {
+    match Command::new("ipconfig").arg("/all").output() {
+        Ok(output) => parse_ipconfig(&String::from_utf8_lossy(&output.stdout)),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[cfg(not(windows))]
+fn vpn_adapters() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/net") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| is_vpn_name(name))
+        .collect()
+}
+
+// 名称是否像 VPN/代理网卡
+fn is_vpn_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    !IGNORED_ADAPTERS.iter().any(|ignored| name.contains(ignored))
+        && VPN_KEYWORDS.iter().any(|keyword| name.contains(keyword))
+}
+
+// 解析 ipconfig /all 的输出，返回已连接的 VPN 网卡名称
+#[cfg(any(windows, test))]
+fn parse_ipconfig(output: &str) -> Vec<String> {
+    let mut adapters = Vec::new();
+    let mut current: Option<(String, bool, bool)> = None;
+
+    let mut finish = |adapter: Option<(String, bool, bool)>| {
+        if let Some((name, is_vpn, disconnected)) = adapter {
+            if is_vpn && !disconnected {
+                adapters.push(name);
+            }
+        }
+    };
+
+    for line in output.lines() {
+        if line.is_empty() {
+            continue;
+        }
+        if !line.starts_with(' ') && line.trim_end().ends_with(':') {
+            // 新的网卡段落，例如 "Unknown adapter Clash:"
+            finish(current.take());
+            let name = line.trim_end().trim_end_matches(':').to_string();
+            let is_vpn = is_vpn_name(&name);
+            current = Some((name, is_vpn, false));
+        } else if let Some((_, is_vpn, disconnected)) = current.as_mut() {
+            let lower = line.to_lowercase();
+            if lower.contains("media disconnected") || line.contains("媒体已断开") {
+                *disconnected = true;
+            }
+            if (lower.contains("description") || line.contains("描述")) && is_vpn_name(line) {
+                *is_vpn = true;
+            }
+        }
+    }
+    finish(current.take());
+
+    adapters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_registry_proxy() {
+        let enabled = "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings\r\n    ProxyEnable    REG_DWORD    0x1\r\n    ProxyServer    REG_SZ    127.0.0.1:7890\r\n";
+        assert_eq!(parse_registry_proxy(enabled), Some("127.0.0.1:7890".to_string()));
+
+        let disabled = "    ProxyEnable    REG_DWORD    0x0\r\n    ProxyServer    REG_SZ    127.0.0.1:7890\r\n";
+        assert_eq!(parse_registry_proxy(disabled), None);
+    }
+
+    #[test]
+    fn test_parse_ipconfig() {
+        let output = "\
+Windows IP Configuration
+
+Ethernet adapter Ethernet:
+
+   Description . . . . . . . . . . . : Realtek PCIe GbE Family Controller
+   IPv4 Address. . . . . . . . . . . : 10.96.12.34(Preferred)
+
+Unknown adapter Clash:
+
+   Description . . . . . . . . . . . : Meta Tunnel
+   IPv4 Address. . . . . . . . . . . : 198.18.0.1(Preferred)
+
+Ethernet adapter 以太网 2:
+
+   媒体状态  . . . . . . . . . . . . : 媒体已断开连接
+   描述. . . . . . . . . . . . . . . : TAP-Windows Adapter V9
+
+Tunnel adapter isatap.{1234}:
+
+   Media State . . . . . . . . . . . : Media disconnected
+";
+        assert_eq!(parse_ipconfig(output), vec!["Unknown adapter Clash".to_string()]);
+    }
+
+    #[test]
+    fn test_proxy_status() {
+        let status = ProxyStatus::default();
+        assert!(!status.is_active());
+
+        let status = ProxyStatus {
+            proxy: Some("127.0.0.1:7890".to_string()),
+            vpn_adapters: vec!["Clash".to_string()],
+        };
+        assert!(status.is_active());
+        assert_eq!(status.describe(), "proxy 127.0.0.1:7890; VPN adapter Clash");
+    }
+}
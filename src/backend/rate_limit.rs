@@ -0,0 +1,137 @@
+// 门户 HTTP 请求的并发和频率限制，避免多个任务同时请求触发门户的防刷机制
+use parking_lot::Mutex;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// 默认最大并发请求数
+pub const DEFAULT_MAX_CONCURRENT: usize = 2;
+/// 默认两次请求之间的最小间隔
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_millis(1000);
+
+struct LimiterState {
+    in_flight: usize,
+    max_concurrent: usize,
+    min_interval: Duration,
+    // 下一个请求最早可以发出的时间
+    next_slot: Instant,
+}
+
+/// 并发和频率限制器
+pub struct RateLimiter {
+    state: Mutex<LimiterState>,
+    notify: Notify,
+}
+
+/// 请求许可，释放时归还并发名额
+pub struct Permit<'a> {
+    limiter: &'a RateLimiter,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.limiter.state.lock().in_flight -= 1;
+        self.limiter.notify.notify_one();
+    }
+}
+
+impl RateLimiter {
+    pub fn new(max_concurrent: usize, min_interval: Duration) -> Self {
+        Self {
+            state: Mutex::new(LimiterState {
+                in_flight: 0,
+                max_concurrent: max_concurrent.max(1),
+                min_interval,
+                next_slot: Instant::now(),
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    /// 修改限制参数，对之后的请求生效
+    pub fn configure(&self, max_concurrent: usize, min_interval: Duration) {
+        let mut state = self.state.lock();
+        state.max_concurrent = max_concurrent.max(1);
+        state.min_interval = min_interval;
+        drop(state);
+        self.notify.notify_one();
+    }
+
+    /// 等待并发名额和请求间隔，返回的许可在请求结束前应一直持有
+    pub async fn acquire(&self) -> Permit<'_> {
+        loop {
+            let wait = {
+                let mut state = self.state.lock();
+                if state.in_flight < state.max_concurrent {
+                    state.in_flight += 1;
+                    let now = Instant::now();
+                    let slot = state.next_slot.max(now);
+                    state.next_slot = slot + state.min_interval;
+                    Some(slot - now)
+                } else {
+                    None
+                }
+            };
+
+            match wait {
+                Some(wait) => {
+                    let permit = Permit { limiter: self };
+                    if !wait.is_zero() {
+                        tokio::time::sleep(wait).await;
+                    }
+                    return permit;
+                }
+                None => self.notify.notified().await,
+            }
+        }
+    }
+}
+
+/// 所有门户 HTTP 请求共用的限制器
+pub fn portal_limiter() -> &'static RateLimiter {
+    static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| RateLimiter::new(DEFAULT_MAX_CONCURRENT, DEFAULT_MIN_INTERVAL))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_min_interval() {
+        let limiter = RateLimiter::new(10, Duration::from_millis(50));
+        let start = Instant::now();
+        for _ in 0..3 {
+            let _permit = limiter.acquire().await;
+        }
+        // 第一个请求立即发出，之后每个至少间隔 50ms
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrent() {
+        let limiter = Arc::new(RateLimiter::new(2, Duration::ZERO));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..6).map(|_| {
+            let limiter = Arc::clone(&limiter);
+            let in_flight = Arc::clone(&in_flight);
+            let peak = Arc::clone(&peak);
+            tokio::spawn(async move {
+                let _permit = limiter.acquire().await;
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            })
+        }).collect();
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+}
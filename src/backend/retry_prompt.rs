@@ -0,0 +1,193 @@
+// 自动登录连续失败后的提示：不再只是默默延长重试间隔，而是询问用户接下来怎么做，并根据以往的选择调整提示时机和默认选项
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// 未记录过选择时，连续失败多少次后提示
+pub const DEFAULT_PROMPT_AFTER: u32 = 3;
+// 经常选择立即重试时推迟提示，但不超过此次数
+const MAX_PROMPT_AFTER: u32 = 10;
+
+/// 提示中的选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptChoice {
+    RetryNow,
+    OpenPortal,
+    PauseToday,
+    CollectDiagnostics,
+}
+
+impl PromptChoice {
+    pub const ALL: [PromptChoice; 4] = [
+        PromptChoice::RetryNow,
+        PromptChoice::OpenPortal,
+        PromptChoice::PauseToday,
+        PromptChoice::CollectDiagnostics,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PromptChoice::RetryNow => "Retry now",
+            PromptChoice::OpenPortal => "Open portal manually",
+            PromptChoice::PauseToday => "Pause for today",
+            PromptChoice::CollectDiagnostics => "Collect diagnostics",
+        }
+    }
+}
+
+/// 用户以往的选择次数，保存在配置中
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PromptHistory {
+    #[serde(default)]
+    pub retry_now: u32,
+    #[serde(default)]
+    pub open_portal: u32,
+    #[serde(default)]
+    pub pause_today: u32,
+    #[serde(default)]
+    pub collect_diagnostics: u32,
+}
+
+impl PromptHistory {
+    pub fn record(&mut self, choice: PromptChoice) {
+        *self.count_mut(choice) += 1;
+    }
+
+    fn count(&self, choice: PromptChoice) -> u32 {
+        match choice {
+            PromptChoice::RetryNow => self.retry_now,
+            PromptChoice::OpenPortal => self.open_portal,
+            PromptChoice::PauseToday => self.pause_today,
+            PromptChoice::CollectDiagnostics => self.collect_diagnostics,
+        }
+    }
+
+    fn count_mut(&mut self, choice: PromptChoice) -> &mut u32 {
+        match choice {
+            PromptChoice::RetryNow => &mut self.retry_now,
+            PromptChoice::OpenPortal => &mut self.open_portal,
+            PromptChoice::PauseToday => &mut self.pause_today,
+            PromptChoice::CollectDiagnostics => &mut self.collect_diagnostics,
+        }
+    }
+
+    /// 连续失败多少次后提示；经常选择立即重试说明失败多半会自己恢复，每两次推迟一次提示
+    pub fn prompt_after(&self) -> u32 {
+        (DEFAULT_PROMPT_AFTER + self.retry_now / 2).min(MAX_PROMPT_AFTER)
+    }
+
+    /// 选择过至少两次且次数最多的选项，提示中作为默认按钮突出显示
+    pub fn preferred(&self) -> Option<PromptChoice> {
+        PromptChoice::ALL.into_iter()
+            .filter(|choice| self.count(*choice) >= 2)
+            .max_by_key(|choice| self.count(*choice))
+    }
+}
+
+/// 自动登录线程和界面共享的提示状态
+#[derive(Debug)]
+pub struct RetryPrompt {
+    prompt_after: u32,
+    // 等待用户选择时为连续失败次数
+    pending: Option<u32>,
+    // 本轮连续失败中上次提示时的失败次数
+    prompted_at: u32,
+    // 选择暂停的日期，当天不再自动登录
+    paused_on: Option<NaiveDate>,
+}
+
+impl RetryPrompt {
+    pub fn new(prompt_after: u32) -> Self {
+        Self { prompt_after: prompt_after.max(1), pending: None, prompted_at: 0, paused_on: None }
+    }
+
+    pub fn set_prompt_after(&mut self, prompt_after: u32) {
+        self.prompt_after = prompt_after.max(1);
+    }
+
+    /// 记录当前的连续失败次数，需要提示时返回 true；用户选择后仍然失败，再失败同样次数时再次提示
+    pub fn observe_failures(&mut self, failures: u32) -> bool {
+        if failures == 0 {
+            self.prompted_at = 0;
+            self.pending = None;
+            return false;
+        }
+        if self.pending.is_some() || failures < self.prompted_at + self.prompt_after {
+            return false;
+        }
+        self.prompted_at = failures;
+        self.pending = Some(failures);
+        true
+    }
+
+    /// 等待用户选择的提示，值为连续失败次数
+    pub fn pending(&self) -> Option<u32> {
+        self.pending
+    }
+
+    /// 用户做出选择或关闭提示
+    pub fn dismiss(&mut self) {
+        self.pending = None;
+    }
+
+    pub fn pause(&mut self, today: NaiveDate) {
+        self.paused_on = Some(today);
+    }
+
+    pub fn is_paused(&self, today: NaiveDate) -> bool {
+        self.paused_on == Some(today)
+    }
+}
+
+impl Default for RetryPrompt {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROMPT_AFTER)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt() {
+        let mut prompt = RetryPrompt::new(3);
+        assert!(!prompt.observe_failures(2));
+        assert!(prompt.observe_failures(3));
+        assert_eq!(prompt.pending(), Some(3));
+        assert!(!prompt.observe_failures(4));
+
+        // 选择后再失败 3 次才再次提示，成功后重新计数
+        prompt.dismiss();
+        assert!(!prompt.observe_failures(5));
+        assert!(prompt.observe_failures(6));
+        prompt.dismiss();
+        assert!(!prompt.observe_failures(0));
+        assert!(prompt.observe_failures(3));
+
+        let today = NaiveDate::from_ymd_opt(2024, 11, 4).unwrap();
+        prompt.pause(today);
+        assert!(prompt.is_paused(today));
+        assert!(!prompt.is_paused(today.succ_opt().unwrap()));
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = PromptHistory::default();
+        assert_eq!(history.prompt_after(), DEFAULT_PROMPT_AFTER);
+        assert_eq!(history.preferred(), None);
+
+        history.record(PromptChoice::OpenPortal);
+        history.record(PromptChoice::OpenPortal);
+        assert_eq!(history.preferred(), Some(PromptChoice::OpenPortal));
+
+        for _ in 0..5 {
+            history.record(PromptChoice::RetryNow);
+        }
+        assert_eq!(history.prompt_after(), DEFAULT_PROMPT_AFTER + 2);
+        assert_eq!(history.preferred(), Some(PromptChoice::RetryNow));
+        for _ in 0..20 {
+            history.record(PromptChoice::RetryNow);
+        }
+        assert_eq!(history.prompt_after(), MAX_PROMPT_AFTER);
+    }
+}
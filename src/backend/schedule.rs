@@ -0,0 +1,91 @@
+// 下一次行动的时间表：监控线程记录下次检查网络的时间，自动登录线程记录下次登录（或检查是否需要登录）的时间和等待原因，界面据此显示倒计时
+use std::time::{Duration, Instant};
+
+/// 自动登录线程等待的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginWait {
+    // 确认断网（防抖）后登录
+    Debounce,
+    // 登录失败后等待重试，值为连续失败次数
+    Backoff(u32),
+    // 不在线时定期检查是否需要登录
+    Recheck,
+}
+
+impl LoginWait {
+    /// 倒计时后面显示的说明
+    pub fn describe(&self) -> String {
+        match self {
+            LoginWait::Debounce => "confirming the disconnect".to_string(),
+            LoginWait::Backoff(1) => "after 1 failed attempt".to_string(),
+            LoginWait::Backoff(failures) => format!("after {} failed attempts", failures),
+            LoginWait::Recheck => "waiting for the next check".to_string(),
+        }
+    }
+}
+
+/// 监控线程、自动登录线程和界面共享的时间表
+#[derive(Debug, Default)]
+pub struct Schedule {
+    next_check: Option<Instant>,
+    next_login: Option<(Instant, LoginWait)>,
+}
+
+impl Schedule {
+    pub fn check_at(&mut self, at: Instant) {
+        self.next_check = Some(at);
+    }
+
+    pub fn login_at(&mut self, at: Instant, wait: LoginWait) {
+        self.next_login = Some((at, wait));
+    }
+
+    /// 在线或正在登录时没有等待中的登录
+    pub fn clear_login(&mut self) {
+        self.next_login = None;
+    }
+
+    /// 距离下次检查网络的时间
+    pub fn next_check(&self, now: Instant) -> Option<Duration> {
+        self.next_check.map(|at| at.saturating_duration_since(now))
+    }
+
+    /// 距离下次登录的时间和等待原因
+    pub fn next_login(&self, now: Instant) -> Option<(Duration, LoginWait)> {
+        self.next_login.map(|(at, wait)| (at.saturating_duration_since(now), wait))
+    }
+}
+
+/// 倒计时文本，如 01:05
+pub fn countdown(remaining: Duration) -> String {
+    let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule() {
+        let now = Instant::now();
+        let mut schedule = Schedule::default();
+        assert_eq!(schedule.next_check(now), None);
+
+        schedule.check_at(now + Duration::from_secs(30));
+        assert_eq!(schedule.next_check(now + Duration::from_secs(5)), Some(Duration::from_secs(25)));
+        // 检查时间已过（正在检查）时显示为 0
+        assert_eq!(schedule.next_check(now + Duration::from_secs(40)), Some(Duration::ZERO));
+
+        schedule.login_at(now + Duration::from_secs(120), LoginWait::Backoff(4));
+        let (remaining, wait) = schedule.next_login(now).unwrap();
+        assert_eq!(countdown(remaining), "02:00");
+        assert_eq!(wait.describe(), "after 4 failed attempts");
+        schedule.clear_login();
+        assert_eq!(schedule.next_login(now), None);
+
+        // 不足一秒时向上取整，倒计时不会提前显示 00:00
+        assert_eq!(countdown(Duration::from_millis(4200)), "00:05");
+        assert_eq!(countdown(Duration::ZERO), "00:00");
+    }
+}
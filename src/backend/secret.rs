@@ -0,0 +1,96 @@
+// 校园网密码的包装类型：释放时清零内存，Debug 输出中隐藏内容，避免内存转储或误用 {:?} 记录日志时泄露密码
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zeroize::Zeroize;
+
+// 交给输入框编辑前预留的容量，输入过程中本缓冲区不再重新分配，不在释放的旧缓冲区中留下未清零的密码
+const EDIT_CAPACITY: usize = 64;
+
+/// 释放时清零的字符串，配置文件中仍按普通字符串保存
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SecretString(String);
+
+impl SecretString {
+    /// 取出明文，只在发送给门户或浏览器时使用
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 清零后清空
+    pub fn clear(&mut self) {
+        self.0.zeroize();
+    }
+
+    /// 供输入框编辑的可变引用，先把内容搬到足够大的缓冲区并清零原缓冲区。
+    /// 只保证这个缓冲区不留下副本：egui 的 TextEdit 在撤销记录和排版结果中仍会另存文字，这些副本不会被清零
+    pub fn edit(&mut self) -> &mut String {
+        if self.0.capacity() < EDIT_CAPACITY {
+            let mut buffer = String::with_capacity(EDIT_CAPACITY.max(self.0.len()));
+            buffer.push_str(&self.0);
+            self.0.zeroize();
+            self.0 = buffer;
+        }
+        &mut self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl PartialEq<&str> for SecretString {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            write!(f, "\"\"")
+        } else {
+            write!(f, "\"••••••\"")
+        }
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_string() {
+        let mut secret = SecretString::from("campus-pass");
+        assert_eq!(format!("{:?}", secret), "\"••••••\"");
+        assert_eq!(format!("{:?}", SecretString::default()), "\"\"");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"campus-pass\"");
+        assert_eq!(serde_json::from_str::<SecretString>("\"campus-pass\"").unwrap(), secret);
+
+        // 编辑时预留容量，输入不会触发重新分配
+        secret.edit().push_str("-2024");
+        assert!(secret.edit().capacity() >= EDIT_CAPACITY);
+        assert_eq!(secret, "campus-pass-2024");
+
+        secret.clear();
+        assert!(secret.is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+// 夜间自检：每晚在网络空闲时运行一次完整检查（探测目标、门户状态、测速），结果追加到历史文件，
+// 与一周前同一时间的结果比较，找出明显变差的指标，早上在界面中汇总显示
+use crate::backend::paths;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, FixedOffset, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+#[cfg(feature = "speedtest")]
+use std::time::Instant;
+
+/// 每晚几点运行自检（本地时间）
+pub const NIGHTLY_HOUR: u32 = 3;
+/// 早上几点后显示夜间自检的汇总
+pub const MORNING_HOUR: u32 = 7;
+// 测速最多下载的时间和数据量
+#[cfg(feature = "speedtest")]
+const SPEED_SAMPLE_TIME: Duration = Duration::from_secs(10);
+#[cfg(feature = "speedtest")]
+const SPEED_SAMPLE_BYTES: u64 = 20 * 1024 * 1024;
+// 与几天前的结果比较，以及允许的时间偏差
+const COMPARE_DAYS: i64 = 7;
+const COMPARE_TOLERANCE_SECS: i64 = 12 * 3600;
+// 速度降到一周前的一半以下、时延升到两倍以上（且至少多 20 ms）、健康度下降 25 个百分点以上时报告
+const SPEED_DROP_RATIO: f64 = 0.5;
+const LATENCY_RISE_RATIO: f64 = 2.0;
+const LATENCY_RISE_MIN_MS: u64 = 20;
+const HEALTH_DROP: u8 = 25;
+
+/// 一次自检的结果，历史文件中每行一条
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    // 运行时间（Unix 时间戳，秒）
+    pub timestamp: i64,
+    pub health_score: u8,
+    pub latency_ms: Option<u64>,
+    // 门户是否可达，及门户返回的状态或错误
+    pub portal_ok: bool,
+    pub portal: String,
+    // 测速结果（Mbps），未配置测速地址或测速失败时为 None
+    #[serde(default)]
+    pub speed_mbps: Option<f64>,
+}
+
+impl SelfTestReport {
+    /// 汇总中显示的一行说明
+    pub fn summary(&self) -> String {
+        let latency = self.latency_ms.map_or("no latency".to_string(), |ms| format!("latency {} ms", ms));
+        let speed = self.speed_mbps.map_or(String::new(), |mbps| format!(", speed {:.1} Mbps", mbps));
+        format!("Health {}%, {}, portal: {}{}", self.health_score, latency, self.portal, speed)
+    }
+}
+
+/// 与一周前同一时间的结果比较，返回变差的指标；门户不可达时总是报告
+pub fn regressions(history: &[SelfTestReport], latest: &SelfTestReport) -> Vec<String> {
+    let mut found = Vec::new();
+    if !latest.portal_ok {
+        found.push(format!("Portal check failed: {}", latest.portal));
+    }
+
+    let target = latest.timestamp - COMPARE_DAYS * 24 * 3600;
+    let Some(baseline) = history.iter()
+        .filter(|report| (report.timestamp - target).abs() <= COMPARE_TOLERANCE_SECS)
+        .min_by_key(|report| (report.timestamp - target).abs()) else {
+        return found;
+    };
+
+    if let (Some(now), Some(before)) = (latest.speed_mbps, baseline.speed_mbps) {
+        if before > 0.0 && now < before * SPEED_DROP_RATIO {
+            found.push(format!(
+                "Speed dropped {:.0}% week over week ({:.1} → {:.1} Mbps)",
+                (1.0 - now / before) * 100.0, before, now
+            ));
+        }
+    }
+    if let (Some(now), Some(before)) = (latest.latency_ms, baseline.latency_ms) {
+        if now as f64 >= before as f64 * LATENCY_RISE_RATIO && now >= before + LATENCY_RISE_MIN_MS {
+            found.push(format!("Latency rose week over week ({} → {} ms)", before, now));
+        }
+    }
+    if latest.health_score + HEALTH_DROP <= baseline.health_score {
+        found.push(format!("Health fell week over week ({}% → {}%)", baseline.health_score, latest.health_score));
+    }
+    found
+}
+
+/// 距离下一次夜间自检的时间
+pub fn until_next_run(now: DateTime<FixedOffset>) -> Duration {
+    let run_at = NaiveTime::from_hms_opt(NIGHTLY_HOUR, 0, 0).unwrap_or_default();
+    let mut next = now.date_naive().and_time(run_at);
+    if next <= now.naive_local() {
+        next += ChronoDuration::days(1);
+    }
+    (next - now.naive_local()).to_std().unwrap_or_default()
+}
+
+/// 下一次夜间自检的时刻
+pub fn next_run_at(now: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+    now + ChronoDuration::from_std(until_next_run(now)).unwrap_or_default()
+}
+
+/// 是否在夜间（自检之后、早上显示汇总之前）
+pub fn is_night(now: DateTime<FixedOffset>) -> bool {
+    (NIGHTLY_HOUR..MORNING_HOUR).contains(&now.hour())
+}
+
+/// 历史文件路径，与配置文件放在一起
+pub fn history_path() -> PathBuf {
+    paths::config_dir().join("self_test.jsonl")
+}
+
+/// 读取历史结果，跳过无法解析的行；文件不存在时为空
+pub fn load_history(path: &Path) -> Vec<SelfTestReport> {
+    std::fs::read_to_string(path)
+        .map(|text| text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+        .unwrap_or_default()
+}
+
+/// 把一次结果追加到历史文件
+pub fn append_history(path: &Path, report: &SelfTestReport) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(report)?)?;
+    Ok(())
+}
+
+#[cfg(feature = "speedtest")]
+/// 从测速地址下载一段时间，返回平均速度（Mbps）
+pub async fn sample_speed(url: &str) -> Result<f64> {
+    let client = reqwest::Client::builder()
+        .timeout(SPEED_SAMPLE_TIME + Duration::from_secs(5))
+        .build()?;
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let start = Instant::now();
+    let mut bytes = 0u64;
+    while bytes < SPEED_SAMPLE_BYTES {
+        let Some(remaining) = SPEED_SAMPLE_TIME.checked_sub(start.elapsed()) else { break };
+        match tokio::time::timeout(remaining, response.chunk()).await {
+            Ok(Ok(Some(chunk))) => bytes += chunk.len() as u64,
+            Ok(Ok(None)) | Err(_) => break,
+            Ok(Err(e)) => return Err(e.into()),
+        }
+    }
+    let secs = start.elapsed().as_secs_f64().max(0.001);
+    Ok(bytes as f64 * 8.0 / secs / 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn report(days_ago: i64, speed_mbps: Option<f64>, latency_ms: Option<u64>) -> SelfTestReport {
+        SelfTestReport {
+            timestamp: 1_730_000_000 - days_ago * 24 * 3600,
+            health_score: 100,
+            latency_ms,
+            portal_ok: true,
+            portal: "Signed in".to_string(),
+            speed_mbps,
+        }
+    }
+
+    #[test]
+    fn test_regressions() {
+        let history = vec![report(8, Some(95.0), Some(8)), report(7, Some(80.0), Some(10)), report(1, Some(20.0), Some(60))];
+        assert!(regressions(&history, &report(0, Some(60.0), Some(12))).is_empty());
+
+        // 与 7 天前的结果比较，而不是最近一次
+        let found = regressions(&history, &report(0, Some(30.0), Some(45)));
+        assert_eq!(found, vec![
+            "Speed dropped 62% week over week (80.0 → 30.0 Mbps)".to_string(),
+            "Latency rose week over week (10 → 45 ms)".to_string(),
+        ]);
+
+        // 没有一周前的结果时只报告门户故障
+        let mut latest = report(0, Some(1.0), None);
+        latest.portal_ok = false;
+        latest.portal = "timed out".to_string();
+        assert_eq!(regressions(&history[2..], &latest), vec!["Portal check failed: timed out".to_string()]);
+    }
+
+    #[test]
+    fn test_until_next_run() {
+        let zone = FixedOffset::east_opt(8 * 3600).unwrap();
+        let evening = zone.with_ymd_and_hms(2024, 11, 4, 22, 30, 0).unwrap();
+        assert_eq!(until_next_run(evening), Duration::from_secs(4 * 3600 + 30 * 60));
+        let at_run = zone.with_ymd_and_hms(2024, 11, 5, 3, 0, 0).unwrap();
+        assert_eq!(until_next_run(at_run), Duration::from_secs(24 * 3600));
+        assert_eq!(next_run_at(evening), zone.with_ymd_and_hms(2024, 11, 5, 3, 0, 0).unwrap());
+        assert!(is_night(at_run));
+        assert!(!is_night(evening));
+    }
+
+    #[test]
+    fn test_history_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config").join("self_test.jsonl");
+        assert!(load_history(&path).is_empty());
+        append_history(&path, &report(1, None, Some(9))).unwrap();
+        append_history(&path, &report(0, Some(88.5), Some(11))).unwrap();
+        let history = load_history(&path);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].summary(), "Health 100%, latency 11 ms, portal: Signed in, speed 88.5 Mbps");
+    }
+}
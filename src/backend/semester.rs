@@ -0,0 +1,102 @@
+// 学期日历：假期离校期间暂停自动登录，开学当天自动恢复
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// 一个学期的起止日期（YYYY-MM-DD，包含首尾两天）
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Term {
+    pub name: String,
+    pub start: String,
+    pub end: String,
+}
+
+impl Term {
+    /// 解析起止日期，格式错误或结束早于开始时返回错误
+    pub fn dates(&self) -> Result<(NaiveDate, NaiveDate)> {
+        let parse = |value: &str| NaiveDate::parse_from_str(value.trim(), DATE_FORMAT)
+            .map_err(|_| anyhow!("Invalid date \"{}\" in {}, expected YYYY-MM-DD", value, self.name));
+        let (start, end) = (parse(&self.start)?, parse(&self.end)?);
+        if end < start {
+            return Err(anyhow!("{} ends before it starts", self.name));
+        }
+        Ok((start, end))
+    }
+
+    fn contains(&self, date: NaiveDate) -> bool {
+        self.dates().is_ok_and(|(start, end)| start <= date && date <= end)
+    }
+}
+
+/// 某个学年（如 2025 表示 2025-2026 学年）的预设学期，日期为常见校历的大致时间，需按学校校历调整
+pub fn preset(year: i32) -> Vec<Term> {
+    vec![
+        Term {
+            name: format!("{} autumn term", year),
+            start: format!("{}-09-01", year),
+            end: format!("{}-01-15", year + 1),
+        },
+        Term {
+            name: format!("{} spring term", year + 1),
+            start: format!("{}-02-20", year + 1),
+            end: format!("{}-07-10", year + 1),
+        },
+    ]
+}
+
+/// 当前日期所在学年的预设学期，9 月前属于上一学年
+pub fn preset_for(today: NaiveDate) -> Vec<Term> {
+    let year = if today.month() >= 9 { today.year() } else { today.year() - 1 };
+    preset(year)
+}
+
+/// 是否处于假期：配置了有效学期且当天不在任何学期内；未配置学期时始终返回 false
+pub fn in_vacation(terms: &[Term], today: NaiveDate) -> bool {
+    let mut valid = terms.iter().filter(|term| term.dates().is_ok()).peekable();
+    valid.peek().is_some() && !valid.any(|term| term.contains(today))
+}
+
+/// 下一个学期的开始日期
+pub fn next_term_start(terms: &[Term], today: NaiveDate) -> Option<NaiveDate> {
+    terms.iter()
+        .filter_map(|term| term.dates().ok())
+        .map(|(start, _)| start)
+        .filter(|start| *start > today)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, DATE_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn test_vacation() {
+        let terms = preset(2025);
+        assert!(!in_vacation(&terms, date("2025-10-01")));
+        assert!(!in_vacation(&terms, date("2026-01-15")));
+        // 寒假（春节）期间
+        assert!(in_vacation(&terms, date("2026-02-01")));
+        assert_eq!(next_term_start(&terms, date("2026-02-01")), Some(date("2026-02-20")));
+        // 暑假之后没有配置下一学期
+        assert!(in_vacation(&terms, date("2026-08-01")));
+        assert_eq!(next_term_start(&terms, date("2026-08-01")), None);
+
+        // 未配置学期或学期日期无效时不视为假期
+        assert!(!in_vacation(&[], date("2026-02-01")));
+        let invalid = Term { name: "bad".to_string(), start: "2026-03-01".to_string(), end: "2026-02-01".to_string() };
+        assert!(invalid.dates().is_err());
+        assert!(!in_vacation(&[invalid], date("2026-02-15")));
+    }
+
+    #[test]
+    fn test_preset_for() {
+        assert_eq!(preset_for(date("2026-03-01"))[0].start, "2025-09-01");
+        assert_eq!(preset_for(date("2026-09-10"))[0].start, "2026-09-01");
+    }
+}
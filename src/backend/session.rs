@@ -0,0 +1,240 @@
+// 浏览器会话管理：自动登录在重试之间和预热模式下复用同一个 Chrome 和 ChromeDriver，每次使用前检查会话是否仍可用，
+// 浏览器崩溃、连续登录失败或运行时间过长时回收，下次使用时重新启动。窗口最小化较长时间后关闭空闲的预热浏览器，
+// 避免一整天在后台占用几百 MB 内存，需要登录时再启动
+use crate::backend::config::Config;
+use crate::backend::lockout;
+use crate::backend::portal::{self, BrowserPortal};
+use crate::backend::triage::{self, Failure};
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 同一个会话中连续失败此次数后回收，下次重试启动新的浏览器
+pub const MAX_SESSION_FAILURES: u32 = 2;
+/// 会话运行超过此时间后回收，避免浏览器长时间运行后占用过多内存
+pub const MAX_SESSION_AGE: Duration = Duration::from_secs(6 * 60 * 60);
+/// 窗口最小化超过此时间后关闭空闲的浏览器会话
+pub const BACKGROUND_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// 窗口是否已最小化足够久，可以关闭空闲的浏览器会话；minimized_since 为最小化的时间，未最小化时为 None
+pub fn idle_in_background(minimized_since: Option<Instant>, now: Instant) -> bool {
+    minimized_since.is_some_and(|since| now.saturating_duration_since(since) >= BACKGROUND_IDLE_TIMEOUT)
+}
+
+/// 保持一个可复用的浏览器会话
+#[derive(Default)]
+pub struct SessionManager {
+    auth: Option<Box<dyn BrowserPortal>>,
+    // 当前浏览器的启动时间
+    started: Option<Instant>,
+    // 当前会话中连续失败的登录次数
+    failures: u32,
+}
+
+impl SessionManager {
+    /// 是否有正在运行的会话
+    pub fn is_active(&self) -> bool {
+        self.started.is_some()
+    }
+
+    // 会话是否需要回收
+    fn should_recycle(&self, now: Instant) -> bool {
+        self.failures >= MAX_SESSION_FAILURES
+            || self.started.is_some_and(|started| now.saturating_duration_since(started) >= MAX_SESSION_AGE)
+    }
+
+    /// 取得可用的会话：按 config 更新凭据和浏览器设置，浏览器不再响应时重新启动并打开认证页面；门户不支持浏览器登录时返回错误
+    pub async fn session(&mut self, config: &Config) -> Result<&mut Box<dyn BrowserPortal>> {
+        if self.should_recycle(Instant::now()) {
+            info!("Recycling the browser session ({} failed login(s))", self.failures);
+            self.release().await;
+        }
+        let config = Arc::new(config.clone());
+        let auth = match self.auth.take() {
+            Some(auth) => auth,
+            None => portal::browser_provider(Arc::clone(&config))
+                .ok_or_else(|| anyhow!("The {} portal does not support browser login, switch the login method to HTTP portal", config.portal_type.label()))?,
+        };
+        let auth = self.auth.insert(auth);
+        auth.update_config(config).await?;
+        if !auth.is_alive().await {
+            if self.started.take().is_some() {
+                warn!("Browser session stopped responding, starting a new one");
+            }
+            auth.warm_up().await?;
+            self.started = Some(Instant::now());
+        }
+        Ok(auth)
+    }
+
+    // 换浏览器也无济于事的失败：密码错误或账号被锁定时再试一次只会多记一次错误
+    fn is_final(error: &anyhow::Error) -> bool {
+        triage::diagnose(error.as_ref()) == Some(Failure::WrongPassword) || lockout::is_lockout_message(&format!("{:#}", error))
+    }
+
+    /// 用会话登录；复用的会话第一次失败时立即换用新启动的浏览器再试一次，之后的失败留给下一次重试；
+    /// 密码错误或账号被锁定时不再重试
+    pub async fn login(&mut self, config: &Config) -> Result<()> {
+        let reused = self.is_active();
+        let result = match self.session(config).await {
+            Ok(auth) => auth.login_warm().await,
+            Err(e) => Err(e),
+        };
+        let result = match result {
+            Err(e) if reused && self.failures == 0 && !Self::is_final(&e) => {
+                warn!("Login with the running browser failed, starting a new browser: {}", e);
+                self.release().await;
+                match self.session(config).await {
+                    Ok(auth) => auth.login_warm().await,
+                    Err(e) => Err(e),
+                }
+            }
+            result => result,
+        };
+        match result {
+            Ok(()) => self.failures = 0,
+            Err(_) => self.failures += 1,
+        }
+        result
+    }
+
+    /// 关闭浏览器和 ChromeDriver，下次使用时重新启动
+    pub async fn release(&mut self) {
+        if let Some(mut auth) = self.auth.take() {
+            if let Err(e) = auth.quit().await {
+                warn!("Failed to close the browser session: {}", e);
+            }
+        }
+        self.started = None;
+        self.failures = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::auth::OnlineStatus;
+    use crate::backend::authentication::{PageDiagnostics, PortalFingerprint};
+    use crate::backend::maintenance::PortalState;
+    use crate::backend::portal::{PortalProvider, PortalType};
+    use futures_util::future::LocalBoxFuture;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    // 登录总是失败的浏览器会话，记录登录和关闭的次数
+    struct FailingPortal {
+        error: fn() -> anyhow::Error,
+        logins: Rc<Cell<u32>>,
+        quits: Rc<Cell<u32>>,
+    }
+
+    impl PortalProvider for FailingPortal {
+        fn portal_type(&self) -> PortalType {
+            PortalType::Drcom
+        }
+
+        fn login(&mut self) -> LocalBoxFuture<'_, Result<String>> {
+            Box::pin(async { Err(anyhow!("not used")) })
+        }
+
+        fn logout(&mut self) -> LocalBoxFuture<'_, Result<String>> {
+            Box::pin(async { Err(anyhow!("not used")) })
+        }
+
+        fn status(&self) -> LocalBoxFuture<'_, Result<OnlineStatus>> {
+            Box::pin(async { Err(anyhow!("not used")) })
+        }
+
+        fn portal_state(&self) -> LocalBoxFuture<'_, Result<PortalState>> {
+            Box::pin(async { Err(anyhow!("not used")) })
+        }
+
+        fn detect(_page: &str) -> bool {
+            false
+        }
+    }
+
+    impl BrowserPortal for FailingPortal {
+        fn open_auth_page(&mut self) -> LocalBoxFuture<'_, Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn warm_up(&mut self) -> LocalBoxFuture<'_, Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn is_alive(&self) -> LocalBoxFuture<'_, bool> {
+            Box::pin(async { true })
+        }
+
+        fn login_warm(&mut self) -> LocalBoxFuture<'_, Result<()>> {
+            self.logins.set(self.logins.get() + 1);
+            let error = (self.error)();
+            Box::pin(async move { Err(error) })
+        }
+
+        fn update_config(&mut self, _config: Arc<Config>) -> LocalBoxFuture<'_, Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn quit(&mut self) -> LocalBoxFuture<'_, Result<()>> {
+            self.quits.set(self.quits.get() + 1);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn last_diagnostics(&self) -> Option<&PageDiagnostics> {
+            None
+        }
+
+        fn last_fingerprint(&self) -> Option<&PortalFingerprint> {
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn test_final_failure_keeps_session() {
+        let errors: [fn() -> anyhow::Error; 2] = [
+            || anyhow::Error::new(Failure::WrongPassword),
+            || anyhow!("账号已被锁定，请10分钟后再试"),
+        ];
+        for error in errors {
+            let (logins, quits) = (Rc::new(Cell::new(0)), Rc::new(Cell::new(0)));
+            let mut sessions = SessionManager {
+                auth: Some(Box::new(FailingPortal { error, logins: Rc::clone(&logins), quits: Rc::clone(&quits) })),
+                started: Some(Instant::now()),
+                failures: 0,
+            };
+            // 不换新浏览器重试，原来的会话保留
+            assert!(sessions.login(&Config::default()).await.is_err());
+            assert_eq!((logins.get(), quits.get()), (1, 0));
+            assert!(sessions.is_active());
+            assert_eq!(sessions.failures, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_recycle() {
+        let mut sessions = SessionManager::default();
+        assert!(!sessions.is_active());
+        assert!(!sessions.should_recycle(Instant::now()));
+
+        let now = Instant::now();
+        sessions.started = Some(now);
+        assert!(sessions.is_active());
+        assert!(!sessions.should_recycle(now + Duration::from_secs(60)));
+        assert!(sessions.should_recycle(now + MAX_SESSION_AGE));
+
+        sessions.failures = MAX_SESSION_FAILURES;
+        assert!(sessions.should_recycle(now));
+
+        // 没有浏览器时关闭会话只清除状态
+        sessions.release().await;
+        assert!(!sessions.is_active());
+        assert!(!sessions.should_recycle(now + MAX_SESSION_AGE));
+
+        assert!(!idle_in_background(None, now));
+        assert!(!idle_in_background(Some(now), now + Duration::from_secs(60)));
+        assert!(idle_in_background(Some(now), now + BACKGROUND_IDLE_TIMEOUT));
+    }
+}
@@ -0,0 +1,186 @@
+// 延迟和丢包统计：保存每个探测目标最近若干次检查的结果，计算平均时延、抖动和丢包率；另外保存最近一小时的检查结果用于绘制曲线
+use crate::backend::probe::{Connectivity, ProbeOutcome};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// 未配置时计入统计的检查次数
+pub const DEFAULT_WINDOW: usize = 20;
+/// 配置值的范围
+pub const MIN_WINDOW: usize = 2;
+pub const MAX_WINDOW: usize = 1000;
+/// 历史曲线保存的时长
+pub const HISTORY_SPAN: Duration = Duration::from_secs(60 * 60);
+
+/// 一个探测目标的统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetStats {
+    pub target: String,
+    // 窗口内的检查次数
+    pub samples: usize,
+    // 成功探测的平均时延（毫秒），全部失败时为 None
+    pub avg_latency_ms: Option<f64>,
+    // 相邻两次成功探测时延之差的平均值（毫秒）
+    pub jitter_ms: Option<f64>,
+    // 失败探测所占比例（0-1）
+    pub loss_rate: f64,
+}
+
+/// 所有探测目标的统计，顺序与探测目标相同
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkStats {
+    pub targets: Vec<TargetStats>,
+}
+
+/// 记录每个目标最近的探测时延，失败记为 None
+pub struct StatsRecorder {
+    window: usize,
+    samples: Vec<(String, VecDeque<Option<u64>>)>,
+}
+
+impl StatsRecorder {
+    pub fn new(window: usize) -> Self {
+        Self { window: window.clamp(MIN_WINDOW, MAX_WINDOW), samples: Vec::new() }
+    }
+
+    /// 修改窗口大小，多出的旧样本被丢弃
+    pub fn set_window(&mut self, window: usize) {
+        self.window = window.clamp(MIN_WINDOW, MAX_WINDOW);
+        for (_, samples) in &mut self.samples {
+            while samples.len() > self.window {
+                samples.pop_front();
+            }
+        }
+    }
+
+    /// 记录一次检查的结果；不再探测的目标被移除
+    pub fn record(&mut self, outcomes: &[ProbeOutcome]) {
+        let mut previous = std::mem::take(&mut self.samples);
+        for outcome in outcomes {
+            let target = outcome.probe.describe();
+            let mut samples = previous.iter()
+                .position(|(name, _)| *name == target)
+                .map(|index| previous.swap_remove(index).1)
+                .unwrap_or_default();
+            samples.push_back(outcome.latency_ms);
+            while samples.len() > self.window {
+                samples.pop_front();
+            }
+            self.samples.push((target, samples));
+        }
+    }
+
+    pub fn snapshot(&self) -> NetworkStats {
+        NetworkStats {
+            targets: self.samples.iter().map(|(target, samples)| summarize(target, samples)).collect(),
+        }
+    }
+}
+
+/// 一次检查的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistorySample {
+    pub at: Instant,
+    // 最快的探测时延，所有目标都不可达时为 None
+    pub latency_ms: Option<u64>,
+    pub connectivity: Connectivity,
+}
+
+/// 最近一段时间（HISTORY_SPAN）的检查结果，按时间顺序
+#[derive(Default)]
+pub struct History {
+    samples: VecDeque<HistorySample>,
+}
+
+impl History {
+    pub fn push(&mut self, sample: HistorySample) {
+        while self.samples.front().is_some_and(|oldest| sample.at.duration_since(oldest.at) > HISTORY_SPAN) {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// 最近 span 时间内的检查结果
+    pub fn since(&self, span: Duration, now: Instant) -> Vec<HistorySample> {
+        self.samples.iter()
+            .filter(|sample| now.duration_since(sample.at) <= span)
+            .copied()
+            .collect()
+    }
+}
+
+fn summarize(target: &str, samples: &VecDeque<Option<u64>>) -> TargetStats {
+    let latencies: Vec<f64> = samples.iter().flatten().map(|ms| *ms as f64).collect();
+    let lost = samples.len() - latencies.len();
+    let avg_latency_ms = (!latencies.is_empty()).then(|| latencies.iter().sum::<f64>() / latencies.len() as f64);
+    let jitter_ms = (latencies.len() >= 2).then(|| {
+        latencies.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum::<f64>() / (latencies.len() - 1) as f64
+    });
+    TargetStats {
+        target: target.to_string(),
+        samples: samples.len(),
+        avg_latency_ms,
+        jitter_ms,
+        loss_rate: if samples.is_empty() { 0.0 } else { lost as f64 / samples.len() as f64 },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::probe::Probe;
+
+    fn outcome(host: &str, latency_ms: Option<u64>) -> ProbeOutcome {
+        ProbeOutcome {
+            probe: Probe::Icmp { host: host.to_string() },
+            latency_ms,
+            error: latency_ms.is_none().then(|| "timed out".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_history() {
+        let start = Instant::now();
+        let sample = |minutes: u64, latency_ms| HistorySample {
+            at: start + Duration::from_secs(minutes * 60),
+            latency_ms,
+            connectivity: if latency_ms.is_some() { Connectivity::Online } else { Connectivity::Offline },
+        };
+        let mut history = History::default();
+        for minutes in [0, 30, 50, 70] {
+            history.push(sample(minutes, Some(minutes)));
+        }
+        history.push(sample(80, None));
+
+        // 超过一小时的样本被丢弃
+        let now = start + Duration::from_secs(80 * 60);
+        assert_eq!(history.since(HISTORY_SPAN, now).len(), 4);
+        let recent = history.since(Duration::from_secs(15 * 60), now);
+        assert_eq!(recent, vec![sample(70, Some(70)), sample(80, None)]);
+    }
+
+    #[test]
+    fn test_stats_window() {
+        let mut recorder = StatsRecorder::new(3);
+        for latency in [Some(100), Some(10), None, Some(20)] {
+            recorder.record(&[outcome("223.5.5.5", latency), outcome("8.8.8.8", None)]);
+        }
+
+        // 窗口为 3，最早的 100ms 已被丢弃
+        let stats = recorder.snapshot();
+        assert_eq!(stats.targets[0].target, "ICMP 223.5.5.5");
+        assert_eq!(stats.targets[0].samples, 3);
+        assert_eq!(stats.targets[0].avg_latency_ms, Some(15.0));
+        assert_eq!(stats.targets[0].jitter_ms, Some(10.0));
+        assert!((stats.targets[0].loss_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.targets[1].avg_latency_ms, None);
+        assert_eq!(stats.targets[1].loss_rate, 1.0);
+
+        // 缩小窗口并移除不再探测的目标
+        recorder.set_window(2);
+        recorder.record(&[outcome("8.8.8.8", Some(30))]);
+        let stats = recorder.snapshot();
+        assert_eq!(stats.targets.len(), 1);
+        assert_eq!(stats.targets[0].samples, 2);
+        assert_eq!(stats.targets[0].loss_rate, 0.5);
+    }
+}
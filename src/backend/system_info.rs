@@ -0,0 +1,203 @@
+// 系统信息查询模块
+
+/// 物理内存低于该值（MB）时建议开启低资源模式
+pub const LOW_MEMORY_THRESHOLD_MB: u64 = 4096;
+
+/// 获取物理内存总量（MB）
+#[cfg(windows)]
+pub fn total_memory_mb() -> Option<u64> {
+    use winapi::um::sysinfoapi::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+    let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+    if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 {
+        return None;
+    }
+    Some(status.ullTotalPhys / 1024 / 1024)
+}
+
+/// 获取物理内存总量（MB）
+#[cfg(not(windows))]
+pub fn total_memory_mb() -> Option<u64> {
+    let content = std::fs::read_to_string("/proc/meminfo").ok()?;
+    parse_meminfo_total(&content)
+}
+
+// 从 /proc/meminfo 中解析 MemTotal（kB）
+#[cfg(any(not(windows), test))]
+fn parse_meminfo_total(content: &str) -> Option<u64> {
+    content.lines()
+        .find(|line| line.starts_with("MemTotal:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb / 1024)
+}
+
+/// 本机用于出站连接的 IPv4 地址（UDP connect 不会发出数据包）
+pub fn local_ipv4() -> Option<std::net::Ipv4Addr> {
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("223.5.5.5:53").ok()?;
+    match socket.local_addr().ok()?.ip() {
+        std::net::IpAddr::V4(ip) if !ip.is_unspecified() => Some(ip),
+        _ => None,
+    }
+}
+
+/// 当前连接的 Wi-Fi 名称（SSID），有线连接或无法查询时为 None
+#[cfg(any(windows, target_os = "macos"))]
+pub fn wifi_ssid() -> Option<String> {
+    let output = if cfg!(windows) {
+        std::process::Command::new("netsh").args(["wlan", "show", "interfaces"]).output()
+    } else {
+        std::process::Command::new("networksetup").args(["-getairportnetwork", "en0"]).output()
+    }.ok()?;
+    parse_ssid(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// 当前连接的 Wi-Fi 名称（SSID），有线连接或无法查询时为 None
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn wifi_ssid() -> Option<String> {
+    let output = std::process::Command::new("iwgetid").arg("-r").output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string()).filter(|ssid| !ssid.is_empty())
+}
+
+// 解析 netsh 的 "SSID : 名称" 行或 networksetup 的 "Current Wi-Fi Network: 名称"
+#[cfg(any(windows, target_os = "macos", test))]
+fn parse_ssid(output: &str) -> Option<String> {
+    output.lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            matches!(key.trim(), "SSID" | "Current Wi-Fi Network").then(|| value.trim())
+        })
+        .filter(|ssid| !ssid.is_empty())
+        .map(str::to_string)
+}
+
+/// 操作系统名称和版本，如 "Ubuntu 22.04.3 LTS"、"Microsoft Windows [Version 10.0.22631.4460]"，无法查询时为 None
+#[cfg(any(windows, target_os = "macos"))]
+pub fn os_version() -> Option<String> {
+    let output = if cfg!(windows) {
+        std::process::Command::new("cmd").args(["/C", "ver"]).output()
+    } else {
+        std::process::Command::new("sw_vers").arg("-productVersion").output()
+    }.ok()?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if version.is_empty() {
+        return None;
+    }
+    Some(if cfg!(windows) { version } else { format!("macOS {}", version) })
+}
+
+/// 操作系统名称和版本，如 "Ubuntu 22.04.3 LTS"、"Microsoft Windows [Version 10.0.22631.4460]"，无法查询时为 None
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn os_version() -> Option<String> {
+    parse_os_release(&std::fs::read_to_string("/etc/os-release").ok()?)
+}
+
+// 从 /etc/os-release 中解析 PRETTY_NAME
+#[cfg(any(not(any(windows, target_os = "macos")), test))]
+fn parse_os_release(content: &str) -> Option<String> {
+    content.lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim().trim_matches('"').to_string())
+        .filter(|name| !name.is_empty())
+}
+/// 在本机 TCP 端口上监听的进程，如 "chromedriver.exe (PID 1234)"，查不到时为 None
+#[cfg(all(windows, feature = "webdriver"))]
+pub fn port_owner(port: u16) -> Option<String> {
+    let output = std::process::Command::new("netstat").args(["-ano", "-p", "TCP"]).output().ok()?;
+    let pid = parse_netstat_pid(&String::from_utf8_lossy(&output.stdout), port)?;
+    let output = std::process::Command::new("tasklist").args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"]).output().ok()?;
+    // 输出形如 "chromedriver.exe","1234",...，没有该进程时为一行提示文字
+    let name = String::from_utf8_lossy(&output.stdout).lines().next()?
+        .strip_prefix('"')
+        .and_then(|line| line.split_once('"'))
+        .map(|(name, _)| name.to_string());
+    Some(match name {
+        Some(name) => format!("{} (PID {})", name, pid),
+        None => format!("PID {}", pid),
+    })
+}
+
+/// 在本机 TCP 端口上监听的进程，如 "chromedriver (PID 1234)"，查不到时为 None
+#[cfg(all(not(windows), feature = "webdriver"))]
+pub fn port_owner(port: u16) -> Option<String> {
+    let output = std::process::Command::new("lsof").args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-Fpc"]).output().ok()?;
+    parse_lsof_owner(&String::from_utf8_lossy(&output.stdout))
+}
+
+// 从 netstat -ano 的输出中找出监听该端口的 PID；状态名随系统语言变化，按远端端口为 0 判断是否在监听
+#[cfg(any(all(windows, feature = "webdriver"), test))]
+fn parse_netstat_pid(output: &str, port: u16) -> Option<u32> {
+    let local_suffix = format!(":{}", port);
+    output.lines().find_map(|line| {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        match columns.as_slice() {
+            ["TCP", local, remote, _, pid] if local.ends_with(&local_suffix) && remote.ends_with(":0") => pid.parse().ok(),
+            _ => None,
+        }
+    })
+}
+
+// 解析 lsof -Fpc 的输出：p 开头的行为 PID，c 开头的行为进程名
+#[cfg(any(all(not(windows), feature = "webdriver"), test))]
+fn parse_lsof_owner(output: &str) -> Option<String> {
+    let pid = output.lines().find_map(|line| line.strip_prefix('p'))?;
+    Some(match output.lines().find_map(|line| line.strip_prefix('c')) {
+        Some(name) => format!("{} (PID {})", name, pid),
+        None => format!("PID {}", pid),
+    })
+}
+
+/// 当前机器内存是否低于阈值
+pub fn is_low_memory() -> bool {
+    total_memory_mb()
+        .map(|mb| mb < LOW_MEMORY_THRESHOLD_MB)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_meminfo_total() {
+        let content = "MemTotal:        3903204 kB\nMemFree:          512000 kB\n";
+        assert_eq!(parse_meminfo_total(content), Some(3811));
+        assert_eq!(parse_meminfo_total("MemFree: 1 kB"), None);
+    }
+
+    #[test]
+    fn test_parse_ssid() {
+        let netsh = "    Name                   : WLAN\n    State                  : connected\n    SSID                   : CSU-WiFi\n    BSSID                  : 00:11:22:33:44:55\n";
+        assert_eq!(parse_ssid(netsh).as_deref(), Some("CSU-WiFi"));
+        assert_eq!(parse_ssid("Current Wi-Fi Network: CSU-Student\n").as_deref(), Some("CSU-Student"));
+        // 未连接 Wi-Fi
+        assert_eq!(parse_ssid("    State                  : disconnected\n"), None);
+        assert_eq!(parse_ssid("You are not associated with an AirPort network.\n"), None);
+    }
+
+    #[test]
+    fn test_parse_os_release() {
+        let content = "NAME=\"Ubuntu\"\nPRETTY_NAME=\"Ubuntu 22.04.3 LTS\"\nVERSION_ID=\"22.04\"\n";
+        assert_eq!(parse_os_release(content).as_deref(), Some("Ubuntu 22.04.3 LTS"));
+        assert_eq!(parse_os_release("NAME=Arch\n"), None);
+    }
+
+    #[test]
+    fn test_parse_port_owner() {
+        let netstat = "\nActive Connections\n\n  Proto  Local Address          Foreign Address        State           PID\n  TCP    127.0.0.1:9515         127.0.0.1:51234        ESTABLISHED     4321\n  TCP    127.0.0.1:9515         0.0.0.0:0              LISTENING       1234\n  TCP    0.0.0.0:19515          0.0.0.0:0              LISTENING       999\n";
+        assert_eq!(parse_netstat_pid(netstat, 9515), Some(1234));
+        assert_eq!(parse_netstat_pid(netstat, 9516), None);
+        assert_eq!(parse_lsof_owner("p1234\ncchromedriver\nf5\n").as_deref(), Some("chromedriver (PID 1234)"));
+        assert_eq!(parse_lsof_owner(""), None);
+    }
+
+    #[test]
+    fn test_total_memory() {
+        // 实际内存大小取决于运行环境，只检查结果合理
+        if let Some(mb) = total_memory_mb() {
+            assert!(mb > 0);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// 时区处理模块
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use chrono_tz::Tz;
+use parking_lot::RwLock;
+
+// 配置的时区，未设置时使用系统本地时区
+static ZONE: RwLock<Option<Tz>> = parking_lot::const_rwlock(None);
+
+/// 解析 IANA 时区名（如 Asia/Shanghai），空字符串表示系统本地时区
+pub fn parse(name: &str) -> Result<Option<Tz>> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Ok(None);
+    }
+    name.parse::<Tz>()
+        .map(Some)
+        .map_err(|_| anyhow!("Unknown time zone: {}", name))
+}
+
+/// 设置程序使用的时区
+pub fn set(zone: Option<Tz>) {
+    *ZONE.write() = zone;
+}
+
+/// 当前时区名称，用于界面显示
+pub fn name() -> String {
+    match *ZONE.read() {
+        Some(tz) => tz.name().to_string(),
+        None => "System local".to_string(),
+    }
+}
+
+/// 按配置的时区获取当前时间，偏移量随夏令时自动变化
+pub fn now() -> DateTime<FixedOffset> {
+    now_in(*ZONE.read())
+}
+
+/// 把 Unix 时间戳转换为配置的时区的时间
+pub fn at(timestamp: i64) -> DateTime<FixedOffset> {
+    at_in(*ZONE.read(), timestamp)
+}
+
+fn at_in(zone: Option<Tz>, timestamp: i64) -> DateTime<FixedOffset> {
+    let utc = DateTime::<Utc>::from_timestamp(timestamp, 0).unwrap_or_default();
+    match zone {
+        Some(tz) => utc.with_timezone(&tz).fixed_offset(),
+        None => utc.with_timezone(&Local).fixed_offset(),
+    }
+}
+
+fn now_in(zone: Option<Tz>) -> DateTime<FixedOffset> {
+    match zone {
+        Some(tz) => Utc::now().with_timezone(&tz).fixed_offset(),
+        None => Local::now().fixed_offset(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("").unwrap(), None);
+        assert_eq!(parse(" Asia/Shanghai ").unwrap(), Some(chrono_tz::Asia::Shanghai));
+        assert!(parse("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn test_at_in_zone() {
+        let time = at_in(Some(chrono_tz::Asia::Shanghai), 1_730_000_000);
+        assert_eq!(time.format("%Y-%m-%d %H:%M:%S").to_string(), "2024-10-27 11:33:20");
+    }
+
+    #[test]
+    fn test_now_in_zone() {
+        let now = now_in(Some(chrono_tz::Asia::Shanghai));
+        assert_eq!(now.offset().local_minus_utc(), 8 * 3600);
+    }
+}
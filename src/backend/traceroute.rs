@@ -0,0 +1,199 @@
+// 路由跟踪模块：并行跟踪多个目标，找出数据包在哪一跳中断
+use anyhow::{anyhow, Result};
+use futures_util::future::join_all;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// 最大跳数
+pub const MAX_HOPS: u8 = 20;
+// 时延的单位：traceroute 和英文 tracert 为 ms，中文 tracert 为毫秒
+const RTT_UNITS: [&str; 2] = ["ms", "毫秒"];
+
+/// 路由中的一跳
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hop {
+    pub ttl: u8,
+    pub addr: Option<IpAddr>,
+    pub rtt: Option<Duration>,
+}
+
+/// 一个目标的跟踪结果
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub target: String,
+    pub target_addr: IpAddr,
+    pub hops: Vec<Hop>,
+}
+
+impl Trace {
+    /// 是否到达目标
+    pub fn reached(&self) -> bool {
+        self.hops.iter().any(|hop| hop.addr == Some(self.target_addr))
+    }
+
+    // 最后一个有响应的跳
+    fn last_responding(&self) -> Option<&Hop> {
+        self.hops.iter().rev().find(|hop| hop.addr.is_some())
+    }
+
+    /// 数据包中断位置的简短判断
+    pub fn verdict(&self) -> String {
+        if self.reached() {
+            return format!("{} reached in {} hops", self.target, self.hops.len());
+        }
+        match self.last_responding() {
+            None => format!("{}: no reply from the first hop, check the cable, Wi-Fi or dorm switch", self.target),
+            Some(hop) if hop.ttl == 1 => format!("{}: packets stop after the dorm gateway {}", self.target, describe_addr(hop)),
+            Some(hop) if is_private(hop.addr) => format!("{}: packets stop inside the campus network after hop {} ({})", self.target, hop.ttl, describe_addr(hop)),
+            Some(hop) => format!("{}: packets stop in the ISP or external network after hop {} ({})", self.target, hop.ttl, describe_addr(hop)),
+        }
+    }
+
+    /// 逐跳的详细结果
+    pub fn report(&self) -> String {
+        let mut lines = vec![format!("Traceroute to {} ({})", self.target, self.target_addr)];
+        for hop in &self.hops {
+            let rtt = hop.rtt
+                .map(|rtt| format!("{} ms", rtt.as_millis()))
+                .unwrap_or_else(|| "*".to_string());
+            lines.push(format!("{:>2}  {:<15}  {}", hop.ttl, describe_addr(hop), rtt));
+        }
+        lines.join("\n")
+    }
+}
+
+fn describe_addr(hop: &Hop) -> String {
+    hop.addr.map(|addr| addr.to_string()).unwrap_or_else(|| "*".to_string())
+}
+
+// 私有地址和运营商级 NAT 地址视为校园网内部
+fn is_private(addr: Option<IpAddr>) -> bool {
+    match addr {
+        Some(IpAddr::V4(ip)) => ip.is_private() || ip.is_loopback() || (ip.octets()[0] == 100 && (64..128).contains(&ip.octets()[1])),
+        Some(IpAddr::V6(ip)) => (ip.segments()[0] & 0xfe00) == 0xfc00,
+        None => false,
+    }
+}
+
+/// 解析 tracert/traceroute 的输出，跳过标题等非跳数行
+pub fn parse_output(text: &str) -> Vec<Hop> {
+    text.lines()
+        .filter_map(|line| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let ttl = tokens.first()?.parse::<u8>().ok()?;
+            let addr = tokens.iter()
+                .find_map(|token| token.trim_matches(|c| c == '(' || c == ')' || c == '[' || c == ']').parse::<IpAddr>().ok());
+            // 时延后跟 ms（中文 Windows 为“毫秒”），Windows 下小于 1 毫秒显示为 <1，按 1 毫秒计
+            let rtt = tokens.windows(2)
+                .find(|pair| RTT_UNITS.contains(&pair[1]))
+                .and_then(|pair| pair[0].trim_start_matches('<').parse::<f64>().ok())
+                .map(|ms| Duration::from_micros((ms * 1000.0) as u64));
+            Some(Hop { ttl, addr, rtt })
+        })
+        .collect()
+}
+
+// 解析目标主机地址
+async fn resolve(host: &str) -> Result<IpAddr> {
+    if let Ok(addr) = host.parse::<IpAddr>() {
+        return Ok(addr);
+    }
+    tokio::net::lookup_host((host, 0))
+        .await?
+        .next()
+        .map(|addr| addr.ip())
+        .ok_or_else(|| anyhow!("Cannot resolve {}", host))
+}
+
+/// 跟踪到指定主机的路由
+pub async fn trace(host: &str) -> Result<Trace> {
+    let target_addr = resolve(host).await?;
+    let max_hops = MAX_HOPS.to_string();
+
+    // tracert 按控制台代码页输出（中文 Windows 为 GBK），先切换到 UTF-8 代码页再运行，输出才能按 UTF-8 解析
+    #[cfg(windows)]
+    let output = Command::new("cmd")
+        .args(["/d", "/c", &format!("chcp 65001 >nul && tracert -d -h {} -w 1000 {}", max_hops, target_addr)])
+        .output()
+        .await?;
+    #[cfg(not(windows))]
+    let output = Command::new("traceroute")
+        .args(["-n", "-q", "1", "-w", "1", "-m", &max_hops, &target_addr.to_string()])
+        .output()
+        .await?;
+
+    let hops = parse_output(&String::from_utf8_lossy(&output.stdout));
+    if hops.is_empty() {
+        return Err(anyhow!("Traceroute to {} failed: {}", host, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    Ok(Trace { target: host.to_string(), target_addr, hops })
+}
+
+/// 并行跟踪多个目标
+pub async fn trace_all(hosts: &[String]) -> Vec<Result<Trace>> {
+    join_all(hosts.iter().map(|host| trace(host))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tracert_output() {
+        let output = "\
+Tracing route to 223.5.5.5 over a maximum of 20 hops
+
+  1    <1 ms    <1 ms    <1 ms  10.96.0.1
+  2     3 ms     2 ms     2 ms  172.16.1.1
+  3     *        *        *     Request timed out.
+
+Trace complete.";
+        let hops = parse_output(output);
+        assert_eq!(hops.len(), 3);
+        assert_eq!(hops[0].addr, Some("10.96.0.1".parse().unwrap()));
+        assert_eq!(hops[0].rtt, Some(Duration::from_millis(1)));
+        assert_eq!(hops[1].rtt, Some(Duration::from_millis(3)));
+        assert_eq!(hops[2], Hop { ttl: 3, addr: None, rtt: None });
+
+        let trace = Trace { target: "223.5.5.5".to_string(), target_addr: "223.5.5.5".parse().unwrap(), hops };
+        assert!(!trace.reached());
+        assert!(trace.verdict().contains("inside the campus network after hop 2"));
+
+        // 中文 Windows 的 tracert，时延单位为“毫秒”
+        let output = "\
+通过最多 20 个跃点跟踪到 223.5.5.5 的路由
+
+  1    <1 毫秒   <1 毫秒   <1 毫秒 10.96.0.1
+  2     4 毫秒     3 毫秒     3 毫秒 172.16.1.1
+  3     *        *        *     请求超时。
+  4    12 毫秒    11 毫秒    11 毫秒 223.5.5.5
+
+跟踪完成。";
+        let hops = parse_output(output);
+        assert_eq!(hops.len(), 4);
+        assert_eq!(hops[0], Hop { ttl: 1, addr: Some("10.96.0.1".parse().unwrap()), rtt: Some(Duration::from_millis(1)) });
+        assert_eq!(hops[1].rtt, Some(Duration::from_millis(4)));
+        assert_eq!(hops[2], Hop { ttl: 3, addr: None, rtt: None });
+        assert_eq!(hops[3].rtt, Some(Duration::from_millis(12)));
+        let trace = Trace { target: "dns".to_string(), target_addr: "223.5.5.5".parse().unwrap(), hops };
+        assert!(trace.reached());
+    }
+
+    #[test]
+    fn test_parse_traceroute_output() {
+        let output = "\
+traceroute to 223.5.5.5 (223.5.5.5), 20 hops max, 60 byte packets
+ 1  10.96.0.1  0.512 ms
+ 2  *
+ 3  223.5.5.5  12.3 ms";
+        let hops = parse_output(output);
+        assert_eq!(hops.len(), 3);
+        assert_eq!(hops[1].addr, None);
+
+        let trace = Trace { target: "dns".to_string(), target_addr: "223.5.5.5".parse().unwrap(), hops };
+        assert!(trace.reached());
+        assert!(trace.report().contains(" 3  223.5.5.5        12 ms"));
+    }
+}
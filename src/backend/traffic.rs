@@ -0,0 +1,205 @@
+// 流量快照与昼夜流量统计：每次查询到本月已用流量时追加一条快照到 config/traffic.jsonl。相邻两次快照之间用掉的流量
+// 按这段时间中在线的部分（由会话历史中的登录、登出推断）与夜间免费时段重合的比例分到夜间和白天，估算本月夜间和
+// 白天各用了多少流量，帮助把大文件下载安排到免费时段
+use crate::backend::auth::format_size;
+use crate::backend::history::{Action, HistoryEntry};
+use crate::backend::login_schedule::TimeWindow;
+use crate::backend::{paths, timezone};
+use anyhow::Result;
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Offset, TimeZone};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 设置了免费时段且在线时，定期查询用量留下快照的间隔
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(30 * 60);
+// 快照保留的时长（秒），统计只用本月的快照，多留一个月用来找月初前的最后一次
+const MAX_AGE_SECS: i64 = 62 * 24 * 60 * 60;
+// 多个线程同时记录时逐行写入
+static WRITE_LOCK: Mutex<()> = parking_lot::const_mutex(());
+
+/// 一次查询到的本月已用流量
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuotaSnapshot {
+    // 查询时间（Unix 时间戳，秒）
+    pub timestamp: i64,
+    pub used_bytes: u64,
+}
+
+/// 快照文件的默认位置
+pub fn default_path() -> PathBuf {
+    paths::config_dir().join("traffic.jsonl")
+}
+
+/// 追加一条快照，同时删除超过保留时长的快照
+pub fn append(path: &Path, snapshot: QuotaSnapshot) -> Result<()> {
+    let _guard = WRITE_LOCK.lock();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let snapshots = load(path);
+    if snapshots.first().is_some_and(|first| snapshot.timestamp - first.timestamp > MAX_AGE_SECS) {
+        let mut content = String::new();
+        for kept in snapshots.iter().filter(|kept| snapshot.timestamp - kept.timestamp <= MAX_AGE_SECS) {
+            content.push_str(&serde_json::to_string(kept)?);
+            content.push('\n');
+        }
+        let temp = path.with_extension("jsonl.tmp");
+        fs::write(&temp, content)?;
+        fs::rename(&temp, path)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(&snapshot)?)?;
+    Ok(())
+}
+
+/// 读取全部快照，跳过无法解析的行；文件不存在时为空
+pub fn load(path: &Path) -> Vec<QuotaSnapshot> {
+    fs::read_to_string(path)
+        .map(|content| content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+        .unwrap_or_default()
+}
+
+/// 本月夜间免费时段和白天的用量
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrafficSplit {
+    pub night_bytes: u64,
+    pub day_bytes: u64,
+}
+
+impl TrafficSplit {
+    /// 夜间用量所占的百分比，还没有用量时为 None
+    pub fn night_percent(&self) -> Option<u64> {
+        (self.night_bytes * 100).checked_div(self.night_bytes + self.day_bytes)
+    }
+
+    /// 用量卡片中每行的名称和数值
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let Some(night_percent) = self.night_percent() else { return Vec::new() };
+        vec![
+            ("Free night period", format!("{} ({}%)", format_size(self.night_bytes), night_percent)),
+            ("Billed daytime", format!("{} ({}%)", format_size(self.day_bytes), 100 - night_percent)),
+        ]
+    }
+}
+
+// from 到 to 之间在线的时间段：from 时在线（刚查询到用量），会话历史中成功登出后离线，成功登录后重新在线
+fn online_spans(history: &[HistoryEntry], from: i64, to: i64) -> Vec<(i64, i64)> {
+    let mut spans = Vec::new();
+    let mut online_since = Some(from);
+    for entry in history.iter().filter(|entry| entry.success && from < entry.timestamp && entry.timestamp < to) {
+        match (entry.action, online_since) {
+            (Action::Logout, Some(since)) => {
+                spans.push((since, entry.timestamp));
+                online_since = None;
+            }
+            (Action::Login, None) => online_since = Some(entry.timestamp),
+            _ => {}
+        }
+    }
+    if let Some(since) = online_since {
+        spans.push((since, to));
+    }
+    spans
+}
+
+/// 统计 since 之后的快照：两次快照之间用掉的流量按在线时间中夜间所占的比例分配；已用流量变小说明进入了新的计费月，
+/// 后一次的用量全部算作这段时间用掉的。since 之后第一次快照之前的用量无法区分时段，不计入
+pub fn split(snapshots: &[QuotaSnapshot], history: &[HistoryEntry], window: &TimeWindow, since: i64) -> TrafficSplit {
+    split_with(snapshots, history, window, since, |timestamp| timezone::at(timestamp).naive_local())
+}
+
+fn split_with(
+    snapshots: &[QuotaSnapshot],
+    history: &[HistoryEntry],
+    window: &TimeWindow,
+    since: i64,
+    local: impl Fn(i64) -> NaiveDateTime,
+) -> TrafficSplit {
+    let mut result = TrafficSplit::default();
+    for pair in snapshots.windows(2) {
+        let (before, after) = (pair[0], pair[1]);
+        if after.timestamp < since || after.timestamp <= before.timestamp {
+            continue;
+        }
+        let used = after.used_bytes.checked_sub(before.used_bytes).unwrap_or(after.used_bytes);
+        if used == 0 {
+            continue;
+        }
+        let mut spans = online_spans(history, before.timestamp, after.timestamp);
+        // 有用量却没有在线时间时，会话历史不完整，按整段时间计算
+        if spans.iter().all(|(from, to)| from >= to) {
+            spans = vec![(before.timestamp, after.timestamp)];
+        }
+        let online: i64 = spans.iter().map(|(from, to)| to - from).sum();
+        let night: i64 = spans.iter().map(|(from, to)| window.overlap(local(*from), local(*to))).sum();
+        let night_bytes = (used as f64 * night as f64 / online as f64).round() as u64;
+        result.night_bytes += night_bytes;
+        result.day_bytes += used - night_bytes;
+    }
+    result
+}
+
+/// 本月（按配置的时区）开始的时间戳
+pub fn month_start() -> i64 {
+    let now = timezone::now();
+    let first = now.date_naive().with_day(1).unwrap_or(now.date_naive()).and_time(NaiveTime::MIN);
+    now.offset().fix().from_local_datetime(&first).single().map_or(now.timestamp(), |start| start.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::history::Trigger;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    fn utc(value: &str) -> i64 {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap().and_utc().timestamp()
+    }
+
+    #[test]
+    fn test_split() {
+        let window = TimeWindow::parse("23:00", "07:00").unwrap().unwrap();
+
+        let snapshot = |at: &str, gb: u64| QuotaSnapshot { timestamp: utc(at), used_bytes: gb * GB };
+        let local = |timestamp: i64| chrono::DateTime::from_timestamp(timestamp, 0).unwrap().naive_utc();
+        let snapshots = [
+            snapshot("2024-10-31 22:00", 30),
+            // 跨月重置：11 月第一次查询时用了 1 GB，其中 22:00–23:00 为白天，23:00–01:00 为夜间
+            snapshot("2024-11-01 01:00", 1),
+            // 01:00–09:00 中 8 GB：在线的 01:00–03:00 和 07:00–09:00 各占一半
+            snapshot("2024-11-01 09:00", 9),
+        ];
+        let login = |at: &str, action: Action| HistoryEntry {
+            timestamp: utc(at),
+            action,
+            trigger: Trigger::Schedule,
+            method: "HTTP".to_string(),
+            success: true,
+            error: None,
+            latency_ms: 100,
+        };
+        let history = [login("2024-11-01 03:00", Action::Logout), login("2024-11-01 07:00", Action::Login)];
+        let split = split_with(&snapshots, &history, &window, utc("2024-11-01 00:00"), local);
+        assert_eq!(split.night_bytes, 4 * GB + (GB as f64 * 2.0 / 3.0).round() as u64);
+        assert_eq!(split.night_bytes + split.day_bytes, 9 * GB);
+        assert_eq!(split.night_percent(), Some(51));
+        assert_eq!(split.rows()[0], ("Free night period", "4.67 GB (51%)".to_string()));
+
+        // 快照追加到文件并读回
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("traffic.jsonl");
+        for snapshot in snapshots {
+            append(&path, snapshot).unwrap();
+        }
+        assert_eq!(load(&path), snapshots);
+        // 超过保留时长的快照在下次追加时删除
+        let later = QuotaSnapshot { timestamp: snapshots[1].timestamp + MAX_AGE_SECS, used_bytes: 0 };
+        append(&path, later).unwrap();
+        assert_eq!(load(&path), vec![snapshots[1], snapshots[2], later]);
+    }
+}
@@ -0,0 +1,202 @@
+// 常见故障分类：后台对能识别的故障返回带类型的错误，界面据此显示处理建议，不依赖错误文本
+use std::error::Error;
+use std::io::ErrorKind;
+
+/// 能给出处理建议的常见故障，界面和自动登录按类型处理，不匹配错误文本
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Failure {
+    // 程序目录下没有 ChromeDriver
+    #[cfg(feature = "webdriver")]
+    #[error("ChromeDriver not found at: {0}")]
+    ChromeDriverMissing(String),
+    // 找不到 Chrome
+    #[cfg(feature = "webdriver")]
+    #[error("Chrome browser not found. Please install Chrome or specify its location.")]
+    ChromeMissing,
+    // 选择 Edge 时程序目录下没有 msedgedriver
+    #[cfg(feature = "webdriver")]
+    #[error("msedgedriver not found at: {0}")]
+    EdgeDriverMissing(String),
+    // 选择 Edge 但没有安装 Edge
+    #[cfg(any(feature = "webdriver", feature = "downloader"))]
+    #[error("Microsoft Edge not found. Please install Edge or switch the browser to Chrome.")]
+    EdgeMissing,
+    // ChromeDriver 的端口被其他程序占用，owner 为占用端口的进程，查不到时为 "another program"
+    #[cfg(feature = "webdriver")]
+    #[error("Port {port} is used by {owner}, ChromeDriver cannot start")]
+    DriverPortBusy { port: u16, owner: String },
+    // 连不上门户（网线未接、没有连上校园网等）
+    #[error("Portal unreachable: {0}")]
+    PortalUnreachable(String),
+    // 门户提示账号或密码错误
+    #[error("Wrong username or password")]
+    WrongPassword,
+    // 没有发送 ICMP 的权限
+    #[error("No permission to send ICMP packets")]
+    IcmpPermissionDenied,
+    // 下载的压缩包与程序中固定的 SHA-256 不一致
+    #[cfg(feature = "downloader")]
+    #[error("{0} does not match the pinned SHA-256 checksum")]
+    ChecksumMismatch(String),
+    // 压缩包没有固定的 SHA-256（不是程序固定版本的 Chrome 和 ChromeDriver），不安装未经校验的文件
+    #[cfg(feature = "downloader")]
+    #[error("{0} has no pinned SHA-256 checksum and cannot be verified")]
+    ChecksumUnpinned(String),
+    // 所有下载地址都失败
+    #[cfg(feature = "downloader")]
+    #[error("Failed to download {file}: {reason}")]
+    DownloadFailed { file: String, reason: String },
+    // 没有写入程序目录的权限
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+}
+
+/// 提示卡片上的快捷操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintAction {
+    // 下载当前所选浏览器需要的组件
+    InstallChrome,
+    // 改用 HTTP 认证，不再依赖 Chrome
+    #[cfg(any(feature = "webdriver", feature = "downloader"))]
+    UseHttpLogin,
+}
+
+/// 提示卡片内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    pub title: &'static str,
+    pub advice: &'static str,
+    pub action: Option<(HintAction, &'static str)>,
+}
+
+impl Failure {
+    /// 对应的处理建议
+    pub fn hint(&self) -> Hint {
+        match self {
+            #[cfg(feature = "webdriver")]
+            Failure::ChromeDriverMissing(_) | Failure::ChromeMissing => Hint {
+                title: "Chrome is not installed",
+                advice: "Click Install Chrome to download Chrome and ChromeDriver next to the program, or switch to HTTP login.",
+                action: Some((HintAction::InstallChrome, "🔧 Install Chrome")),
+            },
+            #[cfg(feature = "webdriver")]
+            Failure::EdgeDriverMissing(_) => Hint {
+                title: "msedgedriver is not installed",
+                advice: "Click Install msedgedriver to download the driver matching the installed Edge, or switch to HTTP login.",
+                action: Some((HintAction::InstallChrome, "🔧 Install msedgedriver")),
+            },
+            #[cfg(any(feature = "webdriver", feature = "downloader"))]
+            Failure::EdgeMissing => Hint {
+                title: "Microsoft Edge is not installed",
+                advice: "Install Microsoft Edge, or switch the login browser back to Chrome in the settings.",
+                action: Some((HintAction::UseHttpLogin, "Use HTTP login")),
+            },
+            #[cfg(feature = "webdriver")]
+            Failure::DriverPortBusy { .. } => Hint {
+                title: "ChromeDriver port is busy",
+                advice: "Another program (often a leftover chromedriver.exe) is using the port. Close it in Task Manager, or switch to HTTP login.",
+                action: Some((HintAction::UseHttpLogin, "Use HTTP login")),
+            },
+            Failure::PortalUnreachable(_) => Hint {
+                title: "Cannot reach the campus portal",
+                advice: "Check the cable or make sure Wi-Fi is connected to the campus network, then try again.",
+                action: None,
+            },
+            Failure::WrongPassword => Hint {
+                title: "Wrong username or password",
+                advice: "Check the username, password and ISP in the settings. Repeated failures may lock the account for a while.",
+                action: None,
+            },
+            Failure::IcmpPermissionDenied => Hint {
+                title: "Ping is not permitted",
+                advice: "Run the program as administrator, or replace the ICMP probes with TCP or HTTP probes in the settings.",
+                action: None,
+            },
+            #[cfg(feature = "downloader")]
+            Failure::DownloadFailed { .. } => Hint {
+                title: "Download failed",
+                advice: "Check the internet connection, or add a download mirror such as npmmirror in the settings, then try again.",
+                action: Some((HintAction::InstallChrome, "🔧 Try again")),
+            },
+            #[cfg(feature = "downloader")]
+            Failure::ChecksumMismatch(_) => Hint {
+                title: "Downloaded file is corrupted",
+                advice: "The archive was damaged in transit or altered by a mirror and was not installed. Try again, or remove the mirror in the settings.",
+                action: Some((HintAction::InstallChrome, "🔧 Download again")),
+            },
+            #[cfg(feature = "downloader")]
+            Failure::ChecksumUnpinned(_) => Hint {
+                title: "Download cannot be verified",
+                advice: "Only the Chrome version bundled with the program and its ChromeDriver can be installed automatically. Put a ChromeDriver matching your Chrome next to the program, or switch to HTTP login.",
+                action: Some((HintAction::UseHttpLogin, "Use HTTP login")),
+            },
+            Failure::PermissionDenied(_) => Hint {
+                title: "No permission to write files",
+                advice: "Run the program as administrator, or move it to a folder you can write to, such as your Documents.",
+                action: None,
+            },
+        }
+    }
+}
+
+/// 沿错误链查找能识别的故障
+pub fn diagnose(error: &(dyn Error + 'static)) -> Option<Failure> {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(failure) = error.downcast_ref::<Failure>() {
+            return Some(failure.clone());
+        }
+        current = error.source();
+    }
+    None
+}
+
+/// 包装写文件的错误：没有权限（程序放在 Program Files 等目录下）时转换为 Failure::PermissionDenied，
+/// 其他操作的 PermissionDenied 不经过这里，不会被当成写入失败
+pub fn write_error(error: std::io::Error) -> anyhow::Error {
+    if error.kind() == ErrorKind::PermissionDenied {
+        Failure::PermissionDenied(error.to_string()).into()
+    } else {
+        error.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_diagnose() {
+        let error: Box<dyn Error> = Box::new(Failure::WrongPassword);
+        assert_eq!(diagnose(error.as_ref()), Some(Failure::WrongPassword));
+
+        // 包装了上下文的错误也能识别
+        #[cfg(feature = "webdriver")]
+        {
+            let busy = Failure::DriverPortBusy { port: 9515, owner: "chromedriver.exe (PID 1234)".to_string() };
+            assert_eq!(busy.to_string(), "Port 9515 is used by chromedriver.exe (PID 1234), ChromeDriver cannot start");
+            let error = Err::<(), _>(busy.clone()).context("Failed to start ChromeDriver").unwrap_err();
+            assert_eq!(diagnose(error.as_ref()), Some(busy.clone()));
+            assert_eq!(busy.hint().action.map(|(action, _)| action), Some(HintAction::UseHttpLogin));
+        }
+        let error = Err::<(), _>(Failure::WrongPassword).context("Login failed").unwrap_err();
+        assert_eq!(diagnose(error.as_ref()), Some(Failure::WrongPassword));
+
+        // 只有写文件的错误才提示没有写入权限
+        let error = Err::<(), _>(write_error(std::io::Error::from(ErrorKind::PermissionDenied))).context("创建文件失败").unwrap_err();
+        assert!(matches!(diagnose(error.as_ref()), Some(Failure::PermissionDenied(_))));
+        let error = Err::<(), _>(std::io::Error::from(ErrorKind::PermissionDenied)).context("读取日志失败").unwrap_err();
+        assert_eq!(diagnose(error.as_ref()), None);
+        let error = write_error(std::io::Error::from(ErrorKind::NotFound));
+        assert_eq!(diagnose(error.as_ref()), None);
+        #[cfg(feature = "downloader")]
+        {
+            let failure = Failure::DownloadFailed { file: "chrome-win64.zip".to_string(), reason: "timed out".to_string() };
+            assert_eq!(failure.to_string(), "Failed to download chrome-win64.zip: timed out");
+        }
+
+        let error = anyhow::anyhow!("Login failed: Still on login page");
+        assert_eq!(diagnose(error.as_ref()), None);
+    }
+}
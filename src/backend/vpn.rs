@@ -0,0 +1,136 @@
+// 校园 VPN 联动（可选）：门户登录成功后执行配置的命令连接 EasyConnect、WebVPN 客户端等，并用状态命令判断 VPN 是否已连接
+use crate::backend::config::Config;
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// 界面中定期执行状态命令的间隔
+pub const STATUS_INTERVAL: Duration = Duration::from_secs(60);
+// 连接命令和状态命令的最长等待时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+const STATUS_TIMEOUT: Duration = Duration::from_secs(10);
+// 连接命令返回后客户端可能仍在握手，按此间隔再检查几次状态
+const SETTLE_INTERVAL: Duration = Duration::from_secs(3);
+const SETTLE_CHECKS: usize = 5;
+
+/// VPN 连接状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VpnState {
+    Connected,
+    Disconnected,
+    // 没有配置状态命令或状态命令无法执行
+    Unknown,
+}
+
+impl fmt::Display for VpnState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VpnState::Connected => write!(f, "Connected"),
+            VpnState::Disconnected => write!(f, "Disconnected"),
+            VpnState::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// 配置的连接命令和状态命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VpnHook {
+    connect_command: String,
+    status_command: String,
+}
+
+impl VpnHook {
+    /// 从配置读取，没有配置连接命令时返回 None
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let connect_command = config.vpn_connect_command.trim();
+        if connect_command.is_empty() {
+            return None;
+        }
+        Some(Self {
+            connect_command: connect_command.to_string(),
+            status_command: config.vpn_status_command.trim().to_string(),
+        })
+    }
+
+    /// 执行状态命令，退出码为 0 表示已连接
+    pub async fn status(&self) -> VpnState {
+        if self.status_command.is_empty() {
+            return VpnState::Unknown;
+        }
+        match run(&self.status_command, STATUS_TIMEOUT).await {
+            Ok(true) => VpnState::Connected,
+            Ok(false) => VpnState::Disconnected,
+            Err(e) => {
+                log::warn!("VPN status command failed: {}", e);
+                VpnState::Unknown
+            }
+        }
+    }
+
+    /// 未连接时执行连接命令，返回连接后的状态；连接命令退出码不为 0 时返回错误
+    pub async fn connect(&self) -> Result<VpnState> {
+        if self.status().await == VpnState::Connected {
+            return Ok(VpnState::Connected);
+        }
+        if !run(&self.connect_command, CONNECT_TIMEOUT).await? {
+            return Err(anyhow!("VPN connect command exited with an error"));
+        }
+
+        let mut state = self.status().await;
+        for _ in 1..SETTLE_CHECKS {
+            if state != VpnState::Disconnected {
+                break;
+            }
+            tokio::time::sleep(SETTLE_INTERVAL).await;
+            state = self.status().await;
+        }
+        Ok(state)
+    }
+}
+
+// 通过系统 shell 执行命令，返回退出码是否为 0
+async fn run(command: &str, timeout: Duration) -> Result<bool> {
+    #[cfg(windows)]
+    let mut child = Command::new("cmd");
+    #[cfg(windows)]
+    child.args(["/C", command]);
+    #[cfg(not(windows))]
+    let mut child = Command::new("sh");
+    #[cfg(not(windows))]
+    child.args(["-c", command]);
+
+    let status = tokio::time::timeout(timeout, child.kill_on_drop(true).status())
+        .await
+        .map_err(|_| anyhow!("\"{}\" timed out after {} seconds", command, timeout.as_secs()))?
+        .map_err(|e| anyhow!("Failed to run \"{}\": {}", command, e))?;
+    Ok(status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(connect: &str, status: &str) -> VpnHook {
+        let config = Config {
+            vpn_connect_command: connect.to_string(),
+            vpn_status_command: status.to_string(),
+            ..Default::default()
+        };
+        VpnHook::from_config(&config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_vpn_hook() {
+        assert_eq!(VpnHook::from_config(&Config::default()), None);
+
+        assert_eq!(hook("exit 0", "").status().await, VpnState::Unknown);
+        assert_eq!(hook("exit 0", "exit 0").status().await, VpnState::Connected);
+        assert_eq!(hook("exit 0", "exit 1").status().await, VpnState::Disconnected);
+
+        // 已连接时不执行连接命令
+        assert_eq!(hook("exit 1", "exit 0").connect().await.unwrap(), VpnState::Connected);
+        assert_eq!(hook("exit 0", "").connect().await.unwrap(), VpnState::Unknown);
+        assert!(hook("exit 3", "").connect().await.is_err());
+    }
+}
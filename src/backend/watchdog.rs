@@ -0,0 +1,257 @@
+// 后台线程健康检查模块：线程心跳、停止信号，以及记录所有命名后台线程状态的任务表
+use parking_lot::Mutex;
+use std::any::Any;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+
+/// 线程心跳，后台线程每轮循环更新一次时间戳
+#[derive(Clone, Debug, Default)]
+pub struct Heartbeat {
+    last_beat_ms: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        let heartbeat = Self::default();
+        heartbeat.beat();
+        heartbeat
+    }
+
+    /// 记录一次心跳
+    pub fn beat(&self) {
+        self.last_beat_ms.store(now_ms(), Ordering::Relaxed);
+    }
+
+    /// 距离上次心跳的时间
+    pub fn age(&self) -> Duration {
+        Duration::from_millis(now_ms().saturating_sub(self.last_beat_ms.load(Ordering::Relaxed)))
+    }
+
+    /// 超过指定时间没有心跳则认为线程已失去响应
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.age() > max_age
+    }
+
+    // 是否为同一个线程的心跳（克隆得到的心跳相同）
+    fn same_as(&self, other: &Heartbeat) -> bool {
+        Arc::ptr_eq(&self.last_beat_ms, &other.last_beat_ms)
+    }
+}
+
+/// 创建一对停止信号：界面持有 StopHandle，后台线程持有 StopToken
+pub fn stop_signal() -> (StopHandle, StopToken) {
+    let (sender, receiver) = watch::channel(false);
+    (StopHandle(sender), StopToken(receiver))
+}
+
+/// 停止信号的发送端，调用 stop 或释放后线程在下一次检查或等待时退出
+pub struct StopHandle(watch::Sender<bool>);
+
+impl StopHandle {
+    pub fn stop(self) {
+        let _ = self.0.send(true);
+    }
+}
+
+/// 停止信号的接收端，线程用它代替 sleep，收到信号时提前醒来
+#[derive(Clone)]
+pub struct StopToken(watch::Receiver<bool>);
+
+impl StopToken {
+    /// 是否已收到停止信号
+    pub fn is_stopped(&self) -> bool {
+        *self.0.borrow() || self.0.has_changed().is_err()
+    }
+
+    /// 等到收到停止信号
+    pub async fn stopped(&mut self) {
+        let _ = self.0.wait_for(|stopped| *stopped).await;
+    }
+
+    /// 等待 timeout，期间收到停止信号时提前返回 true
+    pub async fn sleep(&mut self, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, self.stopped()).await.is_ok()
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 后台任务的运行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    Running,
+    Finished,
+    Panicked,
+}
+
+/// 任务表中的一项
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+    pub name: &'static str,
+    pub state: TaskState,
+    // 最近一次启动或心跳，长期运行的线程每轮循环更新
+    pub heartbeat: Heartbeat,
+    // 同名任务第一次之后又启动的次数，周期性任务即为执行次数
+    pub restarts: u32,
+}
+
+/// 所有命名后台线程的状态，按第一次启动的顺序
+#[derive(Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<Vec<TaskInfo>>,
+}
+
+impl TaskRegistry {
+    fn start(&self, name: &'static str, heartbeat: Heartbeat) {
+        heartbeat.beat();
+        let mut tasks = self.tasks.lock();
+        match tasks.iter_mut().find(|task| task.name == name) {
+            Some(task) => {
+                task.state = TaskState::Running;
+                task.heartbeat = heartbeat;
+                task.restarts += 1;
+            }
+            None => tasks.push(TaskInfo { name, state: TaskState::Running, heartbeat, restarts: 0 }),
+        }
+    }
+
+    // 同名线程已重新启动时，旧线程退出不影响新线程的状态
+    fn finish(&self, name: &'static str, heartbeat: &Heartbeat, state: TaskState) {
+        if let Some(task) = self.tasks.lock().iter_mut().find(|task| task.name == name && task.heartbeat.same_as(heartbeat)) {
+            task.state = state;
+            task.heartbeat.beat();
+        }
+    }
+
+    /// 所有任务的当前状态
+    pub fn snapshot(&self) -> Vec<TaskInfo> {
+        self.tasks.lock().clone()
+    }
+}
+
+/// 全局任务表
+pub fn registry() -> &'static TaskRegistry {
+    static REGISTRY: OnceLock<TaskRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(TaskRegistry::default)
+}
+
+// 线程结束（包括 panic 展开）时更新任务状态
+struct FinishGuard(&'static str, Heartbeat);
+
+impl Drop for FinishGuard {
+    fn drop(&mut self) {
+        let state = if std::thread::panicking() { TaskState::Panicked } else { TaskState::Finished };
+        registry().finish(self.0, &self.1, state);
+    }
+}
+
+/// 启动命名的后台线程并登记到任务表
+pub fn spawn<T: Send + 'static>(name: &'static str, f: impl FnOnce() -> T + Send + 'static) -> JoinHandle<T> {
+    spawn_with_heartbeat(name, Heartbeat::new(), f)
+}
+
+/// 启动命名的后台线程，线程每轮循环更新的心跳同时作为任务表中的最近活动时间
+pub fn spawn_with_heartbeat<T: Send + 'static>(
+    name: &'static str,
+    heartbeat: Heartbeat,
+    f: impl FnOnce() -> T + Send + 'static,
+) -> JoinHandle<T> {
+    registry().start(name, heartbeat.clone());
+    std::thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || {
+            let _guard = FinishGuard(name, heartbeat);
+            f()
+        })
+        .expect("failed to spawn thread")
+}
+
+/// 从线程 panic 的返回值中提取错误信息
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat() {
+        let heartbeat = Heartbeat::new();
+        assert!(!heartbeat.is_stale(Duration::from_secs(5)));
+
+        // 模拟很久没有心跳
+        heartbeat.last_beat_ms.store(now_ms() - 10_000, Ordering::Relaxed);
+        assert!(heartbeat.is_stale(Duration::from_secs(5)));
+
+        // 克隆的心跳共享同一个时间戳
+        heartbeat.clone().beat();
+        assert!(!heartbeat.is_stale(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_task_registry() {
+        let handle = spawn("test-task", || std::thread::current().name().map(str::to_string));
+        assert_eq!(handle.join().unwrap().as_deref(), Some("test-task"));
+        let task = registry().snapshot().into_iter().find(|task| task.name == "test-task").unwrap();
+        assert_eq!(task.state, TaskState::Finished);
+        assert_eq!(task.restarts, 0);
+
+        assert!(spawn("test-task", || panic!("boom")).join().is_err());
+        let task = registry().snapshot().into_iter().find(|task| task.name == "test-task").unwrap();
+        assert_eq!(task.state, TaskState::Panicked);
+        assert_eq!(task.restarts, 1);
+
+        // 旧线程在新线程启动后才退出时，任务仍显示为运行中
+        let (stop_old, mut old_token) = stop_signal();
+        let old = spawn("test-task", move || {
+            tokio::runtime::Runtime::new().unwrap().block_on(old_token.stopped());
+        });
+        let (stop_new, new_token) = stop_signal();
+        let new = spawn("test-task", move || while !new_token.is_stopped() {
+            std::thread::sleep(Duration::from_millis(5));
+        });
+        stop_old.stop();
+        old.join().unwrap();
+        let task = registry().snapshot().into_iter().find(|task| task.name == "test-task").unwrap();
+        assert_eq!(task.state, TaskState::Running);
+        // 释放发送端同样会停止线程
+        drop(stop_new);
+        new.join().unwrap();
+        let task = registry().snapshot().into_iter().find(|task| task.name == "test-task").unwrap();
+        assert_eq!(task.state, TaskState::Finished);
+    }
+
+    #[tokio::test]
+    async fn test_stop_token_sleep() {
+        let (stop, mut token) = stop_signal();
+        assert!(!token.sleep(Duration::from_millis(10)).await);
+        assert!(!token.is_stopped());
+        stop.stop();
+        assert!(token.is_stopped());
+        assert!(token.sleep(Duration::from_secs(5)).await);
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let err = std::thread::spawn(|| panic!("Failed to create runtime")).join().unwrap_err();
+        assert_eq!(panic_message(err.as_ref()), "Failed to create runtime");
+
+        let err = std::thread::spawn(|| panic!("code {}", 42)).join().unwrap_err();
+        assert_eq!(panic_message(err.as_ref()), "code 42");
+    }
+}
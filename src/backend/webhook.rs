@@ -0,0 +1,358 @@
+// Webhook 通知：断网、恢复、自动登录成功和失败时向配置的地址 POST 一条 JSON，便于把提醒转发到钉钉、企业微信、
+// Server酱或 Telegram。每个 Webhook 可只订阅部分事件；设置了密钥时通用格式在请求头中附上 HMAC-SHA256 签名，
+// 钉钉格式按机器人的加签方式在地址上附上 timestamp 和 sign。断网和登录失败发生时通常发不出去，先放进 Outbox，
+// 恢复在线后与恢复或登录成功的消息一起发送，恢复的消息中附上断网时长
+use crate::backend::timezone;
+use crate::backend::watchdog;
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::time::Duration;
+
+/// 通用格式签名所在的请求头，值为 sha256=<十六进制>
+pub const SIGNATURE_HEADER: &str = "X-CSUNetwork-Signature";
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+// 断网期间最多保留的事件数，更早的丢弃
+const MAX_QUEUED: usize = 10;
+
+/// 发送 Webhook 的事件
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    // 自动登录成功
+    LoginSuccess,
+    // 连续失败中只发送第一次
+    LoginFailed,
+    // 网络从在线变为断开或需要登录
+    NetworkDown,
+    // 网络恢复在线
+    NetworkUp,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 4] = [WebhookEvent::LoginSuccess, WebhookEvent::LoginFailed, WebhookEvent::NetworkDown, WebhookEvent::NetworkUp];
+
+    /// 消息中的事件名，与配置中的写法相同
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::LoginSuccess => "login_success",
+            WebhookEvent::LoginFailed => "login_failed",
+            WebhookEvent::NetworkDown => "network_down",
+            WebhookEvent::NetworkUp => "network_up",
+        }
+    }
+
+    /// 消息的标题
+    pub fn title(&self) -> &'static str {
+        match self {
+            WebhookEvent::LoginSuccess => "Auto login succeeded",
+            WebhookEvent::LoginFailed => "Auto login failed",
+            WebhookEvent::NetworkDown => "Network disconnected",
+            WebhookEvent::NetworkUp => "Network restored",
+        }
+    }
+}
+
+/// 消息格式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    // 事件的完整 JSON，适合自己的服务或自动化平台
+    #[default]
+    Json,
+    // 钉钉群机器人
+    DingTalk,
+    // 企业微信群机器人
+    WeCom,
+    // Server酱（sctapi.ftqq.com/<SendKey>.send）
+    ServerChan,
+    // Telegram Bot API 的 sendMessage，chat_id 写在地址的查询参数中
+    Telegram,
+}
+
+impl WebhookFormat {
+    pub const ALL: [WebhookFormat; 5] = [
+        WebhookFormat::Json,
+        WebhookFormat::DingTalk,
+        WebhookFormat::WeCom,
+        WebhookFormat::ServerChan,
+        WebhookFormat::Telegram,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WebhookFormat::Json => "JSON",
+            WebhookFormat::DingTalk => "钉钉 DingTalk",
+            WebhookFormat::WeCom => "企业微信 WeCom",
+            WebhookFormat::ServerChan => "Server酱",
+            WebhookFormat::Telegram => "Telegram",
+        }
+    }
+}
+
+/// 一个 Webhook
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Webhook {
+    pub url: String,
+    // 订阅的事件，为空时发送全部事件
+    pub events: Vec<WebhookEvent>,
+    pub format: WebhookFormat,
+    // 签名用的密钥，为空时不签名
+    pub secret: String,
+}
+
+impl Webhook {
+    /// 是否需要发送该事件
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        !self.url.trim().is_empty() && (self.events.is_empty() || self.events.contains(&event))
+    }
+}
+
+/// 一次事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notice {
+    pub event: WebhookEvent,
+    pub message: String,
+    // 当前账号，没有时为空
+    pub account: String,
+    // 事件发生的时间（Unix 时间戳，毫秒）
+    pub timestamp_ms: i64,
+}
+
+impl Notice {
+    pub fn new(event: WebhookEvent, message: impl Into<String>, account: &str) -> Self {
+        Self { event, message: message.into(), account: account.to_string(), timestamp_ms: timezone::now().timestamp_millis() }
+    }
+
+    // 聊天机器人中显示的一行文字
+    fn text(&self) -> String {
+        match self.account.is_empty() {
+            true => format!("[CSUNetwork] {}: {}", self.event.title(), self.message),
+            false => format!("[CSUNetwork] {} ({}): {}", self.event.title(), self.account, self.message),
+        }
+    }
+}
+
+// 断网时长的文字，如 "1 h 5 min"、"42 s"
+fn describe_outage(secs: i64) -> String {
+    match secs {
+        ..=59 => format!("{} s", secs.max(0)),
+        60..=3599 => format!("{} min", secs / 60),
+        _ => format!("{} h {} min", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// 离线期间的事件：断网和登录失败时网络不通，先排队，恢复在线后一起发送
+#[derive(Debug, Default)]
+pub struct Outbox {
+    queued: Vec<Notice>,
+    // 断网的时间（Unix 时间戳，毫秒），没有断网时为 None
+    down_since: Option<i64>,
+}
+
+impl Outbox {
+    /// 网络不通时发生的事件，留到恢复在线后发送
+    pub fn hold(&mut self, notice: Notice) {
+        if self.queued.len() >= MAX_QUEUED {
+            self.queued.remove(0);
+        }
+        self.down_since.get_or_insert(notice.timestamp_ms);
+        self.queued.push(notice);
+    }
+
+    /// 恢复在线时的事件：返回排队的事件和该事件，该事件的消息中附上断网时长
+    pub fn release(&mut self, mut notice: Notice) -> Vec<Notice> {
+        if let Some(since) = self.down_since.take() {
+            notice.message = format!("{} (offline for {})", notice.message, describe_outage((notice.timestamp_ms - since) / 1000));
+        }
+        let mut notices = std::mem::take(&mut self.queued);
+        notices.push(notice);
+        notices
+    }
+}
+
+/// 按格式生成的请求
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outgoing {
+    pub url: Url,
+    pub body: Value,
+    // 通用格式设置了密钥时的签名请求头
+    pub signature: Option<String>,
+}
+
+fn hmac_sha256(secret: &str, message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// 生成发送给该 Webhook 的请求
+pub fn outgoing(webhook: &Webhook, notice: &Notice) -> Result<Outgoing> {
+    let mut url = Url::parse(webhook.url.trim()).map_err(|e| anyhow!("Invalid webhook URL {}: {}", webhook.url, e))?;
+    let secret = webhook.secret.trim();
+    let mut signature = None;
+    let body = match webhook.format {
+        WebhookFormat::Json => {
+            let body = json!({
+                "event": notice.event.name(),
+                "title": notice.event.title(),
+                "message": notice.message,
+                "account": notice.account,
+                "timestamp": notice.timestamp_ms / 1000,
+                "time": timezone::at(notice.timestamp_ms / 1000).format("%Y-%m-%d %H:%M:%S").to_string(),
+            });
+            if !secret.is_empty() {
+                let digest = hmac_sha256(secret, body.to_string().as_bytes());
+                signature = Some(format!("sha256={}", digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>()));
+            }
+            body
+        }
+        WebhookFormat::DingTalk | WebhookFormat::WeCom => {
+            // 钉钉加签：对 "timestamp\n密钥" 做 HMAC-SHA256 后 Base64
+            if webhook.format == WebhookFormat::DingTalk && !secret.is_empty() {
+                let sign = STANDARD.encode(hmac_sha256(secret, format!("{}\n{}", notice.timestamp_ms, secret).as_bytes()));
+                url.query_pairs_mut()
+                    .append_pair("timestamp", &notice.timestamp_ms.to_string())
+                    .append_pair("sign", &sign);
+            }
+            json!({ "msgtype": "text", "text": { "content": notice.text() } })
+        }
+        WebhookFormat::ServerChan => json!({ "title": notice.event.title(), "desp": notice.text() }),
+        WebhookFormat::Telegram => {
+            let chat_id = url.query_pairs().find(|(name, _)| name == "chat_id").map(|(_, value)| value.into_owned());
+            json!({ "chat_id": chat_id, "text": notice.text() })
+        }
+    };
+    Ok(Outgoing { url, body, signature })
+}
+
+// 聊天机器人接口在 HTTP 200 中用 errcode、code 或 ok 表示失败
+fn check_reply(format: WebhookFormat, status: u16, text: &str) -> Result<()> {
+    if !(200..300).contains(&status) {
+        bail!("HTTP {}: {}", status, text.chars().take(200).collect::<String>());
+    }
+    let Ok(reply) = serde_json::from_str::<Value>(text) else {
+        return Ok(());
+    };
+    let failed = match format {
+        WebhookFormat::Json => false,
+        WebhookFormat::DingTalk | WebhookFormat::WeCom => reply["errcode"].as_i64().is_some_and(|code| code != 0),
+        WebhookFormat::ServerChan => reply["code"].as_i64().is_some_and(|code| code != 0),
+        WebhookFormat::Telegram => reply["ok"].as_bool() == Some(false),
+    };
+    if failed {
+        let detail = ["errmsg", "message", "description"].iter()
+            .find_map(|name| reply[name].as_str())
+            .unwrap_or(text);
+        bail!("rejected: {}", detail);
+    }
+    Ok(())
+}
+
+/// 向一个 Webhook 发送事件，不检查是否订阅
+pub async fn send(webhook: &Webhook, notice: &Notice) -> Result<()> {
+    let outgoing = outgoing(webhook, notice)?;
+    let client = Client::builder().timeout(SEND_TIMEOUT).build()?;
+    let mut request = client.post(outgoing.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(outgoing.body.to_string());
+    if let Some(signature) = outgoing.signature {
+        request = request.header(SIGNATURE_HEADER, signature);
+    }
+    let response = request.send().await?;
+    let status = response.status().as_u16();
+    check_reply(webhook.format, status, &response.text().await?)
+}
+
+/// 向订阅了该事件的所有 Webhook 发送，失败只记录日志
+pub async fn send_all(webhooks: &[Webhook], notice: &Notice) {
+    for webhook in webhooks.iter().filter(|webhook| webhook.wants(notice.event)) {
+        if let Err(e) = send(webhook, notice).await {
+            log::warn!("Failed to send the {} webhook: {}", notice.event.name(), e);
+        }
+    }
+}
+
+/// 在后台线程中按顺序发送，不阻塞调用方；没有 Webhook 订阅这些事件时什么也不做
+pub fn dispatch(webhooks: &[Webhook], notices: Vec<Notice>) {
+    let webhooks: Vec<Webhook> = webhooks.iter()
+        .filter(|webhook| notices.iter().any(|notice| webhook.wants(notice.event)))
+        .cloned()
+        .collect();
+    if webhooks.is_empty() {
+        return;
+    }
+    watchdog::spawn("webhook", move || match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime.block_on(async {
+            for notice in &notices {
+                send_all(&webhooks, notice).await;
+            }
+        }),
+        Err(e) => log::warn!("Failed to send webhooks: {}", e),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outgoing() {
+        let notice = Notice {
+            event: WebhookEvent::LoginFailed,
+            message: "Wrong username or password".to_string(),
+            account: "8208000000".to_string(),
+            timestamp_ms: 1_730_000_000_000,
+        };
+        let mut webhook = Webhook { url: "https://example.com/hook".to_string(), ..Webhook::default() };
+        assert!(webhook.wants(WebhookEvent::NetworkUp));
+        webhook.events = vec![WebhookEvent::LoginFailed];
+        assert!(!webhook.wants(WebhookEvent::NetworkUp));
+        assert!(!Webhook::default().wants(WebhookEvent::LoginFailed));
+
+        let request = outgoing(&webhook, &notice).unwrap();
+        assert_eq!(request.body["event"], "login_failed");
+        assert_eq!(request.body["timestamp"], 1_730_000_000);
+        assert_eq!(request.signature, None);
+        // RFC 4231 测试用例 2 的密钥和消息
+        assert_eq!(
+            hmac_sha256("Jefe", b"what do ya want for nothing?").iter().map(|byte| format!("{:02x}", byte)).collect::<String>(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        webhook.secret = "Jefe".to_string();
+        let signature = outgoing(&webhook, &notice).unwrap().signature.unwrap();
+        assert!(signature.starts_with("sha256=") && signature.len() == 7 + 64);
+
+        webhook.format = WebhookFormat::DingTalk;
+        webhook.url = "https://oapi.dingtalk.com/robot/send?access_token=abc".to_string();
+        let request = outgoing(&webhook, &notice).unwrap();
+        assert_eq!(request.body["text"]["content"], "[CSUNetwork] Auto login failed (8208000000): Wrong username or password");
+        assert!(request.url.as_str().starts_with("https://oapi.dingtalk.com/robot/send?access_token=abc&timestamp=1730000000000&sign="));
+
+        webhook.format = WebhookFormat::Telegram;
+        webhook.url = "https://api.telegram.org/bot123:ABC/sendMessage?chat_id=-1001".to_string();
+        assert_eq!(outgoing(&webhook, &notice).unwrap().body["chat_id"], "-1001");
+
+        assert!(check_reply(WebhookFormat::Json, 204, "").is_ok());
+        assert!(check_reply(WebhookFormat::Json, 200, r#"{"code": 500}"#).is_ok());
+        assert_eq!(check_reply(WebhookFormat::DingTalk, 200, r#"{"errcode": 310000, "errmsg": "sign not match"}"#).unwrap_err().to_string(), "rejected: sign not match");
+        assert!(check_reply(WebhookFormat::Telegram, 200, r#"{"ok": false, "description": "chat not found"}"#).is_err());
+        assert!(check_reply(WebhookFormat::ServerChan, 502, "Bad Gateway").unwrap_err().to_string().starts_with("HTTP 502"));
+
+        // 断网期间的事件等恢复后与恢复事件一起发送
+        let mut outbox = Outbox::default();
+        outbox.hold(Notice { event: WebhookEvent::NetworkDown, timestamp_ms: 1_730_000_000_000, ..notice.clone() });
+        outbox.hold(notice.clone());
+        let up = Notice { event: WebhookEvent::NetworkUp, message: "Network is online again".to_string(), timestamp_ms: 1_730_003_900_000, ..notice };
+        let sent = outbox.release(up.clone());
+        assert_eq!(sent.iter().map(|notice| notice.event).collect::<Vec<_>>(), vec![WebhookEvent::NetworkDown, WebhookEvent::LoginFailed, WebhookEvent::NetworkUp]);
+        assert_eq!(sent[2].message, "Network is online again (offline for 1 h 5 min)");
+        assert_eq!(outbox.release(up.clone()), vec![up]);
+    }
+}
@@ -0,0 +1,604 @@
+// 命令行子命令，执行后直接退出，不启动图形界面
+use crate::backend::address_watch::AddressWatcher;
+use crate::backend::auth::OnlineStatus;
+use crate::backend::autostart;
+use crate::backend::config::Config;
+use crate::backend::history::{self, Action, Trigger};
+use crate::backend::lockout::{self, LockoutTracker};
+use crate::backend::logger::Logger;
+use crate::backend::maintenance::PortalState;
+#[cfg(feature = "mqtt")]
+use crate::backend::mqtt::{MqttPublisher, MqttSettings, NetworkState};
+#[cfg(feature = "control_api")]
+use crate::backend::config_handle::ConfigHandle;
+#[cfg(feature = "control_api")]
+use crate::backend::control_api::{ControlApiServer, ControlApiSettings};
+use crate::backend::network_monitor::NetworkMonitor;
+use crate::backend::notifier::QuietHours;
+use crate::backend::paths::{self, Location};
+use crate::backend::probe::{Connectivity, ProbeOutcome};
+use crate::backend::webhook::{Notice, Outbox, WebhookEvent};
+use crate::backend::{keep_alive, login_schedule, portal, semester, system_info, timezone};
+use clap::{Parser, Subcommand};
+use log::{error, info, warn};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 不带子命令时启动图形界面
+#[derive(Parser, Debug)]
+#[command(name = "sn", version, about = "Campus network assistant, starts the GUI when no command is given")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Started by Windows at sign-in, see backend/autostart.rs
+    #[arg(long, hide = true)]
+    autostart: bool,
+    /// Keep the config and logs in ./config and ./logs instead of the user's config directory
+    #[arg(long, global = true)]
+    portable: bool,
+    /// Config directory to use, passed to the Windows service by "sn service install"
+    #[arg(long, global = true, hide = true, conflicts_with = "portable")]
+    config_dir: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+enum Command {
+    /// Log in to the campus portal over HTTP
+    Login,
+    /// Log out of the campus portal
+    Logout,
+    /// Show whether the portal considers this device signed in
+    Status,
+    /// Stay signed in, logging in again when the network drops or the local address changes
+    Daemon {
+        /// Seconds between connectivity checks
+        #[arg(long, default_value_t = 30)]
+        interval: u64,
+    },
+    /// Manage the Windows service that keeps this computer signed in without a desktop session
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+    /// Print the current log file
+    Logs {
+        /// Keep printing new lines as they are written
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// Run a full connectivity check and print a JSON report
+    Monitor {
+        /// Check once and exit
+        #[arg(long, required = true)]
+        once: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+enum ServiceAction {
+    /// Install and start the service (run as administrator)
+    Install,
+    /// Stop and remove the service
+    Uninstall,
+    /// Run as the service, used by the service manager
+    #[command(hide = true)]
+    Run,
+}
+
+// 各子命令的退出码
+const EXIT_ONLINE: i32 = 0;
+const EXIT_NOT_AUTHENTICATED: i32 = 1;
+const EXIT_PORTAL_DOWN: i32 = 2;
+const EXIT_OFFLINE: i32 = 3;
+const EXIT_ERROR: i32 = 4;
+
+/// 登录、登出的结果
+#[derive(Serialize, Debug)]
+struct ActionReport {
+    action: &'static str,
+    success: bool,
+    message: String,
+}
+
+/// 门户记录的在线状态
+#[derive(Serialize, Debug)]
+struct StatusReport {
+    // online / not_authenticated / unreachable
+    status: &'static str,
+    account: Option<String>,
+    ip: Option<String>,
+    used_bytes: Option<u64>,
+    duration_secs: Option<u64>,
+    error: Option<String>,
+}
+
+/// 一次完整连通性检查的结果
+#[derive(Serialize, Debug)]
+struct MonitorReport {
+    // online / not_authenticated / portal_down / offline
+    status: &'static str,
+    // 探测得到的连接状态：online / portal_redirect / offline
+    connectivity: Connectivity,
+    health_score: u8,
+    probes: Vec<ProbeOutcome>,
+    // 门户状态：healthy / maintenance / server_error / unreachable
+    portal: &'static str,
+    portal_error: Option<String>,
+    // 门户记录的认证状态和账号，门户不可用时为 null
+    signed_in: Option<bool>,
+    account: Option<String>,
+    ip: Option<String>,
+    // DNS 被门户劫持时不存在的域名被解析到的地址
+    dns_hijack: Option<String>,
+}
+
+impl MonitorReport {
+    // 根据探测、门户检查和门户记录的认证状态判断整体状态：门户认为未登录时即使探测成功（白名单地址、DNS 劫持）也是
+    // not_authenticated，门户认为已登录而外网不通时是 offline（出口故障），门户不可用时按探测结果判断
+    fn new(health_score: u8, connectivity: Connectivity, probes: Vec<ProbeOutcome>, portal: Result<PortalState, String>, online: Option<OnlineStatus>) -> Self {
+        let (portal, portal_error) = match portal {
+            Ok(PortalState::Healthy) => ("healthy", None),
+            Ok(PortalState::MaintenancePage) => ("maintenance", None),
+            Ok(state @ PortalState::ServerError(_)) => ("server_error", Some(state.to_string())),
+            Err(e) => ("unreachable", Some(e)),
+        };
+        let signed_in = online.as_ref().map(|status| status.online);
+        let status = if signed_in == Some(false) || connectivity == Connectivity::PortalRedirect {
+            "not_authenticated"
+        } else if connectivity == Connectivity::Online {
+            "online"
+        } else if signed_in == Some(true) {
+            "offline"
+        } else if portal == "healthy" {
+            "not_authenticated"
+        } else if portal == "unreachable" {
+            "offline"
+        } else {
+            "portal_down"
+        };
+        let (account, ip) = online.map(|status| (status.account, status.ip)).unwrap_or_default();
+        Self { status, connectivity, health_score, probes, portal, portal_error, signed_in, account, ip, dns_hijack: None }
+    }
+
+    fn exit_code(&self) -> i32 {
+        match self.status {
+            "online" => EXIT_ONLINE,
+            "not_authenticated" => EXIT_NOT_AUTHENTICATED,
+            "portal_down" => EXIT_PORTAL_DOWN,
+            _ => EXIT_OFFLINE,
+        }
+    }
+}
+
+// 以 JSON 输出报告
+fn print_json<T: Serialize>(report: &T) -> bool {
+    match serde_json::to_string_pretty(report) {
+        Ok(json) => {
+            println!("{}", json);
+            true
+        }
+        Err(e) => {
+            eprintln!("Failed to serialize report: {}", e);
+            false
+        }
+    }
+}
+
+// 读取配置并应用限速、日志策略和时区，失败时返回退出码
+fn load_config() -> Result<Config, i32> {
+    let config = Config::load().map_err(|e| {
+        eprintln!("Failed to load config: {}", e);
+        EXIT_ERROR
+    })?;
+    config.apply_rate_limits();
+    config.apply_log_policy();
+    if let Err(e) = config.apply_time_zone() {
+        eprintln!("{}, using system time zone", e);
+    }
+    Ok(config)
+}
+
+// 登录或登出一次，门户拒绝时退出码为 1
+async fn login_or_logout(logout: bool) -> i32 {
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+    let result = portal::http_action(&config, if logout { Action::Logout } else { Action::Login }, Trigger::Command, &history::default_path()).await;
+    let report = ActionReport {
+        action: if logout { "logout" } else { "login" },
+        success: result.is_ok(),
+        message: result.unwrap_or_else(|e| e),
+    };
+    if !print_json(&report) {
+        return EXIT_ERROR;
+    }
+    if report.success { EXIT_ONLINE } else { EXIT_NOT_AUTHENTICATED }
+}
+
+// 查询门户记录的在线状态
+async fn status() -> i32 {
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+    let (report, code) = match portal::http_provider(&config).status().await {
+        Ok(status) => {
+            let code = if status.online { EXIT_ONLINE } else { EXIT_NOT_AUTHENTICATED };
+            (StatusReport {
+                status: if status.online { "online" } else { "not_authenticated" },
+                account: status.account,
+                ip: status.ip,
+                used_bytes: status.used_bytes,
+                duration_secs: status.duration.map(|duration| duration.as_secs()),
+                error: None,
+            }, code)
+        }
+        Err(e) => (StatusReport {
+            status: "unreachable",
+            account: None,
+            ip: None,
+            used_bytes: None,
+            duration_secs: None,
+            error: Some(e.to_string()),
+        }, EXIT_OFFLINE),
+    };
+    if !print_json(&report) {
+        return EXIT_ERROR;
+    }
+    code
+}
+
+// 常驻运行，Ctrl+C 退出
+async fn daemon(interval: Duration) -> i32 {
+    // 先初始化日志，load_config 中设置的各模块日志级别在初始化之后才生效
+    Logger::set_format(Config::saved_log_format());
+    Logger::set_policy(Config::saved_log_policy());
+    if let Err(e) = Logger::init() {
+        eprintln!("Failed to initialize logger: {}", e);
+        return EXIT_ERROR;
+    }
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+    run_daemon(config, interval, async {
+        let _ = tokio::signal::ctrl_c().await;
+    }).await;
+    EXIT_ONLINE
+}
+
+/// 定期检查网络，断网或本机地址变化时重新登录，直到 shutdown 完成；daemon 子命令和 Windows 服务共用
+pub async fn run_daemon(config: Config, interval: Duration, shutdown: impl std::future::Future<Output = ()>) {
+    tokio::pin!(shutdown);
+    let monitor = Arc::new(NetworkMonitor::new());
+    monitor.set_console_output(false);
+    monitor.configure(&config);
+    #[cfg(feature = "control_api")]
+    let _control_api = start_control_api(&config, &monitor);
+    let mut address_watcher = AddressWatcher::default();
+    let mut lockout = LockoutTracker::default();
+    let mut in_vacation = false;
+    let mut scheduled_off = false;
+    // 上次检查定时规则的时间，启动前已过的规则不补执行
+    let mut schedule_checked = timezone::now().naive_local();
+    // 上一轮是否在线、是否断开过，以及登录是否正在连续失败，用于发送 Webhook
+    let mut was_connected = false;
+    let mut went_down = false;
+    let mut login_failing = false;
+    // 上次发送保活心跳的时间
+    let mut keep_alive_at: Option<Instant> = None;
+    // 断网和登录失败的 Webhook 留到恢复在线后发送；发送在后台线程中进行，不耽误登录
+    let mut outbox = Outbox::default();
+    // 免打扰时段内暂存的 Webhook，时段结束后一起发送
+    let mut quiet_hours = QuietHours::default();
+    #[cfg(feature = "mqtt")]
+    let mut mqtt = MqttSettings::from_config(&config)
+        .and_then(|settings| MqttPublisher::connect(settings).map_err(|e| error!("{}", e)).ok());
+    // 发布到 MQTT 时每轮都查询认证状态，否则只在需要登录时查询
+    let publishing = cfg!(feature = "mqtt") && !config.mqtt_url.trim().is_empty();
+    info!("Daemon started, checking every {} seconds", interval.as_secs());
+
+    loop {
+        monitor.check_connection().await;
+        let connected = monitor.is_connected();
+        if was_connected && !connected {
+            outbox.hold(Notice::new(WebhookEvent::NetworkDown, "Network disconnected", &config.username));
+            went_down = true;
+        } else if went_down && !was_connected && connected {
+            quiet_hours.dispatch(config.in_quiet_hours(), &config.webhooks, outbox.release(Notice::new(WebhookEvent::NetworkUp, "Network is online again", &config.username)));
+            went_down = false;
+        }
+        was_connected = connected;
+        if let Some(summary) = quiet_hours.release(config.in_quiet_hours(), &config.webhooks) {
+            info!("Quiet hours ended, held notifications: {}", summary);
+        }
+        // 在线时按保活间隔发送心跳，与界面相同
+        if config.keep_alive && connected && keep_alive_at.is_none_or(|at| at.elapsed() >= keep_alive::interval(&config)) {
+            keep_alive_at = Some(Instant::now());
+            if let Err(e) = keep_alive::beat(&config).await.result {
+                warn!("Keep-alive heartbeat failed: {}", e);
+            }
+        }
+        let address_change = address_watcher.observe(system_info::local_ipv4());
+        let today = timezone::now().date_naive();
+
+        // 假期期间和账号锁定期间不尝试登录
+        let vacation = semester::in_vacation(&config.semester_terms, today);
+        if vacation != in_vacation {
+            in_vacation = vacation;
+            info!("{}", if vacation { "Vacation, auto login paused" } else { "Term started, auto login resumed" });
+        }
+
+        // 执行到时间的定时规则；定时登出之后到下一条登录规则之前不自动登录
+        let now = timezone::now().naive_local();
+        if let Some(action) = login_schedule::due(&config.schedule_rules, schedule_checked, now) {
+            info!("Scheduled {}...", action.label().to_lowercase());
+            match portal::http_action(&config, action, Trigger::Schedule, &history::default_path()).await {
+                Ok(message) => info!("Scheduled {} succeeded: {}", action.label().to_lowercase(), message),
+                Err(e) => error!("Scheduled {} failed: {}", action.label().to_lowercase(), e),
+            }
+        }
+        schedule_checked = now;
+        let off = login_schedule::paused(&config.schedule_rules, now);
+        if off != scheduled_off {
+            scheduled_off = off;
+            match off {
+                true => info!("Scheduled logout, auto login paused {}", login_schedule::resumes(&config.schedule_rules, now)),
+                false => info!("Scheduled login time reached, auto login resumed"),
+            }
+        }
+
+        let needs_login = !vacation && !off && !lockout.is_active() && (!monitor.is_connected() || address_change.is_some());
+        let status = if needs_login || publishing { portal::http_provider(&config).status().await.ok() } else { None };
+        #[cfg(feature = "mqtt")]
+        if let Some(publisher) = &mut mqtt {
+            publisher.publish(&NetworkState::new(monitor.is_connected(), status.as_ref()));
+        }
+
+        if needs_login {
+            // 外网不通但门户显示已认证时是外网故障，重新登录无济于事
+            let signed_in = address_change.is_none() && status.is_some_and(|status| status.online);
+            if !signed_in {
+                match address_change {
+                    Some(change) => warn!("{}, re-authenticating...", change),
+                    None => warn!("Network disconnected, attempting login..."),
+                }
+                match portal::http_action(&config, Action::Login, Trigger::Auto, &history::default_path()).await {
+                    Ok(message) => {
+                        info!("Login successful: {}", message);
+                        quiet_hours.dispatch(config.in_quiet_hours(), &config.webhooks, outbox.release(Notice::new(WebhookEvent::LoginSuccess, message, &config.username)));
+                        login_failing = false;
+                    }
+                    Err(e) => {
+                        error!("Login failed: {}", e);
+                        // 连续失败中只发送第一次
+                        if !login_failing {
+                            outbox.hold(Notice::new(WebhookEvent::LoginFailed, e.clone(), &config.username));
+                            login_failing = true;
+                        }
+                        if let Some(cooldown) = lockout.record(&e) {
+                            warn!("{}", lockout::paused_message(cooldown));
+                        }
+                    }
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = &mut shutdown => {
+                info!("Daemon stopped");
+                return;
+            }
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+}
+
+// 按配置启动本机控制接口，返回值在守护进程退出前保持
+#[cfg(feature = "control_api")]
+fn start_control_api(config: &Config, monitor: &Arc<NetworkMonitor>) -> Option<ControlApiServer> {
+    if config.control_api && config.control_api_token.trim().is_empty() {
+        warn!("Control API is enabled but has no token, not starting it");
+    }
+    let settings = ControlApiSettings { http_only: true, ..ControlApiSettings::from_config(config)? };
+    match ControlApiServer::start(settings, ConfigHandle::new(config.clone()), Arc::clone(monitor)) {
+        Ok(server) => {
+            info!("Control API listening on http://{}", server.address());
+            Some(server)
+        }
+        Err(e) => {
+            error!("{}", e);
+            None
+        }
+    }
+}
+
+// 执行一次完整检查：所有探测目标、门户可达性和认证状态，输出 JSON 报告
+async fn monitor_once() -> i32 {
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+
+    let monitor = NetworkMonitor::new();
+    monitor.set_console_output(false);
+    monitor.configure(&config);
+    let probes = monitor.check_connection().await;
+
+    let provider = portal::http_provider(&config);
+    let portal = provider.portal_state().await.map_err(|e| e.to_string());
+    let online = match portal {
+        Ok(PortalState::Healthy) => provider.status().await.ok(),
+        _ => None,
+    };
+
+    let mut report = MonitorReport::new(monitor.health_score(), monitor.connectivity(), probes, portal, online);
+    report.dns_hijack = monitor.dns_hijack().map(|ip| ip.to_string());
+    if !print_json(&report) {
+        return EXIT_ERROR;
+    }
+    report.exit_code()
+}
+
+// 安装、卸载或运行 Windows 服务
+#[cfg(windows)]
+fn service_command(action: ServiceAction) -> i32 {
+    use crate::service;
+    let result = match action {
+        ServiceAction::Install => service::install().map(|()| println!("Service installed and started")),
+        ServiceAction::Uninstall => service::uninstall().map(|()| println!("Service removed")),
+        ServiceAction::Run => service::run(),
+    };
+    match result {
+        Ok(()) => EXIT_ONLINE,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            EXIT_ERROR
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn service_command(_action: ServiceAction) -> i32 {
+    eprintln!("Windows services are only available on Windows, run `sn daemon` under systemd or another supervisor instead");
+    EXIT_ERROR
+}
+
+/// 处理命令行参数（含程序名），返回退出码；没有子命令时返回 None，继续启动图形界面
+pub async fn run(args: impl IntoIterator<Item = String>) -> Option<i32> {
+    let cli = match Cli::try_parse_from(args) {
+        Ok(cli) => cli,
+        // --help、--version 正常退出；参数错误使用 EXIT_ERROR，避免与检查结果的退出码混淆
+        Err(e) => {
+            let _ = e.print();
+            return Some(if e.use_stderr() { EXIT_ERROR } else { 0 });
+        }
+    };
+    paths::set_location(match cli.config_dir {
+        Some(dir) => Location::Dir(dir),
+        None if cli.portable => Location::Portable,
+        None => Location::Platform,
+    });
+    // 开机启动时工作目录不是程序目录，先切换过去再读取配置和写日志
+    if cli.autostart {
+        if let Err(e) = autostart::enter_program_dir() {
+            eprintln!("Failed to enter the program directory: {}", e);
+        }
+    }
+    // 旧版本的配置在程序目录下，第一次使用系统配置目录时移过来
+    if let Err(e) = paths::adopt_program_dir_config() {
+        eprintln!("Failed to move the config from the program directory: {}", e);
+    }
+    let code = match cli.command? {
+        Command::Login => login_or_logout(false).await,
+        Command::Logout => login_or_logout(true).await,
+        Command::Status => status().await,
+        Command::Daemon { interval } => daemon(Duration::from_secs(interval.max(1))).await,
+        Command::Service { action } => service_command(action),
+        // 输出当前日志文件，便于脚本使用
+        Command::Logs { follow } => match Logger::print_current(follow) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Failed to read log: {}", e);
+                1
+            }
+        },
+        // 执行一次检查后退出，供 cron 等脚本使用
+        Command::Monitor { .. } => monitor_once().await,
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::probe::Probe;
+
+    fn portal_status(online: bool) -> OnlineStatus {
+        OnlineStatus {
+            online,
+            ip: Some("10.96.12.34".to_string()),
+            account: online.then(|| "8208190101".to_string()),
+            used_bytes: None,
+            duration: None,
+            stack: None,
+        }
+    }
+
+    fn outcome(reachable: bool) -> ProbeOutcome {
+        ProbeOutcome {
+            probe: Probe::Icmp { host: "223.5.5.5".to_string() },
+            latency_ms: reachable.then_some(12),
+            error: (!reachable).then(|| "timed out".to_string()),
+        }
+    }
+
+    fn parse(args: &[&str]) -> Result<Option<Command>, clap::Error> {
+        Cli::try_parse_from(std::iter::once("sn").chain(args.iter().copied())).map(|cli| cli.command)
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse(&[]).unwrap(), None);
+        assert_eq!(parse(&["--autostart"]).unwrap(), None);
+        assert_eq!(parse(&["login"]).unwrap(), Some(Command::Login));
+        assert_eq!(parse(&["daemon"]).unwrap(), Some(Command::Daemon { interval: 30 }));
+        assert_eq!(parse(&["daemon", "--interval", "120"]).unwrap(), Some(Command::Daemon { interval: 120 }));
+        assert_eq!(parse(&["logs", "-f"]).unwrap(), Some(Command::Logs { follow: true }));
+        assert_eq!(parse(&["monitor", "--once"]).unwrap(), Some(Command::Monitor { once: true }));
+        assert_eq!(parse(&["service", "install"]).unwrap(), Some(Command::Service { action: ServiceAction::Install }));
+        assert_eq!(parse(&["--portable", "login"]).unwrap(), Some(Command::Login));
+        assert_eq!(parse(&["daemon", "--portable"]).unwrap(), Some(Command::Daemon { interval: 30 }));
+        let cli = Cli::try_parse_from(["sn", "--config-dir", r"C:\Users\me\AppData\Roaming\CSUNetwork", "service", "run"]).unwrap();
+        assert_eq!(cli.config_dir, Some(PathBuf::from(r"C:\Users\me\AppData\Roaming\CSUNetwork")));
+        assert!(parse(&["--portable", "--config-dir", "config"]).is_err());
+        // monitor 必须带 --once，未知子命令报错而不是启动界面
+        assert!(parse(&["monitor"]).is_err());
+        assert!(parse(&["connect"]).is_err());
+    }
+
+    #[test]
+    fn test_monitor_report_status() {
+        let signed_in = portal_status(true);
+        let report = MonitorReport::new(100, Connectivity::Online, vec![outcome(true)], Ok(PortalState::Healthy), Some(signed_in.clone()));
+        assert_eq!(report.exit_code(), EXIT_ONLINE);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["signed_in"], true);
+        assert_eq!(json["account"], "8208190101");
+        assert_eq!(json["ip"], "10.96.12.34");
+
+        let report = MonitorReport::new(0, Connectivity::Offline, vec![outcome(false)], Ok(PortalState::Healthy), None);
+        assert_eq!(report.status, "not_authenticated");
+        assert_eq!(report.exit_code(), EXIT_NOT_AUTHENTICATED);
+
+        // 门户认为未登录：探测经白名单地址或被劫持的 DNS 成功也不算在线
+        let report = MonitorReport::new(100, Connectivity::Online, vec![outcome(true)], Ok(PortalState::Healthy), Some(portal_status(false)));
+        assert_eq!(report.exit_code(), EXIT_NOT_AUTHENTICATED);
+        assert_eq!(serde_json::to_value(&report).unwrap()["signed_in"], false);
+
+        // 门户认为已登录但外网不通
+        let report = MonitorReport::new(0, Connectivity::Offline, vec![outcome(false)], Ok(PortalState::Healthy), Some(signed_in));
+        assert_eq!(report.status, "offline");
+        assert_eq!(report.exit_code(), EXIT_OFFLINE);
+
+        // ping 成功但请求被门户拦截
+        let report = MonitorReport::new(100, Connectivity::PortalRedirect, vec![outcome(true)], Ok(PortalState::Healthy), None);
+        assert_eq!(report.exit_code(), EXIT_NOT_AUTHENTICATED);
+        assert_eq!(serde_json::to_value(&report).unwrap()["connectivity"], "portal_redirect");
+
+        let report = MonitorReport::new(0, Connectivity::Offline, vec![outcome(false)], Ok(PortalState::MaintenancePage), None);
+        assert_eq!(report.exit_code(), EXIT_PORTAL_DOWN);
+
+        let report = MonitorReport::new(0, Connectivity::Offline, vec![outcome(false)], Err("connection refused".to_string()), None);
+        assert_eq!(report.exit_code(), EXIT_OFFLINE);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["portal"], "unreachable");
+        assert_eq!(json["signed_in"], serde_json::Value::Null);
+        assert_eq!(json["probes"][0]["protocol"], "icmp");
+        assert_eq!(json["probes"][0]["error"], "timed out");
+    }
+}
@@ -0,0 +1,257 @@
+// 界面日志条目
+use chrono::{DateTime, FixedOffset};
+use crate::backend::log_bus::BusRecord;
+use crate::backend::timezone;
+use crate::backend::triage::Failure;
+use eframe::egui;
+
+// 日志级别，按严重程度排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 3] = [LogLevel::Info, LogLevel::Warn, LogLevel::Error];
+
+    /// 级别筛选中显示的名称，表示该级别及以上
+    pub fn filter_label(&self) -> &'static str {
+        match self {
+            LogLevel::Info => "All levels",
+            LogLevel::Warn => "Warnings and errors",
+            LogLevel::Error => "Errors only",
+        }
+    }
+
+    /// 复制日志时使用的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+}
+
+// 日志分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogCategory {
+    Login,
+    Logout,
+    Network,
+    System,
+}
+
+impl LogCategory {
+    pub const ALL: [LogCategory; 4] = [LogCategory::Login, LogCategory::Logout, LogCategory::Network, LogCategory::System];
+
+    /// 分类筛选中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogCategory::Login => "Login",
+            LogCategory::Logout => "Logout",
+            LogCategory::Network => "Network",
+            LogCategory::System => "System",
+        }
+    }
+
+    /// 日志面板中显示的图标
+    pub fn icon(&self) -> &'static str {
+        match self {
+            LogCategory::Login => "🔑",
+            LogCategory::Logout => "🚪",
+            LogCategory::Network => "🌐",
+            LogCategory::System => "⚙",
+        }
+    }
+}
+
+// 一条结构化的日志记录
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: DateTime<FixedOffset>,
+    pub level: LogLevel,
+    pub category: LogCategory,
+    pub message: String,
+    // 点击条目时显示的详细信息（如完整的错误链）
+    pub detail: Option<String>,
+    // 能识别的故障，界面据此显示处理建议
+    pub failure: Option<Failure>,
+}
+
+impl LogEntry {
+    pub fn new(level: LogLevel, category: LogCategory, message: impl Into<String>) -> Self {
+        Self {
+            time: timezone::now(),
+            level,
+            category,
+            message: message.into(),
+            detail: None,
+            failure: None,
+        }
+    }
+
+    pub fn info(category: LogCategory, message: impl Into<String>) -> Self {
+        Self::new(LogLevel::Info, category, message)
+    }
+
+    pub fn warn(category: LogCategory, message: impl Into<String>) -> Self {
+        Self::new(LogLevel::Warn, category, message)
+    }
+
+    pub fn error(category: LogCategory, message: impl Into<String>) -> Self {
+        Self::new(LogLevel::Error, category, message)
+    }
+
+    /// 附加详细信息
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// 附加能识别的故障
+    pub fn with_failure(mut self, failure: Option<Failure>) -> Self {
+        self.failure = failure;
+        self
+    }
+
+    /// 日志文字颜色，普通信息使用默认颜色
+    pub fn color(&self) -> Option<egui::Color32> {
+        match self.level {
+            LogLevel::Info => None,
+            LogLevel::Warn => Some(egui::Color32::from_rgb(230, 160, 0)),
+            LogLevel::Error => Some(egui::Color32::RED),
+        }
+    }
+
+    /// 日志面板中显示的单行文本
+    pub fn summary(&self) -> String {
+        format!("[{}] {} {}", self.time.format("%H:%M:%S"), self.category.icon(), self.message)
+    }
+
+    /// 复制到剪贴板的文本，带日期、级别和分类名，详细信息另起一行缩进
+    pub fn plain_text(&self) -> String {
+        let mut text = format!(
+            "{} {:<5} {:<7} {}",
+            self.time.format("%Y-%m-%d %H:%M:%S"),
+            self.level.label(),
+            self.category.label(),
+            self.message
+        );
+        if let Some(detail) = &self.detail {
+            for line in detail.lines() {
+                text.push_str("\n    ");
+                text.push_str(line);
+            }
+        }
+        text
+    }
+}
+
+/// 日志面板的筛选条件
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    // 只显示该级别及以上
+    pub min_level: LogLevel,
+    // 为 None 时显示所有分类
+    pub category: Option<LogCategory>,
+    // 在消息和详细信息中查找，不区分大小写
+    pub search: String,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self { min_level: LogLevel::Info, category: None, search: String::new() }
+    }
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        if entry.level < self.min_level || self.category.is_some_and(|category| category != entry.category) {
+            return false;
+        }
+        let search = self.search.trim().to_lowercase();
+        search.is_empty()
+            || entry.message.to_lowercase().contains(&search)
+            || entry.detail.as_ref().is_some_and(|detail| detail.to_lowercase().contains(&search))
+    }
+
+    /// 是否设置了任何筛选条件
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+// 日志总线上的记录按来源模块归类
+impl From<BusRecord> for LogEntry {
+    fn from(record: BusRecord) -> Self {
+        let level = match record.level {
+            log::Level::Error => LogLevel::Error,
+            log::Level::Warn => LogLevel::Warn,
+            _ => LogLevel::Info,
+        };
+        let category = match record.module() {
+            "auth" | "authentication" | "credential_provider" | "lockout" => LogCategory::Login,
+            "network_monitor" | "probe" | "proxy_detect" | "traceroute" => LogCategory::Network,
+            _ => LogCategory::System,
+        };
+        Self::new(level, category, record.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_entry() {
+        let entry = LogEntry::error(LogCategory::Login, "Login failed: timeout")
+            .with_detail("timeout after 10s");
+        assert_eq!(entry.color(), Some(egui::Color32::RED));
+        assert!(entry.summary().ends_with("🔑 Login failed: timeout"));
+        assert_eq!(entry.detail.as_deref(), Some("timeout after 10s"));
+        assert_eq!(entry.failure, None);
+
+        let entry = LogEntry::info(LogCategory::Network, "Network status changed to: Connected");
+        assert_eq!(entry.color(), None);
+        assert!(entry.summary().contains("🌐"));
+    }
+
+    #[test]
+    fn test_log_filter() {
+        let entries = [
+            LogEntry::info(LogCategory::Network, "Network status changed to: Connected"),
+            LogEntry::warn(LogCategory::Login, "Network disconnected, attempting auto login..."),
+            LogEntry::error(LogCategory::Login, "Auto login failed").with_detail("ChromeDriver not found on port 9515"),
+        ];
+        let shown = |filter: &LogFilter| entries.iter().filter(|entry| filter.matches(entry)).count();
+
+        let mut filter = LogFilter::default();
+        assert!(!filter.is_active());
+        assert_eq!(shown(&filter), 3);
+
+        filter.min_level = LogLevel::Warn;
+        assert_eq!(shown(&filter), 2);
+        filter.category = Some(LogCategory::Network);
+        assert_eq!(shown(&filter), 0);
+
+        // 搜索不区分大小写，也查找详细信息
+        let filter = LogFilter { search: " chromedriver ".to_string(), ..LogFilter::default() };
+        assert!(filter.is_active());
+        assert_eq!(shown(&filter), 1);
+        assert!(entries[2].plain_text().contains("ERROR Login   Auto login failed\n    ChromeDriver"));
+    }
+
+    #[test]
+    fn test_from_bus_record() {
+        let entry = LogEntry::from(BusRecord {
+            level: log::Level::Warn,
+            target: "sn::backend::authentication".to_string(),
+            message: "Failed to install page diagnostics".to_string(),
+        });
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.category, LogCategory::Login);
+    }
+}
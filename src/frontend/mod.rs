@@ -1 +1,4 @@
-pub mod ui; 
\ No newline at end of file
+pub mod log_entry;
+#[cfg(test)]
+pub mod snapshot;
+pub mod ui;
\ No newline at end of file
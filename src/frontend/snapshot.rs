@@ -0,0 +1,48 @@
+// 界面快照测试：在没有显示器的环境中渲染一帧，把画出的文字按绘制顺序记录下来，与 snapshots 目录中的快照比较
+// 设置环境变量 UPDATE_SNAPSHOTS=1 运行测试会重写快照，修改界面后检查差异再提交
+use eframe::egui;
+use eframe::epaint::Shape;
+use std::fs;
+use std::path::PathBuf;
+
+/// 渲染一帧，返回画出的文字，每段一行
+pub fn render(mut add_contents: impl FnMut(&mut egui::Ui)) -> String {
+    let ctx = egui::Context::default();
+    let input = egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0))),
+        ..Default::default()
+    };
+    // Grid 等控件在第一帧只测量尺寸而不显示，取第二帧的结果
+    let mut run = || ctx.run(input.clone(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| add_contents(ui));
+    });
+    run();
+    let output = run();
+
+    let mut lines = Vec::new();
+    for clipped in output.shapes {
+        collect_text(&clipped.shape, &mut lines);
+    }
+    lines.join("\n") + "\n"
+}
+
+fn collect_text(shape: &Shape, lines: &mut Vec<String>) {
+    match shape {
+        Shape::Text(text) => lines.push(text.galley.text().to_string()),
+        Shape::Vec(shapes) => shapes.iter().for_each(|shape| collect_text(shape, lines)),
+        _ => {}
+    }
+}
+
+/// 与 snapshots/<name>.txt 比较
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(file!()).with_file_name("snapshots").join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("Snapshot {} not found, run the tests with UPDATE_SNAPSHOTS=1 to create it", path.display()));
+    assert_eq!(actual, expected, "UI snapshot {} changed, run the tests with UPDATE_SNAPSHOTS=1 if this is intended", name);
+}
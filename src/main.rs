@@ -2,14 +2,29 @@ use std::sync::Arc;
 use log::{info, error};
 use crate::frontend::ui::UI;
 use crate::backend::network_monitor::NetworkMonitor;
+use crate::backend::autostart;
+use crate::backend::config::Config;
 use crate::backend::logger::Logger;
 
+mod cli;
 mod frontend;
 mod backend;
+#[cfg(windows)]
+mod service;
 
 #[tokio::main]
 async fn main() {
-    // 初始化日志系统
+    // 命令行子命令不启动界面，也不写入启动日志
+    if let Some(code) = cli::run(std::env::args()).await {
+        std::process::exit(code);
+    }
+
+    // 开机启动时按设置最小化，工作目录已在 cli::run 中切换到程序目录
+    let autostart = std::env::args().any(|arg| arg == autostart::AUTOSTART_ARG);
+
+    // 初始化日志系统，日志文件从第一行起使用配置的格式、大小上限和保留策略
+    Logger::set_format(Config::saved_log_format());
+    Logger::set_policy(Config::saved_log_policy());
     if let Err(e) = Logger::init() {
         eprintln!("Failed to initialize logger: {}", e);
         std::process::exit(1);
@@ -20,7 +35,10 @@ async fn main() {
     let network_monitor = Arc::new(NetworkMonitor::new());
     
     // 创建并运行UI
-    let ui = UI::new(network_monitor);
+    let mut ui = UI::new(network_monitor);
+    if autostart {
+        ui.launched_at_startup();
+    }
     if let Err(e) = ui.run() {
         error!("UI error: {}", e);
         std::process::exit(1);
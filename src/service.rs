@@ -0,0 +1,139 @@
+// Windows 服务：没有用户登录桌面时也在后台运行网络监控和自动登录，逻辑与 daemon 子命令相同
+use crate::backend::autostart::enter_program_dir;
+use crate::backend::config::Config;
+use crate::backend::logger::Logger;
+use crate::backend::paths;
+use crate::cli;
+use anyhow::{anyhow, Context, Result};
+use std::ffi::OsString;
+use std::time::Duration;
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_dispatcher};
+
+const SERVICE_NAME: &str = "CSUNetwork";
+const SERVICE_DISPLAY_NAME: &str = "CSU Network Auto Login";
+const SERVICE_DESCRIPTION: &str = "Keeps the campus network signed in, even when nobody is logged in to the desktop";
+// 服务检查网络的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// 由服务管理器启动时调用，阻塞到服务停止
+pub fn run() -> Result<()> {
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+        .context("Failed to connect to the service manager, this command is only started by the service itself")
+}
+
+/// 安装并启动服务，需要管理员权限；服务以 LocalSystem 运行，通过命令行参数使用安装者的配置目录（便携模式下为程序目录下的配置），
+/// 读不到安装者的凭据存储，密码按本机范围加密另存一份给服务
+pub fn install() -> Result<()> {
+    enter_program_dir()?;
+    let config = Config::load()?;
+    if !config.has_credentials() {
+        return Err(anyhow!("No saved credentials, save the username and password with \"Remember password\" checked first"));
+    }
+    if config.credential_provider.is_empty() {
+        config.save_service_credentials().context("Failed to save the password for the service")?;
+    }
+
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE)
+        .context("Failed to open the service manager, run as administrator")?;
+    let info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from(SERVICE_DISPLAY_NAME),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: std::env::current_exe()?,
+        launch_arguments: paths::location_args().into_iter().chain(["service".to_string(), "run".to_string()]).map(OsString::from).collect(),
+        dependencies: Vec::new(),
+        account_name: None,
+        account_password: None,
+    };
+    let service = manager.create_service(&info, ServiceAccess::CHANGE_CONFIG | ServiceAccess::START)
+        .context("Failed to create the service")?;
+    service.set_description(SERVICE_DESCRIPTION)?;
+    service.start::<&str>(&[]).context("Service installed but failed to start")?;
+    Ok(())
+}
+
+/// 停止并删除服务
+pub fn uninstall() -> Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .context("Failed to open the service manager, run as administrator")?;
+    let service = manager.open_service(SERVICE_NAME, ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE)
+        .context("Service is not installed")?;
+    if service.query_status()?.current_state != ServiceState::Stopped {
+        service.stop()?;
+    }
+    service.delete()?;
+    Config::remove_service_credentials()?;
+    Ok(())
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        log::error!("Service failed: {:#}", e);
+    }
+}
+
+fn status(state: ServiceState, controls_accepted: ServiceControlAccept, exit_code: u32) -> ServiceStatus {
+    ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted,
+        exit_code: ServiceExitCode::Win32(exit_code),
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    }
+}
+
+fn run_service() -> Result<()> {
+    enter_program_dir()?;
+    Logger::set_format(Config::saved_log_format());
+    Logger::set_policy(Config::saved_log_policy());
+    Logger::init().map_err(|e| anyhow!("Failed to initialize logger: {}", e))?;
+
+    // 服务管理器发出停止或关机时结束常驻循环
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+    let mut shutdown_tx = Some(shutdown_tx);
+    let event_handler = move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            if let Some(tx) = shutdown_tx.take() {
+                let _ = tx.send(());
+            }
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    };
+    let status_handle = service_control_handler::register(SERVICE_NAME, event_handler)?;
+    status_handle.set_service_status(status(ServiceState::Running, ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN, 0))?;
+
+    let result = (|| -> Result<()> {
+        let config = Config::load_for_service()?;
+        if !config.has_credentials() {
+            return Err(anyhow!("No credentials for the service, run \"sn service install\" again as the user who saved the password"));
+        }
+        config.apply_rate_limits();
+        config.apply_log_policy();
+        if let Err(e) = config.apply_time_zone() {
+            log::warn!("{}, using system time zone", e);
+        }
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(cli::run_daemon(config, CHECK_INTERVAL, async {
+            let _ = shutdown_rx.await;
+        }));
+        Ok(())
+    })();
+
+    // 出错时以非零退出码停止，服务管理器会记录失败
+    status_handle.set_service_status(status(ServiceState::Stopped, ServiceControlAccept::empty(), if result.is_ok() { 0 } else { 1 }))?;
+    result
+}